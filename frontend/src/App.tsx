@@ -25,7 +25,7 @@ function App() {
   const location = useLocation();
   const aliveRef = useKeepAliveRef();
 
-  useDeviceWebSocket();
+  useDeviceWebSocket(messageApi);
 
   const outlet = useOutlet();
 
@@ -0,0 +1,309 @@
+import { Button, Flex, Input, Select, Space, Tabs, Tag } from "antd";
+import { useEffect, useMemo, useRef, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { useMessageContext } from "../hooks";
+import { useAppSelector } from "../store/store";
+import { withApiToken } from "../utils";
+
+type LogLevel = "V" | "D" | "I" | "W" | "E" | "F";
+
+type LogcatEntry = {
+  date: string;
+  time: string;
+  pid: number;
+  tid: number;
+  level: LogLevel;
+  tag: string;
+  message: string;
+};
+
+type ConsoleEvent =
+  | { type: "Opened"; session: string; device_id: string; kind: string }
+  | { type: "Output"; session: string; data: string }
+  | { type: "Log"; session: string; line: string; entry: LogcatEntry | null }
+  | { type: "Dropped"; session: string; count: number }
+  | { type: "Closed"; session: string; error: string | null }
+  | { type: "Error"; session: string | null; message: string };
+
+const SHELL_SESSION = "shell";
+const LOGCAT_SESSION = "logcat";
+// older output is dropped from the view
+const MAX_SHELL_CHARS = 200000;
+const MAX_LOG_LINES = 2000;
+
+const LEVEL_COLORS: Record<LogLevel, string> = {
+  V: "default",
+  D: "blue",
+  I: "green",
+  W: "orange",
+  E: "red",
+  F: "magenta",
+};
+
+type LogLine = { key: number; line: string; entry: LogcatEntry | null };
+
+export default function AdbConsole() {
+  const { t } = useTranslation();
+  const messageApi = useMessageContext();
+  const adbDevices = useAppSelector((state) => state.other.adbDevices);
+  const onlineDevices = useMemo(
+    () => adbDevices.filter((d) => d.status === "device"),
+    [adbDevices],
+  );
+
+  const wsRef = useRef<WebSocket | null>(null);
+  const logKeyRef = useRef(0);
+  const shellOutputRef = useRef<HTMLPreElement | null>(null);
+  const logOutputRef = useRef<HTMLDivElement | null>(null);
+
+  const [deviceId, setDeviceId] = useState<string>();
+  const [connected, setConnected] = useState(false);
+  const [openSessions, setOpenSessions] = useState<string[]>([]);
+  const [shellOutput, setShellOutput] = useState("");
+  const [shellInput, setShellInput] = useState("");
+  const [logLines, setLogLines] = useState<LogLine[]>([]);
+  const [logTags, setLogTags] = useState<string[]>([]);
+  const [logLevel, setLogLevel] = useState<LogLevel>("V");
+  const [logContains, setLogContains] = useState("");
+
+  useEffect(() => {
+    if (
+      onlineDevices.length > 0 &&
+      !onlineDevices.some((d) => d.id === deviceId)
+    ) {
+      setDeviceId(onlineDevices[0].id);
+    }
+  }, [onlineDevices]);
+
+  useEffect(() => {
+    const protocol = window.location.protocol === "https:" ? "wss:" : "ws:";
+    const ws = new WebSocket(
+      withApiToken(`${protocol}//${window.location.host}/api/ws/adb`),
+    );
+    ws.onopen = () => setConnected(true);
+    ws.onclose = () => {
+      setConnected(false);
+      setOpenSessions([]);
+    };
+    ws.onmessage = (event) => {
+      let msg: ConsoleEvent;
+      try {
+        msg = JSON.parse(event.data);
+      } catch {
+        return;
+      }
+      handleEvent(msg);
+    };
+    wsRef.current = ws;
+    return () => ws.close();
+  }, []);
+
+  useEffect(() => {
+    const el = shellOutputRef.current;
+    if (el) el.scrollTop = el.scrollHeight;
+  }, [shellOutput]);
+
+  useEffect(() => {
+    const el = logOutputRef.current;
+    if (el) el.scrollTop = el.scrollHeight;
+  }, [logLines]);
+
+  function appendLog(line: string, entry: LogcatEntry | null) {
+    const key = logKeyRef.current++;
+    setLogLines((lines) =>
+      [...lines, { key, line, entry }].slice(-MAX_LOG_LINES),
+    );
+  }
+
+  function handleEvent(msg: ConsoleEvent) {
+    switch (msg.type) {
+      case "Opened":
+        setOpenSessions((sessions) => [...sessions, msg.session]);
+        break;
+      case "Output":
+        setShellOutput((output) =>
+          (output + msg.data).slice(-MAX_SHELL_CHARS),
+        );
+        break;
+      case "Log":
+        appendLog(msg.line, msg.entry);
+        break;
+      case "Dropped":
+        appendLog(t("devices.console.dropped", { count: msg.count }), null);
+        break;
+      case "Closed":
+        setOpenSessions((sessions) =>
+          sessions.filter((session) => session !== msg.session),
+        );
+        if (msg.error) messageApi?.error(msg.error);
+        break;
+      case "Error":
+        messageApi?.error(msg.message);
+        break;
+    }
+  }
+
+  function send(request: Record<string, unknown>) {
+    if (wsRef.current?.readyState !== WebSocket.OPEN) {
+      messageApi?.error(t("devices.console.disconnected"));
+      return;
+    }
+    wsRef.current.send(JSON.stringify(request));
+  }
+
+  function toggleShell() {
+    if (openSessions.includes(SHELL_SESSION)) {
+      send({ type: "Close", session: SHELL_SESSION });
+    } else if (deviceId) {
+      setShellOutput("");
+      send({ type: "OpenShell", session: SHELL_SESSION, device_id: deviceId });
+    }
+  }
+
+  function sendShellInput() {
+    send({ type: "Input", session: SHELL_SESSION, data: `${shellInput}\n` });
+    setShellInput("");
+  }
+
+  function toggleLogcat() {
+    if (openSessions.includes(LOGCAT_SESSION)) {
+      send({ type: "Close", session: LOGCAT_SESSION });
+    } else if (deviceId) {
+      setLogLines([]);
+      send({
+        type: "OpenLogcat",
+        session: LOGCAT_SESSION,
+        device_id: deviceId,
+        tags: logTags,
+        level: logLevel,
+        contains: logContains || null,
+        tail: 200,
+      });
+    }
+  }
+
+  const shellOpen = openSessions.includes(SHELL_SESSION);
+  const logcatOpen = openSessions.includes(LOGCAT_SESSION);
+
+  return (
+    <Flex vertical gap={12}>
+      <Space>
+        <Select
+          className="w-60"
+          placeholder={t("devices.console.device")}
+          value={deviceId}
+          onChange={setDeviceId}
+          options={onlineDevices.map((d) => ({ value: d.id, label: d.id }))}
+        />
+        {!connected && (
+          <Tag color="red">{t("devices.console.disconnected")}</Tag>
+        )}
+      </Space>
+      <Tabs
+        items={[
+          {
+            key: "shell",
+            label: t("devices.console.shell"),
+            children: (
+              <Flex vertical gap={8}>
+                <pre
+                  ref={shellOutputRef}
+                  className="h-80 overflow-auto scrollbar m-0 p-2 font-mono text-xs bg-black text-white rounded"
+                >
+                  {shellOutput}
+                </pre>
+                <Space.Compact>
+                  <Button
+                    type={shellOpen ? "default" : "primary"}
+                    disabled={!deviceId || !connected}
+                    onClick={toggleShell}
+                  >
+                    {shellOpen
+                      ? t("devices.console.close")
+                      : t("devices.console.open")}
+                  </Button>
+                  <Input
+                    className="font-mono"
+                    disabled={!shellOpen}
+                    placeholder={t("devices.console.inputPlaceholder")}
+                    value={shellInput}
+                    onChange={(e) => setShellInput(e.target.value)}
+                    onPressEnter={sendShellInput}
+                  />
+                </Space.Compact>
+              </Flex>
+            ),
+          },
+          {
+            key: "logcat",
+            label: "Logcat",
+            children: (
+              <Flex vertical gap={8}>
+                <Space wrap>
+                  <Select
+                    className="min-w-60"
+                    mode="tags"
+                    placeholder={t("devices.console.tags")}
+                    value={logTags}
+                    onChange={setLogTags}
+                    disabled={logcatOpen}
+                  />
+                  <Select<LogLevel>
+                    className="w-32"
+                    value={logLevel}
+                    onChange={setLogLevel}
+                    disabled={logcatOpen}
+                    options={(["V", "D", "I", "W", "E", "F"] as const).map(
+                      (level) => ({
+                        value: level,
+                        label: t(`devices.console.level${level}`),
+                      }),
+                    )}
+                  />
+                  <Input
+                    className="w-60"
+                    placeholder={t("devices.console.contains")}
+                    value={logContains}
+                    onChange={(e) => setLogContains(e.target.value)}
+                    disabled={logcatOpen}
+                  />
+                  <Button
+                    type={logcatOpen ? "default" : "primary"}
+                    disabled={!deviceId || !connected}
+                    onClick={toggleLogcat}
+                  >
+                    {logcatOpen
+                      ? t("devices.console.stop")
+                      : t("devices.console.start")}
+                  </Button>
+                  <Button onClick={() => setLogLines([])}>
+                    {t("devices.console.clear")}
+                  </Button>
+                </Space>
+                <div
+                  ref={logOutputRef}
+                  className="h-80 overflow-auto scrollbar font-mono text-xs"
+                >
+                  {logLines.map(({ key, line, entry }) =>
+                    entry ? (
+                      <div key={key}>
+                        <Tag color={LEVEL_COLORS[entry.level]}>
+                          {entry.level}
+                        </Tag>
+                        {entry.time} {entry.pid} {entry.tag}: {entry.message}
+                      </div>
+                    ) : (
+                      <div key={key} className="opacity-60">
+                        {line}
+                      </div>
+                    ),
+                  )}
+                </div>
+              </Flex>
+            ),
+          },
+        ]}
+      />
+    </Flex>
+  );
+}
@@ -0,0 +1,166 @@
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { Button, Checkbox, Flex, Input, Popconfirm, Select } from "antd";
+import { ItemBox, ItemBoxContainer } from "./common/ItemBox";
+import { requestGet, requestPost } from "../utils";
+import { useMessageContext } from "../hooks";
+import { useAppSelector } from "../store/store";
+
+export default function ConfigProfiles() {
+  const { t } = useTranslation();
+  const messageApi = useMessageContext();
+  const activeProfile = useAppSelector(
+    (state) => state.localConfig.activeProfile
+  );
+  const [profiles, setProfiles] = useState<string[]>([]);
+  const [selected, setSelected] = useState<string | undefined>();
+  const [newName, setNewName] = useState("");
+  const [overwrite, setOverwrite] = useState(false);
+
+  async function loadProfiles() {
+    try {
+      const res = await requestGet("/api/config/profiles");
+      setProfiles(res.data.profiles);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+  }
+
+  useEffect(() => {
+    loadProfiles();
+  }, []);
+
+  useEffect(() => {
+    if (activeProfile) setSelected(activeProfile);
+  }, [activeProfile]);
+
+  async function profileAction(action: string, name: string) {
+    try {
+      const res = await requestPost(`/api/config/profiles/${action}`, { name });
+      messageApi?.success(res.message);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+    loadProfiles();
+  }
+
+  async function exportBundle() {
+    try {
+      const res = await requestGet("/api/config/export_bundle");
+      const blob = new Blob([JSON.stringify(res.data, null, 2)], {
+        type: "application/json",
+      });
+      const url = URL.createObjectURL(blob);
+      const a = document.createElement("a");
+      a.href = url;
+      a.download = "scrcpy-mask-bundle.json";
+      a.click();
+      URL.revokeObjectURL(url);
+      // names the mapping files that couldn't be read, if any
+      messageApi?.success(res.message);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+  }
+
+  function importBundle() {
+    const input = document.createElement("input");
+    input.type = "file";
+    input.accept = ".json,application/json";
+    input.onchange = async () => {
+      const file = input.files?.[0];
+      if (!file) return;
+      try {
+        const bundle = JSON.parse(await file.text());
+        const res = await requestPost("/api/config/import_bundle", {
+          bundle,
+          overwrite,
+        });
+        messageApi?.success(res.message);
+        if (res.data.skipped.length > 0) {
+          messageApi?.warning(
+            `${t("settings.bundleSkipped")}: ${res.data.skipped.join(", ")}`
+          );
+        }
+      } catch (err: any) {
+        messageApi?.error(err instanceof SyntaxError ? err.message : err);
+      }
+      loadProfiles();
+    };
+    input.click();
+  }
+
+  return (
+    <ItemBoxContainer className="mb-6">
+      <ItemBox
+        label={t("settings.configProfile")}
+        tooltip={t("settings.configProfileTip")}
+      >
+        <Flex gap="small" align="center">
+          <Select
+            className="w-sm"
+            value={selected}
+            placeholder={t("settings.configProfileNone")}
+            options={profiles.map((name) => ({ label: name, value: name }))}
+            onChange={setSelected}
+          />
+          <Button
+            type="primary"
+            disabled={!selected}
+            onClick={() => selected && profileAction("apply", selected)}
+          >
+            {t("settings.configProfileApply")}
+          </Button>
+          <Popconfirm
+            title={t("settings.configProfileDeleteConfirm")}
+            disabled={!selected}
+            onConfirm={() => {
+              if (!selected) return;
+              profileAction("delete", selected);
+              setSelected(undefined);
+            }}
+          >
+            <Button danger disabled={!selected}>
+              {t("settings.configProfileDelete")}
+            </Button>
+          </Popconfirm>
+        </Flex>
+      </ItemBox>
+      <ItemBox label={t("settings.configProfileSave")}>
+        <Flex gap="small" align="center">
+          <Input
+            className="w-sm"
+            placeholder={t("settings.configProfileName")}
+            value={newName}
+            onChange={(e) => setNewName(e.target.value)}
+          />
+          <Button
+            type="primary"
+            disabled={!newName.trim()}
+            onClick={() => {
+              profileAction("save", newName);
+              setNewName("");
+            }}
+          >
+            {t("settings.configProfileSaveCurrent")}
+          </Button>
+        </Flex>
+      </ItemBox>
+      <ItemBox
+        label={t("settings.configBundle")}
+        tooltip={t("settings.configBundleTip")}
+      >
+        <Flex gap="small" align="center">
+          <Button onClick={exportBundle}>{t("settings.configBundleExport")}</Button>
+          <Button onClick={importBundle}>{t("settings.configBundleImport")}</Button>
+          <Checkbox
+            checked={overwrite}
+            onChange={(e) => setOverwrite(e.target.checked)}
+          >
+            {t("settings.configBundleOverwrite")}
+          </Checkbox>
+        </Flex>
+      </ItemBox>
+    </ItemBoxContainer>
+  );
+}
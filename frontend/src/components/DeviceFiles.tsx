@@ -0,0 +1,308 @@
+import {
+  Button,
+  Flex,
+  Input,
+  Modal,
+  Popconfirm,
+  Space,
+  Table,
+  Upload,
+  type TableProps,
+} from "antd";
+import {
+  ArrowUpOutlined,
+  DeleteOutlined,
+  DownloadOutlined,
+  FileOutlined,
+  FolderOutlined,
+  InboxOutlined,
+  LinkOutlined,
+  ReloadOutlined,
+} from "@ant-design/icons";
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import IconButton from "./common/IconButton";
+import { useMessageContext } from "../hooks";
+import { requestGet, requestPost, requestUpload } from "../utils";
+
+type DeviceFileEntry = {
+  name: string;
+  kind: "file" | "dir" | "link" | "other";
+  size: number;
+  permissions: string;
+  modified: string;
+  link_target: string | null;
+};
+
+const DEFAULT_PATH = "/sdcard/Download";
+
+function joinPath(dir: string, name: string) {
+  return `${dir.replace(/\/+$/, "")}/${name}`;
+}
+
+function parentPath(path: string) {
+  const trimmed = path.replace(/\/+$/, "");
+  const index = trimmed.lastIndexOf("/");
+  return index <= 0 ? "/" : trimmed.slice(0, index);
+}
+
+function formatSize(size: number) {
+  if (size < 1024) return `${size} B`;
+  if (size < 1024 * 1024) return `${(size / 1024).toFixed(1)} KB`;
+  if (size < 1024 * 1024 * 1024)
+    return `${(size / 1024 / 1024).toFixed(1)} MB`;
+  return `${(size / 1024 / 1024 / 1024).toFixed(1)} GB`;
+}
+
+export default function DeviceFiles({
+  deviceId,
+  onCancel,
+}: {
+  deviceId: string | null;
+  onCancel: () => void;
+}) {
+  const { t } = useTranslation();
+  const messageApi = useMessageContext();
+  const [path, setPath] = useState(DEFAULT_PATH);
+  const [pathInput, setPathInput] = useState(DEFAULT_PATH);
+  const [entries, setEntries] = useState<DeviceFileEntry[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [uninstallPackage, setUninstallPackage] = useState("");
+
+  async function loadDir(dir: string) {
+    if (deviceId === null) return;
+    setLoading(true);
+    try {
+      const res = await requestPost<{
+        path: string;
+        entries: DeviceFileEntry[];
+      }>("/api/device/files/list", { device_id: deviceId, path: dir });
+      setPath(res.data.path);
+      setPathInput(res.data.path);
+      setEntries(res.data.entries);
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+    setLoading(false);
+  }
+
+  useEffect(() => {
+    if (deviceId !== null) loadDir(path);
+  }, [deviceId]);
+
+  async function download(entry: DeviceFileEntry) {
+    if (deviceId === null) return;
+    try {
+      const res = await requestGet<Blob>(
+        "/api/device/files/pull",
+        { device_id: deviceId, path: joinPath(path, entry.name) },
+        "blob",
+      );
+      const url = URL.createObjectURL(res.data);
+      const a = document.createElement("a");
+      a.href = url;
+      a.download = entry.name;
+      a.click();
+      URL.revokeObjectURL(url);
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  async function remove(entry: DeviceFileEntry) {
+    if (deviceId === null) return;
+    try {
+      const res = await requestPost("/api/device/files/delete", {
+        device_id: deviceId,
+        path: joinPath(path, entry.name),
+      });
+      messageApi?.success(res.message);
+      await loadDir(path);
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  // like dropping onto the mask, apks are installed and other files pushed,
+  // their progress shows up through the device transfer notifications
+  async function upload(file: File) {
+    if (deviceId === null) return;
+    try {
+      if (file.name.toLowerCase().endsWith(".apk")) {
+        await requestUpload("/api/device/apk/install", file, {
+          device_id: deviceId,
+          name: file.name,
+        });
+      } else {
+        await requestUpload("/api/device/files/push", file, {
+          device_id: deviceId,
+          dir: path,
+          name: file.name,
+        });
+      }
+      await loadDir(path);
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  async function uninstall() {
+    if (deviceId === null || !uninstallPackage) return;
+    try {
+      await requestPost("/api/device/apk/uninstall", {
+        device_id: deviceId,
+        package: uninstallPackage,
+      });
+      setUninstallPackage("");
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  const columns: TableProps<DeviceFileEntry>["columns"] = [
+    {
+      title: t("devices.files.name"),
+      key: "name",
+      render: (_, record) => {
+        const icon =
+          record.kind === "dir" ? (
+            <FolderOutlined />
+          ) : record.kind === "link" ? (
+            <LinkOutlined />
+          ) : (
+            <FileOutlined />
+          );
+        const openable = record.kind === "dir" || record.kind === "link";
+        return (
+          <Space>
+            {icon}
+            {openable ? (
+              <a onClick={() => loadDir(joinPath(path, record.name))}>
+                {record.name}
+              </a>
+            ) : (
+              record.name
+            )}
+            {record.link_target && (
+              <span className="opacity-60">→ {record.link_target}</span>
+            )}
+          </Space>
+        );
+      },
+    },
+    {
+      title: t("devices.files.size"),
+      key: "size",
+      width: 100,
+      render: (_, record) =>
+        record.kind === "file" ? formatSize(record.size) : "",
+    },
+    {
+      title: t("devices.files.modified"),
+      dataIndex: "modified",
+      key: "modified",
+      width: 160,
+    },
+    {
+      title: t("devices.controlledDevices.action"),
+      key: "action",
+      align: "center",
+      width: 1,
+      render: (_, record) => (
+        <Space size="middle" className="text-4">
+          {record.kind === "file" && (
+            <IconButton
+              color="info"
+              size={18}
+              tooltip={t("devices.files.download")}
+              icon={<DownloadOutlined />}
+              onClick={() => download(record)}
+            />
+          )}
+          <Popconfirm
+            title={t("devices.files.deleteTitle")}
+            description={t("devices.files.deletePrompt", {
+              name: record.name,
+            })}
+            onConfirm={() => remove(record)}
+            okText={t("mappings.home.confirmYes")}
+            cancelText={t("mappings.home.confirmNo")}
+          >
+            <IconButton
+              color="error"
+              size={18}
+              tooltip={t("devices.files.delete")}
+              icon={<DeleteOutlined />}
+            />
+          </Popconfirm>
+        </Space>
+      ),
+    },
+  ];
+
+  return (
+    <Modal
+      title={`${t("devices.files.title")}: ${deviceId ?? ""}`}
+      open={deviceId !== null}
+      onCancel={onCancel}
+      footer={null}
+      width={860}
+    >
+      <Flex vertical gap={12}>
+        <Space.Compact>
+          <Button
+            icon={<ArrowUpOutlined />}
+            onClick={() => loadDir(parentPath(path))}
+          />
+          <Input
+            value={pathInput}
+            onChange={(e) => setPathInput(e.target.value)}
+            onPressEnter={() => loadDir(pathInput)}
+          />
+          <Button icon={<ReloadOutlined />} onClick={() => loadDir(path)} />
+        </Space.Compact>
+        <Table<DeviceFileEntry>
+          size="small"
+          rowKey="name"
+          loading={loading}
+          columns={columns}
+          dataSource={entries}
+          pagination={{ pageSize: 10 }}
+        />
+        <Upload.Dragger
+          multiple
+          showUploadList={false}
+          beforeUpload={(file) => {
+            upload(file);
+            return false;
+          }}
+        >
+          <p className="ant-upload-drag-icon">
+            <InboxOutlined />
+          </p>
+          <p className="ant-upload-text">{t("devices.files.dropHint")}</p>
+        </Upload.Dragger>
+        <Space.Compact>
+          <Input
+            placeholder={t("devices.files.packagePlaceholder")}
+            value={uninstallPackage}
+            onChange={(e) => setUninstallPackage(e.target.value)}
+          />
+          <Popconfirm
+            title={t("devices.files.uninstallPrompt", {
+              package: uninstallPackage,
+            })}
+            onConfirm={uninstall}
+            okText={t("mappings.home.confirmYes")}
+            cancelText={t("mappings.home.confirmNo")}
+            disabled={!uninstallPackage}
+          >
+            <Button danger disabled={!uninstallPackage}>
+              {t("devices.files.uninstall")}
+            </Button>
+          </Popconfirm>
+        </Space.Compact>
+      </Flex>
+    </Modal>
+  );
+}
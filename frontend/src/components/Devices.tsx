@@ -33,6 +33,7 @@ import {
   DisconnectOutlined,
   DownOutlined,
   EnterOutlined,
+  FolderOpenOutlined,
   InfoCircleOutlined,
   LinkOutlined,
   ReloadOutlined,
@@ -42,6 +43,8 @@ import {
   UpOutlined,
 } from "@ant-design/icons";
 import IconButton from "./common/IconButton";
+import AdbConsole from "./AdbConsole";
+import DeviceFiles from "./DeviceFiles";
 import { useEffect, useMemo, useRef, useState } from "react";
 import { ItemBox, ItemBoxContainer } from "./common/ItemBox";
 import { setAdbDevices, setControlledDevices, setIsLoading } from "../store/other";
@@ -72,6 +75,7 @@ function ControlledDevices({
     null,
   );
   const [startAppLoading, setStartAppLoading] = useState(false);
+  const [filesDevice, setFilesDevice] = useState<string | null>(null);
   const [startAppSubmitting, setStartAppSubmitting] = useState(false);
   const [startAppForceStop, setStartAppForceStop] = useState(false);
   const [apps, setApps] = useState<AndroidApp[]>([]);
@@ -450,6 +454,13 @@ function ControlledDevices({
             icon={<AppstoreOutlined />}
             onClick={() => openStartAppModal(record)}
           />
+          <IconButton
+            tooltip={t("devices.files.title")}
+            size={18}
+            color="info"
+            icon={<FolderOpenOutlined />}
+            onClick={() => setFilesDevice(record.device_id)}
+          />
           <IconButton
             tooltip={t("devices.controlledDevices.actionClose")}
             size={18}
@@ -470,6 +481,10 @@ function ControlledDevices({
         columns={columns}
         dataSource={controlledDevices}
       />
+      <DeviceFiles
+        deviceId={filesDevice}
+        onCancel={() => setFilesDevice(null)}
+      />
       <Modal
         title={t("devices.startApp.title")}
         open={startAppDevice !== null}
@@ -543,6 +558,7 @@ function OtherDevices({
 
   const [isVideo, setIsVideo] = videoState;
   const [isAudio, setIsAudio] = audioState;
+  const [filesDevice, setFilesDevice] = useState<string | null>(null);
 
   async function controlDevice(device: AdbDevice) {
     dispatch(setIsLoading(true));
@@ -601,18 +617,31 @@ function OtherDevices({
             icon={<LinkOutlined />}
             onClick={() => controlDevice(record)}
           />
+          <IconButton
+            color="info"
+            tooltip={t("devices.files.title")}
+            size={18}
+            icon={<FolderOpenOutlined />}
+            onClick={() => setFilesDevice(record.id)}
+          />
         </Space>
       ),
     },
   ];
 
   return (
-    <Table<AdbDevice>
-      rowKey={(record) => record.id}
-      pagination={{ pageSize: 5 }}
-      columns={columns}
-      dataSource={otherDevices}
-    />
+    <>
+      <Table<AdbDevice>
+        rowKey={(record) => record.id}
+        pagination={{ pageSize: 5 }}
+        columns={columns}
+        dataSource={otherDevices}
+      />
+      <DeviceFiles
+        deviceId={filesDevice}
+        onCancel={() => setFilesDevice(null)}
+      />
+    </>
   );
 }
 
@@ -793,6 +822,10 @@ export default function Devices() {
           audioState={audioState}
         />
       </section>
+      <section className="mt-4">
+        <h2 className="title-with-line">{t("devices.console.title")}</h2>
+        <AdbConsole />
+      </section>
     </div>
   );
 }
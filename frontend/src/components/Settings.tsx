@@ -1,3 +1,4 @@
+import { useEffect, useState } from "react";
 import { useTranslation } from "react-i18next";
 import { useAppDispatch, useAppSelector } from "../store/store";
 import { ItemBox, ItemBoxContainer } from "./common/ItemBox";
@@ -30,6 +31,7 @@ import {
   setVideoBitRate,
   setVideoMaxSize,
   setVideoMaxFps,
+  setAdaptiveVideo,
   setDisplayId,
   setNewDisplayEnabled,
   setNewDisplayUseMainSize,
@@ -43,6 +45,10 @@ import {
   setAudioBitRate,
   setAudioSource,
   setAudioDup,
+  setAudioBufferMs,
+  setAudioOutputDevice,
+  setAudioGain,
+  setAudioMuted,
   setStayAwake,
   setScreenOffTimeout,
   setPowerOffOnClose,
@@ -52,9 +58,11 @@ import {
   setShowUpdateDialog,
   setUpdateInfo,
 } from "../store/other";
-import { requestGet } from "../utils";
+import { requestGet, requestPost, withApiToken } from "../utils";
 import i18n, { languageOptions } from "../i18n";
 import { useMessageContext } from "../hooks";
+import WebAuthSettings from "./WebAuthSettings";
+import ConfigProfiles from "./ConfigProfiles";
 import {
   BilibiliFilled,
   CloudSyncOutlined,
@@ -79,6 +87,9 @@ const audioSourceOptions = ["OUTPUT", "PLAYBACK", "MIC"].map((v) => ({
 }));
 
 const audioBitRateMin = 16000;
+const audioGainMax = 4;
+const audioBufferMsMin = 10;
+const audioBufferMsMax = 1000;
 
 const webBindAddrOptions = [
   { value: "127.0.0.1", label: "127.0.0.1" },
@@ -91,6 +102,24 @@ export default function Settings() {
   const messageApi = useMessageContext();
   const localConfig = useAppSelector((state) => state.localConfig);
   const updateInfo = useAppSelector((state) => state.other.updateInfo);
+  const audioStats = useAppSelector((state) => state.other.audioStats);
+  const adaptiveVideoStatus = useAppSelector(
+    (state) => state.other.adaptiveVideoStatus
+  );
+  const adaptiveVideo = localConfig.adaptiveVideo;
+  const audioBufferKey =
+    localConfig.audioCodec.toLowerCase() as keyof typeof localConfig.audioBufferMs;
+  const [audioOutputDevices, setAudioOutputDevices] = useState<string[]>([]);
+  const [audioCapturing, setAudioCapturing] = useState(false);
+  const audioStreamUrl = withApiToken(
+    `${window.location.origin}/api/audio/stream.wav`
+  );
+
+  useEffect(() => {
+    requestGet("/api/audio/status")
+      .then((res) => setAudioCapturing(res.data.capture !== null))
+      .catch(() => {});
+  }, []);
 
   async function loadLocalConfig() {
     dispatch(setIsLoading(true));
@@ -104,6 +133,39 @@ export default function Settings() {
     dispatch(setIsLoading(false));
   }
 
+  async function loadAudioOutputDevices() {
+    try {
+      const res = await requestGet("/api/config/audio_output_devices");
+      setAudioOutputDevices(res.data.devices);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+  }
+
+  async function reconfigureVideo() {
+    try {
+      const res = await requestPost("/api/device/reconfigure_video");
+      messageApi?.success(res.message);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+  }
+
+  async function toggleAudioCapture() {
+    try {
+      const res = audioCapturing
+        ? await requestPost("/api/audio/capture/stop")
+        : await requestPost("/api/audio/capture/start", {});
+      setAudioCapturing(!audioCapturing);
+      messageApi?.success(res.message);
+    } catch (err: any) {
+      messageApi?.error(err);
+      // the capture may have stopped on its own, e.g. after a write error
+      const res = await requestGet("/api/audio/status").catch(() => null);
+      if (res) setAudioCapturing(res.data.capture !== null);
+    }
+  }
+
   async function openDataPath() {
     dispatch(setIsLoading(true));
     try {
@@ -174,6 +236,8 @@ export default function Settings() {
             />
           </ItemBox>
         </ItemBoxContainer>
+        <h3 className="title-with-line-sub">{t("settings.title.profiles")}</h3>
+        <ConfigProfiles />
         <h3 className="title-with-line-sub">{t("settings.title.mask")}</h3>
         <ItemBoxContainer className="mb-6">
           <ItemBox label={t("settings.alwaysOnTop")}>
@@ -325,6 +389,94 @@ export default function Settings() {
               onChange={(v) => v !== null && dispatch(setVideoMaxFps(v))}
             />
           </ItemBox>
+          <ItemBox
+            label={t("settings.videoReconfigure")}
+            tooltip={t("settings.videoReconfigureTip")}
+          >
+            <Button onClick={reconfigureVideo}>
+              {t("settings.videoReconfigureApply")}
+            </Button>
+          </ItemBox>
+          <ItemBox
+            label={t("settings.adaptiveVideo")}
+            tooltip={t("settings.adaptiveVideoTip")}
+          >
+            <Switch
+              checked={adaptiveVideo.enabled}
+              onChange={(v) =>
+                dispatch(setAdaptiveVideo({ ...adaptiveVideo, enabled: v }))
+              }
+            />
+          </ItemBox>
+          {adaptiveVideo.enabled && (
+            <>
+              <ItemBox label={t("settings.adaptiveVideoBitRate")}>
+                <Space>
+                  <InputNumber
+                    controls={false}
+                    min={100000}
+                    max={adaptiveVideo.max_bit_rate}
+                    suffix="bps"
+                    value={adaptiveVideo.min_bit_rate}
+                    onChange={(v) =>
+                      v !== null &&
+                      dispatch(
+                        setAdaptiveVideo({ ...adaptiveVideo, min_bit_rate: v })
+                      )
+                    }
+                  />
+                  <InputNumber
+                    controls={false}
+                    min={adaptiveVideo.min_bit_rate}
+                    suffix="bps"
+                    value={adaptiveVideo.max_bit_rate}
+                    onChange={(v) =>
+                      v !== null &&
+                      dispatch(
+                        setAdaptiveVideo({ ...adaptiveVideo, max_bit_rate: v })
+                      )
+                    }
+                  />
+                </Space>
+              </ItemBox>
+              <ItemBox label={t("settings.adaptiveVideoFps")}>
+                <Space>
+                  <InputNumber
+                    controls={false}
+                    min={1}
+                    max={adaptiveVideo.max_fps}
+                    value={adaptiveVideo.min_fps}
+                    onChange={(v) =>
+                      v !== null &&
+                      dispatch(setAdaptiveVideo({ ...adaptiveVideo, min_fps: v }))
+                    }
+                  />
+                  <InputNumber
+                    controls={false}
+                    min={adaptiveVideo.min_fps}
+                    value={adaptiveVideo.max_fps}
+                    onChange={(v) =>
+                      v !== null &&
+                      dispatch(setAdaptiveVideo({ ...adaptiveVideo, max_fps: v }))
+                    }
+                  />
+                </Space>
+              </ItemBox>
+              {adaptiveVideoStatus && (
+                <ItemBox label={t("settings.adaptiveVideoStatus")}>
+                  <Typography.Text type="secondary">
+                    {t("settings.adaptiveVideoStatusText", {
+                      health: t(
+                        `settings.adaptiveVideoHealth.${adaptiveVideoStatus.health}`
+                      ),
+                      bitRate: (adaptiveVideoStatus.bit_rate / 1000000).toFixed(1),
+                      fps: adaptiveVideoStatus.max_fps,
+                    })}
+                  </Typography.Text>
+                </ItemBox>
+              )}
+            </>
+          )}
           <ItemBox
             label={t("settings.displayId")}
             tooltip={t("settings.displayIdTip")}
@@ -439,6 +591,96 @@ export default function Settings() {
               onChange={(v) => dispatch(setAudioDup(v))}
             />
           </ItemBox>
+          <ItemBox
+            label={t("settings.audioBufferMs")}
+            tooltip={t("settings.audioBufferMsTip")}
+          >
+            <InputNumber
+              className="w-sm"
+              controls={false}
+              min={audioBufferMsMin}
+              max={audioBufferMsMax}
+              suffix="ms"
+              value={localConfig.audioBufferMs[audioBufferKey]}
+              onChange={(v) =>
+                v !== null &&
+                dispatch(
+                  setAudioBufferMs({
+                    ...localConfig.audioBufferMs,
+                    [audioBufferKey]: v,
+                  }),
+                )
+              }
+            />
+          </ItemBox>
+          {audioStats && (
+            <ItemBox label={t("settings.audioStats")}>
+              <Typography.Text type="secondary">
+                {t("settings.audioStatsText", {
+                  codec: audioStats.codec,
+                  buffered: audioStats.buffered_ms.toFixed(0),
+                  target: audioStats.target_ms.toFixed(0),
+                  underflows: audioStats.underflow_count,
+                  dropped: audioStats.dropped_samples,
+                  compensation: (audioStats.compensation_rate * 100).toFixed(2),
+                })}
+              </Typography.Text>
+            </ItemBox>
+          )}
+          <ItemBox
+            label={t("settings.audioOutputDevice")}
+            tooltip={t("settings.audioOutputDeviceTip")}
+          >
+            <Select
+              className="w-sm"
+              value={localConfig.audioOutputDevice}
+              options={[
+                { value: "", label: t("settings.audioOutputDeviceDefault") },
+                ...audioOutputDevices.map((v) => ({ value: v, label: v })),
+              ]}
+              onOpenChange={(open) => open && loadAudioOutputDevices()}
+              onChange={(v) => dispatch(setAudioOutputDevice(v))}
+            />
+          </ItemBox>
+          <ItemBox label={t("settings.audioGain")}>
+            <Slider
+              className="w-sm"
+              min={0}
+              max={audioGainMax}
+              step={0.05}
+              disabled={localConfig.audioMuted}
+              value={localConfig.audioGain}
+              tooltip={{ formatter: (v) => `${Math.round((v ?? 0) * 100)}%` }}
+              onChange={(v) => dispatch(setAudioGain(v))}
+            />
+          </ItemBox>
+          <ItemBox label={t("settings.audioMuted")}>
+            <Switch
+              checked={localConfig.audioMuted}
+              onChange={(v) => dispatch(setAudioMuted(v))}
+            />
+          </ItemBox>
+          <ItemBox
+            label={t("settings.audioCapture")}
+            tooltip={t("settings.audioCaptureTip")}
+          >
+            <Button onClick={toggleAudioCapture} danger={audioCapturing}>
+              {audioCapturing
+                ? t("settings.audioCaptureStop")
+                : t("settings.audioCaptureStart")}
+            </Button>
+          </ItemBox>
+          <ItemBox
+            label={t("settings.audioStream")}
+            tooltip={t("settings.audioStreamTip")}
+          >
+            <Typography.Text
+              className="font-mono break-all"
+              copyable={{ text: audioStreamUrl }}
+            >
+              {audioStreamUrl}
+            </Typography.Text>
+          </ItemBox>
         </ItemBoxContainer>
 
         <h3 className="title-with-line-sub">{t("settings.title.advance")}</h3>
@@ -501,6 +743,7 @@ export default function Settings() {
             </Button>
           </ItemBox>
         </ItemBoxContainer>
+        <WebAuthSettings />
       </section>
       <section>
         <h2 className="title-with-line">{t("settings.about.title")}</h2>
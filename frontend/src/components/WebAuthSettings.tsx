@@ -0,0 +1,203 @@
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import {
+  Button,
+  Checkbox,
+  Flex,
+  Input,
+  Popconfirm,
+  Space,
+  Switch,
+  Tag,
+  Typography,
+} from "antd";
+import { ItemBox, ItemBoxContainer } from "./common/ItemBox";
+import { getApiToken, requestGet, requestPost, setApiToken } from "../utils";
+import { useMessageContext } from "../hooks";
+
+type AuthScope = "read" | "control" | "config";
+
+interface AuthStatus {
+  enabled: boolean;
+  allow_loopback: boolean;
+  authenticated: boolean;
+  token_name: string | null;
+  scopes: AuthScope[];
+}
+
+interface TokenInfo {
+  name: string;
+  scopes: AuthScope[];
+  hint: string;
+}
+
+const scopeOptions: AuthScope[] = ["control", "config"];
+
+export default function WebAuthSettings() {
+  const { t } = useTranslation();
+  const messageApi = useMessageContext();
+  const [browserToken, setBrowserToken] = useState(getApiToken());
+  const [status, setStatus] = useState<AuthStatus | null>(null);
+  const [tokens, setTokens] = useState<TokenInfo[]>([]);
+  const [newName, setNewName] = useState("");
+  const [newScopes, setNewScopes] = useState<AuthScope[]>([]);
+  const [createdToken, setCreatedToken] = useState("");
+
+  const canConfig = status?.scopes.includes("config") ?? false;
+
+  async function loadStatus() {
+    try {
+      const res = await requestGet<AuthStatus>("/api/auth/status");
+      setStatus(res.data);
+      if (res.data.scopes.includes("config")) {
+        const tokensRes = await requestGet<TokenInfo[]>("/api/auth/tokens");
+        setTokens(tokensRes.data);
+      }
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+  }
+
+  useEffect(() => {
+    loadStatus();
+  }, []);
+
+  function saveBrowserToken() {
+    setApiToken(browserToken.trim());
+    // the WebSocket and cached data were loaded with the previous token
+    window.location.reload();
+  }
+
+  async function updateSettings(enabled: boolean, allowLoopback: boolean) {
+    try {
+      const res = await requestPost("/api/auth/settings", {
+        enabled,
+        allow_loopback: allowLoopback,
+      });
+      messageApi?.success(res.message);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+    loadStatus();
+  }
+
+  async function createToken() {
+    try {
+      const res = await requestPost("/api/auth/tokens/create", {
+        name: newName,
+        scopes: newScopes,
+      });
+      messageApi?.success(res.message);
+      setCreatedToken(res.data.token);
+      setNewName("");
+      setNewScopes([]);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+    loadStatus();
+  }
+
+  async function deleteToken(name: string) {
+    try {
+      const res = await requestPost("/api/auth/tokens/delete", { name });
+      messageApi?.success(res.message);
+    } catch (err: any) {
+      messageApi?.error(err);
+    }
+    loadStatus();
+  }
+
+  return (
+    <ItemBoxContainer className="mb-6">
+      <ItemBox
+        label={t("settings.webAuthBrowserToken")}
+        tooltip={t("settings.webAuthBrowserTokenTip")}
+      >
+        <Space.Compact className="w-md">
+          <Input.Password
+            value={browserToken}
+            onChange={(e) => setBrowserToken(e.target.value)}
+          />
+          <Button type="primary" onClick={saveBrowserToken}>
+            {t("settings.webAuthSaveToken")}
+          </Button>
+        </Space.Compact>
+      </ItemBox>
+      {status && (
+        <>
+          <ItemBox
+            label={t("settings.webAuthEnabled")}
+            tooltip={t("settings.webAuthEnabledTip")}
+          >
+            <Switch
+              checked={status.enabled}
+              disabled={!canConfig}
+              onChange={(v) => updateSettings(v, status.allow_loopback)}
+            />
+          </ItemBox>
+          <ItemBox
+            label={t("settings.webAuthAllowLoopback")}
+            tooltip={t("settings.webAuthAllowLoopbackTip")}
+          >
+            <Switch
+              checked={status.allow_loopback}
+              disabled={!canConfig}
+              onChange={(v) => updateSettings(status.enabled, v)}
+            />
+          </ItemBox>
+        </>
+      )}
+      {canConfig && (
+        <ItemBox label={t("settings.webAuthTokens")}>
+          <Flex vertical gap="small">
+            {tokens.map((token) => (
+              <Flex key={token.name} gap="small" align="center">
+                <Typography.Text strong>{token.name}</Typography.Text>
+                <Typography.Text type="secondary">{token.hint}</Typography.Text>
+                {token.scopes.map((scope) => (
+                  <Tag key={scope}>{t(`settings.webAuthScope.${scope}`)}</Tag>
+                ))}
+                <Popconfirm
+                  title={t("settings.webAuthDeleteTokenConfirm")}
+                  onConfirm={() => deleteToken(token.name)}
+                >
+                  <Button size="small" danger>
+                    {t("settings.webAuthDeleteToken")}
+                  </Button>
+                </Popconfirm>
+              </Flex>
+            ))}
+            <Flex gap="small" align="center">
+              <Input
+                className="w-sm"
+                placeholder={t("settings.webAuthTokenName")}
+                value={newName}
+                onChange={(e) => setNewName(e.target.value)}
+              />
+              <Checkbox.Group
+                options={scopeOptions.map((scope) => ({
+                  label: t(`settings.webAuthScope.${scope}`),
+                  value: scope,
+                }))}
+                value={newScopes}
+                onChange={(v) => setNewScopes(v as AuthScope[])}
+              />
+              <Button
+                type="primary"
+                disabled={!newName.trim()}
+                onClick={createToken}
+              >
+                {t("settings.webAuthCreateToken")}
+              </Button>
+            </Flex>
+            {createdToken && (
+              <Typography.Text copyable={{ text: createdToken }}>
+                {t("settings.webAuthCreatedToken")}: <code>{createdToken}</code>
+              </Typography.Text>
+            )}
+          </Flex>
+        </ItemBox>
+      )}
+    </ItemBoxContainer>
+  );
+}
@@ -13,6 +13,7 @@ import {
   SettingFooter,
   SettingMappingId,
   SettingModal,
+  SettingAnchor,
   SettingNote,
   SettingScriptHooks,
 } from "./Common";
@@ -148,6 +149,10 @@ function Setting({
           bind={config.bind}
           onBindChange={(bind) => onConfigChange((pre) => ({ ...pre, bind }))}
         />
+        <SettingAnchor
+          anchor={config.anchor}
+          onAnchorChange={(anchor) => onConfigChange({ ...config, anchor })}
+        />
         <SettingNote
           note={config.note}
           onNoteChange={(note) => onConfigChange({ ...config, note })}
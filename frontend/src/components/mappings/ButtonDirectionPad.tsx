@@ -27,6 +27,7 @@ import {
   SettingFooter,
   SettingMappingId,
   SettingModal,
+  SettingAnchor,
   SettingNote,
   SettingPointerId,
   SettingScriptHooks,
@@ -519,6 +520,10 @@ function Setting({
             />
           </ItemBox>
         )}
+        <SettingAnchor
+          anchor={config.anchor}
+          onAnchorChange={(anchor) => onConfigChange({ ...config, anchor })}
+        />
         <SettingNote
           note={config.note}
           onNoteChange={(note) => onConfigChange({ ...config, note })}
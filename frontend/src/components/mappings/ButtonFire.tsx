@@ -13,6 +13,7 @@ import {
   SettingFooter,
   SettingMappingId,
   SettingModal,
+  SettingAnchor,
   SettingNote,
   SettingPointerId,
   SettingScriptHooks,
@@ -213,6 +214,10 @@ function Setting({
             </Space.Compact>
           </ItemBox>
         )}
+        <SettingAnchor
+          anchor={config.anchor}
+          onAnchorChange={(anchor) => onConfigChange({ ...config, anchor })}
+        />
         <SettingNote
           note={config.note}
           onNoteChange={(note) => onConfigChange({ ...config, note })}
@@ -13,6 +13,7 @@ import {
   SettingFooter,
   SettingMappingId,
   SettingModal,
+  SettingAnchor,
   SettingNote,
   SettingPointerId,
 } from "./Common";
@@ -432,6 +433,10 @@ function Setting({
             />
           </Space.Compact>
         </ItemBox>
+        <SettingAnchor
+          anchor={config.anchor}
+          onAnchorChange={(anchor) => onConfigChange({ ...config, anchor })}
+        />
         <SettingNote
           note={config.note}
           onNoteChange={(note) => onConfigChange({ ...config, note })}
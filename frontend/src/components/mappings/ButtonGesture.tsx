@@ -0,0 +1,289 @@
+import { useEffect, useMemo, useState } from "react";
+import type {
+  GestureConfig,
+  GestureKind,
+  MappingUpdater,
+  SingleSwipeStrategy,
+} from "./mapping";
+import { Flex, InputNumber, Select, Tooltip, Typography } from "antd";
+import {
+  mappingButtonDragFactory,
+  mappingButtonScaledPresetStyle,
+  mappingButtonTransformStyle,
+} from "./tools";
+import { useAppSelector } from "../../store/store";
+import { ItemBoxContainer, ItemBox } from "../common/ItemBox";
+import {
+  SettingBind,
+  SettingFooter,
+  SettingMappingId,
+  SettingModal,
+  SettingAnchor,
+  SettingNote,
+  SettingPointerId,
+  SettingScriptHooks,
+} from "./Common";
+import { useTranslation } from "react-i18next";
+
+const gestureKinds: GestureKind[] = ["pinch_in", "pinch_out", "rotate"];
+const swipeStrategies: SingleSwipeStrategy[] = [
+  "Linear",
+  "ArcWithCubicEasing",
+  "ArcWithEaseOut",
+  "ArcWithEaseInOut",
+];
+
+export default function ButtonGesture({
+  index,
+  config,
+  originalSize,
+  onConfigChange,
+  onConfigDelete,
+  onConfigCopy,
+}: {
+  index: number;
+  config: GestureConfig;
+  originalSize: { width: number; height: number };
+  onConfigChange: MappingUpdater<GestureConfig>;
+  onConfigDelete: () => void;
+  onConfigCopy: () => void;
+}) {
+  const { t } = useTranslation();
+  const id = `mapping-gesture-${index}`;
+  const bindText = config.bind.length > 0 ? config.bind.join("+") : "???";
+  const className =
+    "rounded-full absolute box-border border-solid border-2 color-text " +
+    (config.bind.length > 0
+      ? "border-text-secondary hover:border-text"
+      : "border-primary hover:border-primary-hover");
+
+  const maskArea = useAppSelector((state) => state.other.maskArea);
+  const [showSetting, setShowSetting] = useState(false);
+
+  const scale = useMemo(() => {
+    return {
+      x: maskArea.width / originalSize.width,
+      y: maskArea.height / originalSize.height,
+    };
+  }, [originalSize, maskArea]);
+
+  const buttonStyle = useMemo(
+    () => mappingButtonScaledPresetStyle(52, maskArea),
+    [maskArea],
+  );
+
+  useEffect(() => {
+    const element = document.getElementById(id);
+    if (element) {
+      element.style.transform = mappingButtonTransformStyle(
+        config.position.x,
+        config.position.y,
+        scale,
+      );
+    }
+  }, [index, config, scale]);
+
+  const handleDrag = mappingButtonDragFactory(
+    maskArea,
+    originalSize,
+    ({ x, y }) => {
+      onConfigChange({
+        ...config,
+        position: {
+          x,
+          y,
+        },
+      });
+    },
+  );
+
+  const handleSetting = (e: React.MouseEvent) => {
+    e.preventDefault();
+    setShowSetting(true);
+  };
+
+  return (
+    <>
+      <SettingModal open={showSetting} onClose={() => setShowSetting(false)}>
+        <Setting
+          config={config}
+          onConfigChange={onConfigChange}
+          onConfigDelete={() => {
+            setShowSetting(false);
+            onConfigDelete();
+          }}
+          onConfigCopy={() => {
+            setShowSetting(false);
+            onConfigCopy();
+          }}
+        />
+      </SettingModal>
+      <Flex
+        id={id}
+        style={buttonStyle}
+        className={className}
+        onMouseDown={handleDrag}
+        onContextMenu={handleSetting}
+        justify="center"
+        align="center"
+        vertical
+      >
+        <Tooltip trigger="click" title={`${config.type}: ${bindText}`}>
+          <Typography.Text ellipsis={true} className="text-2.5 font-bold">
+            {bindText}
+          </Typography.Text>
+        </Tooltip>
+        <Typography.Text className="text-2">
+          {t(`mappings.gesture.kind.${config.kind}`)}
+        </Typography.Text>
+      </Flex>
+    </>
+  );
+}
+
+function Setting({
+  config,
+  onConfigChange,
+  onConfigDelete,
+  onConfigCopy,
+}: {
+  config: GestureConfig;
+  onConfigChange: MappingUpdater<GestureConfig>;
+  onConfigDelete: () => void;
+  onConfigCopy: () => void;
+}) {
+  const { t } = useTranslation();
+
+  return (
+    <div>
+      <h1 className="title-with-line">
+        {t("mappings.gesture.setting.title")}
+      </h1>
+      <ItemBoxContainer className="max-h-70vh overflow-y-auto pr-2 scrollbar">
+        <SettingMappingId id={config.id} />
+        <SettingBind
+          bind={config.bind}
+          onBindChange={(bind) => onConfigChange((pre) => ({ ...pre, bind }))}
+        />
+        <SettingPointerId
+          pointerId={config.pointer_id}
+          onPointerIdChange={(pointerId) =>
+            onConfigChange({ ...config, pointer_id: pointerId })
+          }
+        />
+        <ItemBox label={t("mappings.gesture.setting.secondPointerId")}>
+          <InputNumber
+            className="w-full"
+            value={config.second_pointer_id}
+            min={0}
+            precision={0}
+            onChange={(v) =>
+              v !== null && onConfigChange({ ...config, second_pointer_id: v })
+            }
+          />
+        </ItemBox>
+        <ItemBox label={t("mappings.gesture.setting.kind")}>
+          <Select
+            className="w-full"
+            value={config.kind}
+            options={gestureKinds.map((kind) => ({
+              value: kind,
+              label: t(`mappings.gesture.kind.${kind}`),
+            }))}
+            onChange={(kind) => onConfigChange({ ...config, kind })}
+          />
+        </ItemBox>
+        <ItemBox
+          label={t("mappings.gesture.setting.span")}
+          tooltip={t("mappings.gesture.setting.spanHint")}
+        >
+          <InputNumber
+            className="w-full"
+            value={config.span}
+            min={1}
+            onChange={(v) => v !== null && onConfigChange({ ...config, span: v })}
+          />
+        </ItemBox>
+        {config.kind !== "rotate" && (
+          <ItemBox
+            label={t("mappings.gesture.setting.minSpan")}
+            tooltip={t("mappings.gesture.setting.minSpanHint")}
+          >
+            <InputNumber
+              className="w-full"
+              value={config.min_span}
+              min={0}
+              onChange={(v) =>
+                v !== null && onConfigChange({ ...config, min_span: v })
+              }
+            />
+          </ItemBox>
+        )}
+        <ItemBox
+          label={t("mappings.gesture.setting.angle")}
+          tooltip={t("mappings.gesture.setting.angleHint")}
+        >
+          <InputNumber
+            className="w-full"
+            value={config.angle}
+            min={-360}
+            max={360}
+            onChange={(v) => v !== null && onConfigChange({ ...config, angle: v })}
+          />
+        </ItemBox>
+        {config.kind === "rotate" && (
+          <ItemBox
+            label={t("mappings.gesture.setting.rotation")}
+            tooltip={t("mappings.gesture.setting.rotationHint")}
+          >
+            <InputNumber
+              className="w-full"
+              value={config.rotation}
+              min={-360}
+              max={360}
+              onChange={(v) =>
+                v !== null && onConfigChange({ ...config, rotation: v })
+              }
+            />
+          </ItemBox>
+        )}
+        <ItemBox label={t("mappings.gesture.setting.duration")}>
+          <InputNumber
+            className="w-full"
+            value={config.duration}
+            min={0}
+            onChange={(v) =>
+              v !== null && onConfigChange({ ...config, duration: v })
+            }
+          />
+        </ItemBox>
+        <ItemBox label={t("mappings.gesture.setting.strategy")}>
+          <Select
+            className="w-full"
+            value={config.strategy}
+            options={swipeStrategies.map((strategy) => ({
+              value: strategy,
+              label: strategy,
+            }))}
+            onChange={(strategy) => onConfigChange({ ...config, strategy })}
+          />
+        </ItemBox>
+        <SettingAnchor
+          anchor={config.anchor}
+          onAnchorChange={(anchor) => onConfigChange({ ...config, anchor })}
+        />
+        <SettingNote
+          note={config.note}
+          onNoteChange={(note) => onConfigChange({ ...config, note })}
+        />
+        <SettingScriptHooks
+          scriptHooks={config.script_hooks}
+          onScriptHooksChange={(script_hooks) =>
+            onConfigChange({ ...config, script_hooks })
+          }
+        />
+        <SettingFooter onDelete={onConfigDelete} onCopy={onConfigCopy} />
+      </ItemBoxContainer>
+    </div>
+  );
+}
@@ -28,6 +28,7 @@ import {
   SettingFooter,
   SettingMappingId,
   SettingModal,
+  SettingAnchor,
   SettingNote,
   SettingPointerId,
   SettingScriptHooks,
@@ -699,6 +700,10 @@ function Setting({
             {t("mappings.mouseCastSpell.setting.edit")}
           </Button>
         </ItemBox>
+        <SettingAnchor
+          anchor={config.anchor}
+          onAnchorChange={(anchor) => onConfigChange({ ...config, anchor })}
+        />
         <SettingNote
           note={config.note}
           onNoteChange={(note) => onConfigChange({ ...config, note })}
@@ -0,0 +1,234 @@
+import { useEffect, useMemo, useState } from "react";
+import type {
+  MappingUpdater,
+  ScrollDragAxis,
+  ScrollDragConfig,
+} from "./mapping";
+import { Flex, InputNumber, Select, Tooltip, Typography } from "antd";
+import {
+  mappingButtonDragFactory,
+  mappingButtonScaledPresetStyle,
+  mappingButtonTransformStyle,
+} from "./tools";
+import { useAppSelector } from "../../store/store";
+import { ItemBoxContainer, ItemBox } from "../common/ItemBox";
+import {
+  SettingBind,
+  SettingFooter,
+  SettingMappingId,
+  SettingModal,
+  SettingAnchor,
+  SettingNote,
+  SettingPointerId,
+  SettingScriptHooks,
+} from "./Common";
+import { useTranslation } from "react-i18next";
+
+const scrollDragAxes: ScrollDragAxis[] = ["vertical", "horizontal", "both"];
+
+export default function ButtonScrollDrag({
+  index,
+  config,
+  originalSize,
+  onConfigChange,
+  onConfigDelete,
+  onConfigCopy,
+}: {
+  index: number;
+  config: ScrollDragConfig;
+  originalSize: { width: number; height: number };
+  onConfigChange: MappingUpdater<ScrollDragConfig>;
+  onConfigDelete: () => void;
+  onConfigCopy: () => void;
+}) {
+  const { t } = useTranslation();
+  const id = `mapping-scroll-drag-${index}`;
+  // the bind is only a modifier, an empty one means plain wheel
+  const bindText =
+    config.bind.length > 0 ? `${config.bind.join("+")}+Wheel` : "Wheel";
+  const className =
+    "rounded-full absolute box-border border-solid border-2 color-text border-text-secondary hover:border-text";
+
+  const maskArea = useAppSelector((state) => state.other.maskArea);
+  const [showSetting, setShowSetting] = useState(false);
+
+  const scale = useMemo(() => {
+    return {
+      x: maskArea.width / originalSize.width,
+      y: maskArea.height / originalSize.height,
+    };
+  }, [originalSize, maskArea]);
+
+  const buttonStyle = useMemo(
+    () => mappingButtonScaledPresetStyle(52, maskArea),
+    [maskArea],
+  );
+
+  useEffect(() => {
+    const element = document.getElementById(id);
+    if (element) {
+      element.style.transform = mappingButtonTransformStyle(
+        config.position.x,
+        config.position.y,
+        scale,
+      );
+    }
+  }, [index, config, scale]);
+
+  const handleDrag = mappingButtonDragFactory(
+    maskArea,
+    originalSize,
+    ({ x, y }) => {
+      onConfigChange({
+        ...config,
+        position: {
+          x,
+          y,
+        },
+      });
+    },
+  );
+
+  const handleSetting = (e: React.MouseEvent) => {
+    e.preventDefault();
+    setShowSetting(true);
+  };
+
+  return (
+    <>
+      <SettingModal open={showSetting} onClose={() => setShowSetting(false)}>
+        <Setting
+          config={config}
+          onConfigChange={onConfigChange}
+          onConfigDelete={() => {
+            setShowSetting(false);
+            onConfigDelete();
+          }}
+          onConfigCopy={() => {
+            setShowSetting(false);
+            onConfigCopy();
+          }}
+        />
+      </SettingModal>
+      <Flex
+        id={id}
+        style={buttonStyle}
+        className={className}
+        onMouseDown={handleDrag}
+        onContextMenu={handleSetting}
+        justify="center"
+        align="center"
+        vertical
+      >
+        <Tooltip trigger="click" title={`${config.type}: ${bindText}`}>
+          <Typography.Text ellipsis={true} className="text-2.5 font-bold">
+            {bindText}
+          </Typography.Text>
+        </Tooltip>
+        <Typography.Text className="text-2">
+          {t(`mappings.scrollDrag.axis.${config.axis}`)}
+        </Typography.Text>
+      </Flex>
+    </>
+  );
+}
+
+function Setting({
+  config,
+  onConfigChange,
+  onConfigDelete,
+  onConfigCopy,
+}: {
+  config: ScrollDragConfig;
+  onConfigChange: MappingUpdater<ScrollDragConfig>;
+  onConfigDelete: () => void;
+  onConfigCopy: () => void;
+}) {
+  const { t } = useTranslation();
+
+  return (
+    <div>
+      <h1 className="title-with-line">
+        {t("mappings.scrollDrag.setting.title")}
+      </h1>
+      <ItemBoxContainer className="max-h-70vh overflow-y-auto pr-2 scrollbar">
+        <SettingMappingId id={config.id} />
+        <SettingBind
+          bind={config.bind}
+          label={t("mappings.scrollDrag.setting.modifier")}
+          tooltip={t("mappings.scrollDrag.setting.modifierHint")}
+          onBindChange={(bind) => onConfigChange((pre) => ({ ...pre, bind }))}
+        />
+        <SettingPointerId
+          pointerId={config.pointer_id}
+          onPointerIdChange={(pointerId) =>
+            onConfigChange({ ...config, pointer_id: pointerId })
+          }
+        />
+        <ItemBox label={t("mappings.scrollDrag.setting.axis")}>
+          <Select
+            className="w-full"
+            value={config.axis}
+            options={scrollDragAxes.map((axis) => ({
+              value: axis,
+              label: t(`mappings.scrollDrag.axis.${axis}`),
+            }))}
+            onChange={(axis) => onConfigChange({ ...config, axis })}
+          />
+        </ItemBox>
+        <ItemBox
+          label={t("mappings.scrollDrag.setting.step")}
+          tooltip={t("mappings.scrollDrag.setting.stepHint")}
+        >
+          <InputNumber
+            className="w-full"
+            value={config.step}
+            onChange={(v) => v !== null && onConfigChange({ ...config, step: v })}
+          />
+        </ItemBox>
+        <ItemBox
+          label={t("mappings.scrollDrag.setting.maxDistance")}
+          tooltip={t("mappings.scrollDrag.setting.maxDistanceHint")}
+        >
+          <InputNumber
+            className="w-full"
+            value={config.max_distance}
+            min={0}
+            onChange={(v) =>
+              v !== null && onConfigChange({ ...config, max_distance: v })
+            }
+          />
+        </ItemBox>
+        <ItemBox
+          label={t("mappings.scrollDrag.setting.releaseDelay")}
+          tooltip={t("mappings.scrollDrag.setting.releaseDelayHint")}
+        >
+          <InputNumber
+            className="w-full"
+            value={config.release_delay}
+            min={1}
+            precision={0}
+            onChange={(v) =>
+              v !== null && onConfigChange({ ...config, release_delay: v })
+            }
+          />
+        </ItemBox>
+        <SettingAnchor
+          anchor={config.anchor}
+          onAnchorChange={(anchor) => onConfigChange({ ...config, anchor })}
+        />
+        <SettingNote
+          note={config.note}
+          onNoteChange={(note) => onConfigChange({ ...config, note })}
+        />
+        <SettingScriptHooks
+          scriptHooks={config.script_hooks}
+          onScriptHooksChange={(script_hooks) =>
+            onConfigChange({ ...config, script_hooks })
+          }
+        />
+        <SettingFooter onDelete={onConfigDelete} onCopy={onConfigCopy} />
+      </ItemBoxContainer>
+    </div>
+  );
+}
@@ -29,7 +29,13 @@ import {
 } from "react";
 import IconButton from "../common/IconButton";
 
-import type { ButtonBinding, MappingScriptHooks } from "./mapping";
+import type {
+  ButtonBinding,
+  HorizontalAnchor,
+  MappingAnchor,
+  MappingScriptHooks,
+  VerticalAnchor,
+} from "./mapping";
 import { EVENT_CODE_TO_KEY_CODE, KEY_NAMES } from "./keyCode";
 import { debounce } from "../../utils";
 import { useTranslation } from "react-i18next";
@@ -142,6 +148,46 @@ export function SettingNote({ note, onNoteChange }: SettingNoteProps) {
   );
 }
 
+type SettingAnchorProps = {
+  anchor: MappingAnchor;
+  onAnchorChange: (anchor: MappingAnchor) => void;
+};
+
+const HORIZONTAL_ANCHORS: HorizontalAnchor[] = ["scale", "left", "center", "right"];
+const VERTICAL_ANCHORS: VerticalAnchor[] = ["scale", "top", "center", "bottom"];
+
+export function SettingAnchor({ anchor, onAnchorChange }: SettingAnchorProps) {
+  const { t } = useTranslation();
+
+  return (
+    <ItemBox
+      label={t("mappings.common.anchor.label")}
+      tooltip={t("mappings.common.anchor.hint")}
+    >
+      <Space.Compact className="w-full">
+        <Select
+          className="w-full"
+          value={anchor.horizontal}
+          onChange={(horizontal) => onAnchorChange({ ...anchor, horizontal })}
+          options={HORIZONTAL_ANCHORS.map((value) => ({
+            value,
+            label: t(`mappings.common.anchor.horizontal.${value}`),
+          }))}
+        />
+        <Select
+          className="w-full"
+          value={anchor.vertical}
+          onChange={(vertical) => onAnchorChange({ ...anchor, vertical })}
+          options={VERTICAL_ANCHORS.map((value) => ({
+            value,
+            label: t(`mappings.common.anchor.vertical.${value}`),
+          }))}
+        />
+      </Space.Compact>
+    </ItemBox>
+  );
+}
+
 export function SettingMappingId({ id }: { id: string }) {
   const { t } = useTranslation();
 
@@ -292,8 +338,15 @@ function mappingButtonBindFactory(
   };
 
   const handleWheel = (() => {
-    const debounced = debounce((deltaY: number) => {
-      const key = deltaY > 0 ? "ScrollDown" : "ScrollUp";
+    const debounced = debounce((deltaX: number, deltaY: number) => {
+      const key =
+        Math.abs(deltaX) > Math.abs(deltaY)
+          ? deltaX > 0
+            ? "ScrollRight"
+            : "ScrollLeft"
+          : deltaY > 0
+            ? "ScrollDown"
+            : "ScrollUp";
       pressedKeys.add(key);
       onBindChange([...pressedKeys]);
       pressedKeys.delete(key);
@@ -302,8 +355,8 @@ function mappingButtonBindFactory(
     return (e: WheelEvent) => {
       e.preventDefault();
 
-      if (e.deltaY === 0) return;
-      debounced(e.deltaY);
+      if (e.deltaX === 0 && e.deltaY === 0) return;
+      debounced(e.deltaX, e.deltaY);
     };
   })();
 
@@ -0,0 +1,296 @@
+import { Button, Flex, Modal, Popconfirm, Space, Table, Tag } from "antd";
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { requestGet, requestPost } from "../../utils";
+import { useMessageContext } from "../../hooks";
+
+type MappingRevision = {
+  id: string;
+  time: number;
+  size: number;
+  mapping_count: number | null;
+};
+
+type MappingSummary = {
+  id: string;
+  type: string;
+  note: string;
+};
+
+type MappingDiff = {
+  fields: string[];
+  added: MappingSummary[];
+  removed: MappingSummary[];
+  changed: (MappingSummary & { fields: string[] })[];
+};
+
+type TrashedMapping = {
+  id: string;
+  file: string;
+  time: number;
+};
+
+function formatTime(time: number) {
+  return new Date(time).toLocaleString();
+}
+
+function summaryLabel(mapping: MappingSummary) {
+  return `${mapping.type} ${mapping.note || mapping.id}`;
+}
+
+function DiffView({ diff }: { diff: MappingDiff }) {
+  const { t } = useTranslation();
+
+  if (
+    diff.fields.length === 0 &&
+    diff.added.length === 0 &&
+    diff.removed.length === 0 &&
+    diff.changed.length === 0
+  ) {
+    return <div>{t("mappings.history.noChanges")}</div>;
+  }
+
+  return (
+    <Flex vertical gap={4} className="font-mono text-sm">
+      {diff.fields.map((field) => (
+        <div key={`field-${field}`}>
+          <Tag color="blue">{t("mappings.history.changed")}</Tag>
+          {field}
+        </div>
+      ))}
+      {diff.added.map((mapping) => (
+        <div key={`added-${mapping.id}`}>
+          <Tag color="green">{t("mappings.history.added")}</Tag>
+          {summaryLabel(mapping)}
+        </div>
+      ))}
+      {diff.removed.map((mapping) => (
+        <div key={`removed-${mapping.id}`}>
+          <Tag color="red">{t("mappings.history.removed")}</Tag>
+          {summaryLabel(mapping)}
+        </div>
+      ))}
+      {diff.changed.map((mapping) => (
+        <div key={`changed-${mapping.id}`}>
+          <Tag color="blue">{t("mappings.history.changed")}</Tag>
+          {summaryLabel(mapping)}: {mapping.fields.join(", ")}
+        </div>
+      ))}
+    </Flex>
+  );
+}
+
+export function RevisionHistory({
+  file,
+  onCancel,
+  onRestored,
+}: {
+  file: string | null;
+  onCancel: () => void;
+  onRestored: (file: string) => void;
+}) {
+  const { t } = useTranslation();
+  const messageApi = useMessageContext();
+  const [revisions, setRevisions] = useState<MappingRevision[]>([]);
+  const [diff, setDiff] = useState<{
+    revision: string;
+    diff: MappingDiff;
+  } | null>(null);
+
+  async function loadRevisions(file: string) {
+    try {
+      const res = await requestGet<{ revisions: MappingRevision[] }>(
+        "/api/mapping/list_revisions",
+        { file },
+      );
+      setRevisions(res.data.revisions);
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  useEffect(() => {
+    setDiff(null);
+    setRevisions([]);
+    if (file !== null) loadRevisions(file);
+  }, [file]);
+
+  async function showDiff(file: string, revision: string) {
+    try {
+      const res = await requestGet<{ diff: MappingDiff }>(
+        "/api/mapping/diff_revision",
+        { file, revision },
+      );
+      setDiff({ revision, diff: res.data.diff });
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  async function restore(file: string, revision: string) {
+    try {
+      const res = await requestPost("/api/mapping/restore_revision", {
+        file,
+        revision,
+      });
+      messageApi?.success(res.message);
+      setDiff(null);
+      await loadRevisions(file);
+      onRestored(file);
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  return (
+    <Modal
+      title={`${t("mappings.history.title")}: ${file ?? ""}`}
+      open={file !== null}
+      onCancel={onCancel}
+      footer={null}
+      width={720}
+    >
+      <Table<MappingRevision>
+        size="small"
+        rowKey="id"
+        dataSource={revisions}
+        pagination={{ pageSize: 8 }}
+        locale={{ emptyText: t("mappings.history.empty") }}
+        columns={[
+          {
+            title: t("mappings.history.time"),
+            key: "time",
+            render: (_, record) => formatTime(record.time),
+          },
+          {
+            title: t("mappings.history.mappingCount"),
+            key: "mapping_count",
+            render: (_, record) => record.mapping_count ?? "-",
+          },
+          {
+            title: t("mappings.home.action"),
+            key: "action",
+            width: 1,
+            render: (_, record) =>
+              file !== null && (
+                <Space>
+                  <Button
+                    size="small"
+                    onClick={() => showDiff(file, record.id)}
+                  >
+                    {t("mappings.history.diff")}
+                  </Button>
+                  <Popconfirm
+                    title={t("mappings.history.restorePrompt")}
+                    onConfirm={() => restore(file, record.id)}
+                    okText={t("mappings.home.confirmYes")}
+                    cancelText={t("mappings.home.confirmNo")}
+                  >
+                    <Button size="small" type="primary">
+                      {t("mappings.history.restore")}
+                    </Button>
+                  </Popconfirm>
+                </Space>
+              ),
+          },
+        ]}
+      />
+      {diff && (
+        <Flex vertical gap={8}>
+          <div>
+            {t("mappings.history.diffTitle", {
+              time: formatTime(
+                revisions.find((r) => r.id === diff.revision)?.time ?? 0,
+              ),
+            })}
+          </div>
+          <DiffView diff={diff.diff} />
+        </Flex>
+      )}
+    </Modal>
+  );
+}
+
+export function MappingTrash({
+  open,
+  onCancel,
+  onRestored,
+}: {
+  open: boolean;
+  onCancel: () => void;
+  onRestored: () => void;
+}) {
+  const { t } = useTranslation();
+  const messageApi = useMessageContext();
+  const [trash, setTrash] = useState<TrashedMapping[]>([]);
+
+  async function loadTrash() {
+    try {
+      const res = await requestGet<{ trash: TrashedMapping[] }>(
+        "/api/mapping/list_trash",
+      );
+      setTrash(res.data.trash);
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  useEffect(() => {
+    if (open) loadTrash();
+  }, [open]);
+
+  async function restore(id: string) {
+    try {
+      const res = await requestPost("/api/mapping/restore_trash", { id });
+      messageApi?.success(res.message);
+      await loadTrash();
+      onRestored();
+    } catch (error) {
+      messageApi?.error(error as string);
+    }
+  }
+
+  return (
+    <Modal
+      title={t("mappings.history.trashTitle")}
+      open={open}
+      onCancel={onCancel}
+      footer={null}
+      width={640}
+    >
+      <Table<TrashedMapping>
+        size="small"
+        rowKey="id"
+        dataSource={trash}
+        pagination={{ pageSize: 8 }}
+        locale={{ emptyText: t("mappings.history.trashEmpty") }}
+        columns={[
+          {
+            title: t("mappings.home.file"),
+            dataIndex: "file",
+            key: "file",
+          },
+          {
+            title: t("mappings.history.deletedAt"),
+            key: "time",
+            render: (_, record) => formatTime(record.time),
+          },
+          {
+            title: t("mappings.home.action"),
+            key: "action",
+            width: 1,
+            render: (_, record) => (
+              <Button
+                size="small"
+                type="primary"
+                onClick={() => restore(record.id)}
+              >
+                {t("mappings.history.restore")}
+              </Button>
+            ),
+          },
+        ]}
+      />
+    </Modal>
+  );
+}
@@ -1,7 +1,12 @@
 import {
   type DirectionBinding,
+  MAPPING_SCHEMA_VERSION,
   type MappingConfig,
   type MappingType,
+  type MigrationChange,
+  type MigrationMode,
+  type MigrationOptions,
+  type SafeArea,
   newMappingId,
   normalizeMappingConfig,
 } from "./mapping";
@@ -21,6 +26,7 @@ import {
   Splitter,
   Table,
   type TableProps,
+  Upload,
 } from "antd";
 import {
   useEffect,
@@ -41,12 +47,16 @@ import {
   FileAddOutlined,
   FileSyncOutlined,
   FileTextOutlined,
+  HistoryOutlined,
+  ImportOutlined,
+  RestOutlined,
   RollbackOutlined,
   SaveOutlined,
   SettingOutlined,
   SnippetsOutlined,
 } from "@ant-design/icons";
 import IconButton from "../common/IconButton";
+import { MappingTrash, RevisionHistory } from "./MappingHistory";
 import {
   type ApiError,
   deepClone,
@@ -61,6 +71,7 @@ import ButtonRepeatTap from "./ButtonRepeatTap";
 import ButtonMultipleTap from "./ButtonMultipleTap";
 import { clientPositionToMappingPosition } from "./tools";
 import ButtonSwipe from "./ButtonSwipe";
+import ButtonGesture from "./ButtonGesture";
 import ButtonDirectionPad from "./ButtonDirectionPad";
 import ButtonMouseCastSpell from "./ButtonMouseCastSpell";
 import { CursorPos, DeviceBackground, RefreshImageButton } from "./Common";
@@ -74,6 +85,7 @@ import { useTranslation } from "react-i18next";
 import { ItemBox, ItemBoxContainer } from "../common/ItemBox";
 import ButtonFire from "./ButtonFire";
 import ButtonScript from "./ButtonScript";
+import ButtonScrollDrag from "./ButtonScrollDrag";
 import { MappingOverlayProvider } from "./MappingOverlay";
 
 type MappingFileTabelItem = {
@@ -103,7 +115,7 @@ type ScriptDiagnostic = {
 };
 
 type MappingDiagnostic = {
-  severity: "error";
+  severity: "error" | "warning";
   code: string;
   message: string;
   mappingType?: string;
@@ -116,6 +128,7 @@ type MappingDiagnostic = {
 type MappingValidateResult = {
   valid: boolean;
   diagnostics: MappingDiagnostic[];
+  warnings?: MappingDiagnostic[];
 };
 
 function isValidationError(error: unknown): error is ApiError<MappingValidateResult> {
@@ -207,6 +220,9 @@ function Manager({
   onCreateAction,
   onRenameAction,
   onMigrateAction,
+  onPreviewMigrateAction,
+  onImportAction,
+  onRestoredAction,
 }: {
   open: boolean;
   onCancel: () => void;
@@ -224,8 +240,18 @@ function Manager({
   onMigrateAction: (
     file: string,
     newFile: string,
+    options: MigrationOptions,
+  ) => void;
+  onPreviewMigrateAction: (
+    file: string,
+    options: MigrationOptions,
+  ) => Promise<MigrationChange[]>;
+  onImportAction: (
+    file: string,
+    content: string,
     size: { width: number; height: number },
   ) => void;
+  onRestoredAction: (file?: string) => void;
 }) {
   const { t } = useTranslation();
   const messageApi = useMessageContext();
@@ -240,6 +266,27 @@ function Manager({
     width: 1280,
     height: 720,
   });
+  const [migrationMode, setMigrationMode] = useState<MigrationMode>("scale");
+  const [newSafeArea, setNewSafeArea] = useState<SafeArea>({
+    left: 0,
+    top: 0,
+    right: 0,
+    bottom: 0,
+  });
+  const [migrationPreview, setMigrationPreview] = useState<MigrationChange[]>(
+    [],
+  );
+  const [importSource, setImportSource] = useState<{
+    name: string;
+    content: string;
+  } | null>(null);
+  const [historyFile, setHistoryFile] = useState<string | null>(null);
+  const [isTrashOpen, setIsTrashOpen] = useState(false);
+  const migrationOptions: MigrationOptions = {
+    size: newSize,
+    mode: migrationMode,
+    safe_area: newSafeArea,
+  };
 
   const mappingFiles = useMemo<MappingFileTabelItem[]>(() => {
     return mappingList.map((file) => {
@@ -290,6 +337,79 @@ function Manager({
               tooltip={t("mappings.home.create")}
               icon={<FileAddOutlined />}
               onClick={() => {
+                setMigrationPreview([]);
+                const mainDevice = controlledDevices.find((d) => d.main);
+                if (mainDevice) {
+                  setNewSize({
+                    width: mainDevice.device_size[0],
+                    height: mainDevice.device_size[1],
+                  });
+                }
+              }}
+            />
+          </Confirm>
+          <Confirm
+            title={t("mappings.home.importTitle")}
+            onConfirm={(newFile) => {
+              if (importSource === null) {
+                messageApi?.error(t("mappings.home.importNoFile"));
+                return;
+              }
+              onImportAction(newFile, importSource.content, newSize);
+            }}
+            defaultValue=""
+            extral={
+              <>
+                <ItemBox label={t("mappings.home.importFile")}>
+                  <Upload
+                    accept=".json"
+                    showUploadList={false}
+                    beforeUpload={(file) => {
+                      file.text().then((content) =>
+                        setImportSource({ name: file.name, content }),
+                      );
+                      return false;
+                    }}
+                  >
+                    <Button>
+                      {importSource?.name ?? t("mappings.home.importChoose")}
+                    </Button>
+                  </Upload>
+                </ItemBox>
+                <ItemBox
+                  label={t("mappings.home.size")}
+                  tooltip={t("mappings.home.importSizeHint")}
+                >
+                  <Space.Compact className="w-full">
+                    <InputNumber
+                      className="w-full"
+                      prefix="W:"
+                      value={newSize.width}
+                      min={1}
+                      onChange={(v) =>
+                        v !== null && setNewSize({ ...newSize, width: v })
+                      }
+                    />
+                    <InputNumber
+                      className="w-full"
+                      prefix="H:"
+                      value={newSize.height}
+                      min={1}
+                      onChange={(v) =>
+                        v !== null && setNewSize({ ...newSize, height: v })
+                      }
+                    />
+                  </Space.Compact>
+                </ItemBox>
+              </>
+            }
+          >
+            <IconButton
+              color="info"
+              tooltip={t("mappings.home.import")}
+              icon={<ImportOutlined />}
+              onClick={() => {
+                setImportSource(null);
                 const mainDevice = controlledDevices.find((d) => d.main);
                 if (mainDevice) {
                   setNewSize({
@@ -300,6 +420,12 @@ function Manager({
               }}
             />
           </Confirm>
+          <IconButton
+            color="info"
+            tooltip={t("mappings.history.trash")}
+            icon={<RestOutlined />}
+            onClick={() => setIsTrashOpen(true)}
+          />
         </Space>
       ),
       dataIndex: "file",
@@ -368,6 +494,12 @@ function Manager({
               icon={<DeleteOutlined />}
             />
           </Popconfirm>
+          <IconButton
+            color="info"
+            tooltip={t("mappings.history.title")}
+            icon={<HistoryOutlined />}
+            onClick={() => setHistoryFile(record.file)}
+          />
           <Confirm
             title={t("mappings.home.duplicateTitle")}
             onConfirm={(newFile) => {
@@ -391,33 +523,107 @@ function Manager({
               if (newFile === record.file) {
                 messageApi?.warning(t("mappings.home.differentName"));
               } else {
-                onMigrateAction(record.file, newFile, newSize);
+                onMigrateAction(record.file, newFile, migrationOptions);
               }
             }}
             defaultValue={record.file}
             extral={
-              <ItemBox label={t("mappings.home.size")}>
-                <Space.Compact className="w-full">
-                  <InputNumber
+              <>
+                <ItemBox label={t("mappings.home.size")}>
+                  <Space.Compact className="w-full">
+                    <InputNumber
+                      className="w-full"
+                      prefix="W:"
+                      value={newSize.width}
+                      min={1}
+                      onChange={(v) =>
+                        v !== null && setNewSize({ ...newSize, width: v })
+                      }
+                    />
+                    <InputNumber
+                      className="w-full"
+                      prefix="H:"
+                      value={newSize.height}
+                      min={1}
+                      onChange={(v) =>
+                        v !== null && setNewSize({ ...newSize, height: v })
+                      }
+                    />
+                  </Space.Compact>
+                </ItemBox>
+                <ItemBox
+                  label={t("mappings.home.migrationMode")}
+                  tooltip={t("mappings.home.migrationModeHint")}
+                >
+                  <Select
                     className="w-full"
-                    prefix="W:"
-                    value={newSize.width}
-                    min={1}
-                    onChange={(v) =>
-                      v !== null && setNewSize({ ...newSize, width: v })
-                    }
+                    value={migrationMode}
+                    onChange={(v) => {
+                      setMigrationMode(v);
+                      setMigrationPreview([]);
+                    }}
+                    options={[
+                      {
+                        value: "scale",
+                        label: t("mappings.home.migrationModeScale"),
+                      },
+                      {
+                        value: "anchor",
+                        label: t("mappings.home.migrationModeAnchor"),
+                      },
+                    ]}
                   />
-                  <InputNumber
-                    className="w-full"
-                    prefix="H:"
-                    value={newSize.height}
-                    min={1}
-                    onChange={(v) =>
-                      v !== null && setNewSize({ ...newSize, height: v })
+                </ItemBox>
+                {migrationMode === "anchor" && (
+                  <ItemBox
+                    label={t("mappings.home.safeArea")}
+                    tooltip={t("mappings.home.safeAreaHint")}
+                  >
+                    <Space.Compact className="w-full">
+                      {(["left", "top", "right", "bottom"] as const).map(
+                        (side) => (
+                          <InputNumber
+                            key={side}
+                            className="w-full"
+                            prefix={`${side[0].toUpperCase()}:`}
+                            value={newSafeArea[side]}
+                            min={0}
+                            onChange={(v) =>
+                              v !== null &&
+                              setNewSafeArea({ ...newSafeArea, [side]: v })
+                            }
+                          />
+                        ),
+                      )}
+                    </Space.Compact>
+                  </ItemBox>
+                )}
+                <ItemBox>
+                  <Button
+                    block
+                    onClick={async () =>
+                      setMigrationPreview(
+                        await onPreviewMigrateAction(
+                          record.file,
+                          migrationOptions,
+                        ),
+                      )
                     }
-                  />
-                </Space.Compact>
-              </ItemBox>
+                  >
+                    {t("mappings.home.migrationPreview")}
+                  </Button>
+                  {migrationPreview.length > 0 && (
+                    <div className="max-h-40 overflow-y-auto scrollbar mt-2">
+                      {migrationPreview.map((change) => (
+                        <div key={change.id} className="font-mono text-xs">
+                          {change.type} {change.id}: ({change.from.x},{" "}
+                          {change.from.y}) → ({change.to.x}, {change.to.y})
+                        </div>
+                      ))}
+                    </div>
+                  )}
+                </ItemBox>
+              </>
             }
           >
             <IconButton
@@ -457,6 +663,16 @@ function Manager({
         columns={columns}
         dataSource={mappingFiles}
       />
+      <RevisionHistory
+        file={historyFile}
+        onCancel={() => setHistoryFile(null)}
+        onRestored={onRestoredAction}
+      />
+      <MappingTrash
+        open={isTrashOpen}
+        onCancel={() => setIsTrashOpen(false)}
+        onRestored={() => onRestoredAction()}
+      />
     </Modal>
   );
 }
@@ -473,6 +689,7 @@ const buttonTypes = [
   "RepeatTap",
   "MultipleTap",
   "Swipe",
+  "Gesture",
   "DirectionPad",
   "MouseCastSpell",
   "PadCastSpell",
@@ -482,6 +699,7 @@ const buttonTypes = [
   "Fire",
   "RawInput",
   "Script",
+  "ScrollDrag",
 ];
 
 const mappingButtonMap = {
@@ -489,6 +707,7 @@ const mappingButtonMap = {
   RepeatTap: ButtonRepeatTap,
   MultipleTap: ButtonMultipleTap,
   Swipe: ButtonSwipe,
+  Gesture: ButtonGesture,
   DirectionPad: ButtonDirectionPad,
   MouseCastSpell: ButtonMouseCastSpell,
   PadCastSpell: ButtonPadCastSpell,
@@ -498,6 +717,7 @@ const mappingButtonMap = {
   Fire: ButtonFire,
   RawInput: ButtonRawInput,
   Script: ButtonScript,
+  ScrollDrag: ButtonScrollDrag,
 };
 
 const mappingConstructorMap: any = Object.fromEntries(
@@ -527,6 +747,13 @@ function mappingPointerIds(mapping: MappingType): number[] {
   ) {
     pointerIds.push(mapping.pointer_id);
   }
+  if (
+    mapping.type === "Gesture" &&
+    Number.isInteger(mapping.second_pointer_id) &&
+    mapping.second_pointer_id >= firstAutoPointerId
+  ) {
+    pointerIds.push(mapping.second_pointer_id);
+  }
   if (mapping.type !== "Fps" || mapping.touch_mode.type !== "dual") {
     return pointerIds;
   }
@@ -564,6 +791,13 @@ function assignNextAvailablePointerId(
   if ("pointer_id" in mapping) {
     mapping.pointer_id = getNextAvailablePointerId(mappings);
   }
+  if (mapping.type === "Gesture") {
+    mapping.second_pointer_id = getNextAvailablePointerIdWithReserved(
+      mappings,
+      [mapping.pointer_id],
+    );
+    return;
+  }
   if (mapping.type !== "Fps" || mapping.touch_mode.type !== "dual") {
     return;
   }
@@ -831,6 +1065,7 @@ export default function Mappings() {
   const [validationDiagnostics, setValidationDiagnostics] = useState<
     MappingDiagnostic[]
   >([]);
+  const [lintWarnings, setLintWarnings] = useState<MappingDiagnostic[]>([]);
 
   const mappingListOptions = useMemo(() => {
     return mappingList.map((item) => ({
@@ -981,6 +1216,7 @@ export default function Mappings() {
           });
           messageApi?.success(res.message);
           setValidationDiagnostics([]);
+          setLintWarnings(validateRes.data.warnings ?? []);
           setEditState({
             file: editState.file,
             edited: false,
@@ -1039,6 +1275,13 @@ export default function Mappings() {
     dispatch(setIsLoading(false));
   }
 
+  async function reloadRestoredMapping(file?: string) {
+    await loadMappingList(true);
+    if (file !== undefined && file === displayedMappingFile) {
+      await changeDisplayedMapping(file);
+    }
+  }
+
   async function createMappingFile(
     file: string,
     size: { width: number; height: number },
@@ -1048,7 +1291,7 @@ export default function Mappings() {
       const res = await requestPost("/api/mapping/create_mapping", {
         file,
         config: {
-          version: "0.0.1",
+          version: MAPPING_SCHEMA_VERSION,
           original_size: size,
           mappings: [],
         },
@@ -1076,21 +1319,80 @@ export default function Mappings() {
     dispatch(setIsLoading(false));
   }
 
+  async function importMappingFile(
+    file: string,
+    content: string,
+    size: { width: number; height: number },
+  ) {
+    let keymap: unknown;
+    try {
+      keymap = JSON.parse(content);
+    } catch (error) {
+      messageApi?.error(String(error));
+      return;
+    }
+    dispatch(setIsLoading(true));
+    try {
+      const res = await requestPost<{ diagnostics: MappingDiagnostic[] }>(
+        "/api/mapping/import",
+        {
+          file,
+          content: keymap,
+          width: size.width,
+          height: size.height,
+        },
+      );
+      await loadMappingList(true);
+      messageApi?.success(res.message);
+      setLintWarnings(res.data.diagnostics);
+    } catch (error) {
+      if (isValidationError(error)) {
+        setValidationDiagnostics(error.data?.diagnostics ?? []);
+      } else {
+        messageApi?.error(error as string);
+      }
+    }
+    dispatch(setIsLoading(false));
+  }
+
+  async function previewMigration(
+    file: string,
+    options: MigrationOptions,
+  ): Promise<MigrationChange[]> {
+    try {
+      const res = await requestPost<{ changes: MigrationChange[] }>(
+        "/api/mapping/migrate_mapping",
+        {
+          file,
+          new_file: file,
+          width: options.size.width,
+          height: options.size.height,
+          mode: options.mode,
+          safe_area: options.safe_area,
+          preview: true,
+        },
+      );
+      return res.data.changes;
+    } catch (error) {
+      messageApi?.error(error as string);
+      return [];
+    }
+  }
+
   async function migrateMappingFile(
     file: string,
     newFile: string,
-    size: {
-      width: number;
-      height: number;
-    },
+    options: MigrationOptions,
   ) {
     dispatch(setIsLoading(true));
     try {
       const res = await requestPost("/api/mapping/migrate_mapping", {
         file,
         new_file: newFile,
-        width: size.width,
-        height: size.height,
+        width: options.size.width,
+        height: options.size.height,
+        mode: options.mode,
+        safe_area: options.safe_area,
       });
       await loadMappingList(true);
       messageApi?.success(res.message);
@@ -1120,6 +1422,24 @@ export default function Mappings() {
           ))}
         </Flex>
       </Modal>
+      <Modal
+        title="Mapping saved with warnings"
+        open={lintWarnings.length > 0}
+        onCancel={() => setLintWarnings([])}
+        footer={null}
+        width={720}
+      >
+        <Flex vertical gap={8}>
+          {lintWarnings.map((diagnostic, index) => (
+            <div
+              key={`${diagnostic.code}-${index}`}
+              className="whitespace-pre-wrap rounded border border-solid border-yellow-500/40 px-3 py-2 font-mono text-sm"
+            >
+              {formatMappingDiagnostic(diagnostic)}
+            </div>
+          ))}
+        </Flex>
+      </Modal>
       <Flex
         vertical
         gap={32}
@@ -1138,6 +1458,9 @@ export default function Mappings() {
         onCreateAction={createMappingFile}
         onRenameAction={renameMappingFile}
         onMigrateAction={migrateMappingFile}
+        onPreviewMigrateAction={previewMigration}
+        onImportAction={importMappingFile}
+        onRestoredAction={reloadRestoredMapping}
       />
       <section>
         <Flex justify="space-between" align="center">
@@ -385,7 +385,7 @@ const KEYBOARD_KEYS = [
   "F35",
 ];
 const MOUSE_KEYS = ["M-Left", "M-Right", "M-Middle", "M-Back", "M-Forward"];
-const SCROLL_KEYS = ["ScrollDown", "ScrollUp"];
+const SCROLL_KEYS = ["ScrollDown", "ScrollUp", "ScrollLeft", "ScrollRight"];
 const GAMEPAD_KEYS = [
   "G-South",
   "G-East",
@@ -5,12 +5,16 @@ import {
   default_random_offset,
 } from "../../utils";
 
+// keep in sync with MAPPING_SCHEMA_VERSION in src/mask/mapping/migration.rs
+export const MAPPING_SCHEMA_VERSION = 2;
+
 export interface MappingConfig {
-  version: string;
+  version: number;
   original_size: {
     width: number;
     height: number;
   };
+  safe_area?: SafeArea;
   mappings: MappingType[];
 }
 
@@ -19,6 +23,7 @@ export type MappingType =
   | RepeatTapConfig
   | MultipleTapConfig
   | SwipeConfig
+  | GestureConfig
   | DirectionPadConfig
   | MouseCastSpellConfig
   | PadCastSpellConfig
@@ -27,13 +32,48 @@ export type MappingType =
   | FpsConfig
   | FireConfig
   | RawInputConfig
-  | ScriptConfig;
+  | ScriptConfig
+  | ScrollDragConfig;
 
 export type Position = {
   x: number;
   y: number;
 };
 
+export type HorizontalAnchor = "scale" | "left" | "center" | "right";
+export type VerticalAnchor = "scale" | "top" | "center" | "bottom";
+
+export interface MappingAnchor {
+  horizontal: HorizontalAnchor;
+  vertical: VerticalAnchor;
+}
+
+export interface SafeArea {
+  left: number;
+  top: number;
+  right: number;
+  bottom: number;
+}
+
+export type MigrationMode = "scale" | "anchor";
+
+export interface MigrationOptions {
+  size: { width: number; height: number };
+  mode: MigrationMode;
+  safe_area: SafeArea;
+}
+
+export interface MigrationChange {
+  id: string;
+  type: string;
+  from: Position;
+  to: Position;
+}
+
+export function defaultAnchor(): MappingAnchor {
+  return { horizontal: "scale", vertical: "scale" };
+}
+
 export type ButtonBinding = string[];
 
 export interface MappingScriptHooks {
@@ -58,8 +98,11 @@ export function newMappingId(): string {
     .padStart(8, "0");
 }
 
+export type TapTriggerMode = "hold" | "toggle";
+
 export interface SingleTapConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   duration: number;
   note: string;
@@ -69,12 +112,14 @@ export interface SingleTapConfig {
   random_offset_y: number;
   script_hooks: MappingScriptHooks;
   sync: boolean;
+  trigger_mode: TapTriggerMode;
   type: "SingleTap";
 }
 
 export function newSingleTap(position: Position): SingleTapConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     duration: 50,
     note: "",
@@ -84,12 +129,14 @@ export function newSingleTap(position: Position): SingleTapConfig {
     random_offset_y: default_random_offset,
     script_hooks: defaultScriptHooks(),
     sync: false,
+    trigger_mode: "hold",
     type: "SingleTap",
   };
 }
 
 export interface RepeatTapConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   duration: number;
   interval: number;
@@ -99,12 +146,15 @@ export interface RepeatTapConfig {
   random_offset_x: number;
   random_offset_y: number;
   script_hooks: MappingScriptHooks;
+  trigger_mode: TapTriggerMode;
+  repeat_limit: number;
   type: "RepeatTap";
 }
 
 export function newRepeatTap(position: Position): RepeatTapConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     duration: 50,
     interval: 100,
@@ -114,6 +164,8 @@ export function newRepeatTap(position: Position): RepeatTapConfig {
     random_offset_x: default_random_offset,
     random_offset_y: default_random_offset,
     script_hooks: defaultScriptHooks(),
+    trigger_mode: "hold",
+    repeat_limit: 0,
     type: "RepeatTap",
   };
 }
@@ -126,6 +178,7 @@ export interface MultipleTapItem {
 
 export interface MultipleTapConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   items: MultipleTapItem[];
   note: string;
@@ -139,6 +192,7 @@ export interface MultipleTapConfig {
 export function newMultipleTap(position: Position): MultipleTapConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     items: [
       {
@@ -158,6 +212,7 @@ export function newMultipleTap(position: Position): MultipleTapConfig {
 
 export interface SwipeConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   enable_randomization: boolean;
   duration: number;
@@ -171,6 +226,7 @@ export interface SwipeConfig {
 export function newSwipe(position: Position): SwipeConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     enable_randomization: false,
     duration: 100,
@@ -182,6 +238,54 @@ export function newSwipe(position: Position): SwipeConfig {
   };
 }
 
+export type SingleSwipeStrategy =
+  | "Linear"
+  | "ArcWithCubicEasing"
+  | "ArcWithEaseOut"
+  | "ArcWithEaseInOut";
+
+export type GestureKind = "pinch_in" | "pinch_out" | "rotate";
+
+export interface GestureConfig {
+  id: string;
+  anchor: MappingAnchor;
+  angle: number;
+  bind: ButtonBinding;
+  duration: number;
+  kind: GestureKind;
+  min_span: number;
+  note: string;
+  pointer_id: number;
+  position: Position;
+  rotation: number;
+  script_hooks: MappingScriptHooks;
+  second_pointer_id: number;
+  span: number;
+  strategy: SingleSwipeStrategy;
+  type: "Gesture";
+}
+
+export function newGesture(position: Position): GestureConfig {
+  return {
+    id: newMappingId(),
+    anchor: defaultAnchor(),
+    angle: 0,
+    bind: [],
+    duration: 300,
+    kind: "pinch_in",
+    min_span: 100,
+    note: "",
+    pointer_id: 1,
+    position,
+    rotation: 90,
+    script_hooks: defaultScriptHooks(),
+    second_pointer_id: 2,
+    span: 500,
+    strategy: "ArcWithEaseInOut",
+    type: "Gesture",
+  };
+}
+
 export interface DirectionButtonBinding {
   type: "Button";
   up: ButtonBinding;
@@ -202,6 +306,7 @@ export type DirectionBinding =
 
 export interface DirectionPadConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: DirectionBinding;
   enable_randomization: boolean;
   initial_duration: number;
@@ -225,6 +330,7 @@ export interface DirectionPadConfig {
 export function newDirectionPad(position: Position): DirectionPadConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: {
       type: "Button",
       up: [],
@@ -256,6 +362,7 @@ export type MouseCastReleaseMode = "OnPress" | "OnRelease" | "OnSecondPress";
 
 export interface MouseCastSpellConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   cast_no_direction: boolean;
   cast_radius: number;
@@ -281,6 +388,7 @@ export function newMouseCastSpell(
 ): MouseCastSpellConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     cast_no_direction: false,
     cast_radius: 200,
@@ -305,6 +413,7 @@ export type PadCastReleaseMode = "OnRelease" | "OnSecondPress";
 
 export interface PadCastSpellConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   block_direction_pad: boolean;
   drag_radius: number;
@@ -323,6 +432,7 @@ export interface PadCastSpellConfig {
 export function newPadCastSpell(position: Position): PadCastSpellConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     block_direction_pad: false,
     drag_radius: 150,
@@ -347,6 +457,7 @@ export function newPadCastSpell(position: Position): PadCastSpellConfig {
 
 export interface CancelCastConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   note: string;
   position: Position;
@@ -357,6 +468,7 @@ export interface CancelCastConfig {
 export function newCancelCast(position: Position): CancelCastConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     note: "",
     position,
@@ -367,6 +479,7 @@ export function newCancelCast(position: Position): CancelCastConfig {
 
 export interface ObservationConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   max_radius: number;
   note: string;
@@ -383,6 +496,7 @@ export interface ObservationConfig {
 export function newObservation(position: Position): ObservationConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     max_radius: 0,
     note: "",
@@ -399,6 +513,7 @@ export function newObservation(position: Position): ObservationConfig {
 
 export interface FpsConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   note: string;
   pointer_id: number;
@@ -408,9 +523,29 @@ export interface FpsConfig {
   max_offset_x: number;
   max_offset_y: number;
   touch_mode: FpsTouchMode;
+  acceleration: FpsAcceleration;
+  smoothing: FpsSmoothing;
+  ads_key: ButtonBinding | null;
+  ads_sensitivity: number;
   type: "Fps";
 }
 
+export type FpsAcceleration =
+  | { type: "off" }
+  | { type: "linear"; gain: number; offset: number; cap: number }
+  | {
+      type: "power";
+      gain: number;
+      exponent: number;
+      offset: number;
+      cap: number;
+    };
+
+export type FpsSmoothing =
+  | { type: "off" }
+  | { type: "exponential"; alpha: number }
+  | { type: "one_euro"; min_cutoff: number; beta: number; d_cutoff: number };
+
 export type FpsTouchMode =
   | { type: "single"; interval: number }
   | {
@@ -424,6 +559,7 @@ export type FpsTouchMode =
 export function newFps(position: Position): FpsConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     note: "",
     pointer_id: 0,
@@ -433,12 +569,17 @@ export function newFps(position: Position): FpsConfig {
     max_offset_x: 0,
     max_offset_y: 0,
     touch_mode: { type: "single", interval: 0 },
+    acceleration: { type: "off" },
+    smoothing: { type: "off" },
+    ads_key: null,
+    ads_sensitivity: 1.0,
     type: "Fps",
   };
 }
 
 export interface FireConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   note: string;
   pointer_id: number;
@@ -455,6 +596,7 @@ export interface FireConfig {
 export function newFire(position: Position): FireConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     note: "",
     pointer_id: 0,
@@ -471,6 +613,7 @@ export function newFire(position: Position): FireConfig {
 
 export interface RawInputConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   note: string;
   position: Position;
@@ -480,6 +623,7 @@ export interface RawInputConfig {
 export function newRawInput(position: Position): RawInputConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     note: "",
     position,
@@ -489,6 +633,7 @@ export function newRawInput(position: Position): RawInputConfig {
 
 export interface ScriptConfig {
   id: string;
+  anchor: MappingAnchor;
   bind: ButtonBinding;
   note: string;
   position: Position;
@@ -502,6 +647,7 @@ export interface ScriptConfig {
 export function newScript(position: Position): ScriptConfig {
   return {
     id: newMappingId(),
+    anchor: defaultAnchor(),
     bind: [],
     note: "",
     position,
@@ -513,6 +659,40 @@ export function newScript(position: Position): ScriptConfig {
   };
 }
 
+export type ScrollDragAxis = "vertical" | "horizontal" | "both";
+
+export interface ScrollDragConfig {
+  id: string;
+  anchor: MappingAnchor;
+  axis: ScrollDragAxis;
+  bind: ButtonBinding;
+  max_distance: number;
+  note: string;
+  pointer_id: number;
+  position: Position;
+  release_delay: number;
+  script_hooks: MappingScriptHooks;
+  step: number;
+  type: "ScrollDrag";
+}
+
+export function newScrollDrag(position: Position): ScrollDragConfig {
+  return {
+    id: newMappingId(),
+    anchor: defaultAnchor(),
+    axis: "vertical",
+    bind: [],
+    max_distance: 400,
+    note: "",
+    pointer_id: 1,
+    position,
+    release_delay: 150,
+    script_hooks: defaultScriptHooks(),
+    step: 60,
+    type: "ScrollDrag",
+  };
+}
+
 export type MappingUpdater<T> = (updater: T | ((pre: T) => T)) => void;
 
 function withDefaultRandomOffset(value?: number): number {
@@ -532,6 +712,13 @@ function withDefaultScriptHooks(
   };
 }
 
+function withDefaultAnchor(value?: Partial<MappingAnchor>): MappingAnchor {
+  return {
+    horizontal: value?.horizontal ?? "scale",
+    vertical: value?.vertical ?? "scale",
+  };
+}
+
 function normalizeFpsTouchMode(value?: Partial<FpsTouchMode>): FpsTouchMode {
   switch (value?.type) {
     case "single":
@@ -564,6 +751,9 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
     ...config,
     mappings: config.mappings.map((mapping) => {
       const currentId = (mapping as { id?: string }).id;
+      const anchor = withDefaultAnchor(
+        (mapping as { anchor?: Partial<MappingAnchor> }).anchor
+      );
       const id = currentId && !usedIds.has(currentId) ? currentId : newMappingId();
       usedIds.add(id);
       switch (mapping.type) {
@@ -571,6 +761,8 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
+            trigger_mode: mapping.trigger_mode ?? "hold",
             random_offset_x: withDefaultRandomOffset(mapping.random_offset_x),
             random_offset_y: withDefaultRandomOffset(mapping.random_offset_y),
             script_hooks: withDefaultScriptHooks(mapping.script_hooks),
@@ -579,6 +771,9 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
+            trigger_mode: mapping.trigger_mode ?? "hold",
+            repeat_limit: mapping.repeat_limit ?? 0,
             random_offset_x: withDefaultRandomOffset(mapping.random_offset_x),
             random_offset_y: withDefaultRandomOffset(mapping.random_offset_y),
             script_hooks: withDefaultScriptHooks(mapping.script_hooks),
@@ -587,20 +782,42 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
             random_offset_x: withDefaultRandomOffset(mapping.random_offset_x),
             random_offset_y: withDefaultRandomOffset(mapping.random_offset_y),
             script_hooks: withDefaultScriptHooks(mapping.script_hooks),
           };
+        case "Gesture":
+          return {
+            ...mapping,
+            id,
+            anchor,
+            angle: mapping.angle ?? 0,
+            rotation: mapping.rotation ?? 0,
+            strategy: mapping.strategy ?? "Linear",
+            script_hooks: withDefaultScriptHooks(mapping.script_hooks),
+          };
+        case "ScrollDrag":
+          return {
+            ...mapping,
+            id,
+            anchor,
+            axis: mapping.axis ?? "vertical",
+            max_distance: mapping.max_distance ?? 0,
+            script_hooks: withDefaultScriptHooks(mapping.script_hooks),
+          };
         case "Swipe":
           return {
             ...mapping,
             id,
+            anchor,
             script_hooks: withDefaultScriptHooks(mapping.script_hooks),
           };
         case "Fire":
           return {
             ...mapping,
             id,
+            anchor,
             preserve_fps_control: mapping.preserve_fps_control ?? true,
             random_offset_x: withDefaultRandomOffset(mapping.random_offset_x),
             random_offset_y: withDefaultRandomOffset(mapping.random_offset_y),
@@ -610,6 +827,7 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
             random_offset_x: withDefaultRandomOffset(mapping.random_offset_x),
             random_offset_y: withDefaultRandomOffset(mapping.random_offset_y),
             script_hooks: withDefaultScriptHooks(mapping.script_hooks),
@@ -618,6 +836,7 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
             enable_initial_swipe_randomization:
               mapping.enable_initial_swipe_randomization ?? false,
             initial_duration: mapping.initial_duration ?? 0,
@@ -629,6 +848,7 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
             enable_randomization: mapping.enable_randomization ?? false,
             random_offset_x: withDefaultRandomOffset(mapping.random_offset_x),
             random_offset_y: withDefaultRandomOffset(mapping.random_offset_y),
@@ -648,6 +868,7 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
             enable_randomization: mapping.enable_randomization ?? false,
             random_offset_x: withDefaultRandomOffset(mapping.random_offset_x),
             random_offset_y: withDefaultRandomOffset(mapping.random_offset_y),
@@ -657,6 +878,7 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
           return {
             ...mapping,
             id,
+            anchor,
             script_hooks: withDefaultScriptHooks(mapping.script_hooks),
           };
         case "Fps":
@@ -668,9 +890,14 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
             return {
               ...normalized,
               id,
+              anchor,
               max_offset_x: normalized.max_offset_x ?? 0,
               max_offset_y: normalized.max_offset_y ?? 0,
               touch_mode: normalizeFpsTouchMode(normalized.touch_mode),
+              acceleration: normalized.acceleration ?? { type: "off" },
+              smoothing: normalized.smoothing ?? { type: "off" },
+              ads_key: normalized.ads_key ?? null,
+              ads_sensitivity: normalized.ads_sensitivity ?? 1.0,
             };
           }
         case "RawInput":
@@ -682,12 +909,14 @@ export function normalizeMappingConfig(config: MappingConfig): MappingConfig {
             return {
               ...normalized,
               id,
+              anchor,
             };
           }
         default:
           return {
             ...mapping,
             id,
+            anchor,
           };
       }
     }),
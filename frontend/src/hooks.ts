@@ -1,10 +1,10 @@
 import type { MessageInstance } from "antd/es/message/interface";
 import { createContext, useCallback, useContext, useEffect, useRef } from "react";
 import { useAppDispatch, useAppSelector } from "./store/store";
-import { setAdbDevices, setBackgroundImage, setControlledDevices, setDeviceRotation, setIsLoading } from "./store/other";
+import { setAdaptiveVideoStatus, setAdbDevices, setAudioStats, setBackgroundImage, setControlledDevices, setDeviceRotation, setIsLoading } from "./store/other";
 import { forceSetLocalConfig } from "./store/localConfig";
 import { useTranslation } from "react-i18next";
-import { requestGet, requestPost } from "./utils";
+import { requestGet, requestPost, withApiToken } from "./utils";
 import { createFromIconfontCN } from "@ant-design/icons";
 import i18n from "./i18n";
 
@@ -63,7 +63,64 @@ export function useRefreshBackgroundImage() {
   };
 }
 
-export function useDeviceWebSocket() {
+type DeviceTransfer = {
+  id: string;
+  kind: "push" | "pull" | "install" | "uninstall";
+  name: string;
+  stage: "transferring" | "installing" | "done" | "failed";
+  transferred: number;
+  total: number;
+  message: string | null;
+};
+
+// one message per transfer, updated in place until it's done
+function showTransfer(messageApi: MessageInstance, transfer: DeviceTransfer) {
+  const action = i18n.t(`devices.files.transfer.${transfer.kind}`, {
+    name: transfer.name,
+  });
+  const key = `transfer-${transfer.id}`;
+  switch (transfer.stage) {
+    case "transferring": {
+      const percent =
+        transfer.total > 0
+          ? Math.floor((transfer.transferred / transfer.total) * 100)
+          : 0;
+      messageApi.open({
+        key,
+        type: "loading",
+        content: `${action} ${percent}%`,
+        duration: 0,
+      });
+      break;
+    }
+    case "installing":
+      messageApi.open({
+        key,
+        type: "loading",
+        content: i18n.t("devices.files.transfer.installing", {
+          name: transfer.name,
+        }),
+        duration: 0,
+      });
+      break;
+    case "done":
+      messageApi.open({
+        key,
+        type: "success",
+        content: i18n.t("devices.files.transfer.done", { action }),
+      });
+      break;
+    case "failed":
+      messageApi.open({
+        key,
+        type: "error",
+        content: `${action}: ${transfer.message ?? ""}`,
+      });
+      break;
+  }
+}
+
+export function useDeviceWebSocket(messageApi: MessageInstance) {
   const dispatch = useAppDispatch();
   const wsRef = useRef<WebSocket | null>(null);
   const reconnectTimerRef = useRef<number | null>(null);
@@ -103,7 +160,9 @@ export function useDeviceWebSocket() {
     if (!mountedRef.current) return;
 
     const protocol = window.location.protocol === "https:" ? "wss:" : "ws:";
-    const ws = new WebSocket(`${protocol}//${window.location.host}/api/ws/connect`);
+    const ws = new WebSocket(
+      withApiToken(`${protocol}//${window.location.host}/api/ws/connect`),
+    );
 
     ws.onmessage = (event) => {
       try {
@@ -126,6 +185,27 @@ export function useDeviceWebSocket() {
           case "ConfigChanged":
             refreshConfig();
             break;
+          case "AudioStats":
+            dispatch(setAudioStats(msg.stats));
+            break;
+          case "AdaptiveVideo":
+            dispatch(setAdaptiveVideoStatus(msg.status));
+            break;
+          case "DeviceTransfer":
+            showTransfer(messageApi, msg);
+            break;
+          case "DataFileChanged":
+            if (msg.applied) {
+              messageApi.success(msg.message);
+            } else {
+              messageApi.error(
+                [
+                  msg.message,
+                  ...msg.diagnostics.map((d: { message: string }) => d.message),
+                ].join("; "),
+              );
+            }
+            break;
           default:
             refreshDevices();
         }
@@ -145,7 +225,7 @@ export function useDeviceWebSocket() {
     };
 
     wsRef.current = ws;
-  }, [refreshDevices, refreshConfig]);
+  }, [refreshDevices, refreshConfig, messageApi]);
 
   useEffect(() => {
     mountedRef.current = true;
@@ -41,6 +41,21 @@ function updateLocalConfig(
   debounceMap.set(key, timeout);
 }
 
+export interface AudioBufferTargets {
+  opus: number;
+  aac: number;
+  flac: number;
+  raw: number;
+}
+
+export interface AdaptiveVideoConfig {
+  enabled: boolean;
+  min_bit_rate: number;
+  max_bit_rate: number;
+  min_fps: number;
+  max_fps: number;
+}
+
 export interface LocalConfigState {
   // port
   webPort: number;
@@ -68,6 +83,7 @@ export interface LocalConfigState {
   videoBitRate: number;
   videoMaxSize: number;
   videoMaxFps: number;
+  adaptiveVideo: AdaptiveVideoConfig;
   displayId: number;
   newDisplayEnabled: boolean;
   newDisplayUseMainSize: boolean;
@@ -79,10 +95,16 @@ export interface LocalConfigState {
   audioBitRate: number;
   audioSource: string;
   audioDup: boolean;
+  audioBufferMs: AudioBufferTargets;
+  audioOutputDevice: string;
+  audioGain: number;
+  audioMuted: boolean;
   // device behavior
   stayAwake: boolean;
   screenOffTimeout: number;
   powerOffOnClose: boolean;
+  // profile
+  activeProfile: string;
 }
 
 const initialState: LocalConfigState = {
@@ -105,6 +127,13 @@ const initialState: LocalConfigState = {
   videoBitRate: 8000000,
   videoMaxSize: 0,
   videoMaxFps: 0,
+  adaptiveVideo: {
+    enabled: false,
+    min_bit_rate: 2000000,
+    max_bit_rate: 16000000,
+    min_fps: 30,
+    max_fps: 60,
+  },
   displayId: 0,
   newDisplayEnabled: false,
   newDisplayUseMainSize: true,
@@ -115,9 +144,14 @@ const initialState: LocalConfigState = {
   audioBitRate: 128000,
   audioSource: "OUTPUT",
   audioDup: false,
+  audioBufferMs: { opus: 50, aac: 50, flac: 120, raw: 50 },
+  audioOutputDevice: "",
+  audioGain: 1,
+  audioMuted: false,
   stayAwake: false,
   screenOffTimeout: -1,
   powerOffOnClose: false,
+  activeProfile: "",
 };
 
 const localConfigSlice = createSlice({
@@ -225,6 +259,10 @@ const localConfigSlice = createSlice({
       state.newDisplayWidth = action.payload;
       updateLocalConfig("new_display_width", action.payload);
     },
+    setAdaptiveVideo: (state, action: PayloadAction<AdaptiveVideoConfig>) => {
+      state.adaptiveVideo = action.payload;
+      updateLocalConfig("adaptive_video", action.payload);
+    },
     setNewDisplayHeight: (state, action: PayloadAction<number>) => {
       state.newDisplayHeight = action.payload;
       updateLocalConfig("new_display_height", action.payload);
@@ -253,6 +291,22 @@ const localConfigSlice = createSlice({
       state.audioDup = action.payload;
       updateLocalConfig("audio_dup", action.payload);
     },
+    setAudioBufferMs: (state, action: PayloadAction<AudioBufferTargets>) => {
+      state.audioBufferMs = action.payload;
+      updateLocalConfig("audio_buffer_ms", action.payload);
+    },
+    setAudioOutputDevice: (state, action: PayloadAction<string>) => {
+      state.audioOutputDevice = action.payload;
+      updateLocalConfig("audio_output_device", action.payload);
+    },
+    setAudioGain: (state, action: PayloadAction<number>) => {
+      state.audioGain = action.payload;
+      updateLocalConfig("audio_gain", action.payload, 500, false);
+    },
+    setAudioMuted: (state, action: PayloadAction<boolean>) => {
+      state.audioMuted = action.payload;
+      updateLocalConfig("audio_muted", action.payload);
+    },
     setStayAwake: (state, action: PayloadAction<boolean>) => {
       state.stayAwake = action.payload;
       updateLocalConfig("stay_awake", action.payload);
@@ -289,6 +343,7 @@ export const {
   setVideoBitRate,
   setVideoMaxSize,
   setVideoMaxFps,
+  setAdaptiveVideo,
   setDisplayId,
   setNewDisplayEnabled,
   setNewDisplayUseMainSize,
@@ -299,6 +354,10 @@ export const {
   setAudioBitRate,
   setAudioSource,
   setAudioDup,
+  setAudioBufferMs,
+  setAudioOutputDevice,
+  setAudioGain,
+  setAudioMuted,
   setStayAwake,
   setScreenOffTimeout,
   setPowerOffOnClose,
@@ -7,6 +7,24 @@ export interface DeviceRotation {
   height: number;
 }
 
+export interface AudioStats {
+  codec: string;
+  target_ms: number;
+  buffered_ms: number;
+  underflow_count: number;
+  underflow_ms: number;
+  dropped_samples: number;
+  compensation_rate: number;
+}
+
+export interface AdaptiveVideoStatus {
+  health: "healthy" | "fair" | "degraded";
+  bit_rate: number;
+  max_fps: number;
+  adjusted: boolean;
+  reason: string;
+}
+
 export interface OtherState {
   isLoading: boolean;
   maskArea: {
@@ -19,6 +37,8 @@ export interface OtherState {
   controlledDevices: ControlledDevice[];
   adbDevices: AdbDevice[];
   deviceRotations: Record<string, DeviceRotation>;
+  audioStats: AudioStats | null;
+  adaptiveVideoStatus: AdaptiveVideoStatus | null;
   updateInfo: {
     hasUpdate: boolean;
     currentVersion: string;
@@ -42,6 +62,8 @@ const initialState: OtherState = {
   controlledDevices: [],
   adbDevices: [],
   deviceRotations: {},
+  audioStats: null,
+  adaptiveVideoStatus: null,
   updateInfo: {
     hasUpdate: false,
     currentVersion: "Unknown",
@@ -81,6 +103,15 @@ const otherSlice = createSlice({
     setAdbDevices: (state, action: PayloadAction<OtherState["adbDevices"]>) => {
       state.adbDevices = action.payload;
     },
+    setAudioStats: (state, action: PayloadAction<OtherState["audioStats"]>) => {
+      state.audioStats = action.payload;
+    },
+    setAdaptiveVideoStatus: (
+      state,
+      action: PayloadAction<OtherState["adaptiveVideoStatus"]>
+    ) => {
+      state.adaptiveVideoStatus = action.payload;
+    },
     setUpdateInfo: (state, action: PayloadAction<OtherState["updateInfo"]>) => {
       state.updateInfo = action.payload;
     },
@@ -111,6 +142,8 @@ export const {
   setControlledDevices,
   setAdbDevices,
   setDeviceRotation,
+  setAudioStats,
+  setAdaptiveVideoStatus,
   setUpdateInfo,
   setShowUpdateDialog,
 } = otherSlice.actions;
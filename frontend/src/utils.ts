@@ -1,5 +1,35 @@
 import axios, { type AxiosResponse, type ResponseType } from "axios";
 
+const API_TOKEN_KEY = "apiToken";
+
+export function getApiToken(): string {
+  return localStorage.getItem(API_TOKEN_KEY) ?? "";
+}
+
+export function setApiToken(token: string) {
+  if (token) {
+    localStorage.setItem(API_TOKEN_KEY, token);
+  } else {
+    localStorage.removeItem(API_TOKEN_KEY);
+  }
+}
+
+// WebSocket and media elements cannot send headers, they pass the token in the query
+export function withApiToken(url: string): string {
+  const token = getApiToken();
+  if (!token) return url;
+  const sep = url.includes("?") ? "&" : "?";
+  return `${url}${sep}token=${encodeURIComponent(token)}`;
+}
+
+axios.interceptors.request.use((config) => {
+  const token = getApiToken();
+  if (token) {
+    config.headers.Authorization = `Bearer ${token}`;
+  }
+  return config;
+});
+
 export type ApiError<D = any> = {
   code?: number;
   message: string;
@@ -72,6 +102,20 @@ export async function requestPost<D = any>(
   });
 }
 
+// the file is sent as the raw request body, anything else goes in the query
+export async function requestUpload<D = any>(
+  url: string,
+  file: Blob,
+  params?: Record<string, string | number>,
+): Promise<{ message: string; data: D }> {
+  return await handleRequest(() =>
+    axios.post(url, file, {
+      params,
+      headers: { "Content-Type": "application/octet-stream" },
+    }),
+  );
+}
+
 export interface ControlledDevice {
   device_id: string;
   device_size: [number, number];
@@ -0,0 +1,554 @@
+use std::{
+    fs,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::LocalConfig,
+    mask::mapping::config::load_mapping_config,
+    scrcpy::{
+        adb::{Adb, Device},
+        constant::Keycode,
+    },
+    utils::RunMode,
+};
+
+// an instance on the same machine answers almost immediately
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// token for an instance with web API access control enabled
+const TOKEN_ENV: &str = "SCRCPY_MASK_TOKEN";
+
+pub const USAGE: &str = "\
+Usage:
+  scrcpy-mask [--headless]                start the app, --headless runs without the mask window
+
+Commands, sent to the running instance or run directly through adb when none is running:
+  scrcpy-mask devices                     list adb devices and the controlled ones
+  scrcpy-mask control <device_id> [--no-video] [--audio]
+  scrcpy-mask decontrol <device_id>
+  scrcpy-mask mapping activate <file>     activate a mapping file, or make it active for the next start
+  scrcpy-mask script run <file>           run a script file on the controlled device
+  scrcpy-mask key <KEYCODE> [--device <device_id>]
+  scrcpy-mask screenshot [--device <device_id>] [-o <file.png>]
+  scrcpy-mask help
+
+Set SCRCPY_MASK_TOKEN when the running instance requires a web API token.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    Devices,
+    Control {
+        device_id: String,
+        video: bool,
+        audio: bool,
+    },
+    Decontrol {
+        device_id: String,
+    },
+    MappingActivate {
+        file: String,
+    },
+    ScriptRun {
+        file: PathBuf,
+    },
+    Key {
+        keycode: String,
+        device_id: Option<String>,
+    },
+    Screenshot {
+        device_id: Option<String>,
+        output: Option<PathBuf>,
+    },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invocation {
+    App(RunMode),
+    Command(CliCommand),
+}
+
+/// Parse the arguments after the program name
+pub fn parse(args: &[String]) -> Result<Invocation, String> {
+    let Some(first) = args.first() else {
+        return Ok(Invocation::App(RunMode::Window));
+    };
+    if first.starts_with("--") && first != "--help" {
+        return parse_run_mode(args).map(Invocation::App);
+    }
+
+    let mut rest: Vec<String> = args[1..].to_vec();
+    let command = match first.as_str() {
+        "devices" => CliCommand::Devices,
+        "control" => {
+            let video = !take_flag(&mut rest, "--no-video");
+            let audio = take_flag(&mut rest, "--audio");
+            let [device_id] = positionals(rest, ["device_id"])?;
+            CliCommand::Control {
+                device_id,
+                video,
+                audio,
+            }
+        }
+        "decontrol" => {
+            let [device_id] = positionals(rest, ["device_id"])?;
+            CliCommand::Decontrol { device_id }
+        }
+        "mapping" => {
+            let [action, file] = positionals(rest, ["action", "file"])?;
+            if action != "activate" {
+                return Err(format!("Unknown mapping action: {}", action));
+            }
+            CliCommand::MappingActivate { file }
+        }
+        "script" => {
+            let [action, file] = positionals(rest, ["action", "file"])?;
+            if action != "run" {
+                return Err(format!("Unknown script action: {}", action));
+            }
+            CliCommand::ScriptRun { file: file.into() }
+        }
+        "key" => {
+            let device_id = take_option(&mut rest, &["--device"])?;
+            let [keycode] = positionals(rest, ["KEYCODE"])?;
+            CliCommand::Key { keycode, device_id }
+        }
+        "screenshot" => {
+            let device_id = take_option(&mut rest, &["--device"])?;
+            let output = take_option(&mut rest, &["-o", "--output"])?.map(PathBuf::from);
+            positionals(rest, [])?;
+            CliCommand::Screenshot { device_id, output }
+        }
+        "help" | "--help" | "-h" => CliCommand::Help,
+        _ => return Err(format!("Unknown command: {}", first)),
+    };
+    Ok(Invocation::Command(command))
+}
+
+/// `--headless` runs without the mask window
+fn parse_run_mode(args: &[String]) -> Result<RunMode, String> {
+    let mut mode = RunMode::Window;
+    for arg in args {
+        match arg.as_str() {
+            "--headless" => mode = RunMode::Headless,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(mode)
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != name);
+    args.len() != len
+}
+
+fn take_option(args: &mut Vec<String>, names: &[&str]) -> Result<Option<String>, String> {
+    let Some(index) = args.iter().position(|arg| names.contains(&arg.as_str())) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(format!("{} needs a value", args[index]));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+fn positionals<const N: usize>(args: Vec<String>, names: [&str; N]) -> Result<[String; N], String> {
+    if let Some(arg) = args.iter().find(|arg| arg.starts_with('-')) {
+        return Err(format!("Unknown option: {}", arg));
+    }
+    args.try_into().map_err(|args: Vec<String>| {
+        if args.len() < N {
+            format!("Missing argument: <{}>", names[args.len()])
+        } else {
+            format!("Unexpected argument: {}", args[N])
+        }
+    })
+}
+
+/// Run a command and return the process exit code
+pub fn run(command: CliCommand) -> i32 {
+    if command == CliCommand::Help {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    match runtime.block_on(execute(command)) {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+async fn execute(command: CliCommand) -> Result<String, String> {
+    let instance = Instance::connect().await?;
+    match instance {
+        Some(instance) => execute_on_instance(&instance, command).await,
+        None => execute_one_shot(command),
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    code: u16,
+    message: String,
+    data: Option<Value>,
+}
+
+/// Web API of a running scrcpy-mask
+struct Instance {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Instance {
+    fn base_url() -> String {
+        let config = LocalConfig::get();
+        let host = if config.web_bind_addr.is_unspecified() {
+            Ipv4Addr::LOCALHOST
+        } else {
+            config.web_bind_addr
+        };
+        format!("http://{}:{}", host, config.web_port)
+    }
+
+    async fn connect() -> Result<Option<Instance>, String> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Ok(token) = std::env::var(TOKEN_ENV) {
+            let value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token.trim()))
+                .map_err(|_| format!("Invalid {}", TOKEN_ENV))?;
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .default_headers(headers)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let instance = Instance {
+            client,
+            base_url: Self::base_url(),
+        };
+        let result = instance.get("/api/device/device_list").await;
+        match result {
+            Ok(_) => Ok(Some(instance)),
+            Err(RequestError::Unreachable) => Ok(None),
+            Err(RequestError::Failed(e)) => Err(e),
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Vec<u8>, RequestError> {
+        let response = request.send().await.map_err(|e| {
+            if e.is_connect() {
+                RequestError::Unreachable
+            } else {
+                RequestError::Failed(e.to_string())
+            }
+        })?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| RequestError::Failed(e.to_string()))?;
+        if !status.is_success() {
+            // api errors are json, extractor rejections are plain text
+            let message = serde_json::from_slice::<ApiResponse>(&body)
+                .map(|res| res.message)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&body).into_owned());
+            return Err(RequestError::Failed(format!("{}: {}", status, message)));
+        }
+        Ok(body.to_vec())
+    }
+
+    fn parse(body: Vec<u8>) -> Result<ApiResponse, RequestError> {
+        let res: ApiResponse = serde_json::from_slice(&body)
+            .map_err(|e| RequestError::Failed(format!("Invalid response: {}", e)))?;
+        if res.code != 200 {
+            return Err(RequestError::Failed(res.message));
+        }
+        Ok(res)
+    }
+
+    async fn get(&self, path: &str) -> Result<ApiResponse, RequestError> {
+        let url = format!("{}{}", self.base_url, path);
+        Self::parse(self.send(self.client.get(url)).await?)
+    }
+
+    async fn post(&self, path: &str, body: Value) -> Result<ApiResponse, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let body = self
+            .send(self.client.post(url).json(&body))
+            .await
+            .map_err(RequestError::into_message)?;
+        Self::parse(body).map_err(RequestError::into_message)
+    }
+
+    async fn post_bytes(&self, path: &str, body: Value) -> Result<Vec<u8>, String> {
+        let url = format!("{}{}", self.base_url, path);
+        self.send(self.client.post(url).json(&body))
+            .await
+            .map_err(RequestError::into_message)
+    }
+}
+
+enum RequestError {
+    Unreachable,
+    Failed(String),
+}
+
+impl RequestError {
+    fn into_message(self) -> String {
+        match self {
+            RequestError::Unreachable => "The scrcpy-mask instance stopped responding".to_string(),
+            RequestError::Failed(e) => e,
+        }
+    }
+}
+
+async fn execute_on_instance(instance: &Instance, command: CliCommand) -> Result<String, String> {
+    match command {
+        CliCommand::Devices => {
+            let res = instance
+                .get("/api/device/device_list")
+                .await
+                .map_err(RequestError::into_message)?;
+            Ok(format_device_list(&res.data.unwrap_or_default()))
+        }
+        CliCommand::Control {
+            device_id,
+            video,
+            audio,
+        } => instance
+            .post(
+                "/api/device/control_device",
+                json!({ "device_id": device_id, "video": video, "audio": audio }),
+            )
+            .await
+            .map(|res| res.message),
+        CliCommand::Decontrol { device_id } => instance
+            .post(
+                "/api/device/decontrol_device",
+                json!({ "device_id": device_id }),
+            )
+            .await
+            .map(|res| res.message),
+        CliCommand::MappingActivate { file } => instance
+            .post(
+                "/api/mapping/change_active_mapping",
+                json!({ "file": file }),
+            )
+            .await
+            .map(|res| res.message),
+        CliCommand::ScriptRun { file } => {
+            let script = read_script(&file)?;
+            instance
+                .post("/api/script/run", json!({ "script": script }))
+                .await
+                .map(|res| res.message)
+        }
+        CliCommand::Key { keycode, device_id } => {
+            parse_keycode(&keycode)?;
+            instance
+                .post(
+                    "/api/device/control/send_key",
+                    json!({ "keycode": keycode, "device_id": device_id }),
+                )
+                .await
+                .map(|res| res.message)
+        }
+        CliCommand::Screenshot { device_id, output } => {
+            let device_id = match device_id {
+                Some(device_id) => device_id,
+                None => main_device_id(instance).await?,
+            };
+            let image = instance
+                .post_bytes("/api/device/adb_screenshot", json!({ "id": device_id }))
+                .await?;
+            save_screenshot(output, &image)
+        }
+        CliCommand::Help => Ok(USAGE.to_string()),
+    }
+}
+
+/// Without an instance only the commands adb can do on its own are available
+fn execute_one_shot(command: CliCommand) -> Result<String, String> {
+    let not_running = || {
+        format!(
+            "No scrcpy-mask instance is running on {}, start it first (--headless works without a display)",
+            Instance::base_url()
+        )
+    };
+    match command {
+        CliCommand::Devices => {
+            let devices = Adb::new(LocalConfig::get().adb_path).devices()?;
+            Ok(format_device_list(&json!({ "adb_devices": devices })))
+        }
+        CliCommand::Control { .. }
+        | CliCommand::Decontrol { .. }
+        | CliCommand::ScriptRun { .. } => Err(not_running()),
+        CliCommand::MappingActivate { mut file } => {
+            if !file.ends_with(".json") {
+                file.push_str(".json");
+            }
+            load_mapping_config(&file)?;
+            LocalConfig::set_active_mapping_file(file.clone());
+            Ok(format!(
+                "{} will be the active mapping on the next start",
+                file
+            ))
+        }
+        CliCommand::Key { keycode, device_id } => {
+            let code = parse_keycode(&keycode)?;
+            let device_id = resolve_adb_device(device_id)?;
+            Device::shell_logged(&device_id, ["input", "keyevent", &code.to_string()])?;
+            Ok(format!("{} sent to {}", keycode, device_id))
+        }
+        CliCommand::Screenshot { device_id, output } => {
+            let device_id = resolve_adb_device(device_id)?;
+            let image = Device::screenshot(&device_id)?;
+            save_screenshot(output, &image)
+        }
+        CliCommand::Help => Ok(USAGE.to_string()),
+    }
+}
+
+fn parse_keycode(keycode: &str) -> Result<u32, String> {
+    serde_json::from_value::<Keycode>(Value::String(keycode.to_string()))
+        .map(|keycode| keycode as u32)
+        .map_err(|_| {
+            format!(
+                "Unknown keycode: {}, use names like Home, Back or VolumeUp",
+                keycode
+            )
+        })
+}
+
+fn read_script(file: &Path) -> Result<String, String> {
+    fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))
+}
+
+async fn main_device_id(instance: &Instance) -> Result<String, String> {
+    let res = instance
+        .get("/api/device/device_list")
+        .await
+        .map_err(RequestError::into_message)?;
+    res.data
+        .as_ref()
+        .and_then(|data| data["controlled_devices"].as_array())
+        .and_then(|devices| devices.iter().find(|device| device["main"] == true))
+        .and_then(|device| device["device_id"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| "No device is controlled, choose one with --device".to_string())
+}
+
+/// The given device, or the only one adb sees
+fn resolve_adb_device(device_id: Option<String>) -> Result<String, String> {
+    if let Some(device_id) = device_id {
+        return Ok(device_id);
+    }
+    let devices = Adb::new(LocalConfig::get().adb_path).devices()?;
+    let mut online = devices
+        .into_iter()
+        .filter(|device| device.status == "device");
+    match (online.next(), online.next()) {
+        (Some(device), None) => Ok(device.id),
+        (None, _) => Err("No adb device is connected".to_string()),
+        (Some(_), Some(_)) => {
+            Err("Several adb devices are connected, choose one with --device".to_string())
+        }
+    }
+}
+
+fn save_screenshot(output: Option<PathBuf>, image: &[u8]) -> Result<String, String> {
+    let output = output.unwrap_or_else(|| {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        PathBuf::from(format!("screenshot-{}.png", secs))
+    });
+    fs::write(&output, image)
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+    Ok(output.display().to_string())
+}
+
+fn format_device_list(data: &Value) -> String {
+    let controlled = data["controlled_devices"].as_array();
+    let mut lines = Vec::new();
+    for device in data["adb_devices"].as_array().into_iter().flatten() {
+        let id = device["id"].as_str().unwrap_or_default();
+        let status = device["status"].as_str().unwrap_or_default();
+        let role = controlled
+            .and_then(|devices| devices.iter().find(|d| d["device_id"] == id))
+            .map(|d| if d["main"] == true { "main" } else { "sub" })
+            .unwrap_or("-");
+        lines.push(format!("{}\t{}\t{}", id, status, role));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Invocation, String> {
+        parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn run_modes() {
+        assert_eq!(parse_args(&[]), Ok(Invocation::App(RunMode::Window)));
+        assert_eq!(
+            parse_args(&["--headless"]),
+            Ok(Invocation::App(RunMode::Headless))
+        );
+        assert!(parse_args(&["--headless", "--video"]).is_err());
+    }
+
+    #[test]
+    fn commands_with_options() {
+        assert_eq!(
+            parse_args(&["control", "emulator-5554", "--no-video"]),
+            Ok(Invocation::Command(CliCommand::Control {
+                device_id: "emulator-5554".to_string(),
+                video: false,
+                audio: false,
+            }))
+        );
+        assert_eq!(
+            parse_args(&["screenshot", "-o", "a.png", "--device", "abc"]),
+            Ok(Invocation::Command(CliCommand::Screenshot {
+                device_id: Some("abc".to_string()),
+                output: Some(PathBuf::from("a.png")),
+            }))
+        );
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(parse_args(&["control"]).is_err());
+        assert!(parse_args(&["mapping", "remove", "a.json"]).is_err());
+        assert!(parse_args(&["key", "Home", "--force"]).is_err());
+        assert!(parse_args(&["screenshot", "-o"]).is_err());
+        assert!(parse_args(&["--headles"]).is_err());
+    }
+}
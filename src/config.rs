@@ -1,25 +1,551 @@
 use std::{
-    fs::{File, create_dir_all},
-    io::Write,
+    fs::{self, create_dir_all},
     net::Ipv4Addr,
     path::PathBuf,
     sync::RwLock,
 };
 
 use crate::{
-    DEFAULT_LANGUAGE,
+    DEFAULT_LANGUAGE, is_available_language,
     scrcpy::media::{AudioCodec, AudioSource, VideoCodec},
-    utils::{relate_to_data_path, relate_to_root_path},
+    utils::{is_safe_file_name, relate_to_data_path, relate_to_root_path, write_data_file},
 };
 use once_cell::sync::Lazy;
 use paste::paste;
 use rust_i18n::t;
 use serde::{Deserialize, Serialize};
-use serde_json::to_string_pretty;
+use serde_json::{Map, Value, to_string_pretty};
 
 static CONFIG: Lazy<RwLock<LocalConfig>> = Lazy::new(|| RwLock::default());
 
 pub const AUDIO_BIT_RATE_MIN: u32 = 16_000;
+pub const AUDIO_GAIN_MAX: f32 = 4.0;
+pub const AUDIO_BUFFER_MS_MIN: u32 = 10;
+pub const AUDIO_BUFFER_MS_MAX: u32 = 1000;
+
+/// Target audio buffering in milliseconds for each codec, trading latency for stability
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioBufferTargets {
+    pub opus: u32,
+    pub aac: u32,
+    pub flac: u32,
+    pub raw: u32,
+}
+
+impl Default for AudioBufferTargets {
+    fn default() -> Self {
+        Self {
+            opus: 50,
+            aac: 50,
+            flac: 120,
+            raw: 50,
+        }
+    }
+}
+
+impl AudioBufferTargets {
+    pub fn for_codec(&self, codec: AudioCodec) -> u32 {
+        match codec {
+            AudioCodec::Opus => self.opus,
+            AudioCodec::Aac => self.aac,
+            AudioCodec::Flac => self.flac,
+            AudioCodec::Raw => self.raw,
+        }
+    }
+
+    /// Brings every target within the allowed range, true if any was changed
+    pub fn clamp(&mut self) -> bool {
+        let mut changed = false;
+        for value in [&mut self.opus, &mut self.aac, &mut self.flac, &mut self.raw] {
+            let clamped = (*value).clamp(AUDIO_BUFFER_MS_MIN, AUDIO_BUFFER_MS_MAX);
+            changed |= clamped != *value;
+            *value = clamped;
+        }
+        changed
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for value in [self.opus, self.aac, self.flac, self.raw] {
+            if !(AUDIO_BUFFER_MS_MIN..=AUDIO_BUFFER_MS_MAX).contains(&value) {
+                return Err(format!(
+                    "Audio buffer must be between {} and {} ms",
+                    AUDIO_BUFFER_MS_MIN, AUDIO_BUFFER_MS_MAX
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bounds for the adaptive video mode, which tunes bit rate and fps to the link health
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveVideoConfig {
+    pub enabled: bool,
+    pub min_bit_rate: u32,
+    pub max_bit_rate: u32,
+    pub min_fps: u32,
+    pub max_fps: u32,
+}
+
+impl Default for AdaptiveVideoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_bit_rate: 2_000_000,
+            max_bit_rate: 16_000_000,
+            min_fps: 30,
+            max_fps: 60,
+        }
+    }
+}
+
+impl AdaptiveVideoConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_bit_rate == 0 || self.min_bit_rate > self.max_bit_rate {
+            return Err("Adaptive video bit rate bounds must satisfy 0 < min <= max".to_string());
+        }
+        if self.min_fps == 0 || self.min_fps > self.max_fps {
+            return Err("Adaptive video fps bounds must satisfy 0 < min <= max".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// What a web API token may do, every valid token can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthScope {
+    Read,
+    /// Control devices, run scripts, record and capture
+    Control,
+    /// Change the local config, mappings and tokens
+    Config,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub name: String,
+    pub token: String,
+    pub scopes: Vec<AuthScope>,
+}
+
+impl ApiToken {
+    pub fn allows(&self, scope: AuthScope) -> bool {
+        scope == AuthScope::Read || self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebAuthConfig {
+    pub enabled: bool,
+    /// Requests from this machine skip authentication
+    pub allow_loopback: bool,
+    pub tokens: Vec<ApiToken>,
+}
+
+impl Default for WebAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow_loopback: true,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldKind {
+    Bool,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    Number {
+        min: f64,
+        max: f64,
+    },
+    String,
+    Enum {
+        values: &'static [&'static str],
+    },
+    Ipv4,
+    /// `[x, y]`
+    Point,
+    /// A nested struct, checked by its own type
+    Object,
+}
+
+/// When a changed value is picked up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyMode {
+    Immediate,
+    NextConnection,
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ConfigField {
+    pub key: &'static str,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+    pub apply: ApplyMode,
+    /// False for values owned by another api or by the mask window
+    pub editable: bool,
+    /// Mask pixels, may be sent in physical pixels
+    pub pixels: bool,
+    /// Switched by config profiles, the rest stays per machine
+    pub profile: bool,
+    /// Locale key of the reply when only this field is set
+    #[serde(skip)]
+    pub set_message: Option<&'static str>,
+    /// Locale key of the error for any invalid value
+    #[serde(skip)]
+    pub type_error: Option<&'static str>,
+}
+
+impl ConfigField {
+    const fn new(key: &'static str, kind: FieldKind, apply: ApplyMode) -> Self {
+        Self {
+            key,
+            kind,
+            apply,
+            editable: true,
+            pixels: false,
+            profile: false,
+            set_message: None,
+            type_error: None,
+        }
+    }
+
+    const fn locked(mut self) -> Self {
+        self.editable = false;
+        self
+    }
+
+    const fn pixels(mut self) -> Self {
+        self.pixels = true;
+        self
+    }
+
+    const fn profile(mut self) -> Self {
+        self.profile = true;
+        self
+    }
+
+    const fn set_message(mut self, key: &'static str) -> Self {
+        self.set_message = Some(key);
+        self
+    }
+
+    const fn type_error(mut self, key: &'static str) -> Self {
+        self.type_error = Some(key);
+        self
+    }
+
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        self.check_value(value).map_err(|e| match self.type_error {
+            Some(key) => t!(key).to_string(),
+            None => e,
+        })
+    }
+
+    fn check_value(&self, value: &Value) -> Result<(), String> {
+        let out_of_range = |min: String, max: String| {
+            t!("web.config.valueOutOfRange", key => self.key, min => min, max => max).to_string()
+        };
+        let valid = match self.kind {
+            FieldKind::Bool => value.is_boolean(),
+            FieldKind::Integer { min, max } => {
+                let Some(v) = value.as_i64() else {
+                    return Err(t!("web.config.valueTypeError", key => self.key).to_string());
+                };
+                if !(min..=max).contains(&v) {
+                    return Err(out_of_range(min.to_string(), max.to_string()));
+                }
+                true
+            }
+            FieldKind::Number { min, max } => {
+                let Some(v) = value.as_f64() else {
+                    return Err(t!("web.config.valueTypeError", key => self.key).to_string());
+                };
+                if !(min..=max).contains(&v) {
+                    return Err(out_of_range(min.to_string(), max.to_string()));
+                }
+                true
+            }
+            FieldKind::String => value.is_string(),
+            FieldKind::Enum { values } => {
+                if !value.as_str().is_some_and(|v| values.contains(&v)) {
+                    return Err(t!(
+                        "web.config.valueNotInList",
+                        key => self.key,
+                        values => values.join(", ")
+                    )
+                    .to_string());
+                }
+                true
+            }
+            FieldKind::Ipv4 => value
+                .as_str()
+                .is_some_and(|v| v.parse::<Ipv4Addr>().is_ok()),
+            FieldKind::Point => value.as_array().is_some_and(|v| {
+                v.len() == 2
+                    && v.iter()
+                        .all(|n| n.as_i64().is_some_and(|n| i32::try_from(n).is_ok()))
+            }),
+            // the type is checked when the config is rebuilt
+            FieldKind::Object => value.is_object(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(t!("web.config.valueTypeError", key => self.key).to_string())
+        }
+    }
+}
+
+const U32_MAX: i64 = u32::MAX as i64;
+const I32_MAX: i64 = i32::MAX as i64;
+
+/// Every field of [`LocalConfig`], served at `/api/config/schema` and used to check updates
+pub static CONFIG_SCHEMA: &[ConfigField] = {
+    use ApplyMode::*;
+    use FieldKind::*;
+    &[
+        ConfigField::new("web_port", Integer { min: 1, max: 65535 }, Restart)
+            .set_message("web.config.restartToApplyWebPort")
+            .type_error("web.config.webPortMustBeU16"),
+        ConfigField::new("web_bind_addr", Ipv4, Restart)
+            .set_message("web.config.restartToApplyWebBindAddr")
+            .type_error("web.config.webBindAddrMustBeIpv4"),
+        ConfigField::new("controller_port", Integer { min: 1, max: 65535 }, Restart)
+            .set_message("web.config.restartToApplyControllerPort")
+            .type_error("web.config.controllerPortMustBeU16"),
+        // managed through /api/auth
+        ConfigField::new("web_auth", Object, Immediate).locked(),
+        ConfigField::new("adb_path", String, Immediate)
+            .set_message("web.config.adbPathSetSuccess")
+            .type_error("web.config.adbPathMustBeString"),
+        ConfigField::new("adb_connect_address", String, Immediate)
+            .set_message("web.config.setAdbConnectAddressSuccess")
+            .type_error("web.config.adbConnectAddressMustBeString"),
+        ConfigField::new("always_on_top", Bool, Immediate)
+            .type_error("web.config.alwaysOnTopMustBeBool"),
+        ConfigField::new("titlebar_visible", Bool, Immediate)
+            .type_error("web.config.titlebarVisibleMustBeBool"),
+        // toggled from the mask titlebar
+        ConfigField::new("stats_overlay_visible", Bool, Immediate).locked(),
+        ConfigField::new(
+            "vertical_mask_height",
+            Integer {
+                min: 1,
+                max: U32_MAX,
+            },
+            Immediate,
+        )
+        .pixels()
+        .set_message("web.config.setVerticalMaskHeightSuccess")
+        .type_error("web.config.verticalMaskHeightMustBeu32"),
+        ConfigField::new(
+            "horizontal_mask_width",
+            Integer {
+                min: 1,
+                max: U32_MAX,
+            },
+            Immediate,
+        )
+        .pixels()
+        .set_message("web.config.setHorizontalMaskWidthSuccess")
+        .type_error("web.config.horizontalMaskWidthMustBeu32"),
+        ConfigField::new("vertical_position", Point, Immediate)
+            .pixels()
+            .set_message("web.config.setVerticalPositionSuccess")
+            .type_error("web.config.verticalPositionTypeError"),
+        ConfigField::new("horizontal_position", Point, Immediate)
+            .pixels()
+            .set_message("web.config.setHorizontalPositionSuccess")
+            .type_error("web.config.horizontalPositionTypeError"),
+        // managed through /api/mapping/change_active_mapping
+        ConfigField::new("active_mapping_file", String, Immediate)
+            .locked()
+            .profile(),
+        ConfigField::new(
+            "mapping_label_opacity",
+            Number { min: 0.0, max: 1.0 },
+            Immediate,
+        )
+        .profile()
+        .set_message("web.config.setMappingLabelOpacitySuccess")
+        .type_error("web.config.mappingLabelOpacityRange"),
+        ConfigField::new("language", String, Immediate)
+            .set_message("web.config.setLanguageSuccess")
+            .type_error("web.config.languageMustBeString"),
+        ConfigField::new("clipboard_sync", Bool, Immediate)
+            .profile()
+            .set_message("web.config.setClipboardSyncSuccess")
+            .type_error("web.config.clipboardSyncTypeError"),
+        ConfigField::new(
+            "video_codec",
+            Enum {
+                values: &["H264", "H265", "AV1"],
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setVideoCodecSuccess")
+        .type_error("web.config.invalidVideoCodec"),
+        ConfigField::new(
+            "video_bit_rate",
+            Integer {
+                min: 1,
+                max: U32_MAX,
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setVideoBitRateSuccess")
+        .type_error("web.config.videoBitRateTypeError"),
+        ConfigField::new(
+            "video_max_size",
+            Integer {
+                min: 0,
+                max: U32_MAX,
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setVideoMaxSizeSuccess")
+        .type_error("web.config.videoMaxSizeTypeError"),
+        ConfigField::new(
+            "video_max_fps",
+            Integer {
+                min: 0,
+                max: U32_MAX,
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setVideoMaxFpsSuccess")
+        .type_error("web.config.videoMaxFpsTypeError"),
+        ConfigField::new("adaptive_video", Object, Immediate).profile(),
+        ConfigField::new(
+            "display_id",
+            Integer {
+                min: 0,
+                max: I32_MAX,
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setDisplayIdSuccess")
+        .type_error("web.config.displayIdTypeError"),
+        ConfigField::new("new_display_enabled", Bool, NextConnection)
+            .profile()
+            .set_message("web.config.setNewDisplayEnabledSuccess")
+            .type_error("web.config.newDisplayEnabledTypeError"),
+        ConfigField::new("new_display_use_main_size", Bool, NextConnection)
+            .profile()
+            .set_message("web.config.setNewDisplayUseMainSizeSuccess")
+            .type_error("web.config.newDisplayUseMainSizeTypeError"),
+        ConfigField::new(
+            "new_display_width",
+            Integer {
+                min: 1,
+                max: U32_MAX,
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setNewDisplayWidthSuccess")
+        .type_error("web.config.newDisplayWidthTypeError"),
+        ConfigField::new(
+            "new_display_height",
+            Integer {
+                min: 1,
+                max: U32_MAX,
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setNewDisplayHeightSuccess")
+        .type_error("web.config.newDisplayHeightTypeError"),
+        ConfigField::new(
+            "new_display_dpi",
+            Integer {
+                min: 1,
+                max: U32_MAX,
+            },
+            NextConnection,
+        )
+        .profile()
+        .set_message("web.config.setNewDisplayDpiSuccess")
+        .type_error("web.config.newDisplayDpiTypeError"),
+        ConfigField::new(
+            "audio_codec",
+            Enum {
+                values: &["OPUS", "AAC", "FLAC", "RAW"],
+            },
+            NextConnection,
+        )
+        .profile(),
+        ConfigField::new(
+            "audio_bit_rate",
+            Integer {
+                min: AUDIO_BIT_RATE_MIN as i64,
+                max: U32_MAX,
+            },
+            NextConnection,
+        )
+        .profile(),
+        ConfigField::new(
+            "audio_source",
+            Enum {
+                values: &["OUTPUT", "PLAYBACK", "MIC"],
+            },
+            NextConnection,
+        )
+        .profile(),
+        ConfigField::new("audio_dup", Bool, NextConnection).profile(),
+        ConfigField::new("audio_buffer_ms", Object, NextConnection).profile(),
+        ConfigField::new("audio_output_device", String, Immediate).profile(),
+        ConfigField::new(
+            "audio_gain",
+            Number {
+                min: 0.0,
+                max: AUDIO_GAIN_MAX as f64,
+            },
+            Immediate,
+        )
+        .profile(),
+        ConfigField::new("audio_muted", Bool, Immediate).profile(),
+        ConfigField::new("stay_awake", Bool, NextConnection).profile(),
+        ConfigField::new(
+            "screen_off_timeout",
+            Integer {
+                min: -1,
+                max: I32_MAX,
+            },
+            NextConnection,
+        )
+        .profile(),
+        ConfigField::new("power_off_on_close", Bool, Immediate).profile(),
+        // managed through /api/config/profiles
+        ConfigField::new("active_profile", String, Immediate).locked(),
+    ]
+};
+
+pub fn config_field(key: &str) -> Option<&'static ConfigField> {
+    CONFIG_SCHEMA.iter().find(|field| field.key == key)
+}
+
+fn is_profile_key(key: &str) -> bool {
+    config_field(key).is_some_and(|field| field.profile)
+}
 
 fn default_web_bind_addr() -> Ipv4Addr {
     Ipv4Addr::new(127, 0, 0, 1)
@@ -51,12 +577,14 @@ pub struct LocalConfig {
     #[serde(default = "default_web_bind_addr")]
     pub web_bind_addr: Ipv4Addr,
     pub controller_port: u16,
+    pub web_auth: WebAuthConfig,
     // adb
     pub adb_path: String,
     pub adb_connect_address: String,
     // mask
     pub always_on_top: bool,
     pub titlebar_visible: bool,
+    pub stats_overlay_visible: bool,
     pub vertical_mask_height: u32,
     pub horizontal_mask_width: u32,
     pub vertical_position: (i32, i32),
@@ -73,6 +601,7 @@ pub struct LocalConfig {
     pub video_bit_rate: u32,
     pub video_max_size: u32,
     pub video_max_fps: u32,
+    pub adaptive_video: AdaptiveVideoConfig,
     pub display_id: i32,
     pub new_display_enabled: bool,
     pub new_display_use_main_size: bool,
@@ -84,10 +613,17 @@ pub struct LocalConfig {
     pub audio_bit_rate: u32,
     pub audio_source: AudioSource,
     pub audio_dup: bool,
+    pub audio_buffer_ms: AudioBufferTargets,
+    // audio output, empty device means the system default
+    pub audio_output_device: String,
+    pub audio_gain: f32,
+    pub audio_muted: bool,
     // device behavior
     pub stay_awake: bool,
     pub screen_off_timeout: i32,
     pub power_off_on_close: bool,
+    // last applied profile, empty when none
+    pub active_profile: String,
 }
 
 impl Default for LocalConfig {
@@ -98,8 +634,10 @@ impl Default for LocalConfig {
             web_port: 27799,
             web_bind_addr: default_web_bind_addr(),
             controller_port: 27798,
+            web_auth: WebAuthConfig::default(),
             always_on_top: true,
             titlebar_visible: true,
+            stats_overlay_visible: false,
             vertical_mask_height: 720,
             horizontal_mask_width: 1280,
             vertical_position: (100, 100),
@@ -112,6 +650,7 @@ impl Default for LocalConfig {
             video_bit_rate: 8_000000, // 8M
             video_max_size: 0,        // default no limit
             video_max_fps: 0,         // default no limit
+            adaptive_video: AdaptiveVideoConfig::default(),
             display_id: 0,
             new_display_enabled: false,
             new_display_use_main_size: true,
@@ -122,9 +661,14 @@ impl Default for LocalConfig {
             audio_bit_rate: 128_000,
             audio_source: AudioSource::Output,
             audio_dup: false,
+            audio_buffer_ms: AudioBufferTargets::default(),
+            audio_output_device: String::new(),
+            audio_gain: 1.0,
+            audio_muted: false,
             stay_awake: false,
             screen_off_timeout: -1, // default keep device setting
             power_off_on_close: false,
+            active_profile: String::new(),
         }
     }
 }
@@ -164,14 +708,12 @@ impl LocalConfig {
             create_dir_all(parent)
                 .map_err(|e| format!("{}: {}", t!("localConfig.createConfigDirError"), e))?;
         }
-        let mut file = File::create(path)
-            .map_err(|e| format!("{}: {}", t!("localConfig.createConfigError"), e))?;
-        file.write_all(config_json.as_bytes())
-            .map_err(|e| format!("{}: {}", t!("localConfig.writeConfigError"), e))?;
-        Ok(())
+        write_data_file(&path, config_json.as_bytes())
+            .map_err(|e| format!("{}: {}", t!("localConfig.writeConfigError"), e))
     }
 
-    pub fn load() -> Result<(), String> {
+    /// Reads config.json without touching the config in memory
+    pub fn read_file() -> Result<LocalConfig, String> {
         let path = relate_to_data_path(["config.json"]);
         let config_string = std::fs::read_to_string(&path).map_err(|e| {
             format!(
@@ -181,12 +723,46 @@ impl LocalConfig {
                 e
             )
         })?;
-        let config: LocalConfig = serde_json::from_str(&config_string)
+        Self::parse(&config_string)
+    }
+
+    /// Parses the text of config.json
+    pub fn parse(config_string: &str) -> Result<LocalConfig, String> {
+        let mut config: LocalConfig = serde_json::from_str(config_string)
             .map_err(|e| format!("{}: {}", t!("localConfig.serializeConfigError"), e))?;
-        *CONFIG.write().unwrap() = config;
+        config.clamp_loaded();
+        Ok(config)
+    }
+
+    /// config.json can be edited by hand, keep values the api would reject within their bounds
+    fn clamp_loaded(&mut self) {
+        if !(0.0..=AUDIO_GAIN_MAX).contains(&self.audio_gain) {
+            log::warn!(
+                "[Config] audio_gain {} is out of range, clamped to 0-{}",
+                self.audio_gain,
+                AUDIO_GAIN_MAX
+            );
+            self.audio_gain = self.audio_gain.clamp(0.0, AUDIO_GAIN_MAX);
+        }
+        if self.audio_buffer_ms.clamp() {
+            log::warn!(
+                "[Config] audio_buffer_ms is out of range, clamped to {}-{} ms",
+                AUDIO_BUFFER_MS_MIN,
+                AUDIO_BUFFER_MS_MAX
+            );
+        }
+    }
+
+    pub fn load() -> Result<(), String> {
+        *CONFIG.write().unwrap() = Self::read_file()?;
         Ok(())
     }
 
+    /// Replaces the config in memory without writing it back, used when config.json was edited outside the app
+    pub fn reload(config: LocalConfig) {
+        *CONFIG.write().unwrap() = config;
+    }
+
     pub fn get() -> LocalConfig {
         CONFIG.read().unwrap().clone()
     }
@@ -195,14 +771,20 @@ impl LocalConfig {
         CONFIG.read().unwrap().clipboard_sync
     }
 
+    pub fn get_web_auth() -> WebAuthConfig {
+        CONFIG.read().unwrap().web_auth.clone()
+    }
+
     define_setter!(
         (web_port, u16),
         (web_bind_addr, Ipv4Addr),
         (controller_port, u16),
+        (web_auth, WebAuthConfig),
         (adb_path, String),
         (adb_connect_address, String),
         (always_on_top, bool),
         (titlebar_visible, bool),
+        (stats_overlay_visible, bool),
         (vertical_mask_height, u32),
         (horizontal_mask_width, u32),
         (vertical_position, (i32, i32)),
@@ -215,6 +797,7 @@ impl LocalConfig {
         (video_bit_rate, u32),
         (video_max_size, u32),
         (video_max_fps, u32),
+        (adaptive_video, AdaptiveVideoConfig),
         (display_id, i32),
         (new_display_enabled, bool),
         (new_display_use_main_size, bool),
@@ -225,8 +808,301 @@ impl LocalConfig {
         (audio_bit_rate, u32),
         (audio_source, AudioSource),
         (audio_dup, bool),
+        (audio_buffer_ms, AudioBufferTargets),
+        (audio_output_device, String),
+        (audio_gain, f32),
+        (audio_muted, bool),
         (stay_awake, bool),
         (screen_off_timeout, i32),
         (power_off_on_close, bool),
+        (active_profile, String),
     );
+
+    /// Checks that hold across fields, the schema covers single values
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_available_language(&self.language) {
+            return Err(format!(
+                "{}: {}",
+                t!("web.config.invalidLanguage"),
+                self.language
+            ));
+        }
+        if self.audio_dup && !self.audio_source.is_playback() {
+            return Err("Audio duplication requires playback source".to_string());
+        }
+        self.adaptive_video.validate()?;
+        self.audio_buffer_ms.validate()
+    }
+
+    /// Checks `values` against the schema and returns the updated config without storing it
+    pub fn with_updates(values: &Map<String, Value>) -> Result<LocalConfig, String> {
+        Self::get().updated(values)
+    }
+
+    /// Checks `values` again on the config of the moment and stores them,
+    /// returns the config before and after
+    pub fn update(values: &Map<String, Value>) -> Result<(LocalConfig, LocalConfig), String> {
+        let mut current = CONFIG.write().unwrap();
+        let config = current.updated(values)?;
+        let previous = std::mem::replace(&mut *current, config.clone());
+        drop(current);
+        Self::save()?;
+        Ok((previous, config))
+    }
+
+    fn updated(&self, values: &Map<String, Value>) -> Result<LocalConfig, String> {
+        for (key, value) in values {
+            let field = config_field(key)
+                .ok_or_else(|| format!("{}: {}", t!("web.config.invalidMappingKey"), key))?;
+            if !field.editable {
+                return Err(t!("web.config.fieldNotEditable", key => key).to_string());
+            }
+            field.check(value)?;
+        }
+        let mut config = self.overlaid(values, |_| true)?;
+        // switching away from playback turns duplication off unless it was asked for
+        if !config.audio_source.is_playback() && !values.contains_key("audio_dup") {
+            config.audio_dup = false;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Replaces the whole config, used when applying profiles and bundles
+    pub fn replace(config: LocalConfig) -> Result<(), String> {
+        *CONFIG.write().unwrap() = config;
+        Self::save()
+    }
+
+    /// Overlays `values` on a copy of the current config, unknown keys are ignored
+    pub fn merged_with(
+        values: &Map<String, Value>,
+        keys: impl Fn(&str) -> bool,
+    ) -> Result<LocalConfig, String> {
+        Self::get().overlaid(values, keys)
+    }
+
+    /// Overlays `values` on a copy of this config, unknown keys are ignored
+    pub fn overlaid(
+        &self,
+        values: &Map<String, Value>,
+        keys: impl Fn(&str) -> bool,
+    ) -> Result<LocalConfig, String> {
+        let mut config = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => return Err(t!("localConfig.serializeConfigError").to_string()),
+        };
+        for (key, value) in values {
+            if config.contains_key(key) && keys(key) {
+                config.insert(key.clone(), value.clone());
+            }
+        }
+        serde_json::from_value(Value::Object(config))
+            .map_err(|e| format!("{}: {}", t!("localConfig.serializeConfigError"), e))
+    }
+
+    fn check_profile_name(name: &str) -> Result<&str, String> {
+        let name = name.trim();
+        if name.is_empty() || !is_safe_file_name(name) {
+            return Err(format!(
+                "{}: {}",
+                t!("localConfig.invalidProfileName"),
+                name
+            ));
+        }
+        Ok(name)
+    }
+
+    fn profile_path(name: &str) -> Result<PathBuf, String> {
+        let name = Self::check_profile_name(name)?;
+        Ok(relate_to_data_path(["profiles", &format!("{}.json", name)]))
+    }
+
+    /// Checks a profile from outside against the schema and `base`, returns the values a profile may hold
+    pub fn check_profile(
+        base: &LocalConfig,
+        name: &str,
+        values: &Map<String, Value>,
+    ) -> Result<Map<String, Value>, String> {
+        Self::check_profile_name(name)?;
+        let values: Map<String, Value> = values
+            .iter()
+            .filter(|(key, _)| is_profile_key(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        for (key, value) in &values {
+            config_field(key).unwrap().check(value).map_err(|e| {
+                format!("{}: {}", t!("localConfig.invalidProfile", name => name), e)
+            })?;
+        }
+        base.overlaid(&values, |_| true)
+            .and_then(|config| config.validate())
+            .map_err(|e| format!("{}: {}", t!("localConfig.invalidProfile", name => name), e))?;
+        Ok(values)
+    }
+
+    pub fn list_profiles() -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(relate_to_data_path(["profiles"]))
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter_map(|entry| {
+                        let path = entry.path();
+                        if path.extension()? != "json" {
+                            return None;
+                        }
+                        path.file_stem()?.to_str().map(str::to_string)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    pub fn read_profile(name: &str) -> Result<Map<String, Value>, String> {
+        let path = Self::profile_path(name)?;
+        let text = fs::read_to_string(&path).map_err(|e| {
+            format!(
+                "{}: {}",
+                t!("localConfig.readProfileError", name => name),
+                e
+            )
+        })?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("{}: {}", t!("localConfig.invalidProfile", name => name), e))
+    }
+
+    pub fn write_profile(name: &str, values: &Map<String, Value>) -> Result<(), String> {
+        let path = Self::profile_path(name)?;
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)
+                .map_err(|e| format!("{}: {}", t!("localConfig.createConfigDirError"), e))?;
+        }
+        let text = to_string_pretty(values)
+            .map_err(|e| format!("{}: {}", t!("localConfig.serializeConfigError"), e))?;
+        fs::write(path, text).map_err(|e| {
+            format!(
+                "{}: {}",
+                t!("localConfig.writeProfileError", name => name),
+                e
+            )
+        })
+    }
+
+    /// Stores the profile settings of the current config under `name`
+    pub fn save_profile(name: &str) -> Result<(), String> {
+        let config = serde_json::to_value(Self::get())
+            .map_err(|e| format!("{}: {}", t!("localConfig.serializeConfigError"), e))?;
+        let values: Map<String, Value> = CONFIG_SCHEMA
+            .iter()
+            .filter(|field| field.profile)
+            .filter_map(|field| Some((field.key.to_string(), config.get(field.key)?.clone())))
+            .collect();
+        Self::write_profile(name, &values)?;
+        Self::set_active_profile(name.trim().to_string());
+        Ok(())
+    }
+
+    /// The current config with a saved profile applied, nothing is stored
+    pub fn with_profile(name: &str) -> Result<LocalConfig, String> {
+        let values = Self::read_profile(name)?;
+        let mut config = Self::merged_with(&values, is_profile_key)?;
+        config.validate()?;
+        config.active_profile = name.trim().to_string();
+        Ok(config)
+    }
+
+    pub fn delete_profile(name: &str) -> Result<(), String> {
+        fs::remove_file(Self::profile_path(name)?).map_err(|e| {
+            format!(
+                "{}: {}",
+                t!("localConfig.deleteProfileError", name => name),
+                e
+            )
+        })?;
+        if Self::get().active_profile == name.trim() {
+            Self::set_active_profile(String::new());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_covers_every_field() {
+        let config = serde_json::to_value(LocalConfig::default()).unwrap();
+        let keys: Vec<&str> = config
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        for key in &keys {
+            assert!(
+                config_field(key).is_some(),
+                "{} is missing in the schema",
+                key
+            );
+        }
+        for field in CONFIG_SCHEMA {
+            assert!(
+                keys.contains(&field.key),
+                "{} is not a config field",
+                field.key
+            );
+            // defaults must pass their own schema
+            if field.editable {
+                field.check(&config[field.key]).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn loaded_audio_gain_is_clamped() {
+        let mut config = LocalConfig {
+            audio_gain: 12.0,
+            ..Default::default()
+        };
+        config.clamp_loaded();
+        assert_eq!(config.audio_gain, AUDIO_GAIN_MAX);
+        config.audio_gain = -1.0;
+        config.clamp_loaded();
+        assert_eq!(config.audio_gain, 0.0);
+    }
+
+    #[test]
+    fn loaded_audio_buffer_is_clamped() {
+        let mut config: LocalConfig = serde_json::from_value(serde_json::json!({
+            "audio_buffer_ms": { "opus": 0, "aac": 50, "flac": 5000 },
+        }))
+        .unwrap();
+        assert!(config.audio_buffer_ms.validate().is_err());
+        config.clamp_loaded();
+        let buffer = config.audio_buffer_ms;
+        assert_eq!(
+            (buffer.opus, buffer.aac, buffer.flac, buffer.raw),
+            (AUDIO_BUFFER_MS_MIN, 50, AUDIO_BUFFER_MS_MAX, 50)
+        );
+        assert!(config.audio_buffer_ms.validate().is_ok());
+    }
+
+    #[test]
+    fn values_are_checked_against_the_schema() {
+        let field = config_field("web_port").unwrap();
+        assert!(field.check(&serde_json::json!(8080)).is_ok());
+        assert!(field.check(&serde_json::json!(70000)).is_err());
+        assert!(field.check(&serde_json::json!("8080")).is_err());
+
+        let field = config_field("video_codec").unwrap();
+        assert!(field.check(&serde_json::json!("AV1")).is_ok());
+        assert!(field.check(&serde_json::json!("VP9")).is_err());
+
+        let field = config_field("vertical_position").unwrap();
+        assert!(field.check(&serde_json::json!([10, -20])).is_ok());
+        assert!(field.check(&serde_json::json!([10])).is_err());
+    }
 }
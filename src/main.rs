@@ -1,19 +1,24 @@
 use std::{
+    env,
     fs::File,
     net::{Ipv4Addr, SocketAddrV4},
+    process,
     sync::OnceLock,
+    time::Duration,
 };
 
 use bevy::{
+    app::ScheduleRunnerPlugin,
     log::{BoxedLayer, LogPlugin, tracing_subscriber::Layer},
     prelude::*,
     window::{PresentMode, WindowLevel},
 };
 use scrcpy_mask::{
     DEFAULT_LANGUAGE,
+    cli::{self, Invocation},
     config::LocalConfig,
     is_available_language,
-    mask::{MaskPlugins, mask_command::MaskCommand},
+    mask::{MaskPlugins, headless::HeadlessMaskPlugin, mask_command::MaskCommand},
     scrcpy::{
         control_msg::ScrcpyControlMsg,
         controller::{self, ControllerCommand},
@@ -21,7 +26,7 @@ use scrcpy_mask::{
     tokio_tasks::{TokioTasksPlugin, TokioTasksRuntime},
     utils::{
         ChannelReceiverM, ChannelReceiverV, ChannelSenderCS, ChannelSenderD, ChannelSenderWS,
-        LatestVideoFrame, check_for_update, relate_to_data_path,
+        LatestVideoFrame, check_for_update, relate_to_data_path, set_run_mode,
     },
     web::{self, ws::WebSocketNotification},
 };
@@ -30,6 +35,9 @@ use tracing_appender::non_blocking::WorkerGuard;
 
 static LOG_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
 
+// update rate of the headless app, only scripts and commands run in it
+const HEADLESS_FRAME_TIME: Duration = Duration::from_millis(16);
+
 fn log_custom_layer(_app: &mut App) -> Option<BoxedLayer> {
     let file = File::create(relate_to_data_path(["app.log"])).unwrap_or_else(|e| {
         panic!("Failed to create log file: {}", e);
@@ -48,11 +56,29 @@ fn log_custom_layer(_app: &mut App) -> Option<BoxedLayer> {
 
 fn main() {
     rust_i18n::set_locale(DEFAULT_LANGUAGE);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let invocation = cli::parse(&args).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, cli::USAGE);
+        process::exit(2);
+    });
+
+    let config_loaded = LocalConfig::load();
+    LocalConfig::prefer_bundled_adb();
 
-    if let Err(e) = LocalConfig::load() {
+    let run_mode = match invocation {
+        Invocation::Command(command) => {
+            let language = LocalConfig::get().language;
+            if is_available_language(&language) {
+                rust_i18n::set_locale(&language);
+            }
+            process::exit(cli::run(command));
+        }
+        Invocation::App(run_mode) => run_mode,
+    };
+    set_run_mode(run_mode);
+    if let Err(e) = config_loaded {
         println!("LocalConfig load failed. {}", e);
     }
-    LocalConfig::prefer_bundled_adb();
 
     let mut local_config = LocalConfig::get();
     // update language
@@ -70,6 +96,19 @@ fn main() {
     ffmpeg_next::init().unwrap();
 
     let mut app = App::new();
+    if run_mode.is_headless() {
+        app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(HEADLESS_FRAME_TIME)))
+            .add_plugins(LogPlugin {
+                custom_layer: log_custom_layer,
+                ..default()
+            })
+            .add_plugins(TokioTasksPlugin::default())
+            .add_plugins(HeadlessMaskPlugin)
+            .add_systems(Startup, (start_servers, check_for_update_system));
+        app.run();
+        return;
+    }
+
     app.add_plugins(
         DefaultPlugins
             .set(LogPlugin {
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use rust_i18n::t;
+
+use crate::{
+    mask::{
+        mapping::{
+            config::{ActiveMappingConfig, load_mapping_config},
+            load_active_mapping,
+            script::script_init,
+            script_helper::{
+                ScriptAST, ScriptRuntimeCommand, ScriptRuntimeCommandReceiver,
+                ScriptRuntimeCommandSender, ScriptSharedState,
+            },
+        },
+        mask_command::MaskCommand,
+    },
+    tokio_tasks::TokioTasksRuntime,
+    utils::{ChannelReceiverM, ChannelSenderCS, share::MappingFallback},
+};
+
+const HEADLESS_UNSUPPORTED: &str = "Not available in headless mode";
+
+/// Mapping and script handling of the mask without a window, input or rendering
+pub struct HeadlessMaskPlugin;
+
+impl Plugin for HeadlessMaskPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActiveMappingConfig(None, String::new()))
+            .add_systems(Startup, (init_headless_mapping, script_init))
+            .add_systems(
+                Update,
+                (handle_headless_mask_command, reject_script_runtime_commands),
+            );
+    }
+}
+
+fn init_headless_mapping(mut active_mapping: ResMut<ActiveMappingConfig>) {
+    let (bind_mapping_config, _, file) = load_active_mapping();
+    active_mapping.0 = Some(bind_mapping_config);
+    active_mapping.1 = file;
+}
+
+fn handle_headless_mask_command(
+    m_rx: Res<ChannelReceiverM>,
+    cs_tx_res: Res<ChannelSenderCS>,
+    script_command_tx: Res<ScriptRuntimeCommandSender>,
+    shared_state: Res<ScriptSharedState>,
+    mut active_mapping: ResMut<ActiveMappingConfig>,
+    runtime: ResMut<TokioTasksRuntime>,
+) {
+    for (msg, oneshot_tx) in m_rx.0.try_iter() {
+        match msg {
+            MaskCommand::WinMove { .. }
+            | MaskCommand::WinSwitchLevel { .. }
+            | MaskCommand::ToggleTitlebar => {
+                let _ = oneshot_tx.send(Err(HEADLESS_UNSUPPORTED.to_string()));
+            }
+            MaskCommand::DeviceConnectionChange { connect } => {
+                let msg = if connect {
+                    t!("mask.mainDeviceConnected").to_string()
+                } else {
+                    t!("mask.mainDeviceDisconnected").to_string()
+                };
+                log::info!("[Mask] {}", msg);
+                let _ = oneshot_tx.send(Ok(msg));
+            }
+            MaskCommand::GetActiveMapping => {
+                let _ = oneshot_tx.send(Ok(active_mapping.1.clone()));
+            }
+            MaskCommand::GetScaleFactor => {
+                let _ = oneshot_tx.send(Ok("1".to_string()));
+            }
+            MaskCommand::LoadAndActivateMappingConfig { file_name } => {
+                log::info!(
+                    "[Mapping] {}: {}",
+                    t!("mask.loadActivateMappingConfig"),
+                    file_name
+                );
+                // there is no input to bind, only the mapping is kept for scripts
+                match load_mapping_config(&file_name) {
+                    Ok((mapping_config, _)) => {
+                        active_mapping.0 = Some(mapping_config);
+                        active_mapping.1 = file_name;
+                        MappingFallback::set_blocking(None);
+                        let _ = oneshot_tx.send(Ok(String::new()));
+                    }
+                    Err(e) => {
+                        let _ = oneshot_tx.send(Err(e));
+                    }
+                }
+            }
+            MaskCommand::RunScript { script } => {
+                let ast = match ScriptAST::new(&script) {
+                    Err(e) => {
+                        let _ = oneshot_tx.send(Err(e));
+                        continue;
+                    }
+                    Ok(ast) => ast,
+                };
+
+                let Some(mapping_config) = &active_mapping.0 else {
+                    let _ = oneshot_tx.send(Err(t!("mask.runScriptnoMappingError").to_string()));
+                    continue;
+                };
+                let cs_tx = cs_tx_res.0.clone();
+                let script_command_tx = script_command_tx.0.clone();
+                let shared_state = shared_state.as_ref().clone();
+                let original_size: Vec2 = mapping_config.original_size.into();
+                runtime.spawn_background_task(move |_ctx| async move {
+                    // without a mask, mask coordinates are the mapping's own coordinates
+                    let result = ast
+                        .run_script(
+                            &cs_tx,
+                            &script_command_tx,
+                            &shared_state,
+                            "RunScript",
+                            original_size,
+                            original_size / 2.,
+                            original_size,
+                            false,
+                            false,
+                        )
+                        .await
+                        .map(|_| String::new())
+                        .map_err(|e| e.to_string());
+                    let _ = oneshot_tx.send(result);
+                });
+            }
+        }
+    }
+}
+
+/// Fps, raw input and cast spell modes need the mask window, fail them instead of hanging
+fn reject_script_runtime_commands(command_rx: Res<ScriptRuntimeCommandReceiver>) {
+    for command in command_rx.0.try_iter() {
+        let ack = match command {
+            ScriptRuntimeCommand::EnterFps { ack, .. }
+            | ScriptRuntimeCommand::ExitFps { ack }
+            | ScriptRuntimeCommand::EnterRawInput { ack }
+            | ScriptRuntimeCommand::ExitRawInput { ack }
+            | ScriptRuntimeCommand::CancelCast { ack, .. }
+            | ScriptRuntimeCommand::ReleaseCast { ack } => ack,
+        };
+        let _ = ack.send(Err(HEADLESS_UNSUPPORTED.to_string()));
+    }
+}
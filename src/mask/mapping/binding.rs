@@ -18,10 +18,24 @@ pub enum MergedButton {
     Mouse(MouseButton),
     ScrollDown,
     ScrollUp,
+    ScrollLeft,
+    ScrollRight,
     Keyboard(KeyCode),
     GamePad(GamepadButton),
 }
 
+impl MergedButton {
+    pub fn is_wheel(&self) -> bool {
+        matches!(
+            self,
+            MergedButton::ScrollDown
+                | MergedButton::ScrollUp
+                | MergedButton::ScrollLeft
+                | MergedButton::ScrollRight
+        )
+    }
+}
+
 impl From<MouseButton> for MergedButton {
     fn from(value: MouseButton) -> Self {
         Self::Mouse(value)
@@ -49,6 +63,13 @@ impl From<MergedButton> for BinaryInput {
             MergedButton::ScrollUp => {
                 BinaryInput::Axis(AnalogInput::ScrollWheelY, Threshold::new(0.1))
             }
+            // like ScrollWheelY, positive deltas move the content right when scrolling left
+            MergedButton::ScrollLeft => {
+                BinaryInput::Axis(AnalogInput::ScrollWheelX, Threshold::new(0.1))
+            }
+            MergedButton::ScrollRight => {
+                BinaryInput::Axis(AnalogInput::ScrollWheelX, Threshold::new(-0.1))
+            }
             MergedButton::Mouse(mouse_button) => BinaryInput::MouseButton(mouse_button),
             MergedButton::Keyboard(key_code) => BinaryInput::Key(key_code),
             MergedButton::GamePad(gamepad_button) => BinaryInput::Gamepad(gamepad_button),
@@ -93,6 +114,8 @@ impl ToString for MergedButton {
             },
             MergedButton::ScrollDown => "ScrollDown".to_string(),
             MergedButton::ScrollUp => "ScrollUp".to_string(),
+            MergedButton::ScrollLeft => "ScrollLeft".to_string(),
+            MergedButton::ScrollRight => "ScrollRight".to_string(),
             MergedButton::GamePad(gamepad_button) => match_gamepad_to_string!(gamepad_button;
                 South, East, North, West, C, Z,
                 LeftTrigger, LeftTrigger2, RightTrigger, RightTrigger2,
@@ -157,6 +180,8 @@ macro_rules! match_keycode_from_str {
             "Unidentified" => Ok(MergedButton::Keyboard(KeyCode::Unidentified(NativeKeyCode::Unidentified))),
             "ScrollDown" => Ok(MergedButton::ScrollDown),
             "ScrollUp" => Ok(MergedButton::ScrollUp),
+            "ScrollLeft" => Ok(MergedButton::ScrollLeft),
+            "ScrollRight" => Ok(MergedButton::ScrollRight),
             s if s.starts_with("Android-") => s[8..].parse()
                 .map(|code| MergedButton::Keyboard(KeyCode::Unidentified(NativeKeyCode::Android(code))))
                 .map_err(|e| format!("{}: {}", t!("mask.mapping.invalidCode", name=>"Android"), e)),
@@ -286,6 +311,10 @@ impl ButtonBinding {
         ButtonBinding(buttons)
     }
 
+    pub fn buttons(&self) -> &[MergedButton] {
+        &self.0
+    }
+
     pub fn is_any_key_pressed(&self, key_input: &ButtonInput<KeyCode>) -> bool {
         self.0
             .iter()
@@ -32,6 +32,7 @@ use crate::{
                 MappingLifecycleState, make_mapping_execution_context, run_script_hook,
                 run_with_hooks,
             },
+            layout::MappingAnchor,
             script::{BindMappingScriptHooks, MappingScriptHooks},
             script_helper::ScriptAST,
             script_helper::{ScriptRuntimeCommand, ScriptRuntimeCommandSender, ScriptSharedState},
@@ -276,6 +277,8 @@ impl From<MappingMouseCastSpell> for BindMappingMouseCastSpell {
 pub struct MappingMouseCastSpell {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub position: Position,
@@ -993,6 +996,8 @@ impl From<MappingPadCastSpell> for BindMappingPadCastSpell {
 pub struct MappingPadCastSpell {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub position: Position,
@@ -1493,6 +1498,8 @@ impl From<MappingCancelCast> for BindMappingCancelCast {
 pub struct MappingCancelCast {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub position: Position,
     pub bind: ButtonBinding,
@@ -1,7 +1,6 @@
 use std::{
     collections::{HashMap, HashSet},
-    fs::{File, create_dir_all},
-    io::Write,
+    fs::create_dir_all,
     path::Path,
     str::FromStr,
 };
@@ -29,10 +28,18 @@ use crate::{
         cursor::FPS_MARGIN,
         direction_pad::{BindMappingDirectionPad, MappingDirectionPad},
         fire::{BindMappingFire, BindMappingFps, MappingFire, MappingFps},
+        gesture::{BindMappingGesture, MappingGesture},
+        history::record_revision,
+        layout::SafeArea,
+        migration::{
+            MAPPING_SCHEMA_VERSION, backup_mapping_file, deserialize_schema_version,
+            migrate_mapping_value,
+        },
         observation::{BindMappingObservation, MappingObservation},
         raw_input::{BindMappingRawInput, MappingRawInput},
         script::{BindMappingScript, MappingScript, MappingScriptHooks},
         script_helper::{ScriptAST, ScriptDiagnostic},
+        scroll_drag::{BindMappingScrollDrag, MappingScrollDrag},
         swipe::{BindMappingSwipe, MappingSwipe},
         tap::{
             BindMappingMultipleTap, BindMappingRepeatTap, BindMappingSingleTap, MappingMultipleTap,
@@ -40,7 +47,7 @@ use crate::{
         },
         utils::Size,
     },
-    utils::{is_safe_file_name, relate_to_data_path},
+    utils::{is_safe_file_name, relate_to_data_path, write_data_file},
 };
 
 pub fn default_mapping_id() -> String {
@@ -60,6 +67,8 @@ seq!(N in 1..=32 {
             MultipleTap~N,
             #[ineffable(pulse)]
             Swipe~N,
+            #[ineffable(pulse)]
+            Gesture~N,
             #[ineffable(dual_axis)]
             DirectionPad~N,
             #[ineffable(continuous)]
@@ -80,6 +89,8 @@ seq!(N in 1..=32 {
             RawInput~N,
             #[ineffable(continuous)]
             Script~N,
+            #[ineffable(continuous)]
+            ScrollDrag~N,
         )*
     }
 
@@ -94,6 +105,7 @@ seq!(N in 1..=32 {
                     MappingAction::Observation~N => self.clone()._observation~N(),
                     MappingAction::Fire~N => self.clone()._fire~N(),
                     MappingAction::Script~N => self.clone()._script~N(),
+                    MappingAction::ScrollDrag~N => self.clone()._scrolldrag~N(),
                 )*
                 _ => panic!("ineff_continuous called on non-continuous variant"),
             }
@@ -104,6 +116,7 @@ seq!(N in 1..=32 {
                 #(
                     MappingAction::MultipleTap~N => self.clone()._multipletap~N(),
                     MappingAction::Swipe~N => self.clone()._swipe~N(),
+                    MappingAction::Gesture~N => self.clone()._gesture~N(),
                     MappingAction::CancelCast~N => self.clone()._cancelcast~N(),
                     MappingAction::Fps~N => self.clone()._fps~N(),
                     MappingAction::RawInput~N => self.clone()._rawinput~N(),
@@ -124,7 +137,7 @@ seq!(N in 1..=32 {
     }
 });
 
-macro_rules! impl_mapping_related {
+macro_rules! impl_mapping_types {
     ( $($variant:ident),* $(,)? ) => {
         paste! {
             #[derive(Serialize, Deserialize, Debug, Clone, AsRefStr)]
@@ -165,10 +178,10 @@ macro_rules! impl_mapping_related {
         }
 
         impl BindMappingType {
-            pub fn get_input_binding(&self) -> InputBinding {
+            pub fn id(&self) -> &str {
                 match self {
                     $(
-                        BindMappingType::$variant(inner) => inner.input_binding.clone(),
+                        BindMappingType::$variant(inner) => inner.id.as_str(),
                     )*
                 }
             }
@@ -197,11 +210,32 @@ macro_rules! impl_mapping_related {
     };
 }
 
+/// Mappings after the `;` bind several inputs to their action, in `input_bindings`
+macro_rules! impl_mapping_related {
+    ( $($variant:ident),* ; $($multi:ident),* $(,)? ) => {
+        impl_mapping_types! { $($variant,)* $($multi),* }
+
+        impl BindMappingType {
+            pub fn get_input_bindings(&self) -> Vec<InputBinding> {
+                match self {
+                    $(
+                        BindMappingType::$variant(inner) => vec![inner.input_binding.clone()],
+                    )*
+                    $(
+                        BindMappingType::$multi(inner) => inner.input_bindings.clone(),
+                    )*
+                }
+            }
+        }
+    };
+}
+
 impl_mapping_related! {
     SingleTap,
     RepeatTap,
     MultipleTap,
     Swipe,
+    Gesture,
     DirectionPad,
     MouseCastSpell,
     PadCastSpell,
@@ -210,13 +244,18 @@ impl_mapping_related! {
     Fps,
     Fire,
     RawInput,
-    Script
+    Script;
+    ScrollDrag
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MappingConfig {
-    pub version: String,
+    #[serde(deserialize_with = "deserialize_schema_version")]
+    pub version: u32,
     pub original_size: Size,
+    /// Safe area of the screen the layout was made for, used when migrating it to another screen
+    #[serde(default)]
+    pub safe_area: SafeArea,
     pub mappings: Vec<MappingType>,
 }
 
@@ -239,7 +278,7 @@ pub struct MappingDiagnostic {
 }
 
 impl MappingDiagnostic {
-    fn config(code: impl Into<String>, message: impl Into<String>) -> Self {
+    pub(super) fn config(code: impl Into<String>, message: impl Into<String>) -> Self {
         Self {
             severity: "error".to_string(),
             code: code.into(),
@@ -252,7 +291,7 @@ impl MappingDiagnostic {
         }
     }
 
-    fn mapping(
+    pub(super) fn mapping(
         code: impl Into<String>,
         message: impl Into<String>,
         mapping_type: &str,
@@ -289,11 +328,18 @@ impl MappingDiagnostic {
             script_diagnostic: Some(diagnostic),
         }
     }
+
+    pub(super) fn warning(self) -> Self {
+        Self {
+            severity: "warning".to_string(),
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct BindMappingConfig {
-    pub version: String,
+    pub version: u32,
     pub original_size: Size,
     pub mappings: HashMap<MappingAction, BindMappingType>,
     pub mapping_id_actions: HashMap<String, MappingAction>,
@@ -347,6 +393,7 @@ impl BindMappingConfig {
                         (m.bind.to_string(), m.items[0].position.into())
                     }
                     BindMappingType::Swipe(m) => (m.bind.to_string(), m.positions[0].into()),
+                    BindMappingType::Gesture(m) => (m.bind.to_string(), m.position.into()),
                     BindMappingType::DirectionPad(m) => (String::new(), m.position.into()),
                     BindMappingType::MouseCastSpell(m) => (m.bind.to_string(), m.position.into()),
                     BindMappingType::PadCastSpell(m) => (String::new(), m.position.into()),
@@ -356,6 +403,7 @@ impl BindMappingConfig {
                     BindMappingType::Fire(m) => (m.bind.to_string(), m.position.into()),
                     BindMappingType::RawInput(m) => (m.bind.to_string(), m.position.into()),
                     BindMappingType::Script(m) => (m.bind.to_string(), m.position.into()),
+                    BindMappingType::ScrollDrag(m) => (m.label_text(), m.position.into()),
                 };
                 (mapping, binding, pos, size)
             })
@@ -372,7 +420,7 @@ impl From<&BindMappingConfig> for InputConfig {
                 all_bindings.insert(action.to_string(), vec![m.input_binding.clone()]);
                 all_bindings.insert(m.pad_action.to_string(), vec![m.pad_input_binding.clone()]);
             } else {
-                all_bindings.insert(action.to_string(), vec![mapping.get_input_binding()]);
+                all_bindings.insert(action.to_string(), mapping.get_input_bindings());
             }
         }
 
@@ -389,11 +437,12 @@ pub struct ActiveMappingConfig(pub Option<BindMappingConfig>, pub String);
 
 pub fn default_mapping_config() -> MappingConfig {
     MappingConfig {
-        version: "0.0.1".to_string(),
+        version: MAPPING_SCHEMA_VERSION,
         original_size: Size {
             width: 2560,
             height: 1440,
         },
+        safe_area: SafeArea::default(),
         mappings: vec![],
     }
 }
@@ -485,24 +534,6 @@ pub fn validate_mapping_config_diagnostics(
     diagnostics
 }
 
-pub fn validate_mapping_config(mapping_config: &MappingConfig) -> Result<(), String> {
-    let validate_errors = validate_mapping_config_diagnostics(mapping_config);
-
-    if !validate_errors.is_empty() {
-        let mut validate_errors: Vec<String> = validate_errors
-            .into_iter()
-            .enumerate()
-            .map(|(i, err)| format!("{}. {}", i + 1, format_mapping_diagnostic(&err)))
-            .collect();
-        validate_errors.insert(
-            0,
-            t!("mask.mapping.mappingConfigValidationFailed").to_string(),
-        );
-        return Err(validate_errors.join("\n"));
-    }
-    Ok(())
-}
-
 fn format_mapping_diagnostic(diagnostic: &MappingDiagnostic) -> String {
     let mut prefix = String::new();
     if let (Some(mapping_type), Some(index)) = (&diagnostic.mapping_type, diagnostic.mapping_index)
@@ -576,6 +607,42 @@ fn collect_mapping_specific_diagnostics(
                 &mapping.script_hooks,
             );
         }
+        MappingType::Gesture(mapping) => {
+            if let Err(message) = mapping.validate_gesture() {
+                diagnostics.push(MappingDiagnostic::mapping(
+                    "mapping.gesture.invalid",
+                    message,
+                    mapping_type,
+                    mapping_index,
+                    mapping_id,
+                ));
+            }
+            collect_script_hook_diagnostics(
+                diagnostics,
+                mapping_type,
+                mapping_index,
+                mapping_id,
+                &mapping.script_hooks,
+            );
+        }
+        MappingType::ScrollDrag(mapping) => {
+            if let Err(message) = mapping.validate_scroll_drag() {
+                diagnostics.push(MappingDiagnostic::mapping(
+                    "mapping.scrollDrag.invalid",
+                    message,
+                    mapping_type,
+                    mapping_index,
+                    mapping_id,
+                ));
+            }
+            collect_script_hook_diagnostics(
+                diagnostics,
+                mapping_type,
+                mapping_index,
+                mapping_id,
+                &mapping.script_hooks,
+            );
+        }
         MappingType::DirectionPad(mapping) => collect_script_hook_diagnostics(
             diagnostics,
             mapping_type,
@@ -636,6 +703,33 @@ fn collect_mapping_specific_diagnostics(
                     mapping_id,
                 ));
             }
+            if mapping.ads_sensitivity <= 0.0 {
+                diagnostics.push(MappingDiagnostic::mapping(
+                    "mapping.fps.invalidAdsSensitivity",
+                    "FPS ads_sensitivity must be greater than 0",
+                    mapping_type,
+                    mapping_index,
+                    mapping_id,
+                ));
+            }
+            if let Err(e) = mapping.acceleration.validate() {
+                diagnostics.push(MappingDiagnostic::mapping(
+                    "mapping.fps.invalidAcceleration",
+                    e,
+                    mapping_type,
+                    mapping_index,
+                    mapping_id,
+                ));
+            }
+            if let Err(e) = mapping.smoothing.validate() {
+                diagnostics.push(MappingDiagnostic::mapping(
+                    "mapping.fps.invalidSmoothing",
+                    e,
+                    mapping_type,
+                    mapping_index,
+                    mapping_id,
+                ));
+            }
         }
         MappingType::Fire(mapping) => {
             if mapping.preserve_fps_control && fps_touch_pointer_ids.contains(&mapping.pointer_id) {
@@ -729,50 +823,144 @@ fn collect_script_field_diagnostics(
     }
 }
 
-pub fn load_mapping_config(
-    file_name: impl AsRef<str>,
-) -> Result<(BindMappingConfig, InputConfig), String> {
-    if !is_safe_file_name(file_name.as_ref()) {
-        return Err(format!(
-            "{}: {}",
-            t!("mask.mapping.fileNameNotSafe"),
-            file_name.as_ref()
+/// Reads a mapping file, upgrading it to the current schema in memory.
+/// The file itself is only rewritten by [`migrate_mapping_files`] or when it is saved
+pub fn read_mapping_file(file_name: &str) -> Result<MappingConfig, Vec<MappingDiagnostic>> {
+    read_mapping_file_migrated(file_name).map(|(mapping_config, _)| mapping_config)
+}
+
+/// Like [`read_mapping_file`], also returns the schema version the file had if it was older
+fn read_mapping_file_migrated(
+    file_name: &str,
+) -> Result<(MappingConfig, Option<u32>), Vec<MappingDiagnostic>> {
+    let fail = |code: &str, message: String| vec![MappingDiagnostic::config(code, message)];
+    if !is_safe_file_name(file_name) {
+        return Err(fail(
+            "mapping.file.unsafeName",
+            format!("{}: {}", t!("mask.mapping.fileNameNotSafe"), file_name),
         ));
     }
 
-    // load from file
-    let path = relate_to_data_path(["mapping", file_name.as_ref()]);
+    let path = relate_to_data_path(["mapping", file_name]);
     if !path.exists() {
-        return Err(format!(
-            "{}: {}",
-            t!("mask.mapping.mappingConfigNotFound"),
-            file_name.as_ref()
+        return Err(fail(
+            "mapping.file.notFound",
+            format!(
+                "{}: {}",
+                t!("mask.mapping.mappingConfigNotFound"),
+                file_name
+            ),
         ));
     }
+    let config_string = std::fs::read_to_string(&path).map_err(|e| {
+        fail(
+            "mapping.file.unreadable",
+            format!("{}: {}", t!("web.mapping.cannotReadMappingConfig"), e),
+        )
+    })?;
+    parse_mapping_config(&config_string)
+}
 
-    let config_string = std::fs::read_to_string(path)
-        .map_err(|e| format!("{}: {}", t!("web.mapping.cannotReadMappingConfig"), e))?;
-    let mapping_config: MappingConfig = serde_json::from_str(&config_string)
-        .map_err(|e| format!("{}: {}", t!("web.mapping.cannotDeserializeConfig"), e))?;
-    validate_mapping_config(&mapping_config)?;
+/// Parses and validates the contents of a mapping file, upgrading older schema versions in memory.
+/// Also returns the schema version the contents had if it was older
+pub fn parse_mapping_config(
+    config_string: &str,
+) -> Result<(MappingConfig, Option<u32>), Vec<MappingDiagnostic>> {
+    let fail = |code: &str, message: String| vec![MappingDiagnostic::config(code, message)];
+    let mut value: serde_json::Value = serde_json::from_str(config_string).map_err(|e| {
+        fail(
+            "mapping.file.invalidJson",
+            format!("{}: {}", t!("web.mapping.cannotDeserializeConfig"), e),
+        )
+    })?;
+
+    let migrated_from = migrate_mapping_value(&mut value).map_err(|e| vec![e])?;
+    let mapping_config: MappingConfig = serde_json::from_value(value).map_err(|e| {
+        fail(
+            "mapping.schema.deserializeFailed",
+            format!("{}: {}", t!("web.mapping.cannotDeserializeConfig"), e),
+        )
+    })?;
+
+    let diagnostics = validate_mapping_config_diagnostics(&mapping_config);
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    Ok((mapping_config, migrated_from))
+}
+
+/// Backs up and rewrites the mapping files of older schema versions, run once at startup
+pub fn migrate_mapping_files() {
+    let Ok(entries) = std::fs::read_dir(relate_to_data_path(["mapping"])) else {
+        return;
+    };
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if !path.extension().is_some_and(|ext| ext == "json") {
+            continue;
+        }
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // broken files are reported when they are loaded
+        let Ok((mapping_config, Some(from))) = read_mapping_file_migrated(file_name) else {
+            continue;
+        };
+        match backup_mapping_file(&path, from)
+            .and_then(|_| save_mapping_config(&mapping_config, &path))
+        {
+            Ok(()) => log::info!(
+                "[Mapping] Migrated {} from schema version {} to {}",
+                file_name,
+                from,
+                MAPPING_SCHEMA_VERSION
+            ),
+            Err(e) => log::warn!("[Mapping] Failed to migrate {}: {}", file_name, e),
+        }
+    }
+}
+
+/// Joins diagnostics into a single message for logs and plain error replies
+pub fn format_mapping_diagnostics(diagnostics: &[MappingDiagnostic]) -> String {
+    let mut lines: Vec<String> = diagnostics
+        .iter()
+        .enumerate()
+        .map(|(i, err)| format!("{}. {}", i + 1, format_mapping_diagnostic(err)))
+        .collect();
+    lines.insert(
+        0,
+        t!("mask.mapping.mappingConfigValidationFailed").to_string(),
+    );
+    lines.join("\n")
+}
+
+pub fn load_mapping_config(
+    file_name: impl AsRef<str>,
+) -> Result<(BindMappingConfig, InputConfig), String> {
+    let mapping_config = read_mapping_file(file_name.as_ref())
+        .map_err(|diagnostics| format_mapping_diagnostics(&diagnostics))?;
 
     let bind_mapping_config: BindMappingConfig = mapping_config.into();
     let input_config: InputConfig = InputConfig::from(&bind_mapping_config);
     Ok((bind_mapping_config, input_config))
 }
 
+/// Always writes the current schema version, the config in memory already has the current shape
 pub fn save_mapping_config(config: &MappingConfig, path: &Path) -> Result<(), String> {
-    let json_string = to_string_pretty(config)
+    let config = MappingConfig {
+        version: MAPPING_SCHEMA_VERSION,
+        ..config.clone()
+    };
+    let json_string = to_string_pretty(&config)
         .map_err(|e| format!("{}: {}", t!("web.mapping.cannotDeserializeConfig"), e))?;
+    // a failed snapshot shouldn't keep the edit from being saved
+    if let Err(e) = record_revision(path, &json_string) {
+        log::warn!("[Mapping] {}", e);
+    }
     if let Some(parent) = path.parent() {
         create_dir_all(parent)
             .map_err(|e| format!("{}: {}", t!("mask.mapping.cannotCreateConfigDir"), e))?;
     }
 
-    let mut file = File::create(path)
-        .map_err(|e| format!("{}: {}", t!("mask.mapping.cannotCreateMappingConfig"), e))?;
-    file.write_all(json_string.as_bytes())
-        .map_err(|e| format!("{}: {}", t!("mask.mapping.cannotWriteMappingConfig"), e))?;
-
-    Ok(())
+    write_data_file(path, json_string.as_bytes())
+        .map_err(|e| format!("{}: {}", t!("mask.mapping.cannotWriteMappingConfig"), e))
 }
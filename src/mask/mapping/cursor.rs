@@ -11,7 +11,9 @@ use bevy::{
 
 use crate::{
     mask::{
-        mapping::{MappingState, mask_not_resizing, utils::ControlMsgHelper},
+        mapping::{
+            MappingState, binding::ButtonBinding, mask_not_resizing, utils::ControlMsgHelper,
+        },
         mask_command::{MaskSize, TitlebarState},
         ui::basic::{MaskContentEntity, TITLEBAR_HEIGHT},
     },
@@ -176,6 +178,171 @@ impl FpsTouchMode {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FpsAcceleration {
+    #[default]
+    Off,
+    /// multiplier = 1 + gain * (speed - offset)
+    Linear { gain: f32, offset: f32, cap: f32 },
+    /// multiplier = 1 + (gain * (speed - offset)) ^ exponent
+    Power {
+        gain: f32,
+        exponent: f32,
+        offset: f32,
+        cap: f32,
+    },
+}
+
+impl FpsAcceleration {
+    /// Sensitivity multiplier for a mouse speed in counts per millisecond.
+    /// A cap of 0 means no upper limit.
+    pub fn multiplier(&self, speed: f32) -> f32 {
+        let (multiplier, cap) = match *self {
+            FpsAcceleration::Off => return 1.0,
+            FpsAcceleration::Linear { gain, offset, cap } => {
+                (1.0 + gain * (speed - offset).max(0.0), cap)
+            }
+            FpsAcceleration::Power {
+                gain,
+                exponent,
+                offset,
+                cap,
+            } => (1.0 + (gain * (speed - offset).max(0.0)).powf(exponent), cap),
+        };
+        if cap > 0.0 {
+            multiplier.min(cap)
+        } else {
+            multiplier
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            FpsAcceleration::Off => Ok(()),
+            FpsAcceleration::Linear { gain, offset, cap }
+            | FpsAcceleration::Power {
+                gain, offset, cap, ..
+            } if gain < 0.0 || offset < 0.0 || (cap != 0.0 && cap < 1.0) => Err(
+                "FPS acceleration gain/offset must be 0 or greater and cap must be 0 or at least 1"
+                    .to_string(),
+            ),
+            FpsAcceleration::Power { exponent, .. } if exponent <= 0.0 => {
+                Err("FPS acceleration exponent must be greater than 0".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FpsSmoothing {
+    #[default]
+    Off,
+    /// Per-frame exponential moving average, alpha in (0, 1]
+    Exponential { alpha: f32 },
+    /// One Euro filter, cutoffs in Hz
+    OneEuro {
+        min_cutoff: f32,
+        beta: f32,
+        d_cutoff: f32,
+    },
+}
+
+impl FpsSmoothing {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            FpsSmoothing::Off => Ok(()),
+            FpsSmoothing::Exponential { alpha } if alpha <= 0.0 || alpha > 1.0 => {
+                Err("FPS exponential smoothing alpha must be in (0, 1]".to_string())
+            }
+            FpsSmoothing::OneEuro {
+                min_cutoff,
+                beta,
+                d_cutoff,
+            } if min_cutoff <= 0.0 || d_cutoff <= 0.0 || beta < 0.0 => Err(
+                "FPS one euro min_cutoff/d_cutoff must be greater than 0 and beta 0 or greater"
+                    .to_string(),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn one_euro_alpha(cutoff: f32, dt: f32) -> f32 {
+    let tau = 1.0 / (2.0 * std::f32::consts::PI * cutoff);
+    1.0 / (1.0 + tau / dt)
+}
+
+/// Turns raw mouse deltas into touch deltas: acceleration curve, sensitivity,
+/// then smoothing. Smoothing runs on the accumulated aim position, so the
+/// filtered output keeps catching up after the mouse stops and no motion is lost.
+#[derive(Debug, Clone, Default)]
+pub struct FpsAimFilter {
+    acceleration: FpsAcceleration,
+    smoothing: FpsSmoothing,
+    raw_pos: Vec2,
+    smoothed_pos: Vec2,
+    smoothed_velocity: Vec2,
+}
+
+impl FpsAimFilter {
+    pub fn new(acceleration: FpsAcceleration, smoothing: FpsSmoothing) -> Self {
+        Self {
+            acceleration,
+            smoothing,
+            ..default()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.raw_pos = Vec2::ZERO;
+        self.smoothed_pos = Vec2::ZERO;
+        self.smoothed_velocity = Vec2::ZERO;
+    }
+
+    /// `delta` is the raw mouse motion of this frame and `dt` the frame time in seconds.
+    pub fn process(&mut self, delta: Vec2, sensitivity: Vec2, dt: f32) -> Vec2 {
+        let speed = if dt > 0.0 {
+            delta.length() / (dt * 1000.0)
+        } else {
+            0.0
+        };
+        let delta = delta * self.acceleration.multiplier(speed) * sensitivity;
+        self.raw_pos += delta;
+
+        let next_pos = match self.smoothing {
+            FpsSmoothing::Off => self.raw_pos,
+            FpsSmoothing::Exponential { alpha } => self.smoothed_pos.lerp(self.raw_pos, alpha),
+            FpsSmoothing::OneEuro {
+                min_cutoff,
+                beta,
+                d_cutoff,
+            } => {
+                if dt <= 0.0 {
+                    return Vec2::ZERO;
+                }
+                let velocity = (self.raw_pos - self.smoothed_pos) / dt;
+                self.smoothed_velocity = self
+                    .smoothed_velocity
+                    .lerp(velocity, one_euro_alpha(d_cutoff, dt));
+                let cutoff = min_cutoff + beta * self.smoothed_velocity.length();
+                self.smoothed_pos
+                    .lerp(self.raw_pos, one_euro_alpha(cutoff, dt))
+            }
+        };
+
+        let output = next_pos - self.smoothed_pos;
+        self.smoothed_pos = next_pos;
+        if self.smoothed_pos.distance_squared(self.raw_pos) < 1e-6 {
+            // rebase to keep the accumulated positions small
+            self.reset();
+        }
+        output
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 enum PendingFpsTouch {
     Restore {
@@ -218,6 +385,9 @@ pub struct ActiveCursorFpsConfig {
     pub original_size: Vec2,
     pub max_offset: Vec2,
     pub touch_mode: FpsTouchMode,
+    pub aim_filter: FpsAimFilter,
+    pub ads_key: Option<ButtonBinding>,
+    pub ads_sensitivity: f32,
     pending_touch: Option<PendingFpsTouch>,
 }
 
@@ -233,6 +403,9 @@ impl Default for ActiveCursorFpsConfig {
             original_size: Vec2::ZERO,
             max_offset: Vec2::ZERO,
             touch_mode: FpsTouchMode::default(),
+            aim_filter: FpsAimFilter::default(),
+            ads_key: None,
+            ads_sensitivity: 1.0,
             pending_touch: None,
         }
     }
@@ -247,6 +420,7 @@ impl ActiveCursorFpsConfig {
     pub fn clear_runtime_state(&mut self) {
         self.touch_active = false;
         self.ignore_fps_motion = false;
+        self.aim_filter.reset();
         self.reset_touch_state();
     }
 }
@@ -294,6 +468,7 @@ pub fn restore_fps_touch(
     fps_config: &mut ActiveCursorFpsConfig,
 ) {
     fps_config.reset_touch_state();
+    fps_config.aim_filter.reset();
     fps_config.touch_active = true;
     ControlMsgHelper::send_touch(
         cs_tx,
@@ -617,12 +792,16 @@ fn first_bounds_crossing(
 
 fn send_fps_touch(
     cs_tx: &broadcast::Sender<ScrcpyControlMsg>,
+    fps_config: &ActiveCursorFpsConfig,
     action: MotionEventAction,
     pointer_id: u64,
     mask_size: Vec2,
     pos: Vec2,
 ) {
-    ControlMsgHelper::send_touch(cs_tx, action, pointer_id, mask_size, pos);
+    // send in mapping space, which is usually finer than the mask,
+    // so sub-pixel cursor movement is not lost to rounding
+    let size = fps_config.original_size;
+    ControlMsgHelper::send_touch(cs_tx, action, pointer_id, size, pos * (size / mask_size));
 }
 
 fn cleanup_pending_fps_touch(
@@ -649,7 +828,14 @@ fn cleanup_pending_fps_touch(
             deferred_delta,
             ..
         }) => {
-            send_fps_touch(cs_tx, MotionEventAction::Down, pointer_id, mask_size, pos);
+            send_fps_touch(
+                cs_tx,
+                fps_config,
+                MotionEventAction::Down,
+                pointer_id,
+                mask_size,
+                pos,
+            );
             fps_config.touch_active = true;
             Some(deferred_delta)
         }
@@ -659,7 +845,14 @@ fn cleanup_pending_fps_touch(
             deferred_delta,
             ..
         }) => {
-            send_fps_touch(cs_tx, MotionEventAction::Up, pointer_id, mask_size, pos);
+            send_fps_touch(
+                cs_tx,
+                fps_config,
+                MotionEventAction::Up,
+                pointer_id,
+                mask_size,
+                pos,
+            );
             Some(deferred_delta)
         }
         Some(PendingFpsTouch::Overlap { .. }) | None => None,
@@ -683,8 +876,22 @@ fn consume_overlap_fps_touch(
 
     let (physical_min, physical_max) = physical_bounds(mask_size);
     let pos = clamp_to_bounds(pos + delta, physical_min, physical_max);
-    send_fps_touch(cs_tx, MotionEventAction::Move, pointer_id, mask_size, pos);
-    send_fps_touch(cs_tx, MotionEventAction::Up, pointer_id, mask_size, pos);
+    send_fps_touch(
+        cs_tx,
+        fps_config,
+        MotionEventAction::Move,
+        pointer_id,
+        mask_size,
+        pos,
+    );
+    send_fps_touch(
+        cs_tx,
+        fps_config,
+        MotionEventAction::Up,
+        pointer_id,
+        mask_size,
+        pos,
+    );
     fps_config.pending_touch = None;
     Some(deferred_delta + delta)
 }
@@ -729,7 +936,14 @@ fn recenter_fps_touch(
             | PendingFpsTouch::Overlap {
                 pointer_id, pos, ..
             } => {
-                send_fps_touch(cs_tx, MotionEventAction::Up, pointer_id, mask_size, pos);
+                send_fps_touch(
+                    cs_tx,
+                    fps_config,
+                    MotionEventAction::Up,
+                    pointer_id,
+                    mask_size,
+                    pos,
+                );
             }
         }
     }
@@ -738,6 +952,7 @@ fn recenter_fps_touch(
         FpsTouchMode::Single { interval } => {
             send_fps_touch(
                 cs_tx,
+                fps_config,
                 MotionEventAction::Up,
                 fps_config.active_pointer_id,
                 mask_size,
@@ -755,6 +970,7 @@ fn recenter_fps_touch(
             }
             send_fps_touch(
                 cs_tx,
+                fps_config,
                 MotionEventAction::Down,
                 fps_config.active_pointer_id,
                 mask_size,
@@ -770,6 +986,7 @@ fn recenter_fps_touch(
             let new_pointer_id = alternate_fps_pointer_id(fps_config);
             send_fps_touch(
                 cs_tx,
+                fps_config,
                 MotionEventAction::Down,
                 new_pointer_id,
                 mask_size,
@@ -787,6 +1004,7 @@ fn recenter_fps_touch(
             }
             send_fps_touch(
                 cs_tx,
+                fps_config,
                 MotionEventAction::Up,
                 old_pointer_id,
                 mask_size,
@@ -801,6 +1019,7 @@ fn recenter_fps_touch(
             let new_pointer_id = alternate_fps_pointer_id(fps_config);
             send_fps_touch(
                 cs_tx,
+                fps_config,
                 MotionEventAction::Down,
                 new_pointer_id,
                 mask_size,
@@ -850,6 +1069,7 @@ fn apply_fps_delta(
             let new_pos = clamp_to_bounds(cursor_pos + delta, physical_min, physical_max);
             send_fps_touch(
                 cs_tx,
+                fps_config,
                 MotionEventAction::Move,
                 fps_config.active_pointer_id,
                 mask_size,
@@ -865,6 +1085,7 @@ fn apply_fps_delta(
         let touch_pos = clamp_to_bounds(boundary_pos, physical_min, physical_max);
         send_fps_touch(
             cs_tx,
+            fps_config,
             MotionEventAction::Move,
             fps_config.active_pointer_id,
             mask_size,
@@ -887,6 +1108,9 @@ fn apply_fps_delta(
 
 fn handle_cursor_fps(
     accumulated_motion: Res<AccumulatedMouseMotion>,
+    time: Res<Time>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
     mut cursor_pos: ResMut<CursorPosition>,
     mut fps_config: ResMut<ActiveCursorFpsConfig>,
     mut ignore_first_motion: ResMut<IgnoreFirstMotion>,
@@ -898,7 +1122,19 @@ fn handle_cursor_fps(
         return;
     }
 
-    let delta = accumulated_motion.delta * fps_config.sensitivity;
+    let ads_active = fps_config
+        .ads_key
+        .as_ref()
+        .is_some_and(|b| b.is_any_key_pressed(&key_input) || b.is_any_mouse_pressed(&mouse_input));
+    let sensitivity = if ads_active {
+        fps_config.sensitivity * fps_config.ads_sensitivity
+    } else {
+        fps_config.sensitivity
+    };
+    let delta =
+        fps_config
+            .aim_filter
+            .process(accumulated_motion.delta, sensitivity, time.delta_secs());
 
     if let Some(deferred_delta) =
         cleanup_pending_fps_touch(&cs_tx_res.0, &mut fps_config, mask_size.0)
@@ -998,6 +1234,9 @@ mod tests {
             original_size: Vec2::new(1000.0, 1000.0),
             max_offset: Vec2::new(50.0, 0.0),
             touch_mode,
+            aim_filter: FpsAimFilter::default(),
+            ads_key: None,
+            ads_sensitivity: 1.0,
             pending_touch: None,
         }
     }
@@ -1126,4 +1365,86 @@ mod tests {
             vec![(MotionEventAction::Down, 0, 500, 500)]
         );
     }
+
+    #[test]
+    fn fps_acceleration_multiplier_respects_offset_and_cap() {
+        let linear = FpsAcceleration::Linear {
+            gain: 0.5,
+            offset: 2.0,
+            cap: 2.0,
+        };
+        assert_eq!(linear.multiplier(1.0), 1.0);
+        assert_eq!(linear.multiplier(3.0), 1.5);
+        assert_eq!(linear.multiplier(10.0), 2.0);
+
+        let power = FpsAcceleration::Power {
+            gain: 1.0,
+            exponent: 2.0,
+            offset: 0.0,
+            cap: 0.0,
+        };
+        assert!((power.multiplier(3.0) - 10.0).abs() < 1e-4);
+        assert_eq!(FpsAcceleration::Off.multiplier(100.0), 1.0);
+    }
+
+    #[test]
+    fn fps_aim_filter_passes_through_without_smoothing() {
+        let mut filter = FpsAimFilter::default();
+        let delta = filter.process(Vec2::new(3.0, -2.0), Vec2::new(0.5, 2.0), 0.016);
+        assert_vec2_near(delta, Vec2::new(1.5, -4.0));
+    }
+
+    #[test]
+    fn fps_exponential_smoothing_catches_up_without_losing_motion() {
+        let mut filter = FpsAimFilter::new(
+            FpsAcceleration::Off,
+            FpsSmoothing::Exponential { alpha: 0.5 },
+        );
+        let first = filter.process(Vec2::new(10.0, 0.0), Vec2::ONE, 0.016);
+        assert_vec2_near(first, Vec2::new(5.0, 0.0));
+
+        let mut total = first;
+        for _ in 0..32 {
+            total += filter.process(Vec2::ZERO, Vec2::ONE, 0.016);
+        }
+        assert_vec2_near(total, Vec2::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn fps_one_euro_smoothing_lags_less_on_fast_motion() {
+        let smoothing = FpsSmoothing::OneEuro {
+            min_cutoff: 1.0,
+            beta: 0.05,
+            d_cutoff: 1.0,
+        };
+        let mut slow = FpsAimFilter::new(FpsAcceleration::Off, smoothing);
+        let mut fast = FpsAimFilter::new(FpsAcceleration::Off, smoothing);
+
+        let slow_delta = slow.process(Vec2::new(1.0, 0.0), Vec2::ONE, 0.016);
+        let fast_delta = fast.process(Vec2::new(100.0, 0.0), Vec2::ONE, 0.016);
+
+        assert!(slow_delta.x > 0.0 && slow_delta.x < 1.0);
+        assert!(fast_delta.x / 100.0 > slow_delta.x);
+    }
+
+    #[test]
+    fn fps_touch_is_sent_in_mapping_space() {
+        let (tx, mut rx) = broadcast::channel(32);
+        let mut config = fps_config(FpsTouchMode::Single { interval: 0 });
+        config.original_size = Vec2::new(2000.0, 2000.0);
+        config.original_pos = Vec2::new(1000.0, 1000.0);
+
+        apply_fps_delta(
+            &tx,
+            &mut config,
+            Vec2::new(1000.0, 1000.0),
+            Vec2::new(500.0, 500.0),
+            Vec2::new(0.5, 0.0),
+        );
+
+        assert_eq!(
+            collect_touch_events(&mut rx),
+            vec![(MotionEventAction::Move, 0, 1001, 1000)]
+        );
+    }
 }
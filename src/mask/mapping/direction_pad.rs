@@ -16,6 +16,7 @@ use crate::{
             MappingLifecycleStart, MappingLifecycleState, make_mapping_execution_context,
             run_script_hook,
         },
+        layout::MappingAnchor,
         script::{BindMappingScriptHooks, MappingScriptHooks},
         script_helper::{ScriptRuntimeCommandSender, ScriptSharedState},
         utils::{
@@ -125,6 +126,8 @@ impl From<MappingDirectionPad> for BindMappingDirectionPad {
 pub struct MappingDirectionPad {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub position: Position,
@@ -20,13 +20,14 @@ use crate::{
             binding::{ButtonBinding, ValidateMappingConfig},
             config::{ActiveMappingConfig, BindMappingConfig, BindMappingType},
             cursor::{
-                ActiveCursorFpsConfig, CursorPosition, CursorState, FPS_MARGIN, FpsTouchMode,
-                release_fps_touches, restore_fps_touch,
+                ActiveCursorFpsConfig, CursorPosition, CursorState, FPS_MARGIN, FpsAcceleration,
+                FpsAimFilter, FpsSmoothing, FpsTouchMode, release_fps_touches, restore_fps_touch,
             },
             executor::{
                 MappingLifecycleStart, MappingLifecycleState, make_mapping_execution_context,
                 run_script_hook,
             },
+            layout::MappingAnchor,
             script::{BindMappingScriptHooks, MappingScriptHooks},
             script_helper::{ScriptRuntimeCommandSender, ScriptSharedState},
             utils::{ControlMsgHelper, Position, default_random_offset, random_offset_vec2},
@@ -52,6 +53,10 @@ fn default_preserve_fps_control() -> bool {
     true
 }
 
+fn default_fps_ads_sensitivity() -> f32 {
+    1.0
+}
+
 #[derive(Debug, Clone)]
 pub struct BindMappingFps {
     pub id: String,
@@ -63,6 +68,10 @@ pub struct BindMappingFps {
     pub max_offset_x: f32,
     pub max_offset_y: f32,
     pub touch_mode: FpsTouchMode,
+    pub acceleration: FpsAcceleration,
+    pub smoothing: FpsSmoothing,
+    pub ads_key: Option<ButtonBinding>,
+    pub ads_sensitivity: f32,
     pub bind: ButtonBinding,
     pub input_binding: InputBinding,
 }
@@ -79,6 +88,10 @@ impl From<MappingFps> for BindMappingFps {
             max_offset_x: value.max_offset_x,
             max_offset_y: value.max_offset_y,
             touch_mode: value.touch_mode,
+            acceleration: value.acceleration,
+            smoothing: value.smoothing,
+            ads_key: value.ads_key,
+            ads_sensitivity: value.ads_sensitivity,
             bind: value.bind.clone(),
             input_binding: PulseBinding::just_pressed(value.bind).0,
         }
@@ -89,6 +102,8 @@ impl From<MappingFps> for BindMappingFps {
 pub struct MappingFps {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub position: Position,
@@ -108,6 +123,18 @@ pub struct MappingFps {
     pub max_offset_y: f32,
     #[serde(default)]
     pub touch_mode: FpsTouchMode,
+    #[serde(default)]
+    pub acceleration: FpsAcceleration,
+    #[serde(default)]
+    pub smoothing: FpsSmoothing,
+    // hold to scale sensitivity while aiming down sights / scoped
+    #[serde(default)]
+    pub ads_key: Option<ButtonBinding>,
+    #[serde(
+        default = "default_fps_ads_sensitivity",
+        serialize_with = "crate::mask::mapping::serde_float::serialize_f32_3dp"
+    )]
+    pub ads_sensitivity: f32,
     pub bind: ButtonBinding,
 }
 
@@ -127,6 +154,9 @@ pub fn enter_fps_mode(
     fps_config.sensitivity = (mapping.sensitivity_x, mapping.sensitivity_y).into();
     fps_config.max_offset = Vec2::new(mapping.max_offset_x, mapping.max_offset_y);
     fps_config.touch_mode = mapping.touch_mode;
+    fps_config.aim_filter = FpsAimFilter::new(mapping.acceleration, mapping.smoothing);
+    fps_config.ads_key = mapping.ads_key.clone();
+    fps_config.ads_sensitivity = mapping.ads_sensitivity;
     fps_config.touch_active = true;
 
     ControlMsgHelper::send_touch(
@@ -177,7 +207,11 @@ impl ValidateMappingConfig for MappingFps {
                 "FPS touch_mode another_pointer_id must differ from pointer_id".to_string(),
             );
         }
-        Ok(())
+        if self.ads_sensitivity <= 0.0 {
+            return Err("FPS ads_sensitivity must be greater than 0".to_string());
+        }
+        self.acceleration.validate()?;
+        self.smoothing.validate()
     }
 }
 
@@ -281,6 +315,8 @@ impl From<MappingFire> for BindMappingFire {
 pub struct MappingFire {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub position: Position,
@@ -0,0 +1,316 @@
+use std::time::Duration;
+
+use crate::tokio_tasks::TokioTasksRuntime;
+use bevy::{
+    ecs::system::{Res, ResMut},
+    math::Vec2,
+    state::state::State,
+};
+use bevy_ineffable::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::{
+    mask::mapping::{
+        MappingState,
+        binding::{ButtonBinding, ValidateMappingConfig},
+        config::ActiveMappingConfig,
+        cursor::{CursorPosition, CursorState},
+        executor::{MappingExecutionError, make_mapping_execution_context, run_with_hooks},
+        layout::MappingAnchor,
+        script::{BindMappingScriptHooks, MappingScriptHooks},
+        script_helper::{ScriptRuntimeCommandSender, ScriptSharedState},
+        utils::{ControlMsgHelper, Position, SingleSwipeStrategy},
+    },
+    mask::mask_command::MaskSize,
+    scrcpy::constant::MotionEventAction,
+    utils::ChannelSenderCS,
+};
+
+const GESTURE_STEP_MS: u64 = 16;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GestureKind {
+    /// Both fingers move towards the center
+    #[default]
+    PinchIn,
+    /// Both fingers move away from the center
+    PinchOut,
+    /// Both fingers circle around the center
+    Rotate,
+}
+
+#[derive(Debug, Clone)]
+pub struct BindMappingGesture {
+    pub id: String,
+    pub note: String,
+    pub pointer_id: u64,
+    pub second_pointer_id: u64,
+    pub position: Position,
+    pub kind: GestureKind,
+    pub span: f32,
+    pub min_span: f32,
+    pub angle: f32,
+    pub rotation: f32,
+    pub duration: u64,
+    pub strategy: SingleSwipeStrategy,
+    pub bind: ButtonBinding,
+    pub input_binding: InputBinding,
+    pub script_hooks: BindMappingScriptHooks,
+}
+
+impl From<MappingGesture> for BindMappingGesture {
+    fn from(value: MappingGesture) -> Self {
+        Self {
+            id: value.id,
+            note: value.note,
+            pointer_id: value.pointer_id,
+            second_pointer_id: value.second_pointer_id,
+            position: value.position,
+            kind: value.kind,
+            span: value.span,
+            min_span: value.min_span,
+            angle: value.angle,
+            rotation: value.rotation,
+            duration: value.duration,
+            strategy: value.strategy,
+            bind: value.bind.clone(),
+            input_binding: PulseBinding::just_pressed(value.bind).0,
+            script_hooks: value.script_hooks.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MappingGesture {
+    #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
+    pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
+    pub note: String,
+    pub pointer_id: u64,
+    pub second_pointer_id: u64,
+    /// Center of the gesture
+    pub position: Position,
+    pub kind: GestureKind,
+    /// Distance between the two fingers when fully spread
+    #[serde(serialize_with = "crate::mask::mapping::serde_float::serialize_f32_3dp")]
+    pub span: f32,
+    /// Distance between the two fingers when pinched
+    #[serde(serialize_with = "crate::mask::mapping::serde_float::serialize_f32_3dp")]
+    pub min_span: f32,
+    /// Direction of the line through both fingers in degrees, 0 is horizontal
+    #[serde(
+        default,
+        serialize_with = "crate::mask::mapping::serde_float::serialize_f32_3dp"
+    )]
+    pub angle: f32,
+    /// Rotation in degrees for `Rotate`, positive is clockwise on screen
+    #[serde(
+        default,
+        serialize_with = "crate::mask::mapping::serde_float::serialize_f32_3dp"
+    )]
+    pub rotation: f32,
+    pub duration: u64,
+    #[serde(default)]
+    pub strategy: SingleSwipeStrategy,
+    pub bind: ButtonBinding,
+    #[serde(default)]
+    pub script_hooks: MappingScriptHooks,
+}
+
+impl MappingGesture {
+    pub fn validate_gesture(&self) -> Result<(), String> {
+        if self.pointer_id == self.second_pointer_id {
+            return Err("Gesture's two pointer ids must be different".to_string());
+        }
+        if !(self.span > 0.0 && self.min_span >= 0.0 && self.min_span < self.span) {
+            return Err("Gesture's min_span must be non-negative and less than span".to_string());
+        }
+        if self.kind == GestureKind::Rotate && self.rotation == 0.0 {
+            return Err("Gesture's rotation must not be zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl ValidateMappingConfig for MappingGesture {
+    fn validate(&self) -> Result<(), String> {
+        self.validate_gesture()?;
+        self.script_hooks.validate()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GestureStep {
+    pub first: Vec2,
+    pub second: Vec2,
+    pub wait_ms: u64,
+}
+
+impl BindMappingGesture {
+    fn finger_positions(&self, progress: f32) -> (Vec2, Vec2) {
+        let center: Vec2 = self.position.into();
+        let (distance, angle) = match self.kind {
+            GestureKind::PinchIn => (
+                self.span + (self.min_span - self.span) * progress,
+                self.angle,
+            ),
+            GestureKind::PinchOut => (
+                self.min_span + (self.span - self.min_span) * progress,
+                self.angle,
+            ),
+            GestureKind::Rotate => (self.span, self.angle + self.rotation * progress),
+        };
+        let offset = Vec2::from_angle(angle.to_radians()) * distance / 2.0;
+        (center - offset, center + offset)
+    }
+
+    /// Positions of both fingers over time, the first step is the touch-down position.
+    /// Fingers that would leave the screen of `size` are kept on its edge.
+    pub fn build_steps(&self, size: Vec2) -> Vec<GestureStep> {
+        let steps = (self.duration / GESTURE_STEP_MS).clamp(4, 60);
+        let per_step_wait = self.duration / steps;
+        (0..=steps)
+            .map(|step| {
+                let progress = self.strategy.progress(step as f32 / steps as f32);
+                let (first, second) = self.finger_positions(progress);
+                GestureStep {
+                    first: first.clamp(Vec2::ZERO, size),
+                    second: second.clamp(Vec2::ZERO, size),
+                    wait_ms: if step == 0 { 0 } else { per_step_wait },
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn handle_gesture(
+    ineffable: Res<Ineffable>,
+    active_mapping: Res<ActiveMappingConfig>,
+    cs_tx_res: Res<ChannelSenderCS>,
+    script_command_tx: Res<ScriptRuntimeCommandSender>,
+    shared_state: Res<ScriptSharedState>,
+    mask_size: Res<MaskSize>,
+    cursor_pos: Res<CursorPosition>,
+    mapping_state: Res<State<MappingState>>,
+    cursor_state: Res<State<CursorState>>,
+    runtime: ResMut<TokioTasksRuntime>,
+) {
+    if let Some(active_mapping) = &active_mapping.0 {
+        for (action, mapping) in &active_mapping.mappings {
+            if action.as_ref().starts_with("Gesture") {
+                let mapping = mapping.as_ref_gesture();
+                let original_size: Vec2 = active_mapping.original_size.into();
+                if ineffable.just_pulsed(action.ineff_pulse()) {
+                    let pointer_ids = (mapping.pointer_id, mapping.second_pointer_id);
+                    let steps = mapping.build_steps(original_size);
+                    let hooks = mapping.script_hooks.clone();
+                    let exec_ctx = make_mapping_execution_context(
+                        &cs_tx_res,
+                        &script_command_tx,
+                        &shared_state,
+                        mapping.id.clone(),
+                        original_size,
+                        cursor_pos.0,
+                        mask_size.0,
+                        mapping_state.get() == &MappingState::RawInput,
+                        cursor_state.get() == &CursorState::Fps,
+                    );
+                    runtime.spawn_background_task(move |_ctx| async move {
+                        let result = run_with_hooks(hooks, exec_ctx, move |ctx| async move {
+                            let send_both = |action: MotionEventAction, step: &GestureStep| {
+                                ControlMsgHelper::send_touch(
+                                    &ctx.cs_tx,
+                                    action,
+                                    pointer_ids.0,
+                                    ctx.original_size,
+                                    step.first,
+                                );
+                                ControlMsgHelper::send_touch(
+                                    &ctx.cs_tx,
+                                    action,
+                                    pointer_ids.1,
+                                    ctx.original_size,
+                                    step.second,
+                                );
+                            };
+
+                            send_both(MotionEventAction::Down, &steps[0]);
+                            for step in &steps[1..] {
+                                sleep(Duration::from_millis(step.wait_ms)).await;
+                                send_both(MotionEventAction::Move, step);
+                            }
+                            send_both(MotionEventAction::Up, steps.last().unwrap());
+                            Ok::<(), MappingExecutionError>(())
+                        })
+                        .await;
+                        if let Err(e) = result {
+                            log::error!("[Gesture] mapping execution error: {:?}", e);
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::mapping::binding::MergedButton;
+    use bevy::input::keyboard::KeyCode;
+
+    fn gesture(x: i32, y: i32, kind: GestureKind) -> BindMappingGesture {
+        MappingGesture {
+            id: "gesture".to_string(),
+            anchor: MappingAnchor::default(),
+            note: String::new(),
+            pointer_id: 1,
+            second_pointer_id: 2,
+            position: Position { x, y },
+            kind,
+            span: 400.,
+            min_span: 100.,
+            angle: 0.,
+            rotation: 90.,
+            duration: 160,
+            strategy: SingleSwipeStrategy::default(),
+            bind: ButtonBinding::new(vec![MergedButton::Keyboard(KeyCode::KeyG)]),
+            script_hooks: MappingScriptHooks::default(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn steps_start_spread_and_end_pinched() {
+        let size = Vec2::new(1920., 1080.);
+        let steps = gesture(960, 540, GestureKind::PinchIn).build_steps(size);
+        assert_eq!(steps.len(), 11);
+        assert_eq!(steps[0].wait_ms, 0);
+        assert_eq!(steps[0].first, Vec2::new(760., 540.));
+        assert_eq!(steps[0].second, Vec2::new(1160., 540.));
+        let last = steps.last().unwrap();
+        assert!(((last.second - last.first).length() - 100.).abs() < 0.01);
+    }
+
+    #[test]
+    fn steps_are_clamped_to_the_screen() {
+        let size = Vec2::new(1920., 1080.);
+        for kind in [
+            GestureKind::PinchIn,
+            GestureKind::PinchOut,
+            GestureKind::Rotate,
+        ] {
+            for step in gesture(50, 1060, kind).build_steps(size) {
+                for pos in [step.first, step.second] {
+                    assert!(pos.x >= 0. && pos.x <= size.x, "{:?} {:?}", kind, pos);
+                    assert!(pos.y >= 0. && pos.y <= size.y, "{:?} {:?}", kind, pos);
+                }
+            }
+        }
+        let steps = gesture(50, 540, GestureKind::PinchOut).build_steps(size);
+        assert_eq!(steps.last().unwrap().first, Vec2::new(0., 540.));
+    }
+}
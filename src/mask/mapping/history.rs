@@ -0,0 +1,462 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::utils::{is_safe_file_name, relate_to_data_path};
+
+/// Revisions kept per mapping file, the oldest are dropped first
+pub const MAX_REVISIONS: usize = 50;
+/// Deleted mapping files kept in the trash
+pub const MAX_TRASHED: usize = 50;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MappingRevision {
+    pub id: String,
+    /// Milliseconds since the unix epoch
+    pub time: u64,
+    pub size: u64,
+    /// None when the revision isn't a readable mapping file
+    pub mapping_count: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TrashedMapping {
+    pub id: String,
+    pub file: String,
+    pub time: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MappingSummary {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub mapping_type: String,
+    pub note: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ChangedMapping {
+    #[serde(flatten)]
+    pub mapping: MappingSummary,
+    pub fields: Vec<String>,
+}
+
+/// What changed from one version of a mapping file to another, mappings are matched by id
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct MappingDiff {
+    /// Changed top level fields other than `mappings`
+    pub fields: Vec<String>,
+    pub added: Vec<MappingSummary>,
+    pub removed: Vec<MappingSummary>,
+    pub changed: Vec<ChangedMapping>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn data_root() -> PathBuf {
+    relate_to_data_path([""; 0])
+}
+
+fn history_dir(root: &Path, file: &str) -> PathBuf {
+    root.join("mapping_history").join(file)
+}
+
+fn trash_dir(root: &Path) -> PathBuf {
+    root.join("mapping_trash")
+}
+
+/// Revisions of trashed files, one folder per trash id
+fn trash_history_dir(root: &Path, id: &str) -> PathBuf {
+    root.join("mapping_trash_history").join(id)
+}
+
+/// Entries of `dir` named after the time they were written, oldest first
+fn timestamped_entries(dir: &Path) -> Vec<(u64, String)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut entries = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+        .filter_map(|name| {
+            let time = name.split(['.', '-']).next()?.parse::<u64>().ok()?;
+            Some((time, name))
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+/// Removes the oldest entries beyond `max`, returns their names
+fn prune(dir: &Path, max: usize) -> Vec<String> {
+    let entries = timestamped_entries(dir);
+    let mut removed = Vec::new();
+    for (_, name) in entries.iter().take(entries.len().saturating_sub(max)) {
+        match fs::remove_file(dir.join(name)) {
+            Ok(()) => removed.push(name.clone()),
+            Err(e) => log::warn!("[Mapping] Failed to prune {}: {}", name, e),
+        }
+    }
+    removed
+}
+
+/// A name starting with `time` that isn't taken in `dir` yet
+fn unique_name_from(dir: &Path, mut time: u64, suffix: &str) -> String {
+    loop {
+        let name = format!("{}{}", time, suffix);
+        if !dir.join(&name).exists() {
+            return name;
+        }
+        time += 1;
+    }
+}
+
+/// A name starting with the current time that isn't taken in `dir` yet
+fn unique_name(dir: &Path, suffix: &str) -> String {
+    unique_name_from(dir, now_millis(), suffix)
+}
+
+/// Moves the revisions in `from` to `to`, revisions already in `to` are kept
+fn move_revisions(from: &Path, to: &Path) -> Result<(), String> {
+    if !from.exists() {
+        return Ok(());
+    }
+    if !to.exists() {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create history dir: {}", e))?;
+        }
+        return fs::rename(from, to).map_err(|e| format!("Failed to move history: {}", e));
+    }
+    log::warn!(
+        "[Mapping] History already exists at {}, the revisions are merged",
+        to.display()
+    );
+    for (time, name) in timestamped_entries(from) {
+        let target = to.join(unique_name_from(to, time, ".json"));
+        fs::rename(from.join(&name), target)
+            .map_err(|e| format!("Failed to move revision {}: {}", name, e))?;
+    }
+    prune(to, MAX_REVISIONS);
+    fs::remove_dir_all(from).map_err(|e| format!("Failed to remove history: {}", e))
+}
+
+/// Keeps the current content of `path` as a revision before it's overwritten with `new_content`
+pub fn record_revision(path: &Path, new_content: &str) -> Result<(), String> {
+    record_revision_in(&data_root(), path, new_content)
+}
+
+fn record_revision_in(root: &Path, path: &Path, new_content: &str) -> Result<(), String> {
+    let Some(file) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(());
+    };
+    let Ok(content) = fs::read_to_string(path) else {
+        // nothing to keep for new files
+        return Ok(());
+    };
+    if content == new_content {
+        return Ok(());
+    }
+
+    let dir = history_dir(root, file);
+    if let Some((_, latest)) = timestamped_entries(&dir).last() {
+        if fs::read_to_string(dir.join(latest)).is_ok_and(|latest| latest == content) {
+            return Ok(());
+        }
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create history dir: {}", e))?;
+    fs::write(dir.join(unique_name(&dir, ".json")), content)
+        .map_err(|e| format!("Failed to record revision of {}: {}", file, e))?;
+    prune(&dir, MAX_REVISIONS);
+    Ok(())
+}
+
+/// Revisions of a mapping file, newest first
+pub fn list_revisions(file: &str) -> Vec<MappingRevision> {
+    list_revisions_in(&data_root(), file)
+}
+
+fn list_revisions_in(root: &Path, file: &str) -> Vec<MappingRevision> {
+    let dir = history_dir(root, file);
+    timestamped_entries(&dir)
+        .into_iter()
+        .rev()
+        .map(|(time, name)| {
+            let path = dir.join(&name);
+            let mapping_count = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+                .and_then(|value| value.get("mappings")?.as_array().map(Vec::len));
+            MappingRevision {
+                id: name.trim_end_matches(".json").to_string(),
+                time,
+                size: fs::metadata(&path).map(|m| m.len()).unwrap_or_default(),
+                mapping_count,
+            }
+        })
+        .collect()
+}
+
+pub fn read_revision(file: &str, id: &str) -> Result<String, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid revision id: {}", id));
+    }
+    fs::read_to_string(history_dir(&data_root(), file).join(format!("{}.json", id)))
+        .map_err(|e| format!("Failed to read revision {} of {}: {}", id, file, e))
+}
+
+/// Revisions follow their mapping file when it's renamed
+pub fn move_history(file: &str, new_file: &str) -> Result<(), String> {
+    let root = data_root();
+    move_revisions(&history_dir(&root, file), &history_dir(&root, new_file))
+        .map_err(|e| format!("Failed to move history of {}: {}", file, e))
+}
+
+/// Moves a mapping file and its revisions to the trash instead of deleting them, returns its trash id
+pub fn trash_mapping_file(path: &Path) -> Result<String, String> {
+    trash_mapping_file_in(&data_root(), path)
+}
+
+fn trash_mapping_file_in(root: &Path, path: &Path) -> Result<String, String> {
+    let file = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid mapping file path: {}", path.display()))?;
+    let dir = trash_dir(root);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create trash dir: {}", e))?;
+    let id = unique_name(&dir, &format!("-{}", file));
+    fs::rename(path, dir.join(&id))
+        .map_err(|e| format!("Failed to move {} to trash: {}", file, e))?;
+    // a new file with the same name starts without history
+    if let Err(e) = move_revisions(&history_dir(root, file), &trash_history_dir(root, &id)) {
+        log::warn!("[Mapping] History of {} is left behind: {}", file, e);
+    }
+    for id in prune(&dir, MAX_TRASHED) {
+        let _ = fs::remove_dir_all(trash_history_dir(root, &id));
+    }
+    Ok(id)
+}
+
+/// Trashed mapping files, most recently deleted first
+pub fn list_trash() -> Vec<TrashedMapping> {
+    list_trash_in(&data_root())
+}
+
+fn list_trash_in(root: &Path) -> Vec<TrashedMapping> {
+    timestamped_entries(&trash_dir(root))
+        .into_iter()
+        .rev()
+        .filter_map(|(time, id)| {
+            let file = id.split_once('-')?.1.to_string();
+            Some(TrashedMapping { id, file, time })
+        })
+        .collect()
+}
+
+/// Moves a trashed file and its revisions back to `target`
+pub fn restore_trashed(id: &str, target: &Path) -> Result<(), String> {
+    restore_trashed_in(&data_root(), id, target)
+}
+
+fn restore_trashed_in(root: &Path, id: &str, target: &Path) -> Result<(), String> {
+    if !is_safe_file_name(id) {
+        return Err(format!("Invalid trash id: {}", id));
+    }
+    let path = trash_dir(root).join(id);
+    if !path.exists() {
+        return Err(format!("Trashed mapping not found: {}", id));
+    }
+    let file = target
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid mapping file path: {}", target.display()))?;
+    fs::rename(&path, target).map_err(|e| format!("Failed to restore {}: {}", id, e))?;
+    if let Err(e) = move_revisions(&trash_history_dir(root, id), &history_dir(root, file)) {
+        log::warn!("[Mapping] History of {} is not restored: {}", file, e);
+    }
+    Ok(())
+}
+
+fn mapping_summary(key: &str, mapping: &Value) -> MappingSummary {
+    let field = |name: &str| {
+        mapping
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    MappingSummary {
+        id: key.to_string(),
+        mapping_type: field("type"),
+        note: field("note"),
+    }
+}
+
+/// Mappings by id, files from before ids were stored fall back to their index
+fn mappings_by_id(config: &Value) -> Vec<(String, &Value)> {
+    config
+        .get("mappings")
+        .and_then(Value::as_array)
+        .map(|mappings| {
+            mappings
+                .iter()
+                .enumerate()
+                .map(|(i, mapping)| {
+                    let key = mapping
+                        .get("id")
+                        .and_then(Value::as_str)
+                        .map(|id| id.to_string())
+                        .unwrap_or_else(|| format!("#{}", i));
+                    (key, mapping)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn changed_keys(old: &Value, new: &Value, skip: &[&str]) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+    old.keys()
+        .chain(new.keys())
+        .filter(|key| !skip.contains(&key.as_str()) && old.get(*key) != new.get(*key))
+        .map(|key| key.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+pub fn diff_mapping_configs(old: &Value, new: &Value) -> MappingDiff {
+    let old_mappings = mappings_by_id(old);
+    let new_mappings = mappings_by_id(new);
+    let old_map = old_mappings.iter().cloned().collect::<HashMap<_, _>>();
+    let new_map = new_mappings.iter().cloned().collect::<HashMap<_, _>>();
+
+    let mut diff = MappingDiff {
+        fields: changed_keys(old, new, &["mappings", "version"]),
+        ..Default::default()
+    };
+    for (key, mapping) in &new_mappings {
+        match old_map.get(key) {
+            None => diff.added.push(mapping_summary(key, mapping)),
+            Some(old_mapping) if old_mapping != mapping => diff.changed.push(ChangedMapping {
+                mapping: mapping_summary(key, mapping),
+                fields: changed_keys(old_mapping, mapping, &[]),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, mapping) in &old_mappings {
+        if !new_map.contains_key(key) {
+            diff.removed.push(mapping_summary(key, mapping));
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// An empty data folder of its own for each test
+    fn test_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("scrcpy-mask-history-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("mapping")).unwrap();
+        root
+    }
+
+    #[test]
+    fn revisions_are_pruned_to_the_limit() {
+        let root = test_root("prune");
+        let path = root.join("mapping").join("a.json");
+        fs::write(&path, "0").unwrap();
+        for i in 1..=MAX_REVISIONS + 5 {
+            record_revision_in(&root, &path, &i.to_string()).unwrap();
+            fs::write(&path, i.to_string()).unwrap();
+        }
+        let revisions = list_revisions_in(&root, "a.json");
+        assert_eq!(revisions.len(), MAX_REVISIONS);
+        // the oldest ones are dropped
+        let oldest = &revisions.last().unwrap().id;
+        let content =
+            fs::read_to_string(history_dir(&root, "a.json").join(format!("{}.json", oldest)));
+        assert_eq!(content.unwrap(), "5");
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn history_goes_to_the_trash_and_back() {
+        let root = test_root("trash");
+        let path = root.join("mapping").join("a.json");
+        fs::write(&path, "old").unwrap();
+        record_revision_in(&root, &path, "new").unwrap();
+        fs::write(&path, "new").unwrap();
+
+        let id = trash_mapping_file_in(&root, &path).unwrap();
+        assert!(!path.exists());
+        // a new file with the same name doesn't inherit the history
+        assert!(list_revisions_in(&root, "a.json").is_empty());
+        assert_eq!(list_trash_in(&root)[0].file, "a.json");
+
+        let target = root.join("mapping").join("b.json");
+        restore_trashed_in(&root, &id, &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert!(list_trash_in(&root).is_empty());
+        assert_eq!(list_revisions_in(&root, "b.json").len(), 1);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn moved_history_keeps_existing_revisions() {
+        let root = test_root("move");
+        for (file, content) in [("a.json", "a"), ("b.json", "b")] {
+            let path = root.join("mapping").join(file);
+            fs::write(&path, content).unwrap();
+            record_revision_in(&root, &path, "changed").unwrap();
+        }
+        move_revisions(&history_dir(&root, "a.json"), &history_dir(&root, "b.json")).unwrap();
+        assert!(!history_dir(&root, "a.json").exists());
+        assert_eq!(list_revisions_in(&root, "b.json").len(), 2);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn diff_matches_mappings_by_id() {
+        let old = json!({
+            "version": 1,
+            "original_size": { "width": 1920, "height": 1080 },
+            "mappings": [
+                { "id": "a", "type": "SingleTap", "note": "jump", "position": { "x": 1, "y": 1 } },
+                { "id": "b", "type": "SingleTap", "note": "", "position": { "x": 2, "y": 2 } },
+            ],
+        });
+        let new = json!({
+            "version": 2,
+            "original_size": { "width": 2560, "height": 1440 },
+            "mappings": [
+                { "id": "c", "type": "Fps", "note": "" },
+                { "id": "a", "type": "SingleTap", "note": "jump", "position": { "x": 5, "y": 1 } },
+            ],
+        });
+        let diff = diff_mapping_configs(&old, &new);
+        assert_eq!(diff.fields, ["original_size"]);
+        assert_eq!(diff.added[0].id, "c");
+        assert_eq!(diff.removed[0].id, "b");
+        assert_eq!(diff.changed[0].mapping.id, "a");
+        assert_eq!(diff.changed[0].fields, ["position"]);
+    }
+}
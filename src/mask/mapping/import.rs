@@ -0,0 +1,492 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::mask::mapping::{
+    binding::MergedButton,
+    config::{MappingConfig, MappingDiagnostic, MappingType, default_mapping_config},
+    utils::{Position, Size},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// QtScrcpy keymap, positions are relative to the screen size
+    QtScrcpy,
+    /// scrcpy-mask before 0.9, a `list` of keys laid out on `relativeSize`
+    ScrcpyMaskLegacy,
+}
+
+pub struct ImportedMapping {
+    pub format: ImportFormat,
+    pub config: MappingConfig,
+    /// Entries that were skipped or only partly converted
+    pub diagnostics: Vec<MappingDiagnostic>,
+}
+
+pub fn detect_import_format(value: &Value) -> Option<ImportFormat> {
+    if value.get("keyMapNodes").is_some() {
+        Some(ImportFormat::QtScrcpy)
+    } else if value.get("list").is_some() && value.get("relativeSize").is_some() {
+        Some(ImportFormat::ScrcpyMaskLegacy)
+    } else {
+        None
+    }
+}
+
+/// Converts a keymap of another keymapper into a mapping config.
+/// `size` is the screen the layout is placed on when the source only has relative positions
+pub fn import_mapping_config(
+    value: &Value,
+    format: Option<ImportFormat>,
+    size: Option<Size>,
+) -> Result<ImportedMapping, MappingDiagnostic> {
+    let format = format
+        .or_else(|| detect_import_format(value))
+        .ok_or_else(|| {
+            MappingDiagnostic::config(
+                "mapping.import.unknownFormat",
+                "Unrecognized keymap format, expected a QtScrcpy or legacy scrcpy-mask file",
+            )
+        })?;
+
+    let mut config = default_mapping_config();
+    let mut importer = Importer::default();
+    match format {
+        ImportFormat::QtScrcpy => {
+            if let Some(size) = size {
+                config.original_size = size;
+            }
+            importer.import_qt_scrcpy(value, config.original_size)?;
+        }
+        ImportFormat::ScrcpyMaskLegacy => {
+            config.original_size = legacy_size(value)?;
+            importer.import_legacy(value)?;
+        }
+    }
+    config.mappings = importer.mappings;
+    Ok(ImportedMapping {
+        format,
+        config,
+        diagnostics: importer.diagnostics,
+    })
+}
+
+#[derive(Default)]
+struct Importer {
+    mappings: Vec<MappingType>,
+    diagnostics: Vec<MappingDiagnostic>,
+    next_pointer_id: u64,
+}
+
+impl Importer {
+    fn warn(&mut self, field: String, message: String) {
+        let mut diagnostic = MappingDiagnostic::config("mapping.import.skipped", message).warning();
+        diagnostic.field = Some(field);
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn add(&mut self, field: String, mapping: Result<Value, String>) {
+        match mapping.and_then(|mapping| {
+            serde_json::from_value::<MappingType>(mapping)
+                .map_err(|e| format!("Converted mapping is invalid: {}", e))
+        }) {
+            Ok(mapping) => self.mappings.push(mapping),
+            Err(message) => self.warn(field, message),
+        }
+    }
+
+    /// QtScrcpy has no pointer ids, every converted mapping gets its own
+    fn pointer_id(&mut self) -> u64 {
+        self.next_pointer_id += 1;
+        self.next_pointer_id
+    }
+
+    fn import_qt_scrcpy(&mut self, value: &Value, size: Size) -> Result<(), MappingDiagnostic> {
+        if let Some(mouse_move) = value.get("mouseMoveMap") {
+            let switch_key = value
+                .get("switchKey")
+                .and_then(Value::as_str)
+                .unwrap_or("Key_QuoteLeft");
+            let fps = self.qt_mouse_move(mouse_move, switch_key, size);
+            self.add("mouseMoveMap".to_string(), fps);
+            if mouse_move.get("smallEyes").is_some() {
+                self.warn(
+                    "mouseMoveMap.smallEyes".to_string(),
+                    "Free look (smallEyes) has no counterpart and was skipped".to_string(),
+                );
+            }
+        }
+
+        let nodes = value
+            .get("keyMapNodes")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid_file("keyMapNodes must be an array"))?;
+        for (i, node) in nodes.iter().enumerate() {
+            let field = format!("keyMapNodes[{}]", i);
+            let mapping = match node.get("type").and_then(Value::as_str) {
+                Some("KMT_CLICK") => {
+                    if node.get("switchMap").and_then(Value::as_bool) == Some(true) {
+                        self.warn(
+                            field.clone(),
+                            "switchMap is not supported and was ignored".to_string(),
+                        );
+                    }
+                    self.qt_click(node, size)
+                }
+                Some("KMT_DRAG") => self.qt_drag(node, size),
+                Some("KMT_STEER_WHEEL") => self.qt_steer_wheel(node, size),
+                Some(other) => Err(format!("{} has no counterpart and was skipped", other)),
+                None => Err("Entry without a type was skipped".to_string()),
+            };
+            self.add(field, mapping);
+        }
+        Ok(())
+    }
+
+    fn qt_mouse_move(
+        &mut self,
+        node: &Value,
+        switch_key: &str,
+        size: Size,
+    ) -> Result<Value, String> {
+        // QtScrcpy divides mouse deltas by the speed ratio
+        let ratio = |key: &str| {
+            node.get(key)
+                .or_else(|| node.get("speedRatio"))
+                .and_then(Value::as_f64)
+                .filter(|ratio| *ratio > 0.0)
+                .unwrap_or(1.0)
+        };
+        Ok(json!({
+            "type": "Fps",
+            "note": "",
+            "pointer_id": self.pointer_id(),
+            "position": qt_position(node, "startPos", size)?,
+            "sensitivity_x": 1.0 / ratio("speedRatioX"),
+            "sensitivity_y": 1.0 / ratio("speedRatioY"),
+            "bind": [qt_key(switch_key)?],
+        }))
+    }
+
+    fn qt_click(&mut self, node: &Value, size: Size) -> Result<Value, String> {
+        Ok(json!({
+            "type": "SingleTap",
+            "note": qt_note(node),
+            "pointer_id": self.pointer_id(),
+            "position": qt_position(node, "pos", size)?,
+            "duration": 50,
+            // QtScrcpy keeps the finger down while the key is held
+            "sync": true,
+            "bind": [qt_key(str_field(node, "key")?)?],
+        }))
+    }
+
+    fn qt_drag(&mut self, node: &Value, size: Size) -> Result<Value, String> {
+        Ok(json!({
+            "type": "Swipe",
+            "note": qt_note(node),
+            "pointer_id": self.pointer_id(),
+            "positions": [
+                qt_position(node, "startPos", size)?,
+                qt_position(node, "endPos", size)?,
+            ],
+            "duration": 100,
+            "bind": [qt_key(str_field(node, "key")?)?],
+        }))
+    }
+
+    fn qt_steer_wheel(&mut self, node: &Value, size: Size) -> Result<Value, String> {
+        let offset = |a: &str, b: &str| -> Result<f64, String> {
+            Ok((f64_field(node, a)? + f64_field(node, b)?) / 2.0)
+        };
+        let key = |name: &str| -> Result<Vec<String>, String> {
+            Ok(vec![qt_key(str_field(node, name)?)?])
+        };
+        Ok(json!({
+            "type": "DirectionPad",
+            "note": qt_note(node),
+            "pointer_id": self.pointer_id(),
+            "position": qt_position(node, "centerPos", size)?,
+            "initial_duration": 0,
+            "max_offset_x": offset("leftOffset", "rightOffset")? * size.width as f64,
+            "max_offset_y": offset("upOffset", "downOffset")? * size.height as f64,
+            "bind": {
+                "type": "Button",
+                "up": key("upKey")?,
+                "down": key("downKey")?,
+                "left": key("leftKey")?,
+                "right": key("rightKey")?,
+            },
+        }))
+    }
+
+    fn import_legacy(&mut self, value: &Value) -> Result<(), MappingDiagnostic> {
+        let list = value
+            .get("list")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid_file("list must be an array"))?;
+        for (i, item) in list.iter().enumerate() {
+            let field = format!("list[{}]", i);
+            let mapping = match item.get("type").and_then(Value::as_str) {
+                Some("Tap") => legacy_tap(item),
+                Some("Swipe") => legacy_swipe(item),
+                Some("SteeringWheel") => legacy_steering_wheel(item),
+                Some("Sight") => legacy_sight(item),
+                Some(other) => Err(format!("{} has no counterpart and was skipped", other)),
+                None => Err("Entry without a type was skipped".to_string()),
+            };
+            self.add(field, mapping);
+        }
+        Ok(())
+    }
+}
+
+fn invalid_file(message: &str) -> MappingDiagnostic {
+    MappingDiagnostic::config("mapping.import.invalidFile", message)
+}
+
+fn str_field<'a>(node: &'a Value, key: &str) -> Result<&'a str, String> {
+    node.get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Missing {}", key))
+}
+
+fn f64_field(node: &Value, key: &str) -> Result<f64, String> {
+    node.get(key)
+        .and_then(Value::as_f64)
+        .ok_or_else(|| format!("Missing {}", key))
+}
+
+fn checked_button(button: String, original: &str) -> Result<String, String> {
+    MergedButton::from_str(&button)
+        .map(|_| button)
+        .map_err(|_| format!("Unknown key {}", original))
+}
+
+fn qt_note(node: &Value) -> &str {
+    node.get("comment").and_then(Value::as_str).unwrap_or("")
+}
+
+fn qt_position(node: &Value, key: &str, size: Size) -> Result<Position, String> {
+    let pos = node.get(key).ok_or_else(|| format!("Missing {}", key))?;
+    Ok(Position {
+        x: (f64_field(pos, "x")? * size.width as f64).round() as i32,
+        y: (f64_field(pos, "y")? * size.height as f64).round() as i32,
+    })
+}
+
+/// Qt key names like `Key_W` and `LeftButton` to our button names
+fn qt_key(name: &str) -> Result<String, String> {
+    let button = match name {
+        "LeftButton" => "M-Left".to_string(),
+        "RightButton" => "M-Right".to_string(),
+        "MidButton" | "MiddleButton" => "M-Middle".to_string(),
+        "BackButton" | "XButton1" => "M-Back".to_string(),
+        "ForwardButton" | "XButton2" => "M-Forward".to_string(),
+        _ => {
+            let key = name
+                .strip_prefix("Key_")
+                .ok_or_else(|| format!("Unknown key {}", name))?;
+            match key {
+                k if k.len() == 1 && k.chars().all(|c| c.is_ascii_uppercase()) => {
+                    format!("Key{}", k)
+                }
+                k if k.len() == 1 && k.chars().all(|c| c.is_ascii_digit()) => {
+                    format!("Digit{}", k)
+                }
+                "Up" | "Down" | "Left" | "Right" => format!("Arrow{}", key),
+                "Shift" => "ShiftLeft".to_string(),
+                "Control" => "ControlLeft".to_string(),
+                "Alt" => "AltLeft".to_string(),
+                "Meta" => "SuperLeft".to_string(),
+                "Return" => "Enter".to_string(),
+                "Enter" => "NumpadEnter".to_string(),
+                "QuoteLeft" => "Backquote".to_string(),
+                "Apostrophe" => "Quote".to_string(),
+                // Space, Tab, Escape, F1 and the punctuation keys share their names
+                _ => key.to_string(),
+            }
+        }
+    };
+    checked_button(button, name)
+}
+
+/// Legacy files used `KeyboardEvent.code` names and `M<button>` for the mouse
+fn legacy_key(name: &str) -> Result<String, String> {
+    let button = match name {
+        "M0" => "M-Left",
+        "M1" => "M-Middle",
+        "M2" => "M-Right",
+        "M3" => "M-Back",
+        "M4" => "M-Forward",
+        "WheelUp" => "ScrollUp",
+        "WheelDown" => "ScrollDown",
+        _ => name,
+    };
+    checked_button(button.to_string(), name)
+}
+
+fn legacy_size(value: &Value) -> Result<Size, MappingDiagnostic> {
+    let size = value
+        .get("relativeSize")
+        .ok_or_else(|| invalid_file("Missing relativeSize"))?;
+    let side = |key: &str| {
+        size.get(key)
+            .and_then(Value::as_u64)
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v > 0)
+            .ok_or_else(|| invalid_file("relativeSize must have a positive w and h"))
+    };
+    Ok(Size {
+        width: side("w")?,
+        height: side("h")?,
+    })
+}
+
+fn legacy_position(item: &Value) -> Result<Position, String> {
+    Ok(Position {
+        x: f64_field(item, "posX")?.round() as i32,
+        y: f64_field(item, "posY")?.round() as i32,
+    })
+}
+
+fn legacy_common(item: &Value, mapping_type: &str) -> Result<Value, String> {
+    Ok(json!({
+        "type": mapping_type,
+        "note": item.get("note").and_then(Value::as_str).unwrap_or(""),
+        "pointer_id": item
+            .get("pointerId")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| "Missing pointerId".to_string())?,
+        "position": legacy_position(item)?,
+    }))
+}
+
+fn legacy_tap(item: &Value) -> Result<Value, String> {
+    let mut mapping = legacy_common(item, "SingleTap")?;
+    mapping["duration"] = json!(item.get("time").and_then(Value::as_u64).unwrap_or(50));
+    mapping["sync"] = json!(false);
+    mapping["bind"] = json!([legacy_key(str_field(item, "key")?)?]);
+    Ok(mapping)
+}
+
+fn legacy_swipe(item: &Value) -> Result<Value, String> {
+    let mut mapping = legacy_common(item, "Swipe")?;
+    let positions = item
+        .get("pos")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "Missing pos".to_string())?
+        .iter()
+        .map(|pos| -> Result<Position, String> {
+            Ok(Position {
+                x: f64_field(pos, "x")?.round() as i32,
+                y: f64_field(pos, "y")?.round() as i32,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    mapping["positions"] = json!(positions);
+    mapping["duration"] = json!(
+        item.get("intervalBetweenPos")
+            .and_then(Value::as_u64)
+            .unwrap_or(100)
+    );
+    mapping["bind"] = json!([legacy_key(str_field(item, "key")?)?]);
+    Ok(mapping)
+}
+
+fn legacy_steering_wheel(item: &Value) -> Result<Value, String> {
+    let mut mapping = legacy_common(item, "DirectionPad")?;
+    let keys = item.get("key").ok_or_else(|| "Missing key".to_string())?;
+    let key = |name: &str| -> Result<Vec<String>, String> {
+        Ok(vec![legacy_key(str_field(keys, name)?)?])
+    };
+    let offset = f64_field(item, "offset")?;
+    mapping["initial_duration"] = json!(0);
+    mapping["max_offset_x"] = json!(offset);
+    mapping["max_offset_y"] = json!(offset);
+    mapping["bind"] = json!({
+        "type": "Button",
+        "up": key("up")?,
+        "down": key("down")?,
+        "left": key("left")?,
+        "right": key("right")?,
+    });
+    Ok(mapping)
+}
+
+fn legacy_sight(item: &Value) -> Result<Value, String> {
+    let mut mapping = legacy_common(item, "Fps")?;
+    mapping["sensitivity_x"] = json!(item.get("scaleX").and_then(Value::as_f64).unwrap_or(1.0));
+    mapping["sensitivity_y"] = json!(item.get("scaleY").and_then(Value::as_f64).unwrap_or(1.0));
+    mapping["bind"] = json!([legacy_key(str_field(item, "key")?)?]);
+    Ok(mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qt_scrcpy_keymaps_are_converted() {
+        let value = json!({
+            "switchKey": "Key_QuoteLeft",
+            "mouseMoveMap": { "startPos": { "x": 0.5, "y": 0.5 }, "speedRatio": 2.0 },
+            "keyMapNodes": [
+                {
+                    "type": "KMT_STEER_WHEEL", "centerPos": { "x": 0.2, "y": 0.7 },
+                    "leftOffset": 0.1, "rightOffset": 0.1, "upOffset": 0.2, "downOffset": 0.2,
+                    "leftKey": "Key_A", "rightKey": "Key_D", "upKey": "Key_W", "downKey": "Key_S",
+                },
+                { "type": "KMT_CLICK", "key": "LeftButton", "pos": { "x": 0.9, "y": 0.8 } },
+                { "type": "KMT_CLICK_TWICE", "key": "Key_R", "pos": { "x": 0.9, "y": 0.8 } },
+            ],
+        });
+        let imported = import_mapping_config(
+            &value,
+            None,
+            Some(Size {
+                width: 2000,
+                height: 1000,
+            }),
+        )
+        .unwrap();
+        assert_eq!(imported.format, ImportFormat::QtScrcpy);
+        let types = imported
+            .config
+            .mappings
+            .iter()
+            .map(|m| m.as_ref().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["Fps", "DirectionPad", "SingleTap"]);
+        let MappingType::DirectionPad(pad) = &imported.config.mappings[1] else {
+            unreachable!()
+        };
+        assert_eq!(pad.position, Position { x: 400, y: 700 });
+        assert_eq!(pad.max_offset_x, 200.0);
+        assert_eq!(imported.diagnostics.len(), 1);
+        assert_eq!(
+            imported.diagnostics[0].field.as_deref(),
+            Some("keyMapNodes[2]")
+        );
+    }
+
+    #[test]
+    fn legacy_keymaps_are_converted() {
+        let value = json!({
+            "title": "legacy",
+            "relativeSize": { "w": 1280, "h": 720 },
+            "list": [
+                { "type": "Tap", "note": "", "key": "M2", "time": 80, "pointerId": 2, "posX": 1100, "posY": 540 },
+                { "type": "Macro", "key": "KeyQ", "pointerId": 3, "posX": 10, "posY": 10 },
+                { "type": "Tap", "key": "NotAKey", "pointerId": 4, "posX": 10, "posY": 10 },
+            ],
+        });
+        let imported = import_mapping_config(&value, None, None).unwrap();
+        assert_eq!(imported.format, ImportFormat::ScrcpyMaskLegacy);
+        assert_eq!(imported.config.original_size.width, 1280);
+        assert_eq!(imported.config.mappings.len(), 1);
+        assert_eq!(imported.diagnostics.len(), 2);
+    }
+}
@@ -0,0 +1,398 @@
+use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::mask::mapping::{
+    config::{MappingConfig, MappingType},
+    utils::{Position, Size},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HorizontalAnchor {
+    /// Moves proportionally with the screen width
+    #[default]
+    Scale,
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerticalAnchor {
+    /// Moves proportionally with the screen height
+    #[default]
+    Scale,
+    Top,
+    Center,
+    Bottom,
+}
+
+/// The screen edges a control keeps its margin to when the layout is migrated to another aspect ratio
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MappingAnchor {
+    #[serde(default)]
+    pub horizontal: HorizontalAnchor,
+    #[serde(default)]
+    pub vertical: VerticalAnchor,
+}
+
+impl MappingAnchor {
+    const CENTER: MappingAnchor = MappingAnchor {
+        horizontal: HorizontalAnchor::Center,
+        vertical: VerticalAnchor::Center,
+    };
+}
+
+/// Screen insets the game keeps its HUD out of, e.g. for notches and rounded corners
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SafeArea {
+    #[serde(default)]
+    pub left: u32,
+    #[serde(default)]
+    pub top: u32,
+    #[serde(default)]
+    pub right: u32,
+    #[serde(default)]
+    pub bottom: u32,
+}
+
+impl SafeArea {
+    pub fn validate(&self, size: Size) -> Result<(), String> {
+        // saturated sums are never below the size, so huge insets are rejected as well
+        if self.left.saturating_add(self.right) >= size.width
+            || self.top.saturating_add(self.bottom) >= size.height
+        {
+            return Err(format!(
+                "Safe area insets {}, {}, {}, {} leave no room on a {}x{} screen",
+                self.left, self.top, self.right, self.bottom, size.width, size.height
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeMode {
+    /// Scales every position and length by the width and height ratios
+    #[default]
+    Scale,
+    /// Keeps anchored controls at their margins and their size uniform
+    Anchor,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Edge {
+    Scale,
+    Start,
+    Center,
+    End,
+}
+
+/// The safe area of a screen as start and end coordinates per axis
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    start: Vec2,
+    end: Vec2,
+}
+
+impl Frame {
+    fn new(size: Size, safe_area: SafeArea) -> Self {
+        Self {
+            start: Vec2::new(safe_area.left as f32, safe_area.top as f32),
+            end: Vec2::new(
+                (size.width - safe_area.right) as f32,
+                (size.height - safe_area.bottom) as f32,
+            ),
+        }
+    }
+
+    fn size(&self) -> Vec2 {
+        self.end - self.start
+    }
+}
+
+/// Maps positions and lengths of a layout from one screen to another
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutTransform {
+    mode: ResizeMode,
+    from: Frame,
+    to: Frame,
+    scale: Vec2,
+    control_scale: f32,
+}
+
+impl LayoutTransform {
+    pub fn new(
+        from: (Size, SafeArea),
+        to: (Size, SafeArea),
+        mode: ResizeMode,
+    ) -> Result<Self, String> {
+        from.1.validate(from.0)?;
+        to.1.validate(to.0)?;
+        let (from_frame, to_frame) = (Frame::new(from.0, from.1), Frame::new(to.0, to.1));
+        let control_scale = (to_frame.size() / from_frame.size()).min_element();
+        Ok(Self {
+            mode,
+            from: from_frame,
+            to: to_frame,
+            scale: Vec2::new(
+                to.0.width as f32 / from.0.width as f32,
+                to.0.height as f32 / from.0.height as f32,
+            ),
+            control_scale,
+        })
+    }
+
+    fn axis(&self, v: f32, edge: Edge, axis: usize) -> f32 {
+        let (from_start, from_end) = (self.from.start[axis], self.from.end[axis]);
+        let (to_start, to_end) = (self.to.start[axis], self.to.end[axis]);
+        let k = self.control_scale;
+        match edge {
+            Edge::Scale => {
+                to_start + (v - from_start) * (to_end - to_start) / (from_end - from_start)
+            }
+            Edge::Start => to_start + (v - from_start) * k,
+            Edge::Center => (to_start + to_end) / 2.0 + (v - (from_start + from_end) / 2.0) * k,
+            Edge::End => to_end - (from_end - v) * k,
+        }
+    }
+
+    pub fn point(&self, p: Position, anchor: MappingAnchor) -> Position {
+        if self.mode == ResizeMode::Scale {
+            let mut p = p;
+            p *= self.scale;
+            return p;
+        }
+        let horizontal = match anchor.horizontal {
+            HorizontalAnchor::Scale => Edge::Scale,
+            HorizontalAnchor::Left => Edge::Start,
+            HorizontalAnchor::Center => Edge::Center,
+            HorizontalAnchor::Right => Edge::End,
+        };
+        let vertical = match anchor.vertical {
+            VerticalAnchor::Scale => Edge::Scale,
+            VerticalAnchor::Top => Edge::Start,
+            VerticalAnchor::Center => Edge::Center,
+            VerticalAnchor::Bottom => Edge::End,
+        };
+        Position {
+            x: self.axis(p.x as f32, horizontal, 0).round() as i32,
+            y: self.axis(p.y as f32, vertical, 1).round() as i32,
+        }
+    }
+
+    pub fn x_len(&self, v: f32) -> f32 {
+        match self.mode {
+            ResizeMode::Scale => v * self.scale.x,
+            ResizeMode::Anchor => v * self.control_scale,
+        }
+    }
+
+    pub fn y_len(&self, v: f32) -> f32 {
+        match self.mode {
+            ResizeMode::Scale => v * self.scale.y,
+            ResizeMode::Anchor => v * self.control_scale,
+        }
+    }
+
+    /// Radii and distances, they follow the height when scaling like before anchors existed
+    pub fn len(&self, v: f32) -> f32 {
+        self.y_len(v)
+    }
+}
+
+/// Moves every mapping of `config` onto a screen of `size` with `safe_area`
+pub fn resize_mapping_config(
+    config: &mut MappingConfig,
+    size: Size,
+    safe_area: SafeArea,
+    mode: ResizeMode,
+) -> Result<(), String> {
+    let t = LayoutTransform::new(
+        (config.original_size, config.safe_area),
+        (size, safe_area),
+        mode,
+    )?;
+    config.original_size = size;
+    config.safe_area = safe_area;
+
+    config
+        .mappings
+        .iter_mut()
+        .for_each(|mapping| match mapping {
+            MappingType::SingleTap(m) => {
+                m.position = t.point(m.position, m.anchor);
+                m.random_offset_x = t.x_len(m.random_offset_x);
+                m.random_offset_y = t.y_len(m.random_offset_y);
+            }
+            MappingType::RepeatTap(m) => {
+                m.position = t.point(m.position, m.anchor);
+                m.random_offset_x = t.x_len(m.random_offset_x);
+                m.random_offset_y = t.y_len(m.random_offset_y);
+            }
+            MappingType::MultipleTap(m) => {
+                m.random_offset_x = t.x_len(m.random_offset_x);
+                m.random_offset_y = t.y_len(m.random_offset_y);
+                let anchor = m.anchor;
+                m.items.iter_mut().for_each(|item| {
+                    item.position = t.point(item.position, anchor);
+                });
+            }
+            MappingType::Swipe(m) => {
+                let anchor = m.anchor;
+                m.positions.iter_mut().for_each(|p| {
+                    *p = t.point(*p, anchor);
+                });
+            }
+            MappingType::Gesture(m) => {
+                m.position = t.point(m.position, m.anchor);
+                m.span = t.len(m.span);
+                m.min_span = t.len(m.min_span);
+            }
+            MappingType::DirectionPad(m) => {
+                m.position = t.point(m.position, m.anchor);
+                m.max_offset_x = t.x_len(m.max_offset_x);
+                m.max_offset_y = t.y_len(m.max_offset_y);
+                m.random_offset_x = t.x_len(m.random_offset_x);
+                m.random_offset_y = t.y_len(m.random_offset_y);
+                m.jitter_offset_x = t.x_len(m.jitter_offset_x);
+                m.jitter_offset_y = t.y_len(m.jitter_offset_y);
+            }
+            MappingType::MouseCastSpell(m) => {
+                m.position = t.point(m.position, m.anchor);
+                m.cast_radius = t.len(m.cast_radius);
+                // the cast center follows the character, which stays in the middle of the screen
+                m.center = t.point(m.center, MappingAnchor::CENTER);
+                m.drag_radius = t.len(m.drag_radius);
+            }
+            MappingType::PadCastSpell(m) => {
+                m.drag_radius = t.len(m.drag_radius);
+                m.position = t.point(m.position, m.anchor);
+            }
+            MappingType::CancelCast(m) => {
+                m.position = t.point(m.position, m.anchor);
+            }
+            MappingType::Observation(m) => {
+                m.position = t.point(m.position, m.anchor);
+                m.max_radius = t.len(m.max_radius);
+            }
+            MappingType::Fps(m) => {
+                m.position = t.point(m.position, m.anchor);
+                if m.max_offset_x > 0.0 {
+                    m.max_offset_x = t.x_len(m.max_offset_x);
+                }
+                if m.max_offset_y > 0.0 {
+                    m.max_offset_y = t.y_len(m.max_offset_y);
+                }
+            }
+            MappingType::Fire(m) => {
+                m.position = t.point(m.position, m.anchor);
+            }
+            MappingType::RawInput(m) => {
+                m.position = t.point(m.position, m.anchor);
+            }
+            MappingType::Script(m) => {
+                m.position = t.point(m.position, m.anchor);
+            }
+            MappingType::ScrollDrag(m) => {
+                m.position = t.point(m.position, m.anchor);
+                m.step = t.len(m.step);
+                m.max_distance = t.len(m.max_distance);
+            }
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(width: u32, height: u32) -> Size {
+        Size { width, height }
+    }
+
+    #[test]
+    fn anchored_controls_keep_their_margins() {
+        // 16:9 to 20:9 with the same height
+        let t = LayoutTransform::new(
+            (size(2560, 1440), SafeArea::default()),
+            (size(3200, 1440), SafeArea::default()),
+            ResizeMode::Anchor,
+        )
+        .unwrap();
+        let p = Position { x: 2360, y: 1240 };
+        let right_bottom = MappingAnchor {
+            horizontal: HorizontalAnchor::Right,
+            vertical: VerticalAnchor::Bottom,
+        };
+        assert_eq!(t.point(p, right_bottom), Position { x: 3000, y: 1240 });
+        assert_eq!(
+            t.point(p, MappingAnchor::default()),
+            Position { x: 2950, y: 1240 }
+        );
+        assert_eq!(
+            t.point(Position { x: 1280, y: 720 }, MappingAnchor::CENTER),
+            Position { x: 1600, y: 720 }
+        );
+        assert_eq!(t.len(100.0), 100.0);
+    }
+
+    #[test]
+    fn safe_area_must_leave_room() {
+        let screen = size(2560, 1440);
+        assert!(SafeArea::default().validate(screen).is_ok());
+        let area = SafeArea {
+            left: 1280,
+            right: 1280,
+            ..Default::default()
+        };
+        assert!(area.validate(screen).is_err());
+        let area = SafeArea {
+            top: u32::MAX,
+            bottom: 1,
+            ..Default::default()
+        };
+        assert!(area.validate(screen).is_err());
+    }
+
+    #[test]
+    fn safe_area_moves_the_edges() {
+        let t = LayoutTransform::new(
+            (size(2560, 1440), SafeArea::default()),
+            (
+                size(3200, 1440),
+                SafeArea {
+                    left: 100,
+                    ..Default::default()
+                },
+            ),
+            ResizeMode::Anchor,
+        )
+        .unwrap();
+        let left = MappingAnchor {
+            horizontal: HorizontalAnchor::Left,
+            vertical: VerticalAnchor::Top,
+        };
+        assert_eq!(
+            t.point(Position { x: 200, y: 200 }, left),
+            Position { x: 300, y: 200 }
+        );
+        assert!(
+            LayoutTransform::new(
+                (size(100, 100), SafeArea::default()),
+                (
+                    size(100, 100),
+                    SafeArea {
+                        left: 60,
+                        right: 60,
+                        ..Default::default()
+                    }
+                ),
+                ResizeMode::Anchor,
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+
+use crate::mask::mapping::{
+    binding::{ButtonBinding, DirectionBinding},
+    config::{MappingConfig, MappingDiagnostic, MappingType},
+    cursor::CursorState,
+    script_helper::{ScriptAST, ScriptDiagnostic},
+    utils::{Position, Size},
+};
+
+const ALL_MODES: &[CursorState] = &[CursorState::Normal, CursorState::Fps];
+
+struct LintTarget<'a> {
+    mapping: &'a MappingType,
+    mapping_type: &'a str,
+    mapping_index: usize,
+    id: &'a str,
+}
+
+impl LintTarget<'_> {
+    fn warning(&self, code: &str, message: String) -> MappingDiagnostic {
+        MappingDiagnostic::mapping(
+            code,
+            message,
+            self.mapping_type,
+            self.mapping_index,
+            self.id,
+        )
+        .warning()
+    }
+
+    fn label(&self) -> String {
+        format!("{}-{} ({})", self.mapping_type, self.mapping_index, self.id)
+    }
+}
+
+/// Checks a valid mapping config for setups that load fine but won't behave as intended.
+/// Everything reported here is a warning
+pub fn lint_mapping_config(config: &MappingConfig) -> Vec<MappingDiagnostic> {
+    let mut counts = HashMap::<&str, usize>::new();
+    let targets = config
+        .mappings
+        .iter()
+        .map(|mapping| {
+            let mapping_type = mapping.as_ref();
+            let count = counts.entry(mapping_type).or_default();
+            *count += 1;
+            LintTarget {
+                mapping,
+                mapping_type,
+                mapping_index: *count,
+                id: mapping.id(),
+            }
+        })
+        .collect::<Vec<_>>();
+    let has_fps = config
+        .mappings
+        .iter()
+        .any(|mapping| matches!(mapping, MappingType::Fps(_)));
+
+    let mut warnings = Vec::new();
+    for (i, target) in targets.iter().enumerate() {
+        for other in &targets[..i] {
+            lint_pair(&mut warnings, target, other);
+        }
+        lint_positions(&mut warnings, target, config.original_size);
+        lint_script_refs(&mut warnings, target, &config.mappings);
+        if !has_fps && matches!(target.mapping, MappingType::Fire(_)) {
+            warnings.push(target.warning(
+                "mapping.lint.fireWithoutFps",
+                "Fire only works in FPS mode, but there is no Fps mapping to enter it".to_string(),
+            ));
+        }
+    }
+    warnings
+}
+
+/// Cursor modes in which the mapping reacts to its binding
+fn active_modes(mapping: &MappingType) -> &'static [CursorState] {
+    match mapping {
+        MappingType::Fire(_) => &[CursorState::Fps],
+        MappingType::RawInput(_) => &[CursorState::Normal],
+        _ => ALL_MODES,
+    }
+}
+
+fn button_key(binding: &ButtonBinding) -> Option<String> {
+    let mut buttons = binding
+        .buttons()
+        .iter()
+        .map(|button| button.to_string())
+        .collect::<Vec<_>>();
+    buttons.sort();
+    (!buttons.is_empty()).then(|| buttons.join("+"))
+}
+
+fn direction_keys(binding: &DirectionBinding) -> Vec<String> {
+    match binding {
+        DirectionBinding::Button {
+            up,
+            down,
+            left,
+            right,
+        } => [up, down, left, right]
+            .into_iter()
+            .filter_map(button_key)
+            .collect(),
+        DirectionBinding::JoyStick { x, y } => vec![format!("{:?}+{:?}", x, y)],
+    }
+}
+
+/// Normalized bindings, the aim pad of PadCastSpell only counts while casting and is left out
+fn binding_keys(mapping: &MappingType) -> Vec<String> {
+    let mut keys = match mapping {
+        MappingType::SingleTap(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::RepeatTap(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::MultipleTap(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::Swipe(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::Gesture(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::DirectionPad(m) => direction_keys(&m.bind),
+        MappingType::MouseCastSpell(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::PadCastSpell(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::CancelCast(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::Observation(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::Fps(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::Fire(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::RawInput(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::Script(m) => button_key(&m.bind).into_iter().collect(),
+        MappingType::ScrollDrag(m) => button_key(&m.bind).into_iter().collect(),
+    };
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn pointer_ids(mapping: &MappingType) -> Vec<u64> {
+    match mapping {
+        MappingType::SingleTap(m) => vec![m.pointer_id],
+        MappingType::RepeatTap(m) => vec![m.pointer_id],
+        MappingType::MultipleTap(m) => vec![m.pointer_id],
+        MappingType::Swipe(m) => vec![m.pointer_id],
+        MappingType::Gesture(m) => vec![m.pointer_id, m.second_pointer_id],
+        MappingType::DirectionPad(m) => vec![m.pointer_id],
+        MappingType::MouseCastSpell(m) => vec![m.pointer_id],
+        MappingType::PadCastSpell(m) => vec![m.pointer_id],
+        MappingType::Observation(m) => vec![m.pointer_id],
+        MappingType::Fps(m) => std::iter::once(m.pointer_id)
+            .chain(m.touch_mode.another_pointer_id())
+            .collect(),
+        MappingType::Fire(m) => vec![m.pointer_id],
+        MappingType::ScrollDrag(m) => vec![m.pointer_id],
+        MappingType::CancelCast(_) | MappingType::RawInput(_) | MappingType::Script(_) => {
+            Vec::new()
+        }
+    }
+}
+
+/// Whether the two mappings can never hold their pointers at the same time
+fn pointers_exclusive(a: &MappingType, b: &MappingType) -> bool {
+    match (a, b) {
+        // only one spell is cast at a time
+        (
+            MappingType::MouseCastSpell(_) | MappingType::PadCastSpell(_),
+            MappingType::MouseCastSpell(_) | MappingType::PadCastSpell(_),
+        ) => true,
+        // sharing the Fps pointer is how Fire takes over the aim, validation covers the rest
+        (MappingType::Fire(_), MappingType::Fps(_))
+        | (MappingType::Fps(_), MappingType::Fire(_)) => true,
+        _ => false,
+    }
+}
+
+fn lint_pair(warnings: &mut Vec<MappingDiagnostic>, target: &LintTarget, other: &LintTarget) {
+    let other_modes = active_modes(other.mapping);
+    let modes = active_modes(target.mapping)
+        .iter()
+        .filter(|mode| other_modes.contains(mode))
+        .map(|mode| format!("{:?}", mode))
+        .collect::<Vec<_>>();
+    if modes.is_empty() {
+        return;
+    }
+    let modes = modes.join("/");
+
+    let other_keys = binding_keys(other.mapping);
+    for key in binding_keys(target.mapping) {
+        if other_keys.contains(&key) {
+            warnings.push(target.warning(
+                "mapping.lint.duplicateBinding",
+                format!(
+                    "Binding {} is also used by {} in {} mode",
+                    key,
+                    other.label(),
+                    modes
+                ),
+            ));
+        }
+    }
+
+    if pointers_exclusive(target.mapping, other.mapping) {
+        return;
+    }
+    let other_pointers = pointer_ids(other.mapping);
+    let mut shared = pointer_ids(target.mapping)
+        .into_iter()
+        .filter(|id| other_pointers.contains(id))
+        .collect::<Vec<_>>();
+    shared.dedup();
+    for pointer_id in shared {
+        warnings.push(target.warning(
+            "mapping.lint.sharedPointer",
+            format!(
+                "Pointer id {} is also used by {}, both can be held at the same time in {} mode",
+                pointer_id,
+                other.label(),
+                modes
+            ),
+        ));
+    }
+}
+
+fn positions(mapping: &MappingType) -> Vec<(String, Position)> {
+    let position = |p: Position| vec![("position".to_string(), p)];
+    match mapping {
+        MappingType::SingleTap(m) => position(m.position),
+        MappingType::RepeatTap(m) => position(m.position),
+        MappingType::MultipleTap(m) => m
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (format!("items[{}].position", i), item.position))
+            .collect(),
+        MappingType::Swipe(m) => m
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (format!("positions[{}]", i), *p))
+            .collect(),
+        MappingType::Gesture(m) => position(m.position),
+        MappingType::DirectionPad(m) => position(m.position),
+        MappingType::MouseCastSpell(m) => vec![
+            ("position".to_string(), m.position),
+            ("center".to_string(), m.center),
+        ],
+        MappingType::PadCastSpell(m) => position(m.position),
+        MappingType::CancelCast(m) => position(m.position),
+        MappingType::Observation(m) => position(m.position),
+        MappingType::Fps(m) => position(m.position),
+        MappingType::Fire(m) => position(m.position),
+        MappingType::RawInput(m) => position(m.position),
+        MappingType::Script(m) => position(m.position),
+        MappingType::ScrollDrag(m) => position(m.position),
+    }
+}
+
+fn lint_positions(warnings: &mut Vec<MappingDiagnostic>, target: &LintTarget, size: Size) {
+    for (field, p) in positions(target.mapping) {
+        if p.x < 0 || p.y < 0 || p.x as u32 >= size.width || p.y as u32 >= size.height {
+            let mut warning = target.warning(
+                "mapping.lint.positionOutOfBounds",
+                format!(
+                    "Position ({}, {}) is outside the {}x{} screen",
+                    p.x, p.y, size.width, size.height
+                ),
+            );
+            warning.field = Some(field);
+            warnings.push(warning);
+        }
+    }
+}
+
+fn script_fields(mapping: &MappingType) -> Vec<(&'static str, &str)> {
+    let hooks = match mapping {
+        MappingType::SingleTap(m) => &m.script_hooks,
+        MappingType::RepeatTap(m) => &m.script_hooks,
+        MappingType::MultipleTap(m) => &m.script_hooks,
+        MappingType::Swipe(m) => &m.script_hooks,
+        MappingType::Gesture(m) => &m.script_hooks,
+        MappingType::DirectionPad(m) => &m.script_hooks,
+        MappingType::MouseCastSpell(m) => &m.script_hooks,
+        MappingType::PadCastSpell(m) => &m.script_hooks,
+        MappingType::CancelCast(m) => &m.script_hooks,
+        MappingType::Observation(m) => &m.script_hooks,
+        MappingType::Fire(m) => &m.script_hooks,
+        MappingType::ScrollDrag(m) => &m.script_hooks,
+        MappingType::Script(m) => {
+            return vec![
+                ("pressed_script", m.pressed_script.as_str()),
+                ("held_script", m.held_script.as_str()),
+                ("released_script", m.released_script.as_str()),
+            ];
+        }
+        MappingType::Fps(_) | MappingType::RawInput(_) => return Vec::new(),
+    };
+    vec![
+        ("script_hooks.before_script", hooks.before_script.as_str()),
+        ("script_hooks.after_script", hooks.after_script.as_str()),
+    ]
+}
+
+fn lint_script_refs(
+    warnings: &mut Vec<MappingDiagnostic>,
+    target: &LintTarget,
+    mappings: &[MappingType],
+) {
+    for (field, script) in script_fields(target.mapping) {
+        for reference in ScriptAST::mapping_references(script) {
+            let (expected, found) = match reference.function.as_str() {
+                "enter_fps" => (
+                    "Fps",
+                    mappings
+                        .iter()
+                        .any(|m| matches!(m, MappingType::Fps(_)) && m.id() == reference.id),
+                ),
+                _ => (
+                    "CancelCast",
+                    mappings
+                        .iter()
+                        .any(|m| matches!(m, MappingType::CancelCast(_)) && m.id() == reference.id),
+                ),
+            };
+            if found {
+                continue;
+            }
+            let message = format!(
+                "{}(\"{}\") refers to no {} mapping in this config",
+                reference.function, reference.id, expected
+            );
+            let mut warning = target.warning("mapping.lint.unknownMappingId", message.clone());
+            warning.field = Some(field.to_string());
+            warning.script_diagnostic = Some(ScriptDiagnostic::warning(
+                "mapping.lint.unknownMappingId",
+                message,
+                reference.span,
+            ));
+            warnings.push(warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{keyboard::KeyCode, mouse::MouseButton};
+
+    use super::*;
+    use crate::mask::mapping::{
+        binding::MergedButton,
+        config::parse_mapping_config,
+        fire::MappingFire,
+        layout::{MappingAnchor, SafeArea},
+        migration::MAPPING_SCHEMA_VERSION,
+        raw_input::MappingRawInput,
+        script::{MappingScript, MappingScriptHooks},
+        tap::{MappingSingleTap, TapTriggerMode},
+        utils::default_random_offset,
+    };
+
+    fn config(mappings: Vec<MappingType>) -> MappingConfig {
+        MappingConfig {
+            version: MAPPING_SCHEMA_VERSION,
+            original_size: Size {
+                width: 1920,
+                height: 1080,
+            },
+            safe_area: SafeArea::default(),
+            mappings,
+        }
+    }
+
+    fn bind(button: MergedButton) -> ButtonBinding {
+        ButtonBinding::new(vec![button])
+    }
+
+    fn tap(id: &str, key: KeyCode, pointer_id: u64, x: i32) -> MappingType {
+        MappingType::SingleTap(MappingSingleTap {
+            id: id.to_string(),
+            anchor: MappingAnchor::default(),
+            position: Position { x, y: 500 },
+            note: String::new(),
+            pointer_id,
+            duration: 30,
+            sync: false,
+            trigger_mode: TapTriggerMode::Hold,
+            bind: bind(MergedButton::Keyboard(key)),
+            random_offset_x: default_random_offset(),
+            random_offset_y: default_random_offset(),
+            script_hooks: MappingScriptHooks::default(),
+        })
+    }
+
+    fn fire(id: &str, pointer_id: u64) -> MappingType {
+        MappingType::Fire(MappingFire {
+            id: id.to_string(),
+            anchor: MappingAnchor::default(),
+            note: String::new(),
+            pointer_id,
+            position: Position { x: 100, y: 100 },
+            preserve_fps_control: true,
+            sensitivity_x: 1.0,
+            sensitivity_y: 1.0,
+            bind: bind(MergedButton::Mouse(MouseButton::Left)),
+            random_offset_x: default_random_offset(),
+            random_offset_y: default_random_offset(),
+            script_hooks: MappingScriptHooks::default(),
+        })
+    }
+
+    fn script(id: &str, key: KeyCode, pressed_script: &str) -> MappingType {
+        MappingType::Script(MappingScript {
+            id: id.to_string(),
+            anchor: MappingAnchor::default(),
+            position: Position { x: 100, y: 100 },
+            note: String::new(),
+            pressed_script: pressed_script.to_string(),
+            released_script: String::new(),
+            held_script: String::new(),
+            interval: 0,
+            bind: bind(MergedButton::Keyboard(key)),
+        })
+    }
+
+    fn raw_input(id: &str) -> MappingType {
+        MappingType::RawInput(MappingRawInput {
+            id: id.to_string(),
+            anchor: MappingAnchor::default(),
+            note: String::new(),
+            position: Position { x: 100, y: 100 },
+            bind: bind(MergedButton::Mouse(MouseButton::Left)),
+        })
+    }
+
+    fn codes(config: &MappingConfig) -> Vec<String> {
+        lint_mapping_config(config)
+            .into_iter()
+            .map(|d| d.code)
+            .collect()
+    }
+
+    #[test]
+    fn conflicts_and_bounds_are_reported() {
+        let config = config(vec![
+            tap("a", KeyCode::KeyA, 1, 100),
+            tap("b", KeyCode::KeyA, 2, 200),
+            tap("c", KeyCode::KeyC, 1, 2000),
+        ]);
+        let warnings = lint_mapping_config(&config);
+        assert_eq!(
+            warnings.iter().map(|d| d.code.as_str()).collect::<Vec<_>>(),
+            [
+                "mapping.lint.duplicateBinding",
+                "mapping.lint.sharedPointer",
+                "mapping.lint.positionOutOfBounds",
+            ]
+        );
+        assert!(warnings.iter().all(|d| d.severity == "warning"));
+        assert_eq!(warnings[1].mapping_id.as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn fire_and_script_references_need_their_targets() {
+        let config = config(vec![
+            fire("fire", 3),
+            script("script", KeyCode::KeyF, "enter_fps(\"missing\")"),
+            // fire is only active in fps mode, raw input never is
+            raw_input("raw"),
+        ]);
+        assert_eq!(
+            codes(&config),
+            [
+                "mapping.lint.fireWithoutFps",
+                "mapping.lint.unknownMappingId"
+            ]
+        );
+    }
+
+    #[test]
+    fn mapping_files_are_linted() {
+        let (config, _) = parse_mapping_config(include_str!("fixtures/lint_fps.json")).unwrap();
+        assert!(codes(&config).is_empty());
+
+        let (config, _) =
+            parse_mapping_config(include_str!("fixtures/lint_conflicts.json")).unwrap();
+        let warnings = lint_mapping_config(&config);
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|d| (d.code.as_str(), d.mapping_id.as_deref().unwrap_or_default()))
+                .collect::<Vec<_>>(),
+            [
+                ("mapping.lint.duplicateBinding", "skill"),
+                ("mapping.lint.sharedPointer", "skill"),
+                ("mapping.lint.unknownMappingId", "skill"),
+                ("mapping.lint.positionOutOfBounds", "recall"),
+                ("mapping.lint.fireWithoutFps", "fire"),
+            ]
+        );
+        assert_eq!(
+            warnings[2].field.as_deref(),
+            Some("script_hooks.before_script")
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::{
+    mask::mapping::config::{MappingDiagnostic, default_mapping_id},
+    utils::relate_to_data_path,
+};
+
+/// Schema version written by this build
+pub const MAPPING_SCHEMA_VERSION: u32 = 2;
+/// Files from releases where `version` was a free string like "0.0.1"
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// `MIGRATIONS[i]` upgrades a file from version `i + 1` to `i + 2`
+const MIGRATIONS: [Migration; (MAPPING_SCHEMA_VERSION - 1) as usize] = [migrate_v1_to_v2];
+
+/// Accepts the numeric schema version and the legacy version strings
+pub fn deserialize_schema_version<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawVersion {
+        Number(u32),
+        Legacy(String),
+    }
+    Ok(match RawVersion::deserialize(deserializer)? {
+        RawVersion::Number(version) => version,
+        RawVersion::Legacy(_) => LEGACY_SCHEMA_VERSION,
+    })
+}
+
+fn schema_version(value: &Value) -> Result<u32, MappingDiagnostic> {
+    match value.get("version") {
+        None | Some(Value::String(_)) => Ok(LEGACY_SCHEMA_VERSION),
+        Some(Value::Number(n)) => n
+            .as_u64()
+            .and_then(|n| u32::try_from(n).ok())
+            .filter(|n| *n >= LEGACY_SCHEMA_VERSION)
+            .ok_or_else(|| {
+                MappingDiagnostic::config(
+                    "mapping.schema.invalidVersion",
+                    format!("Invalid mapping schema version: {}", n),
+                )
+            }),
+        Some(other) => Err(MappingDiagnostic::config(
+            "mapping.schema.invalidVersion",
+            format!("Invalid mapping schema version: {}", other),
+        )),
+    }
+}
+
+/// Upgrades a raw mapping file to the current schema, returns the version it was upgraded from
+pub fn migrate_mapping_value(value: &mut Value) -> Result<Option<u32>, MappingDiagnostic> {
+    if !value.is_object() {
+        return Err(MappingDiagnostic::config(
+            "mapping.schema.notAnObject",
+            "Mapping file must contain a json object",
+        ));
+    }
+    let from = schema_version(value)?;
+    if from > MAPPING_SCHEMA_VERSION {
+        return Err(MappingDiagnostic::config(
+            "mapping.schema.unsupportedVersion",
+            format!(
+                "Mapping schema version {} is newer than the supported version {}, please update the app",
+                from, MAPPING_SCHEMA_VERSION
+            ),
+        ));
+    }
+    if from == MAPPING_SCHEMA_VERSION {
+        return Ok(None);
+    }
+    for version in from..MAPPING_SCHEMA_VERSION {
+        MIGRATIONS[(version - 1) as usize](value).map_err(|e| {
+            MappingDiagnostic::config(
+                "mapping.schema.migrationFailed",
+                format!(
+                    "Failed to migrate mapping from version {} to {}: {}",
+                    version,
+                    version + 1,
+                    e
+                ),
+            )
+        })?;
+        value["version"] = Value::from(version + 1);
+    }
+    Ok(Some(from))
+}
+
+/// Keeps the original of a migrated file in `mapping_backup`, named after the version it had.
+/// Earlier backups of the same file are never replaced
+pub fn backup_mapping_file(path: &Path, from: u32) -> Result<(), String> {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| format!("Invalid mapping file path: {}", path.display()))?;
+    let dir = relate_to_data_path(["mapping_backup"]);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup dir: {}", e))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+    let backup_path = (0..)
+        .map(|n| {
+            let suffix = if n == 0 {
+                String::new()
+            } else {
+                format!("-{}", n)
+            };
+            dir.join(format!("{}.v{}.{}{}.json", stem, from, timestamp, suffix))
+        })
+        .find(|path| !path.exists())
+        .unwrap();
+    fs::copy(path, backup_path)
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Mappings without an id got a random one on every load, which broke scripts and
+/// hooks referring to them. Version 2 stores the ids and a numeric version
+fn migrate_v1_to_v2(value: &mut Value) -> Result<(), String> {
+    let Some(mappings) = value.get_mut("mappings") else {
+        return Ok(());
+    };
+    let mappings = mappings
+        .as_array_mut()
+        .ok_or_else(|| "mappings must be an array".to_string())?;
+    for mapping in mappings {
+        let mapping = mapping
+            .as_object_mut()
+            .ok_or_else(|| "every mapping must be an object".to_string())?;
+        if !mapping.get("id").is_some_and(Value::is_string) {
+            mapping.insert("id".to_string(), Value::from(default_mapping_id()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn legacy_files_are_migrated() {
+        let mut value = json!({
+            "version": "0.0.1",
+            "original_size": { "width": 2560, "height": 1440 },
+            "mappings": [{ "type": "SingleTap" }, { "type": "SingleTap", "id": "kept" }],
+        });
+        assert_eq!(migrate_mapping_value(&mut value).unwrap(), Some(1));
+        assert_eq!(value["version"], json!(MAPPING_SCHEMA_VERSION));
+        assert!(value["mappings"][0]["id"].is_string());
+        assert_eq!(value["mappings"][1]["id"], json!("kept"));
+
+        // current files are left alone
+        assert_eq!(migrate_mapping_value(&mut value).unwrap(), None);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let mut value = json!({ "version": MAPPING_SCHEMA_VERSION + 1, "mappings": [] });
+        let diagnostic = migrate_mapping_value(&mut value).unwrap_err();
+        assert_eq!(diagnostic.code, "mapping.schema.unsupportedVersion");
+    }
+}
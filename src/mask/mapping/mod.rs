@@ -5,10 +5,17 @@ pub mod cursor;
 pub mod direction_pad;
 pub mod executor;
 pub mod fire;
+pub mod gesture;
+pub mod history;
+pub mod import;
+pub mod layout;
+pub mod lint;
+pub mod migration;
 pub mod observation;
 pub mod raw_input;
 pub mod script;
 pub mod script_helper;
+pub mod scroll_drag;
 pub mod serde_float;
 pub mod swipe;
 pub mod tap;
@@ -25,13 +32,14 @@ use crate::{
         mapping::{
             config::{
                 ActiveMappingConfig, BindMappingConfig, MappingAction, default_mapping_config,
-                load_mapping_config, save_mapping_config,
+                format_mapping_diagnostics, load_mapping_config, migrate_mapping_files,
+                read_mapping_file, save_mapping_config,
             },
             cursor::cleanup_cursor_capture_on_stop,
             cursor::{CursorFrameSet, CursorPlugins, CursorState},
         },
     },
-    utils::relate_to_data_path,
+    utils::{relate_to_data_path, share::MappingFallback},
 };
 
 #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
@@ -75,6 +83,7 @@ impl Plugin for MappingPlugins {
                     observation::init_observation,
                     raw_input::raw_input_init,
                     script::script_init,
+                    scroll_drag::init_scroll_drag,
                 ),
             )
             // normal mapping mode
@@ -91,7 +100,7 @@ impl Plugin for MappingPlugins {
                     tap::handle_repeat_tap,
                     tap::handle_repeat_tap_trigger,
                     tap::handle_multiple_tap,
-                    swipe::handle_swipe,
+                    (swipe::handle_swipe, gesture::handle_gesture),
                     direction_pad::handle_direction_pad,
                     cast_spell::handle_mouse_cast_spell,
                     cast_spell::handle_mouse_cast_spell_trigger,
@@ -109,7 +118,10 @@ impl Plugin for MappingPlugins {
                     (fire::handle_fire, fire::handle_fire_trigger)
                         .run_if(in_state(CursorState::Fps)),
                     script::handle_script,
-                    script::handle_script_trigger,
+                    (
+                        script::handle_script_trigger,
+                        scroll_drag::handle_scroll_drag,
+                    ),
                 )
                     .in_set(CursorFrameSet::HandleMappings)
                     .run_if(in_state(MappingState::Normal)),
@@ -147,6 +159,7 @@ impl Plugin for MappingPlugins {
                     fire::cleanup_fire_on_stop,
                     fire::cleanup_fps_on_stop,
                     script::cleanup_script_on_stop,
+                    scroll_drag::cleanup_scroll_drag_on_stop,
                     cleanup_cursor_capture_on_stop,
                 )
                     .chain(),
@@ -164,6 +177,7 @@ impl Plugin for MappingPlugins {
                     fire::cleanup_fire_on_stop,
                     fire::cleanup_fps_on_stop,
                     script::cleanup_script_on_stop,
+                    scroll_drag::cleanup_scroll_drag_on_stop,
                     cleanup_cursor_capture_on_stop,
                 )
                     .chain(),
@@ -176,38 +190,63 @@ pub fn mask_not_resizing(resize_state: Res<MaskResizeState>) -> bool {
 }
 
 fn init(mut ineffable: IneffableCommands, mut active_mapping: ResMut<ActiveMappingConfig>) {
-    let config = LocalConfig::get();
-
-    let (bind_mapping_config, input_config, file) =
-        match load_mapping_config(&config.active_mapping_file) {
-            Ok((mapping_config, input_config)) => {
-                log::info!(
-                    "[Mask] {}: {}",
-                    t!("mask.mapping.usingMappingConfig"),
-                    config.active_mapping_file,
-                );
-                (mapping_config, input_config, config.active_mapping_file)
-            }
-            Err(e) => {
-                log::error!("{}", e);
-                log::info!(
-                    "[Mask] {}: default.json",
-                    t!("mask.mapping.useDefaultMapping")
-                );
-                let default_mapping = default_mapping_config();
-                let config_path = relate_to_data_path(["mapping", "default.json"]);
-                save_mapping_config(&default_mapping, &config_path).unwrap();
-                LocalConfig::set_active_mapping_file("default.json".to_string());
-                let default_bind_mapping: BindMappingConfig = default_mapping.into();
-                let input_config: InputConfig = InputConfig::from(&default_bind_mapping);
-                (
-                    default_bind_mapping,
-                    input_config,
-                    "default.json".to_string(),
-                )
-            }
-        };
+    let (bind_mapping_config, input_config, file) = load_active_mapping();
     active_mapping.0 = Some(bind_mapping_config);
     active_mapping.1 = file;
     ineffable.set_config(&input_config);
 }
+
+/// Load the mapping file of the local config, falling back to default.json.
+/// The config keeps naming the file so it is tried again on the next start, why it was passed
+/// over is kept in [`MappingFallback`]. Mapping files of older schema versions are upgraded on disk first
+pub fn load_active_mapping() -> (BindMappingConfig, InputConfig, String) {
+    migrate_mapping_files();
+    let file = LocalConfig::get().active_mapping_file;
+
+    let diagnostics = match read_mapping_file(&file) {
+        Ok(mapping_config) => {
+            log::info!("[Mask] {}: {}", t!("mask.mapping.usingMappingConfig"), file);
+            MappingFallback::set_blocking(None);
+            let bind_mapping_config: BindMappingConfig = mapping_config.into();
+            let input_config = InputConfig::from(&bind_mapping_config);
+            return (bind_mapping_config, input_config, file);
+        }
+        Err(diagnostics) => diagnostics,
+    };
+    log::error!(
+        "[Mask] {}: {}. {}",
+        t!("web.mapping.failedToLoadMappingConfig"),
+        file,
+        format_mapping_diagnostics(&diagnostics)
+    );
+    log::info!(
+        "[Mask] {}: default.json",
+        t!("mask.mapping.useDefaultMapping")
+    );
+    MappingFallback::set_blocking(Some(MappingFallback {
+        file: file.clone(),
+        active: "default.json".to_string(),
+        diagnostics,
+    }));
+    if file != "default.json" {
+        if let Ok((mapping_config, input_config)) = load_mapping_config("default.json") {
+            return (mapping_config, input_config, "default.json".to_string());
+        }
+    }
+
+    let default_mapping = default_mapping_config();
+    let config_path = relate_to_data_path(["mapping", "default.json"]);
+    // never overwrite a default.json that only failed to load
+    if !config_path.exists() {
+        if let Err(e) = save_mapping_config(&default_mapping, &config_path) {
+            log::error!("[Mask] {}", e);
+        }
+    }
+    let default_bind_mapping: BindMappingConfig = default_mapping.into();
+    let input_config: InputConfig = InputConfig::from(&default_bind_mapping);
+    (
+        default_bind_mapping,
+        input_config,
+        "default.json".to_string(),
+    )
+}
@@ -23,6 +23,7 @@ use crate::{
                 MappingLifecycleStart, MappingLifecycleState, make_mapping_execution_context,
                 run_script_hook,
             },
+            layout::MappingAnchor,
             script::{BindMappingScriptHooks, MappingScriptHooks},
             script_helper::{ScriptRuntimeCommandSender, ScriptSharedState},
             utils::{ControlMsgHelper, Position, default_random_offset, random_offset_vec2},
@@ -115,6 +116,8 @@ impl From<MappingObservation> for BindMappingObservation {
 pub struct MappingObservation {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub position: Position,
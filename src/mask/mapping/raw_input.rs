@@ -24,6 +24,7 @@ use crate::{
         MappingState,
         binding::{ButtonBinding, ValidateMappingConfig},
         config::ActiveMappingConfig,
+        layout::MappingAnchor,
         utils::{ControlMsgHelper, Position},
     },
     scrcpy::constant,
@@ -60,6 +61,8 @@ impl From<MappingRawInput> for BindMappingRawInput {
 pub struct MappingRawInput {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub position: Position,
     pub bind: ButtonBinding,
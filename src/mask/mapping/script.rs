@@ -21,6 +21,7 @@ use crate::{
             binding::{ButtonBinding, ValidateMappingConfig},
             config::ActiveMappingConfig,
             cursor::{CursorPosition, CursorState},
+            layout::MappingAnchor,
             script_helper::{
                 ScriptAST, ScriptRuntimeCommand, ScriptRuntimeCommandReceiver,
                 ScriptRuntimeCommandSender, ScriptSharedState,
@@ -131,6 +132,8 @@ impl From<MappingScript> for BindMappingScript {
 pub struct MappingScript {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub position: Position,
     pub note: String,
     pub pressed_script: String,
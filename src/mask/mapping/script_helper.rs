@@ -1642,6 +1642,18 @@ impl ScriptAST {
         parse_script(script).diagnostics
     }
 
+    /// Mapping ids passed as string literals to `enter_fps` and `cancel_cast`
+    pub fn mapping_references(script: &str) -> Vec<ScriptMappingRef> {
+        if script.is_empty() {
+            return Vec::new();
+        }
+        let tokens = Lexer::new(script).lex();
+        let program = ParserState::new(script, tokens).parse_program();
+        let mut analyzer = ScriptAnalyzer::new(script);
+        analyzer.analyze_program(&program);
+        analyzer.mapping_refs
+    }
+
     fn validate_program(&self) -> Vec<ScriptError> {
         let mut analyzer = ScriptAnalyzer::new(&self.script);
         analyzer.analyze_program(&self.program);
@@ -2153,6 +2165,7 @@ impl ScriptAST {
 struct ScriptAnalyzer<'a> {
     source: &'a str,
     errors: Vec<ScriptError>,
+    mapping_refs: Vec<ScriptMappingRef>,
 }
 
 impl<'a> ScriptAnalyzer<'a> {
@@ -2160,6 +2173,7 @@ impl<'a> ScriptAnalyzer<'a> {
         Self {
             source,
             errors: Vec::new(),
+            mapping_refs: Vec::new(),
         }
     }
 
@@ -2495,6 +2509,13 @@ impl<'a> ScriptAnalyzer<'a> {
             "enter_fps" | "cancel_cast" => {
                 self.expect_arity(name, args.len(), 1, Some(1), span);
                 self.expect_non_empty_string(args, 0, name, span);
+                if let Some(Value::Str(id)) = args.get(0).and_then(|arg| arg.const_value.as_ref()) {
+                    self.mapping_refs.push(ScriptMappingRef {
+                        function: name.to_string(),
+                        id: id.clone(),
+                        span,
+                    });
+                }
                 ExprInfo::new(StaticType::Int)
             }
             _ => {
@@ -3327,6 +3348,15 @@ pub struct ScriptRelatedDiagnostic {
 #[serde(rename_all = "lowercase")]
 pub enum ScriptDiagnosticSeverity {
     Error,
+    Warning,
+}
+
+/// A call in a script that refers to another mapping by id
+#[derive(Debug, Clone)]
+pub struct ScriptMappingRef {
+    pub function: String,
+    pub id: String,
+    pub span: SourceSpan,
 }
 
 impl ScriptDiagnostic {
@@ -3344,6 +3374,16 @@ impl ScriptDiagnostic {
             related,
         }
     }
+
+    pub fn warning(code: impl Into<String>, message: String, span: SourceSpan) -> Self {
+        Self {
+            severity: ScriptDiagnosticSeverity::Warning,
+            code: code.into(),
+            message,
+            span,
+            related: Vec::new(),
+        }
+    }
 }
 
 impl From<&ScriptError> for ScriptDiagnostic {
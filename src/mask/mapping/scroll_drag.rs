@@ -0,0 +1,439 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{
+    ecs::{
+        resource::Resource,
+        system::{Commands, Res, ResMut},
+    },
+    input::mouse::{AccumulatedMouseScroll, MouseScrollUnit},
+    math::Vec2,
+    state::state::State,
+    time::{Time, Timer, TimerMode},
+};
+use bevy_ineffable::prelude::{ContinuousBinding, Ineffable, InputBinding};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mask::{
+        mapping::{
+            MappingState,
+            binding::{ButtonBinding, MergedButton, ValidateMappingConfig},
+            config::ActiveMappingConfig,
+            cursor::{CursorPosition, CursorState},
+            executor::{make_mapping_execution_context, run_script_hook},
+            layout::MappingAnchor,
+            script::{BindMappingScriptHooks, MappingScriptHooks},
+            script_helper::{ScriptRuntimeCommandSender, ScriptSharedState},
+            utils::{ControlMsgHelper, Position},
+        },
+        mask_command::MaskSize,
+    },
+    scrcpy::constant::MotionEventAction,
+    tokio_tasks::TokioTasksRuntime,
+    utils::ChannelSenderCS,
+};
+
+// wheel deltas reported in pixels are converted to lines with this ratio
+const SCROLL_PIXELS_PER_LINE: f32 = 20.0;
+
+pub fn init_scroll_drag(mut commands: Commands) {
+    commands.insert_resource(ActiveScrollDragMap::default());
+}
+
+pub fn cleanup_scroll_drag_on_stop(
+    cs_tx_res: Res<ChannelSenderCS>,
+    mut active_map: ResMut<ActiveScrollDragMap>,
+) {
+    for (_, item) in active_map.0.drain() {
+        ControlMsgHelper::send_touch(
+            &cs_tx_res.0,
+            MotionEventAction::Up,
+            item.pointer_id,
+            item.original_size,
+            item.current_pos(),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollDragAxis {
+    /// Wheel up and down drag the finger vertically
+    #[default]
+    Vertical,
+    /// Wheel left and right drag the finger horizontally
+    Horizontal,
+    /// Both wheel axes drag the finger
+    Both,
+}
+
+impl ScrollDragAxis {
+    fn wheel_buttons(&self) -> Vec<MergedButton> {
+        match self {
+            ScrollDragAxis::Vertical => vec![MergedButton::ScrollUp, MergedButton::ScrollDown],
+            ScrollDragAxis::Horizontal => {
+                vec![MergedButton::ScrollLeft, MergedButton::ScrollRight]
+            }
+            ScrollDragAxis::Both => vec![
+                MergedButton::ScrollUp,
+                MergedButton::ScrollDown,
+                MergedButton::ScrollLeft,
+                MergedButton::ScrollRight,
+            ],
+        }
+    }
+
+    fn mask(&self) -> Vec2 {
+        match self {
+            ScrollDragAxis::Vertical => Vec2::Y,
+            ScrollDragAxis::Horizontal => Vec2::X,
+            ScrollDragAxis::Both => Vec2::ONE,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BindMappingScrollDrag {
+    pub id: String,
+    pub note: String,
+    pub pointer_id: u64,
+    pub position: Position,
+    pub axis: ScrollDragAxis,
+    pub step: f32,
+    pub max_distance: f32,
+    pub release_delay: u64,
+    pub bind: ButtonBinding,
+    /// One binding per wheel direction, the action is active when any of them is
+    pub input_bindings: Vec<InputBinding>,
+    pub script_hooks: BindMappingScriptHooks,
+}
+
+impl From<MappingScrollDrag> for BindMappingScrollDrag {
+    fn from(value: MappingScrollDrag) -> Self {
+        let input_bindings: Vec<InputBinding> = value
+            .axis
+            .wheel_buttons()
+            .into_iter()
+            .map(|wheel| {
+                let mut buttons = value.bind.buttons().to_vec();
+                buttons.push(wheel);
+                ContinuousBinding::hold(ButtonBinding::new(buttons)).0
+            })
+            .collect();
+        Self {
+            id: value.id,
+            note: value.note,
+            pointer_id: value.pointer_id,
+            position: value.position,
+            axis: value.axis,
+            step: value.step,
+            max_distance: value.max_distance,
+            release_delay: value.release_delay,
+            bind: value.bind,
+            input_bindings,
+            script_hooks: value.script_hooks.into(),
+        }
+    }
+}
+
+impl BindMappingScrollDrag {
+    pub fn label_text(&self) -> String {
+        if self.bind.buttons().is_empty() {
+            "Wheel".to_string()
+        } else {
+            format!("{}+Wheel", self.bind.to_string())
+        }
+    }
+
+    /// Drag offset for this frame's wheel movement, in original coordinates
+    fn drag_delta(&self, scroll: &AccumulatedMouseScroll) -> Vec2 {
+        let lines = match scroll.unit {
+            MouseScrollUnit::Line => scroll.delta,
+            MouseScrollUnit::Pixel => scroll.delta / SCROLL_PIXELS_PER_LINE,
+        };
+        // positive wheel deltas move the content down or right, just like a
+        // finger dragged down or right on a touch screen
+        lines * self.axis.mask() * self.step
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MappingScrollDrag {
+    #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
+    pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
+    pub note: String,
+    pub pointer_id: u64,
+    /// Where the finger touches down
+    pub position: Position,
+    #[serde(default)]
+    pub axis: ScrollDragAxis,
+    /// Drag distance per wheel line, negative values invert the direction
+    #[serde(serialize_with = "crate::mask::mapping::serde_float::serialize_f32_3dp")]
+    pub step: f32,
+    /// Lift and touch down again at `position` once the finger is this far away, 0 means no limit
+    #[serde(
+        default,
+        serialize_with = "crate::mask::mapping::serde_float::serialize_f32_3dp"
+    )]
+    pub max_distance: f32,
+    /// Milliseconds without wheel movement before the finger is lifted
+    pub release_delay: u64,
+    /// Modifier buttons that must be held while scrolling, may be empty
+    pub bind: ButtonBinding,
+    #[serde(default)]
+    pub script_hooks: MappingScriptHooks,
+}
+
+impl MappingScrollDrag {
+    pub fn validate_scroll_drag(&self) -> Result<(), String> {
+        if self.step == 0.0 {
+            return Err("ScrollDrag's step must not be zero".to_string());
+        }
+        if self.max_distance < 0.0 {
+            return Err("ScrollDrag's max_distance must not be negative".to_string());
+        }
+        if self.release_delay == 0 {
+            return Err("ScrollDrag's release_delay must be greater than zero".to_string());
+        }
+        if self.bind.buttons().iter().any(|button| button.is_wheel()) {
+            return Err("ScrollDrag's bind must not contain wheel directions".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl ValidateMappingConfig for MappingScrollDrag {
+    fn validate(&self) -> Result<(), String> {
+        self.validate_scroll_drag()?;
+        self.script_hooks.validate()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ActiveScrollDragMap(HashMap<String, ScrollDragItem>);
+
+pub struct ScrollDragItem {
+    pointer_id: u64,
+    start_pos: Vec2,
+    offset: Vec2,
+    original_size: Vec2,
+    idle_timer: Timer,
+}
+
+impl ScrollDragItem {
+    fn new(pointer_id: u64, start_pos: Vec2, original_size: Vec2, release_delay: u64) -> Self {
+        Self {
+            pointer_id,
+            start_pos,
+            offset: Vec2::ZERO,
+            original_size,
+            idle_timer: Timer::new(Duration::from_millis(release_delay), TimerMode::Once),
+        }
+    }
+
+    fn current_pos(&self) -> Vec2 {
+        self.start_pos + self.offset
+    }
+
+    /// Moves the finger by the wheel delta, returns the touches to send in order
+    fn drag(&mut self, delta: Vec2, max_distance: f32) -> Vec<(MotionEventAction, Vec2)> {
+        let mut touches = Vec::new();
+        let mut offset = self.offset + delta;
+        if max_distance > 0.0 && offset.length() > max_distance {
+            // out of room, lift the finger and keep dragging from the start position
+            self.offset = self.offset.clamp_length_max(max_distance);
+            touches.push((MotionEventAction::Up, self.current_pos()));
+            touches.push((MotionEventAction::Down, self.start_pos));
+            offset = delta.clamp_length_max(max_distance);
+        }
+        self.offset = offset;
+        self.idle_timer.reset();
+        touches.push((MotionEventAction::Move, self.current_pos()));
+        touches
+    }
+
+    /// Whether the wheel has been idle long enough to lift the finger
+    fn idle(&mut self, elapsed: Duration) -> bool {
+        self.idle_timer.tick(elapsed).is_finished()
+    }
+}
+
+pub fn handle_scroll_drag(
+    ineffable: Res<Ineffable>,
+    active_mapping: Res<ActiveMappingConfig>,
+    cs_tx_res: Res<ChannelSenderCS>,
+    script_command_tx: Res<ScriptRuntimeCommandSender>,
+    shared_state: Res<ScriptSharedState>,
+    mask_size: Res<MaskSize>,
+    cursor_pos: Res<CursorPosition>,
+    mapping_state: Res<State<MappingState>>,
+    cursor_state: Res<State<CursorState>>,
+    scroll: Res<AccumulatedMouseScroll>,
+    time: Res<Time>,
+    runtime: ResMut<TokioTasksRuntime>,
+    mut active_map: ResMut<ActiveScrollDragMap>,
+) {
+    let Some(active_mapping) = &active_mapping.0 else {
+        return;
+    };
+    let original_size: Vec2 = active_mapping.original_size.into();
+    let run_hook = |mapping: &BindMappingScrollDrag, before: bool| {
+        let script_ast = if before {
+            mapping.script_hooks.before_script_ast.clone()
+        } else {
+            mapping.script_hooks.after_script_ast.clone()
+        };
+        if script_ast.empty {
+            return;
+        }
+        let exec_ctx = make_mapping_execution_context(
+            &cs_tx_res,
+            &script_command_tx,
+            &shared_state,
+            mapping.id.clone(),
+            original_size,
+            cursor_pos.0,
+            mask_size.0,
+            mapping_state.get() == &MappingState::RawInput,
+            cursor_state.get() == &CursorState::Fps,
+        );
+        runtime.spawn_background_task(move |_task_ctx| async move {
+            if let Err(e) = run_script_hook(&script_ast, &exec_ctx).await {
+                log::error!("[ScrollDrag] script hook runtime error: {:?}", e);
+            }
+        });
+    };
+
+    for (action, mapping) in &active_mapping.mappings {
+        if !action.as_ref().starts_with("ScrollDrag") {
+            continue;
+        }
+        let mapping = mapping.as_ref_scrolldrag();
+        if !ineffable.is_active(action.ineff_continuous()) {
+            continue;
+        }
+        let delta = mapping.drag_delta(&scroll);
+        if delta == Vec2::ZERO {
+            continue;
+        }
+
+        let item = active_map.0.entry(action.to_string()).or_insert_with(|| {
+            let start_pos: Vec2 = mapping.position.into();
+            ControlMsgHelper::send_touch(
+                &cs_tx_res.0,
+                MotionEventAction::Down,
+                mapping.pointer_id,
+                original_size,
+                start_pos,
+            );
+            run_hook(mapping, true);
+            ScrollDragItem::new(
+                mapping.pointer_id,
+                start_pos,
+                original_size,
+                mapping.release_delay,
+            )
+        });
+        for (action, pos) in item.drag(delta, mapping.max_distance) {
+            ControlMsgHelper::send_touch(
+                &cs_tx_res.0,
+                action,
+                item.pointer_id,
+                item.original_size,
+                pos,
+            );
+        }
+    }
+
+    let mut released = Vec::new();
+    for (action, item) in active_map.0.iter_mut() {
+        if item.idle(time.delta()) {
+            ControlMsgHelper::send_touch(
+                &cs_tx_res.0,
+                MotionEventAction::Up,
+                item.pointer_id,
+                item.original_size,
+                item.current_pos(),
+            );
+            released.push(action.clone());
+        }
+    }
+    for action in released {
+        active_map.0.remove(&action);
+        if let Some((_, mapping)) = active_mapping
+            .mappings
+            .iter()
+            .find(|(mapping_action, _)| mapping_action.as_ref() == action)
+        {
+            run_hook(mapping.as_ref_scrolldrag(), false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scroll_drag(release_delay: u64, bind: &[&str]) -> MappingScrollDrag {
+        serde_json::from_value(json!({
+            "note": "", "pointer_id": 1, "position": { "x": 960, "y": 540 },
+            "step": 40.0, "release_delay": release_delay, "bind": bind,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn release_delay_must_be_positive() {
+        assert!(scroll_drag(150, &[]).validate().is_ok());
+        assert!(scroll_drag(1, &["ShiftLeft"]).validate().is_ok());
+        assert_eq!(
+            scroll_drag(0, &[]).validate().unwrap_err(),
+            "ScrollDrag's release_delay must be greater than zero"
+        );
+    }
+
+    #[test]
+    fn finger_touches_again_after_max_distance() {
+        let start = Vec2::new(960., 540.);
+        let mut item = ScrollDragItem::new(1, start, Vec2::new(1920., 1080.), 150);
+        assert_eq!(
+            item.drag(Vec2::new(0., 60.), 100.),
+            [(MotionEventAction::Move, Vec2::new(960., 600.))]
+        );
+        // 120 would be past the limit, lift at the limit and start over
+        assert_eq!(
+            item.drag(Vec2::new(0., 60.), 100.),
+            [
+                (MotionEventAction::Up, Vec2::new(960., 600.)),
+                (MotionEventAction::Down, start),
+                (MotionEventAction::Move, Vec2::new(960., 600.)),
+            ]
+        );
+        // a single step larger than the limit is cut to it
+        assert_eq!(
+            item.drag(Vec2::new(0., -300.), 100.).last(),
+            Some(&(MotionEventAction::Move, Vec2::new(960., 440.)))
+        );
+
+        // without a limit the finger keeps going
+        let mut item = ScrollDragItem::new(1, start, Vec2::new(1920., 1080.), 150);
+        for _ in 0..10 {
+            assert_eq!(item.drag(Vec2::new(60., 0.), 0.).len(), 1);
+        }
+        assert_eq!(item.current_pos(), Vec2::new(1560., 540.));
+    }
+
+    #[test]
+    fn finger_lifts_after_idle_delay() {
+        let mut item = ScrollDragItem::new(1, Vec2::ZERO, Vec2::new(1920., 1080.), 150);
+        item.drag(Vec2::new(0., 40.), 0.);
+        assert!(!item.idle(Duration::from_millis(100)));
+        // scrolling again restarts the delay
+        item.drag(Vec2::new(0., 40.), 0.);
+        assert!(!item.idle(Duration::from_millis(100)));
+        assert!(item.idle(Duration::from_millis(60)));
+    }
+}
@@ -17,6 +17,7 @@ use crate::{
         config::ActiveMappingConfig,
         cursor::{CursorPosition, CursorState},
         executor::{MappingExecutionError, make_mapping_execution_context, run_with_hooks},
+        layout::MappingAnchor,
         script::{BindMappingScriptHooks, MappingScriptHooks},
         script_helper::{ScriptRuntimeCommandSender, ScriptSharedState},
         utils::{
@@ -70,6 +71,8 @@ impl From<MappingSwipe> for BindMappingSwipe {
 pub struct MappingSwipe {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub positions: Vec<Position>,
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use crate::tokio_tasks::TokioTasksRuntime;
 use bevy::{
@@ -24,6 +27,7 @@ use crate::{
             MappingExecutionError, MappingLifecycleStart, MappingLifecycleState,
             make_mapping_execution_context, run_script_hook, run_with_hooks,
         },
+        layout::MappingAnchor,
         script::{BindMappingScriptHooks, MappingScriptHooks},
         script_helper::{ScriptRuntimeCommandSender, ScriptSharedState},
         utils::{ControlMsgHelper, Position, default_random_offset, random_offset_vec2},
@@ -38,6 +42,7 @@ pub fn tap_init(mut commands: Commands) {
     commands.insert_resource(ActiveSingleTapMap::default());
     commands.insert_resource(SingleTapLifecycleState::default());
     commands.insert_resource(RepeatTapLifecycleState::default());
+    commands.insert_resource(ToggledTapSet::default());
 }
 
 pub fn cleanup_tap_on_stop(
@@ -47,6 +52,7 @@ pub fn cleanup_tap_on_stop(
     mut active_repeat_tap: ResMut<ActiveRepeatTapMap>,
     mut single_lifecycle_state: ResMut<SingleTapLifecycleState>,
     mut repeat_lifecycle_state: ResMut<RepeatTapLifecycleState>,
+    mut toggled: ResMut<ToggledTapSet>,
 ) {
     if let Some(active_mapping) = &active_mapping.0 {
         let original_size: Vec2 = active_mapping.original_size.into();
@@ -74,6 +80,43 @@ pub fn cleanup_tap_on_stop(
     active_repeat_tap.0.clear();
     single_lifecycle_state.0.clear_all();
     repeat_lifecycle_state.0.clear_all();
+    toggled.0.clear();
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TapTriggerMode {
+    /// Active while the binding is held
+    #[default]
+    Hold,
+    /// The first press starts the mapping and the next press stops it
+    Toggle,
+}
+
+/// Ids of the tap mappings currently toggled on.
+#[derive(Resource, Default)]
+pub struct ToggledTapSet(pub HashSet<String>);
+
+/// Turn the binding edges into press/release edges according to the trigger mode.
+fn tap_trigger_edges(
+    just_activated: bool,
+    just_deactivated: bool,
+    mode: TapTriggerMode,
+    toggled: &mut ToggledTapSet,
+    mapping_id: &str,
+) -> (bool, bool) {
+    match mode {
+        TapTriggerMode::Hold => (just_activated, just_deactivated),
+        TapTriggerMode::Toggle if just_activated => {
+            if toggled.0.remove(mapping_id) {
+                (false, true)
+            } else {
+                toggled.0.insert(mapping_id.to_string());
+                (true, false)
+            }
+        }
+        TapTriggerMode::Toggle => (false, false),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +127,7 @@ pub struct BindMappingSingleTap {
     pub pointer_id: u64,
     pub duration: u64,
     pub sync: bool,
+    pub trigger_mode: TapTriggerMode,
     pub bind: ButtonBinding,
     pub input_binding: InputBinding,
     pub random_offset_x: f32,
@@ -100,6 +144,7 @@ impl From<MappingSingleTap> for BindMappingSingleTap {
             pointer_id: value.pointer_id,
             duration: value.duration,
             sync: value.sync,
+            trigger_mode: value.trigger_mode,
             bind: value.bind.clone(),
             random_offset_x: value.random_offset_x,
             random_offset_y: value.random_offset_y,
@@ -113,11 +158,16 @@ impl From<MappingSingleTap> for BindMappingSingleTap {
 pub struct MappingSingleTap {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub position: Position,
     pub note: String,
     pub pointer_id: u64,
     pub duration: u64,
     pub sync: bool,
+    /// Toggle mode always holds the touch, regardless of `sync`
+    #[serde(default)]
+    pub trigger_mode: TapTriggerMode,
     pub bind: ButtonBinding,
     #[serde(
         default = "default_random_offset",
@@ -216,14 +266,23 @@ pub fn handle_single_tap(
     cursor_state: Res<State<CursorState>>,
     runtime: ResMut<TokioTasksRuntime>,
     mut lifecycle_state: ResMut<SingleTapLifecycleState>,
+    mut toggled: ResMut<ToggledTapSet>,
 ) {
     if let Some(active_mapping) = &active_mapping.0 {
         for (action, mapping) in &active_mapping.mappings {
             if action.as_ref().starts_with("SingleTap") {
                 let original_size: Vec2 = active_mapping.original_size.into();
                 let mapping = mapping.as_ref_singletap();
-                if ineffable.just_activated(action.ineff_continuous()) {
-                    if mapping.sync {
+                let (pressed, released) = tap_trigger_edges(
+                    ineffable.just_activated(action.ineff_continuous()),
+                    ineffable.just_deactivated(action.ineff_continuous()),
+                    mapping.trigger_mode,
+                    &mut toggled,
+                    &mapping.id,
+                );
+                let sync = mapping.sync || mapping.trigger_mode == TapTriggerMode::Toggle;
+                if pressed {
+                    if sync {
                         if single_tap_has_before_hook(mapping) {
                             let action = action.to_string();
                             let version = lifecycle_state.0.begin_start(&action);
@@ -371,7 +430,7 @@ pub fn handle_single_tap(
                             }
                         });
                     }
-                } else if mapping.sync && ineffable.just_deactivated(action.ineff_continuous()) {
+                } else if sync && released {
                     let released = apply_single_tap_up(
                         &cs_tx_res,
                         &mut active_single_tap,
@@ -427,6 +486,8 @@ pub struct BindMappingRepeatTap {
     pub pointer_id: u64,
     pub duration: u64,
     pub interval: u32,
+    pub trigger_mode: TapTriggerMode,
+    pub repeat_limit: u32,
     pub bind: ButtonBinding,
     pub input_binding: InputBinding,
     pub random_offset_x: f32,
@@ -443,6 +504,8 @@ impl From<MappingRepeatTap> for BindMappingRepeatTap {
             pointer_id: value.pointer_id,
             duration: value.duration,
             interval: value.interval,
+            trigger_mode: value.trigger_mode,
+            repeat_limit: value.repeat_limit,
             bind: value.bind.clone(),
             input_binding: ContinuousBinding::hold(value.bind).0,
             random_offset_x: value.random_offset_x,
@@ -456,11 +519,18 @@ impl From<MappingRepeatTap> for BindMappingRepeatTap {
 pub struct MappingRepeatTap {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub position: Position,
     pub note: String,
     pub pointer_id: u64,
     pub duration: u64,
     pub interval: u32,
+    #[serde(default)]
+    pub trigger_mode: TapTriggerMode,
+    /// Stop after this many taps, 0 means no limit
+    #[serde(default)]
+    pub repeat_limit: u32,
     pub bind: ButtonBinding,
     #[serde(
         default = "default_random_offset",
@@ -503,6 +573,24 @@ struct RepeatTapTimer {
     original_size: Vec2,
     duration: Duration,
     random_offset: Vec2,
+    remaining: Option<u32>,
+}
+
+impl RepeatTapTimer {
+    fn is_exhausted(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// Advance the timer, true when a tap is due and the repeat limit allows it
+    fn tick(&mut self, delta: Duration) -> bool {
+        if self.is_exhausted() || !self.timer.tick(delta).just_finished() {
+            return false;
+        }
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= 1;
+        }
+        true
+    }
 }
 
 fn repeat_tap_has_before_hook(mapping: &BindMappingRepeatTap) -> bool {
@@ -556,6 +644,7 @@ fn make_repeat_tap_timer(mapping: &BindMappingRepeatTap, original_size: Vec2) ->
         original_size,
         duration: Duration::from_millis(mapping.duration as u64),
         random_offset: Vec2::new(mapping.random_offset_x, mapping.random_offset_y),
+        remaining: (mapping.repeat_limit > 0).then_some(mapping.repeat_limit),
     }
 }
 
@@ -566,7 +655,7 @@ pub fn handle_repeat_tap_trigger(
     runtime: ResMut<TokioTasksRuntime>,
 ) {
     for (_, timer) in active_map.0.iter_mut() {
-        if timer.timer.tick(time.delta()).just_finished() {
+        if timer.tick(time.delta()) {
             spawn_repeat_tap_once(
                 &runtime,
                 &cs_tx_res,
@@ -593,12 +682,32 @@ pub fn handle_repeat_tap(
     cursor_state: Res<State<CursorState>>,
     runtime: ResMut<TokioTasksRuntime>,
     mut lifecycle_state: ResMut<RepeatTapLifecycleState>,
+    mut toggled: ResMut<ToggledTapSet>,
 ) {
     if let Some(active_mapping) = &active_mapping.0 {
         for (action, mapping) in &active_mapping.mappings {
             if action.as_ref().starts_with("RepeatTap") {
                 let mapping = mapping.as_ref_repeattap();
-                if ineffable.just_activated(action.ineff_continuous()) {
+                // a toggled repeat that hit its limit switches itself off
+                let exhausted = active_map
+                    .0
+                    .get(action.as_ref())
+                    .is_some_and(|timer| timer.is_exhausted());
+                let (pressed, released) = if mapping.trigger_mode == TapTriggerMode::Toggle
+                    && exhausted
+                    && toggled.0.remove(&mapping.id)
+                {
+                    (false, true)
+                } else {
+                    tap_trigger_edges(
+                        ineffable.just_activated(action.ineff_continuous()),
+                        ineffable.just_deactivated(action.ineff_continuous()),
+                        mapping.trigger_mode,
+                        &mut toggled,
+                        &mapping.id,
+                    )
+                };
+                if pressed {
                     let original_size: Vec2 = active_mapping.original_size.into();
                     if repeat_tap_has_before_hook(mapping) {
                         let action = action.to_string();
@@ -708,7 +817,7 @@ pub fn handle_repeat_tap(
                             make_repeat_tap_timer(mapping, original_size),
                         );
                     }
-                } else if ineffable.just_deactivated(action.ineff_continuous()) {
+                } else if released {
                     let released = active_map.0.remove(action.as_ref()).is_some();
 
                     if released {
@@ -790,6 +899,8 @@ impl From<MappingMultipleTap> for BindMappingMultipleTap {
 pub struct MappingMultipleTap {
     #[serde(default = "crate::mask::mapping::config::default_mapping_id")]
     pub id: String,
+    #[serde(default)]
+    pub anchor: MappingAnchor,
     pub note: String,
     pub pointer_id: u64,
     pub items: Vec<MappingMultipleTapItem>,
@@ -884,3 +995,85 @@ pub fn handle_multiple_tap(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repeat_timer(interval_ms: u64, remaining: Option<u32>) -> RepeatTapTimer {
+        RepeatTapTimer {
+            timer: Timer::new(Duration::from_millis(interval_ms), TimerMode::Repeating),
+            pointer_id: 0,
+            original_pos: Vec2::ZERO,
+            original_size: Vec2::new(1280., 720.),
+            duration: Duration::from_millis(30),
+            random_offset: Vec2::ZERO,
+            remaining,
+        }
+    }
+
+    #[test]
+    fn hold_mode_passes_edges_through() {
+        let mut toggled = ToggledTapSet::default();
+        let mode = TapTriggerMode::Hold;
+        assert_eq!(
+            tap_trigger_edges(true, false, mode, &mut toggled, "a"),
+            (true, false)
+        );
+        assert_eq!(
+            tap_trigger_edges(false, true, mode, &mut toggled, "a"),
+            (false, true)
+        );
+        assert!(toggled.0.is_empty());
+    }
+
+    #[test]
+    fn toggle_mode_switches_on_presses_only() {
+        let mut toggled = ToggledTapSet::default();
+        let mode = TapTriggerMode::Toggle;
+        assert_eq!(
+            tap_trigger_edges(true, false, mode, &mut toggled, "a"),
+            (true, false)
+        );
+        // releasing the binding keeps the mapping on
+        assert_eq!(
+            tap_trigger_edges(false, true, mode, &mut toggled, "a"),
+            (false, false)
+        );
+        assert!(toggled.0.contains("a"));
+
+        // other mappings toggle independently
+        assert_eq!(
+            tap_trigger_edges(true, false, mode, &mut toggled, "b"),
+            (true, false)
+        );
+        assert_eq!(
+            tap_trigger_edges(true, false, mode, &mut toggled, "a"),
+            (false, true)
+        );
+        assert!(!toggled.0.contains("a"));
+        assert!(toggled.0.contains("b"));
+    }
+
+    #[test]
+    fn repeat_limit_stops_after_limit() {
+        let interval = Duration::from_millis(100);
+        let mut timer = repeat_timer(100, Some(2));
+        assert!(!timer.tick(Duration::from_millis(50)));
+        assert!(timer.tick(Duration::from_millis(50)));
+        assert!(timer.tick(interval));
+        assert!(timer.is_exhausted());
+        assert!(!timer.tick(interval));
+        assert!(!timer.tick(interval));
+    }
+
+    #[test]
+    fn repeat_without_limit_keeps_tapping() {
+        let interval = Duration::from_millis(100);
+        let mut timer = repeat_timer(100, None);
+        for _ in 0..10 {
+            assert!(timer.tick(interval));
+        }
+        assert!(!timer.is_exhausted());
+    }
+}
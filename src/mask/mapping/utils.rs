@@ -46,7 +46,7 @@ impl From<Vec2> for Size {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -182,6 +182,19 @@ pub enum SingleSwipeStrategy {
     ArcWithEaseInOut,
 }
 
+impl SingleSwipeStrategy {
+    /// Travelled fraction at elapsed fraction `t`, ignoring the path shape.
+    pub fn progress(&self, t: f32) -> f32 {
+        let t = clamp01(t);
+        match self {
+            SingleSwipeStrategy::Linear => t,
+            SingleSwipeStrategy::ArcWithCubicEasing => cubic_easing_timing(t),
+            SingleSwipeStrategy::ArcWithEaseOut => ease_out_timing(t),
+            SingleSwipeStrategy::ArcWithEaseInOut => ease_in_out_timing(t),
+        }
+    }
+}
+
 /// Selects both path shape and timing curve for multi-segment swipe generation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MultiSwipeStrategy {
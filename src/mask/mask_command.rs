@@ -14,7 +14,7 @@ use crate::{
         ui::basic::TITLEBAR_HEIGHT,
     },
     tokio_tasks::TokioTasksRuntime,
-    utils::{ChannelReceiverM, ChannelSenderCS},
+    utils::{ChannelReceiverM, ChannelSenderCS, share::MappingFallback},
 };
 
 #[derive(Debug)]
@@ -161,6 +161,7 @@ pub fn handle_mask_command(
                         ineffable.set_config(&input_config);
                         active_mapping.0 = Some(mapping_config);
                         active_mapping.1 = file_name;
+                        MappingFallback::set_blocking(None);
                         oneshot_tx.send(Ok(String::new())).unwrap();
                     }
                     Err(e) => {
@@ -1,3 +1,4 @@
+pub mod headless;
 pub mod mapping;
 pub mod mask_command;
 pub mod ui;
@@ -14,7 +15,7 @@ use bevy::{
     math::Vec2,
     prelude::{ButtonInput, IntoScheduleConfigs, MouseButton, Resource, SystemSet},
     time::{Time, Timer, TimerMode},
-    window::{Window, WindowMoved, WindowPosition, WindowResized},
+    window::{FileDragAndDrop, Window, WindowMoved, WindowPosition, WindowResized},
 };
 use bevy_ui_render::prelude::UiMaterialPlugin;
 
@@ -29,6 +30,8 @@ use crate::{
         ui::basic::TITLEBAR_HEIGHT,
         video::{YuvVideoMaterial, handle_video_msg},
     },
+    scrcpy::file_transfer,
+    tokio_tasks::TokioTasksRuntime,
     utils::{ChannelSenderWS, DeviceOrientation, share::ControlledDevice},
     web::ws::WebSocketNotification,
 };
@@ -59,6 +62,7 @@ impl Plugin for MaskPlugins {
                     handle_mask_command,
                     apply_pending_window_focus.after(handle_mask_command),
                     handle_video_msg,
+                    handle_file_drop,
                 ),
             );
     }
@@ -282,3 +286,45 @@ fn sync_mask_position(
         }
     }
 }
+
+/// Like scrcpy, dropped apks are installed on the main device and other files pushed to it
+fn handle_file_drop(
+    mut drop_reader: MessageReader<FileDragAndDrop>,
+    runtime: ResMut<TokioTasksRuntime>,
+    ws_tx: Res<ChannelSenderWS>,
+) {
+    let paths = drop_reader
+        .read()
+        .filter_map(|e| match e {
+            FileDragAndDrop::DroppedFile { path_buf, .. } => Some(path_buf.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        return;
+    }
+    let Some(device) = ControlledDevice::get_main_device_blocking() else {
+        log::warn!("[Mask] No main device to receive the dropped files");
+        return;
+    };
+
+    let ws_tx = ws_tx.0.clone();
+    runtime.spawn_background_task(move |_ctx| async move {
+        // one at a time, in the order they were dropped
+        for path in paths {
+            let device_id = device.device_id.clone();
+            let ws_tx = ws_tx.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                file_transfer::push_or_install(&device_id, &path, ws_tx)
+                    .map(|_| path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))
+            })
+            .await;
+            match res {
+                Ok(Ok(path)) => log::info!("[Mask] Sent dropped file {}", path.display()),
+                Ok(Err(e)) => log::error!("[Mask] Failed to send dropped file {}", e),
+                Err(e) => log::error!("[Mask] Dropped file task failed: {}", e),
+            }
+        }
+    });
+}
@@ -14,6 +14,10 @@ use crate::{
     mask::{
         MaskFrameSet, MaskResizeState,
         mask_command::TitlebarState,
+        ui::stats_overlay::{
+            STATS_BUTTON_ACTIVE_TEXT, STATS_BUTTON_NORMAL_TEXT, StatsOverlayButton,
+            stats_overlay_bundle,
+        },
         video::{VideoPlayer, YuvVideoMaterial, create_initial_yuv_material},
     },
     scrcpy::{constant::Keycode, controller::ControllerCommand, device_action},
@@ -131,6 +135,11 @@ fn setup_ui(
     } else {
         MAC_PIN_INACTIVE_BG
     };
+    let initial_stats_color = if config.stats_overlay_visible {
+        STATS_BUTTON_ACTIVE_TEXT
+    } else {
+        STATS_BUTTON_NORMAL_TEXT
+    };
 
     // Root container
     let root_entity = commands
@@ -267,6 +276,40 @@ fn setup_ui(
                 ..default()
             })
             .with_children(|right| {
+                // Stream stats overlay toggle
+                right
+                    .spawn((
+                        Button,
+                        Node {
+                            height: Val::Px(20.),
+                            padding: UiRect::px(4., 4., 0., 0.),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(NORMAL_BG),
+                        StatsOverlayButton,
+                    ))
+                    .with_child((
+                        Text::new("FPS"),
+                        TextLayout::no_wrap(),
+                        TextFont {
+                            font_size: FontSize::Px(11.),
+                            ..default()
+                        },
+                        TextColor(initial_stats_color),
+                    ));
+
+                // Separator
+                right
+                    .spawn(Node {
+                        width: Val::Px(1.),
+                        height: Val::Px(16.),
+                        margin: UiRect::px(2., 2., 0., 0.),
+                        ..default()
+                    })
+                    .insert(BackgroundColor(Color::srgba(0.4, 0.4, 0.4, 0.5)));
+
                 // Display & volume buttons
                 for action in [
                     DeviceAction::ScreenOff,
@@ -402,6 +445,9 @@ fn setup_ui(
             BorderColor::all(border_color),
         ));
 
+        // Stream stats, toggled from the titlebar
+        content.spawn(stats_overlay_bundle());
+
         // Resize handles (invisible, layered on top of border)
         let edge_z = ZIndex(10);
         let corner_z = ZIndex(11);
@@ -534,6 +580,7 @@ fn handle_titlebar_drag(
             With<PushpinButton>,
             With<CloseButton>,
             With<DeviceButton>,
+            With<StatsOverlayButton>,
         )>,
     >,
 ) {
@@ -619,7 +666,13 @@ fn button_interaction(
     minimize_query: Query<(Entity, &Interaction), (With<MinimizeButton>, Changed<Interaction>)>,
     pushpin_query: Query<(Entity, &Interaction), (With<PushpinButton>, Changed<Interaction>)>,
     close_query: Query<(Entity, &Interaction), (With<CloseButton>, Changed<Interaction>)>,
-    device_btn_query: Query<(Entity, &Interaction), (With<DeviceButton>, Changed<Interaction>)>,
+    device_btn_query: Query<
+        (Entity, &Interaction),
+        (
+            Or<(With<DeviceButton>, With<StatsOverlayButton>)>,
+            Changed<Interaction>,
+        ),
+    >,
     mut bg_query: Query<&mut BackgroundColor>,
 ) {
     for (entity, interaction) in minimize_query.iter() {
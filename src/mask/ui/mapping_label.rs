@@ -7,6 +7,7 @@ use crate::{
         mapping::{
             MappingState,
             config::{ActiveMappingConfig, BindMappingType},
+            tap::ToggledTapSet,
         },
         mask_command::MaskSize,
         ui::basic::MaskContentEntity,
@@ -55,6 +56,7 @@ fn redraw_raw_input_label(
 
     create_simple_label(
         &mut commands,
+        "",
         "M-Right",
         (25., 25.).into(),
         mask_size.0,
@@ -84,6 +86,7 @@ fn redraw_normal_mapping_label(
                         BindMappingType::DirectionPad(mapping_direction_pad) => {
                             create_pad_label(
                                 &mut commands,
+                                &mapping_direction_pad.id,
                                 mapping_direction_pad
                                     .bind
                                     .to_string_vec()
@@ -101,6 +104,7 @@ fn redraw_normal_mapping_label(
                             bindings.push(mapping_pad_cast_spell.bind.to_string());
                             create_pad_label(
                                 &mut commands,
+                                &mapping_pad_cast_spell.id,
                                 bindings.iter().map(|s| s.as_ref()).collect(),
                                 pos,
                                 size,
@@ -113,6 +117,7 @@ fn redraw_normal_mapping_label(
                 } else {
                     create_simple_label(
                         &mut commands,
+                        mapping.id(),
                         &binding,
                         pos,
                         size,
@@ -127,6 +132,7 @@ fn redraw_normal_mapping_label(
 fn update_labels(
     mask_size: Res<MaskSize>,
     opacity: Res<LabelOpacity>,
+    toggled: Res<ToggledTapSet>,
     window: Single<&Window>,
     mut query: Query<(
         &MappingLabel,
@@ -147,7 +153,11 @@ fn update_labels(
         node.left = Val::Px(new_pos.x);
         node.top = Val::Px(new_pos.y);
 
-        bg.0 = label_background_color(opacity.0);
+        bg.0 = if toggled.0.contains(&label.mapping_id) {
+            label_toggled_background_color(opacity.0)
+        } else {
+            label_background_color(opacity.0)
+        };
         for child in node_children.iter() {
             if let Ok(mut text_color) = text_query.get_mut(child) {
                 text_color.0 = label_text_color(opacity.0);
@@ -169,6 +179,7 @@ pub struct LabelOpacity(f32);
 
 #[derive(Component)]
 struct MappingLabel {
+    mapping_id: String,
     original_pos: Vec2,
     original_size: Vec2,
 }
@@ -189,6 +200,7 @@ struct MappingLabelBundle {
 
 fn create_simple_label(
     commands: &mut Commands,
+    mapping_id: &str,
     binding: &str,
     original_pos: Vec2,
     original_size: Vec2,
@@ -199,6 +211,7 @@ fn create_simple_label(
         parent.spawn((
             MappingLabelBundle {
                 label: MappingLabel {
+                    mapping_id: mapping_id.to_string(),
                     original_pos,
                     original_size,
                 },
@@ -238,12 +251,18 @@ fn label_background_color(opacity: f32) -> Color {
     Color::linear_rgba(0., 0., 0., opacity)
 }
 
+// toggled mappings stay visible even with a fully transparent label
+fn label_toggled_background_color(opacity: f32) -> Color {
+    Color::linear_rgba(0.05, 0.35, 0.85, opacity.max(0.5))
+}
+
 fn label_text_color(opacity: f32) -> Color {
     Color::linear_rgba(1., 1., 1., opacity)
 }
 
 fn create_pad_label(
     commands: &mut Commands,
+    mapping_id: &str,
     bindings: Vec<&str>,
     original_pos: Vec2,
     original_size: Vec2,
@@ -290,6 +309,7 @@ fn create_pad_label(
         parent
             .spawn(MappingLabelBundle {
                 label: MappingLabel {
+                    mapping_id: mapping_id.to_string(),
                     original_pos,
                     original_size,
                 },
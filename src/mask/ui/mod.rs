@@ -1,16 +1,17 @@
 pub mod basic;
 pub mod mapping_label;
+pub mod stats_overlay;
 
 use basic::BasicPlugin;
 pub use basic::{MaskContentEntity, MaskContentMarker, TITLEBAR_HEIGHT};
 use bevy::app::{App, Plugin};
 
-use crate::mask::ui::mapping_label::MappingLabelPlugin;
+use crate::mask::ui::{mapping_label::MappingLabelPlugin, stats_overlay::StatsOverlayPlugin};
 
 pub struct UiPlugins;
 
 impl Plugin for UiPlugins {
     fn build(&self, app: &mut App) {
-        app.add_plugins((BasicPlugin, MappingLabelPlugin));
+        app.add_plugins((BasicPlugin, MappingLabelPlugin, StatsOverlayPlugin));
     }
 }
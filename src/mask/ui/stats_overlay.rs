@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::LocalConfig,
+    scrcpy::{audio::current_audio_stats, video_stats::current_video_stats},
+};
+
+const OVERLAY_REFRESH: Duration = Duration::from_millis(500);
+
+pub const STATS_BUTTON_NORMAL_TEXT: Color = Color::srgba(0.8, 0.8, 0.8, 0.6);
+pub const STATS_BUTTON_ACTIVE_TEXT: Color = Color::srgb(0.4, 0.85, 0.45);
+
+/// Titlebar button toggling the overlay
+#[derive(Component)]
+pub struct StatsOverlayButton;
+
+#[derive(Component)]
+pub struct StatsOverlayMarker;
+
+#[derive(Resource)]
+pub struct StatsOverlayState {
+    pub visible: bool,
+}
+
+#[derive(Resource)]
+struct StatsOverlayTimer(Timer);
+
+pub struct StatsOverlayPlugin;
+
+impl Plugin for StatsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StatsOverlayTimer(Timer::new(
+            OVERLAY_REFRESH,
+            TimerMode::Repeating,
+        )))
+        .add_systems(Startup, init_stats_overlay_state)
+        .add_systems(
+            Update,
+            (
+                handle_stats_overlay_button,
+                sync_stats_overlay_visibility,
+                update_stats_overlay,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn init_stats_overlay_state(mut commands: Commands) {
+    commands.insert_resource(StatsOverlayState {
+        visible: LocalConfig::get().stats_overlay_visible,
+    });
+}
+
+/// Overlay node placed in the top left corner of the mask content
+pub fn stats_overlay_bundle() -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.),
+            left: Val::Px(8.),
+            padding: UiRect::axes(Val::Px(6.), Val::Px(4.)),
+            border_radius: BorderRadius::all(Val::Px(4.)),
+            display: Display::None,
+            ..default()
+        },
+        ZIndex(5),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        Text::new(""),
+        TextFont {
+            font_size: FontSize::Px(12.),
+            ..default()
+        },
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        StatsOverlayMarker,
+    )
+}
+
+fn handle_stats_overlay_button(
+    query: Query<&Interaction, (With<StatsOverlayButton>, Changed<Interaction>)>,
+    mut state: ResMut<StatsOverlayState>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            state.visible = !state.visible;
+            LocalConfig::set_stats_overlay_visible(state.visible);
+        }
+    }
+}
+
+fn sync_stats_overlay_visibility(
+    state: Res<StatsOverlayState>,
+    mut overlay_query: Query<&mut Node, With<StatsOverlayMarker>>,
+    button_query: Query<&Children, With<StatsOverlayButton>>,
+    mut text_color_query: Query<&mut TextColor, Without<StatsOverlayMarker>>,
+    mut timer: ResMut<StatsOverlayTimer>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for mut node in overlay_query.iter_mut() {
+        node.display = if state.visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    let color = if state.visible {
+        STATS_BUTTON_ACTIVE_TEXT
+    } else {
+        STATS_BUTTON_NORMAL_TEXT
+    };
+    for children in button_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut text_color) = text_color_query.get_mut(child) {
+                text_color.0 = color;
+            }
+        }
+    }
+    // refresh right away instead of showing stale text
+    let duration = timer.0.duration();
+    timer.0.set_elapsed(duration);
+}
+
+fn update_stats_overlay(
+    time: Res<Time>,
+    state: Res<StatsOverlayState>,
+    mut timer: ResMut<StatsOverlayTimer>,
+    mut overlay_query: Query<&mut Text, With<StatsOverlayMarker>>,
+) {
+    if !state.visible {
+        return;
+    }
+    // a timer preset to its duration finishes on the first tick
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let text = stats_overlay_text();
+    for mut overlay_text in overlay_query.iter_mut() {
+        if overlay_text.0 != text {
+            overlay_text.0 = text.clone();
+        }
+    }
+}
+
+fn stats_overlay_text() -> String {
+    let mut lines = Vec::new();
+    match current_video_stats() {
+        Some(video) => {
+            lines.push(format!(
+                "{:?} {}x{}  {:.0} kbps",
+                video.codec, video.width, video.height, video.bitrate_kbps
+            ));
+            lines.push(format!(
+                "net {:.0} fps  decode {:.0} fps  render {:.0} fps",
+                video.received_fps, video.decoded_fps, video.rendered_fps
+            ));
+            lines.push(format!(
+                "decode {:.1} ms (max {:.1})  present {:.1} ms  dropped {}",
+                video.decode_ms, video.decode_max_ms, video.present_ms, video.dropped_frames
+            ));
+            lines.push(format!(
+                "jitter {:.1} ms  backlog {:.0} ms",
+                video.jitter_ms, video.backlog_ms
+            ));
+        }
+        None => lines.push("No video stream".to_string()),
+    }
+    if let Some(audio) = current_audio_stats() {
+        lines.push(format!(
+            "audio {:?} {:.0}/{:.0} ms  underflows {}",
+            audio.codec, audio.buffered_ms, audio.target_ms, audio.underflow_count
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,277 @@
+use serde::Serialize;
+
+use super::video_stats::VideoStats;
+use crate::config::AdaptiveVideoConfig;
+
+// a link is degraded once packets queue up or arrive irregularly
+const DEGRADED_BACKLOG_MS: f32 = 150.0;
+const DEGRADED_JITTER_MS: f32 = 25.0;
+const HEALTHY_BACKLOG_MS: f32 = 40.0;
+const HEALTHY_JITTER_MS: f32 = 8.0;
+// evaluations (one per second) a state must last before acting on it
+const DEGRADED_TICKS: u32 = 2;
+const HEALTHY_TICKS: u32 = 10;
+// evaluations to skip after a change, the new stream needs time to settle
+const COOLDOWN_TICKS: u32 = 5;
+const STEP_DOWN_FACTOR: f32 = 0.7;
+const STEP_UP_FACTOR: f32 = 1.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkHealth {
+    Healthy,
+    Fair,
+    Degraded,
+}
+
+impl LinkHealth {
+    fn from_stats(stats: &VideoStats) -> Self {
+        if stats.backlog_ms > DEGRADED_BACKLOG_MS || stats.jitter_ms > DEGRADED_JITTER_MS {
+            LinkHealth::Degraded
+        } else if stats.backlog_ms < HEALTHY_BACKLOG_MS && stats.jitter_ms < HEALTHY_JITTER_MS {
+            LinkHealth::Healthy
+        } else {
+            LinkHealth::Fair
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdaptiveVideoStatus {
+    pub health: LinkHealth,
+    pub bit_rate: u32,
+    pub max_fps: u32,
+    /// Set when this evaluation changed the encoder settings
+    pub adjusted: bool,
+    pub reason: String,
+}
+
+/// Encoder settings the controller wants to switch to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveVideoTarget {
+    pub bit_rate: u32,
+    pub max_fps: u32,
+}
+
+#[derive(Default)]
+pub struct AdaptiveVideoController {
+    health: Option<LinkHealth>,
+    streak: u32,
+    cooldown: u32,
+}
+
+impl AdaptiveVideoController {
+    /// Called once per second, `max_fps` of 0 means the stream is not limited
+    pub fn evaluate(
+        &mut self,
+        stats: &VideoStats,
+        bit_rate: u32,
+        max_fps: u32,
+        config: &AdaptiveVideoConfig,
+    ) -> (AdaptiveVideoStatus, Option<AdaptiveVideoTarget>) {
+        let health = LinkHealth::from_stats(stats);
+        if self.health != Some(health) {
+            self.streak = 0;
+        }
+        self.health = Some(health);
+        self.streak += 1;
+
+        // an unlimited stream counts as running at the upper bound
+        let running = AdaptiveVideoTarget {
+            bit_rate,
+            max_fps: if max_fps == 0 {
+                config.max_fps
+            } else {
+                max_fps
+            },
+        };
+        let current = AdaptiveVideoTarget {
+            bit_rate: bit_rate.clamp(config.min_bit_rate, config.max_bit_rate),
+            max_fps: running.max_fps.clamp(config.min_fps, config.max_fps),
+        };
+        let mut target = None;
+        let mut reason = format!(
+            "jitter {:.1} ms, backlog {:.0} ms",
+            stats.jitter_ms, stats.backlog_ms
+        );
+
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+        } else {
+            match health {
+                LinkHealth::Degraded if self.streak >= DEGRADED_TICKS => {
+                    target = Some(step_down(current, config));
+                    reason = format!("Link degraded, {}", reason);
+                }
+                LinkHealth::Healthy if self.streak >= HEALTHY_TICKS => {
+                    target = Some(step_up(current, config));
+                    reason = format!("Link healthy, {}", reason);
+                }
+                _ => {}
+            }
+        }
+        // settings outside the bounds are corrected even when the link is fine
+        let target = target
+            .or_else(|| (current != running).then_some(current))
+            .filter(|target| *target != running);
+        if target.is_some() {
+            self.streak = 0;
+            self.cooldown = COOLDOWN_TICKS;
+        }
+
+        let applied = target.unwrap_or(current);
+        (
+            AdaptiveVideoStatus {
+                health,
+                bit_rate: applied.bit_rate,
+                max_fps: applied.max_fps,
+                adjusted: target.is_some(),
+                reason,
+            },
+            target,
+        )
+    }
+}
+
+/// Bit rate goes first, fps only drops once the bit rate is at its floor
+fn step_down(current: AdaptiveVideoTarget, config: &AdaptiveVideoConfig) -> AdaptiveVideoTarget {
+    if current.bit_rate > config.min_bit_rate {
+        AdaptiveVideoTarget {
+            bit_rate: ((current.bit_rate as f32 * STEP_DOWN_FACTOR) as u32)
+                .max(config.min_bit_rate),
+            ..current
+        }
+    } else {
+        AdaptiveVideoTarget {
+            max_fps: ((current.max_fps as f32 * STEP_DOWN_FACTOR) as u32).max(config.min_fps),
+            ..current
+        }
+    }
+}
+
+/// Fps recovers first, then the bit rate
+fn step_up(current: AdaptiveVideoTarget, config: &AdaptiveVideoConfig) -> AdaptiveVideoTarget {
+    if current.max_fps < config.max_fps {
+        AdaptiveVideoTarget {
+            max_fps: ((current.max_fps as f32 * STEP_UP_FACTOR).ceil() as u32).min(config.max_fps),
+            ..current
+        }
+    } else {
+        AdaptiveVideoTarget {
+            bit_rate: ((current.bit_rate as f32 * STEP_UP_FACTOR) as u32).min(config.max_bit_rate),
+            ..current
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrcpy::media::VideoCodec;
+
+    fn stats(jitter_ms: f32, backlog_ms: f32) -> VideoStats {
+        VideoStats {
+            codec: VideoCodec::H264,
+            width: 1920,
+            height: 1080,
+            received_fps: 60.0,
+            decoded_fps: 60.0,
+            rendered_fps: 60.0,
+            dropped_frames: 0,
+            bitrate_kbps: 8000.0,
+            decode_ms: 2.0,
+            decode_max_ms: 4.0,
+            present_ms: 1.0,
+            jitter_ms,
+            backlog_ms,
+        }
+    }
+
+    fn config() -> AdaptiveVideoConfig {
+        AdaptiveVideoConfig {
+            enabled: true,
+            min_bit_rate: 2_000_000,
+            max_bit_rate: 10_000_000,
+            min_fps: 30,
+            max_fps: 60,
+        }
+    }
+
+    #[test]
+    fn degraded_link_lowers_bit_rate_then_fps() {
+        let config = config();
+        let mut controller = AdaptiveVideoController::default();
+        let bad = stats(40.0, 300.0);
+
+        let (_, target) = controller.evaluate(&bad, 8_000_000, 60, &config);
+        assert_eq!(target, None);
+        let (status, target) = controller.evaluate(&bad, 8_000_000, 60, &config);
+        assert_eq!(status.health, LinkHealth::Degraded);
+        assert_eq!(
+            target,
+            Some(AdaptiveVideoTarget {
+                bit_rate: 5_600_000,
+                max_fps: 60
+            })
+        );
+
+        let mut controller = AdaptiveVideoController::default();
+        controller.evaluate(&bad, 2_000_000, 60, &config);
+        let (_, target) = controller.evaluate(&bad, 2_000_000, 60, &config);
+        assert_eq!(
+            target,
+            Some(AdaptiveVideoTarget {
+                bit_rate: 2_000_000,
+                max_fps: 42
+            })
+        );
+    }
+
+    #[test]
+    fn healthy_link_recovers_after_cooldown() {
+        let config = config();
+        let mut controller = AdaptiveVideoController::default();
+        let good = stats(1.0, 5.0);
+
+        for _ in 0..HEALTHY_TICKS - 1 {
+            let (_, target) = controller.evaluate(&good, 4_000_000, 42, &config);
+            assert_eq!(target, None);
+        }
+        let (_, target) = controller.evaluate(&good, 4_000_000, 42, &config);
+        assert_eq!(
+            target,
+            Some(AdaptiveVideoTarget {
+                bit_rate: 4_000_000,
+                max_fps: 51
+            })
+        );
+        // nothing changes while cooling down
+        let (_, target) = controller.evaluate(&good, 4_000_000, 51, &config);
+        assert_eq!(target, None);
+    }
+
+    #[test]
+    fn unlimited_fps_is_left_alone_on_a_fair_link() {
+        let config = config();
+        let mut controller = AdaptiveVideoController::default();
+        for _ in 0..HEALTHY_TICKS * 2 {
+            let (status, target) = controller.evaluate(&stats(10.0, 60.0), 8_000_000, 0, &config);
+            assert_eq!(target, None);
+            assert_eq!(status.max_fps, 60);
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_settings_are_clamped() {
+        let config = config();
+        let mut controller = AdaptiveVideoController::default();
+        let (_, target) = controller.evaluate(&stats(10.0, 60.0), 20_000_000, 0, &config);
+        assert_eq!(
+            target,
+            Some(AdaptiveVideoTarget {
+                bit_rate: 10_000_000,
+                max_fps: 60
+            })
+        );
+    }
+}
@@ -1,19 +1,22 @@
 use adb_client::{ADBDeviceExt, server::ADBServer, server_device::ADBServerDevice};
 use rust_i18n::t;
 use serde::Serialize;
-use tokio::{
-    sync::mpsc::{self, UnboundedSender},
-    task::JoinHandle,
-};
+use tokio::{sync::mpsc, task::JoinHandle};
 
-use std::io::{Result as IoResult, Write};
+use std::io::{Error as IoError, ErrorKind, Read, Result as IoResult, Write};
 use std::{
     fs::File,
     io::Cursor,
     net::{Ipv4Addr, SocketAddrV4},
     path::Path,
+    process::Command,
+    sync::mpsc as std_mpsc,
 };
 
+/// Lines buffered between a streaming shell command and its reader, the command
+/// stalls once they are full
+const SHELL_STREAM_CAPACITY: usize = 1024;
+
 #[derive(Clone, Debug, Serialize)]
 pub struct Device {
     pub id: String,
@@ -26,19 +29,23 @@ impl Device {
     }
 
     pub fn push(id: &str, src: &str, des: &str) -> Result<(), String> {
-        let mut device = Device::new_server_device(id);
         let mut input = File::open(Path::new(src))
             .map_err(|e| format!("{} '{}': {}", t!("adb.failedToOpenFile"), src, e))?;
-        device.push(&mut input, des).map_err(|e| {
+        Device::push_from(id, &mut input, src, des)
+    }
+
+    /// Pushes whatever `input` yields, `name` only shows up in errors
+    pub fn push_from(id: &str, input: &mut dyn Read, name: &str, des: &str) -> Result<(), String> {
+        let mut device = Device::new_server_device(id);
+        device.push(input, des).map_err(|e| {
             format!(
                 "{} '{}' to '{}': {}",
                 t!("adb.failedToPushFile"),
-                src,
+                name,
                 des,
                 e
             )
-        })?;
-        Ok(())
+        })
     }
 
     pub fn pull(id: &str, src: String, output: &mut dyn Write) -> Result<(), String> {
@@ -80,6 +87,27 @@ impl Device {
     }
 
     pub fn shell_process<S>(id: &str, shell_args: S) -> JoinHandle<Result<(), String>>
+    where
+        S: IntoIterator,
+        S::Item: Into<String>,
+    {
+        let (mut rx, h) = Device::shell_stream(id, shell_args);
+
+        tokio::spawn(async move {
+            while let Some(line) = rx.recv().await {
+                log::info!("[Adb] {}", line);
+            }
+        });
+
+        h
+    }
+
+    /// Runs a shell command and streams its output line by line.
+    /// The command is stopped once the receiver is dropped.
+    pub fn shell_stream<S>(
+        id: &str,
+        shell_args: S,
+    ) -> (mpsc::Receiver<String>, JoinHandle<Result<(), String>>)
     where
         S: IntoIterator,
         S::Item: Into<String>,
@@ -88,12 +116,42 @@ impl Device {
         let shell_args: Vec<String> = shell_args.into_iter().map(|s| s.into()).collect();
         let command = shell_args.join(" ");
 
-        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (tx, rx) = mpsc::channel(SHELL_STREAM_CAPACITY);
         let h: JoinHandle<Result<(), String>> = tokio::task::spawn_blocking(move || {
-            let mut writer = ChannelWriter { sender: tx };
+            let mut writer = ChannelWriter::new(tx);
+            let res = device.shell_command(&command, Some(&mut writer), None);
+            if writer.is_closed() {
+                // stopped by the receiver, not a failure of the command
+                return Ok(());
+            }
+            writer.flush_pending();
+            res.map(|_| ()).map_err(|e| {
+                let msg = format!("{}: {}", t!("adb.adbShellCommandFailed"), e);
+                log::error!("[Adb] {}", msg);
+                msg
+            })
+        });
+
+        (rx, h)
+    }
+
+    /// Opens an interactive shell. Bytes sent to the returned sender are written to its
+    /// stdin, closing the sender ends the session. Output is passed on as it arrives.
+    pub fn interactive_shell(
+        id: &str,
+    ) -> (
+        std_mpsc::Sender<Vec<u8>>,
+        mpsc::Receiver<String>,
+        JoinHandle<Result<(), String>>,
+    ) {
+        let mut device = Device::new_server_device(id);
+        let (input_tx, input_rx) = std_mpsc::channel::<Vec<u8>>();
+        let (output_tx, output_rx) = mpsc::channel(SHELL_STREAM_CAPACITY);
+
+        let h: JoinHandle<Result<(), String>> = tokio::task::spawn_blocking(move || {
+            let mut reader = ChannelReader::new(input_rx);
             device
-                .shell_command(&command, Some(&mut writer), None)
-                .map(|_| ())
+                .shell(&mut reader, Box::new(ChunkWriter { sender: output_tx }))
                 .map_err(|e| {
                     let msg = format!("{}: {}", t!("adb.adbShellCommandFailed"), e);
                     log::error!("[Adb] {}", msg);
@@ -101,13 +159,7 @@ impl Device {
                 })
         });
 
-        tokio::spawn(async move {
-            while let Some(line) = rx.recv().await {
-                log::info!("[Adb] {}", line);
-            }
-        });
-
-        h
+        (input_tx, output_rx, h)
     }
 
     pub fn shell<S>(id: &str, shell_args: S, output: &mut dyn Write) -> Result<(), String>
@@ -124,6 +176,35 @@ impl Device {
             .map_err(|e| e.to_string())
     }
 
+    /// Runs a shell command and returns its stdout, a non-zero exit code fails with its stderr
+    pub fn shell_checked<S>(id: &str, shell_args: S) -> Result<String, String>
+    where
+        S: IntoIterator,
+        S::Item: Into<String>,
+    {
+        let mut device = Device::new_server_device(id);
+        let shell_args: Vec<String> = shell_args.into_iter().map(|s| s.into()).collect();
+        let command = shell_args.join(" ");
+        let mut stdout = Vec::<u8>::new();
+        let mut stderr = Vec::<u8>::new();
+        let code = device
+            .shell_command(&command, Some(&mut stdout), Some(&mut stderr))
+            .map_err(|e| format!("{}: {}", t!("adb.adbShellCommandFailed"), e))?;
+        let stdout = String::from_utf8_lossy(&stdout).to_string();
+        match code {
+            Some(code) if code != 0 => {
+                let stderr = String::from_utf8_lossy(&stderr);
+                let output = if stderr.trim().is_empty() {
+                    stdout.trim()
+                } else {
+                    stderr.trim()
+                };
+                Err(format!("'{}' exited with {}: {}", command, code, output))
+            }
+            _ => Ok(stdout),
+        }
+    }
+
     pub fn shell_logged<S>(id: &str, shell_args: S) -> Result<(), String>
     where
         S: IntoIterator,
@@ -139,6 +220,39 @@ impl Device {
         Ok(())
     }
 
+    /// PNG screenshot of the first display through screencap
+    pub fn screenshot(id: &str) -> Result<Vec<u8>, String> {
+        let src = "/data/local/tmp/_screenshot_scrcpy_mask.png";
+
+        let mut display_id_info = Vec::new();
+        Device::shell(
+            id,
+            ["dumpsys", "SurfaceFlinger", "--display-id"],
+            &mut display_id_info,
+        )
+        .map_err(|e| format!("failed get display id: {}", e))?;
+        let text = String::from_utf8_lossy(&display_id_info);
+        let first_line = text
+            .lines()
+            .next()
+            .ok_or_else(|| "no display found".to_string())?;
+        let display_id = first_line
+            .split_whitespace()
+            .nth(1)
+            .ok_or_else(|| "invalid display line".to_string())?;
+
+        Device::shell_logged(id, ["screencap", "-p", "-d", display_id, src])
+            .map_err(|e| format!("{} {}: {}", t!("web.device.screenshotError"), id, e))?;
+
+        let mut image_bytes = Vec::<u8>::new();
+        Device::pull(id, src.to_string(), &mut image_bytes)
+            .map_err(|e| format!("{}: {}", t!("web.device.failedGetScreenshotFile"), e))?;
+
+        Device::shell_logged(id, ["rm", src])
+            .map_err(|e| format!("{} {}: {}", t!("web.device.failedRemoveScreenshot"), id, e))?;
+        Ok(image_bytes)
+    }
+
     pub fn screen_size(id: &str) -> Result<(u32, u32), String> {
         let mut device = Device::new_server_device(id);
 
@@ -207,6 +321,31 @@ impl Adb {
         }
     }
 
+    /// Removes a single reverse tunnel, connections made through it stay open.
+    /// adb_client can only remove all of a device's tunnels, so this runs the adb executable
+    pub fn reverse_remove(&self, id: &str, remote: &str) -> Result<(), String> {
+        let mut command = Command::new(&self.adb_path);
+        command.args(["-s", id, "reverse", "--remove", remote]);
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            // CREATE_NO_WINDOW, don't flash a console over the mask
+            command.creation_flags(0x0800_0000);
+        }
+        let output = command
+            .output()
+            .map_err(|e| format!("{}: {}", t!("adb.reverseRemoveFailed", remote => remote), e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{}: {}",
+                t!("adb.reverseRemoveFailed", remote => remote),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+
     pub fn devices(&mut self) -> Result<Vec<Device>, String> {
         let device_list = self.server.devices().map_err(|e| e.to_string())?;
         Ok(device_list
@@ -250,16 +389,46 @@ impl Adb {
     }
 }
 
+/// Splits output into lines, a line cut between two writes is kept until it's complete
 struct ChannelWriter {
-    pub sender: UnboundedSender<String>,
+    sender: mpsc::Sender<String>,
+    pending: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn new(sender: mpsc::Sender<String>) -> Self {
+        Self {
+            sender,
+            pending: Vec::new(),
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn send_line(&self, line: &[u8]) -> IoResult<()> {
+        let line = String::from_utf8_lossy(line);
+        // blocks while the receiver is behind, which stalls the command as well
+        self.sender
+            .blocking_send(line.trim_end_matches('\r').to_string())
+            .map_err(|_| IoError::new(ErrorKind::BrokenPipe, "output receiver closed"))
+    }
+
+    fn flush_pending(&mut self) {
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            let _ = self.send_line(&pending);
+        }
+    }
 }
 
 impl Write for ChannelWriter {
     fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
-        if let Ok(s) = std::str::from_utf8(buf) {
-            for line in s.lines() {
-                let _ = self.sender.send(line.to_string());
-            }
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line = self.pending.drain(..=end).collect::<Vec<_>>();
+            self.send_line(&line[..end])?;
         }
         Ok(buf.len())
     }
@@ -268,3 +437,72 @@ impl Write for ChannelWriter {
         Ok(())
     }
 }
+
+/// Passes output on as it arrives, an interactive prompt has no trailing newline
+struct ChunkWriter {
+    sender: mpsc::Sender<String>,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.sender
+            .blocking_send(String::from_utf8_lossy(buf).to_string())
+            .map_err(|_| IoError::new(ErrorKind::BrokenPipe, "output receiver closed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+/// Stdin of an interactive shell, reads end once the sender is dropped
+struct ChannelReader {
+    receiver: std_mpsc::Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelReader {
+    fn new(receiver: std_mpsc::Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        if self.buffer.is_empty() {
+            match self.receiver.recv() {
+                Ok(data) => self.buffer = data,
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.buffer.len());
+        buf[..n].copy_from_slice(&self.buffer[..n]);
+        self.buffer.drain(..n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_writer_joins_lines_split_across_writes() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let mut writer = ChannelWriter::new(tx);
+        writer.write_all(b"first li").unwrap();
+        writer.write_all(b"ne\r\nsecond\nthi").unwrap();
+        writer.flush_pending();
+        drop(writer);
+
+        let mut lines = Vec::new();
+        while let Ok(line) = rx.try_recv() {
+            lines.push(line);
+        }
+        assert_eq!(lines, ["first line", "second", "thi"]);
+    }
+}
@@ -3,20 +3,28 @@ use std::{
     num::NonZero,
     sync::{
         Arc, Mutex,
-        atomic::{AtomicBool, AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, AtomicUsize, Ordering},
     },
     time::Duration,
 };
 
-use rodio::{DeviceSinkBuilder, MixerDeviceSink, Source};
+use once_cell::sync::Lazy;
+use rodio::{
+    DeviceSinkBuilder, MixerDeviceSink, Source,
+    cpal::{
+        Device,
+        traits::{DeviceTrait, HostTrait},
+    },
+};
+use serde::Serialize;
+use tokio::sync::watch;
 
-use super::media::AudioCodec;
+use super::{audio_tap::AudioTap, media::AudioCodec};
+use crate::config::LocalConfig;
 
 pub const AUDIO_SAMPLE_RATE: u32 = 48_000;
 pub const AUDIO_CHANNELS: u16 = 2;
 
-const DEFAULT_TARGET_BUFFER_MS: usize = 50;
-const FLAC_TARGET_BUFFER_MS: usize = 120;
 const BUFFER_MARGIN_MS: usize = 60;
 const LOCAL_BUFFER_MS: usize = 10;
 const INITIAL_BUFFER_MARGIN_MS: usize = 10;
@@ -39,11 +47,7 @@ struct AudioBufferConfig {
 }
 
 impl AudioBufferConfig {
-    fn for_codec(codec: AudioCodec) -> Self {
-        let target_ms = match codec {
-            AudioCodec::Flac => FLAC_TARGET_BUFFER_MS,
-            AudioCodec::Opus | AudioCodec::Aac | AudioCodec::Raw => DEFAULT_TARGET_BUFFER_MS,
-        };
+    fn for_target_ms(target_ms: usize) -> Self {
         let target_frames = frames_from_ms(target_ms);
         Self {
             target_frames,
@@ -75,10 +79,115 @@ impl AudioBufferConfig {
     }
 }
 
+// output settings shared by every player, changed live from the web config
+struct AudioOutputControl {
+    gain_bits: AtomicU32,
+    muted: AtomicBool,
+    device_tx: watch::Sender<String>,
+}
+
+impl AudioOutputControl {
+    fn effective_gain(&self) -> f32 {
+        if self.muted.load(Ordering::Relaxed) {
+            0.0
+        } else {
+            f32::from_bits(self.gain_bits.load(Ordering::Relaxed))
+        }
+    }
+}
+
+static AUDIO_OUTPUT: Lazy<AudioOutputControl> = Lazy::new(|| {
+    let config = LocalConfig::get();
+    AudioOutputControl {
+        gain_bits: AtomicU32::new(config.audio_gain.to_bits()),
+        muted: AtomicBool::new(config.audio_muted),
+        device_tx: watch::Sender::new(config.audio_output_device),
+    }
+});
+
+pub fn set_audio_output_gain(gain: f32) {
+    AUDIO_OUTPUT
+        .gain_bits
+        .store(gain.to_bits(), Ordering::Relaxed);
+}
+
+pub fn set_audio_output_muted(muted: bool) {
+    AUDIO_OUTPUT.muted.store(muted, Ordering::Relaxed);
+}
+
+/// Switch running players to another output device, an empty name means the system default
+pub fn set_audio_output_device(device: String) {
+    AUDIO_OUTPUT.device_tx.send_replace(device);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioOutputDevices {
+    pub default: Option<String>,
+    pub devices: Vec<String>,
+}
+
+pub fn audio_output_devices() -> Result<AudioOutputDevices, String> {
+    let host = rodio::cpal::default_host();
+    let default = host.default_output_device().and_then(|d| device_name(&d));
+    let devices = host
+        .output_devices()
+        .map_err(|e| format!("Failed to enumerate audio output devices: {e}"))?
+        .filter_map(|d| device_name(&d))
+        .collect();
+    Ok(AudioOutputDevices { default, devices })
+}
+
+// the name is what users pick in the settings, so it is also what we match on
+#[allow(deprecated)]
+fn device_name(device: &Device) -> Option<String> {
+    device.name().ok()
+}
+
+fn find_output_device(name: &str) -> Option<Device> {
+    if name.is_empty() {
+        return None;
+    }
+    let devices = rodio::cpal::default_host().output_devices().ok()?;
+    let device = devices
+        .into_iter()
+        .find(|d| device_name(d).as_deref() == Some(name));
+    if device.is_none() {
+        log::warn!("[Audio] Output device not found, using the default one: {name}");
+    }
+    device
+}
+
 fn frames_from_ms(ms: usize) -> usize {
     (AUDIO_SAMPLE_RATE as usize * ms) / 1000
 }
 
+fn ms_from_frames(frames: usize) -> f32 {
+    frames as f32 * 1000.0 / AUDIO_SAMPLE_RATE as f32
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioStats {
+    pub codec: AudioCodec,
+    pub target_ms: f32,
+    pub buffered_ms: f32,
+    /// Playback chunks that had to be padded with silence
+    pub underflow_count: u64,
+    pub underflow_ms: f32,
+    /// Samples dropped because the buffer was too full
+    pub dropped_samples: u64,
+    /// Resampler compensation relative to the stream, positive values stretch audio to refill the buffer
+    pub compensation_rate: f32,
+}
+
+// the stream being played right now, at most one device has audio at a time
+static ACTIVE_AUDIO: Lazy<Mutex<Option<(AudioCodec, AudioSampleQueue)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+pub fn current_audio_stats() -> Option<AudioStats> {
+    let active = ACTIVE_AUDIO.lock().unwrap();
+    active.as_ref().map(|(codec, queue)| queue.stats(*codec))
+}
+
 #[derive(Clone)]
 pub struct AudioSampleQueue {
     inner: Arc<AudioSampleQueueInner>,
@@ -93,6 +202,11 @@ struct AudioSampleQueueInner {
     received: AtomicBool,
     played: AtomicBool,
     underflow_frames: AtomicUsize,
+    // totals since the stream started, only used for stats
+    underflow_count: AtomicU64,
+    underflow_total_frames: AtomicU64,
+    dropped_samples: AtomicU64,
+    compensation_delta: AtomicI32,
 }
 
 impl AudioSampleQueue {
@@ -107,6 +221,10 @@ impl AudioSampleQueue {
                 received: AtomicBool::new(false),
                 played: AtomicBool::new(false),
                 underflow_frames: AtomicUsize::new(0),
+                underflow_count: AtomicU64::new(0),
+                underflow_total_frames: AtomicU64::new(0),
+                dropped_samples: AtomicU64::new(0),
+                compensation_delta: AtomicI32::new(0),
             }),
         }
     }
@@ -128,6 +246,7 @@ impl AudioSampleQueue {
             self.insert_discontinuity_silence(input_frames);
             regulator.reset(self.inner.config.target_frames);
             self.inner.underflow_frames.store(0, Ordering::Release);
+            self.inner.compensation_delta.store(0, Ordering::Relaxed);
             compensation = Some(AudioCompensation::disabled());
         }
 
@@ -141,6 +260,7 @@ impl AudioSampleQueue {
         if incoming.is_empty() {
             return AudioPushStats::default();
         }
+        AudioTap::feed(&incoming);
 
         let mut queue = self.inner.samples.lock().unwrap();
         let mut skipped_samples = 0;
@@ -172,6 +292,9 @@ impl AudioSampleQueue {
         self.inner
             .sample_count
             .store(buffered_samples, Ordering::Release);
+        self.inner
+            .dropped_samples
+            .fetch_add(skipped_samples as u64, Ordering::Relaxed);
 
         AudioPushStats {
             written_frames: samples_to_frames(incoming_len),
@@ -192,7 +315,33 @@ impl AudioSampleQueue {
 
         let underflow_frames = self.inner.underflow_frames.swap(0, Ordering::AcqRel);
         let mut regulator = self.inner.regulator.lock().unwrap();
-        regulator.finish_push(self.inner.config, input_frames, stats, underflow_frames)
+        let compensation =
+            regulator.finish_push(self.inner.config, input_frames, stats, underflow_frames);
+        if let Some(compensation) = compensation {
+            self.inner
+                .compensation_delta
+                .store(compensation.sample_delta, Ordering::Relaxed);
+        }
+        compensation
+    }
+
+    fn stats(&self, codec: AudioCodec) -> AudioStats {
+        AudioStats {
+            codec,
+            target_ms: ms_from_frames(self.inner.config.target_frames),
+            buffered_ms: ms_from_frames(samples_to_frames(self.sample_count())),
+            underflow_count: self.inner.underflow_count.load(Ordering::Relaxed),
+            underflow_ms: ms_from_frames(
+                self.inner.underflow_total_frames.load(Ordering::Relaxed) as usize
+            ),
+            dropped_samples: self.inner.dropped_samples.load(Ordering::Relaxed),
+            compensation_rate: self.inner.compensation_delta.load(Ordering::Relaxed) as f32
+                / COMPENSATION_DISTANCE_FRAMES as f32,
+        }
+    }
+
+    fn is_same(&self, other: &AudioSampleQueue) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
     }
 
     fn pop_samples(&self, samples: &mut VecDeque<f32>, max_samples: usize) {
@@ -217,9 +366,14 @@ impl AudioSampleQueue {
 
     fn record_underflow_samples(&self, silence_samples: usize) {
         if self.inner.received.load(Ordering::Acquire) {
+            let frames = samples_to_frames(silence_samples);
             self.inner
                 .underflow_frames
-                .fetch_add(samples_to_frames(silence_samples), Ordering::AcqRel);
+                .fetch_add(frames, Ordering::AcqRel);
+            self.inner.underflow_count.fetch_add(1, Ordering::Relaxed);
+            self.inner
+                .underflow_total_frames
+                .fetch_add(frames as u64, Ordering::Relaxed);
         }
     }
 
@@ -439,37 +593,88 @@ impl AudioRingBuffer {
 
 pub struct ScrcpyAudioPlayer {
     queue: AudioSampleQueue,
-    _sink: MixerDeviceSink,
+    config: AudioBufferConfig,
+    sink: MixerDeviceSink,
+    device_rx: watch::Receiver<String>,
 }
 
 impl ScrcpyAudioPlayer {
     pub fn new(codec: AudioCodec) -> Result<Self, String> {
-        let config = AudioBufferConfig::for_codec(codec);
+        let target_ms = LocalConfig::get().audio_buffer_ms.for_codec(codec);
+        let config = AudioBufferConfig::for_target_ms(target_ms as usize);
         let queue = AudioSampleQueue::new(config);
-        let sink = open_audio_sink().map_err(|e| format!("Failed to open audio output: {e}"))?;
-        sink.mixer().add(ScrcpyAudioSource {
-            queue: queue.clone(),
+        let mut device_rx = AUDIO_OUTPUT.device_tx.subscribe();
+        let device = device_rx.borrow_and_update().clone();
+        let sink =
+            open_audio_sink(&device).map_err(|e| format!("Failed to open audio output: {e}"))?;
+        sink.mixer()
+            .add(ScrcpyAudioSource::new(queue.clone(), config, false));
+        *ACTIVE_AUDIO.lock().unwrap() = Some((codec, queue.clone()));
+
+        Ok(Self {
+            queue,
             config,
-            buffered_samples: VecDeque::new(),
-            started: false,
-        });
-
-        Ok(Self { queue, _sink: sink })
+            sink,
+            device_rx,
+        })
     }
 
     pub fn queue(&self) -> AudioSampleQueue {
         self.queue.clone()
     }
+
+    /// Reopen the output whenever the configured device changes, never returns
+    pub async fn follow_output_device(&mut self) {
+        while self.device_rx.changed().await.is_ok() {
+            let device = self.device_rx.borrow_and_update().clone();
+            match open_audio_sink(&device) {
+                Ok(sink) => {
+                    // drop the old sink first so both never pull from the queue together
+                    self.sink = sink;
+                    self.sink.mixer().add(ScrcpyAudioSource::new(
+                        self.queue.clone(),
+                        self.config,
+                        true,
+                    ));
+                    log::info!(
+                        "[Audio] Output device switched: {}",
+                        display_device(&device)
+                    );
+                }
+                Err(e) => log::error!(
+                    "[Audio] Failed to switch output device to {}: {}",
+                    display_device(&device),
+                    e
+                ),
+            }
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+fn display_device(device: &str) -> &str {
+    if device.is_empty() { "default" } else { device }
 }
 
 impl Drop for ScrcpyAudioPlayer {
     fn drop(&mut self) {
         self.queue.close();
+        let mut active = ACTIVE_AUDIO.lock().unwrap();
+        if active
+            .as_ref()
+            .is_some_and(|(_, queue)| queue.is_same(&self.queue))
+        {
+            *active = None;
+        }
     }
 }
 
-fn open_audio_sink() -> Result<MixerDeviceSink, rodio::stream::DeviceSinkError> {
-    DeviceSinkBuilder::from_default_device()?
+fn open_audio_sink(device: &str) -> Result<MixerDeviceSink, rodio::stream::DeviceSinkError> {
+    let builder = match find_output_device(device) {
+        Some(device) => DeviceSinkBuilder::from_device(device)?,
+        None => DeviceSinkBuilder::from_default_device()?,
+    };
+    builder
         .with_buffer_size(rodio::cpal::BufferSize::Fixed(AUDIO_OUTPUT_BUFFER_FRAMES))
         .open_sink_or_fallback()
 }
@@ -479,12 +684,21 @@ struct ScrcpyAudioSource {
     config: AudioBufferConfig,
     buffered_samples: VecDeque<f32>,
     started: bool,
+    gain: f32,
 }
 
-impl Iterator for ScrcpyAudioSource {
-    type Item = f32;
+impl ScrcpyAudioSource {
+    fn new(queue: AudioSampleQueue, config: AudioBufferConfig, started: bool) -> Self {
+        Self {
+            queue,
+            config,
+            buffered_samples: VecDeque::new(),
+            started,
+            gain: AUDIO_OUTPUT.effective_gain(),
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_sample(&mut self) -> Option<f32> {
         if let Some(sample) = self.buffered_samples.pop_front() {
             return Some(sample);
         }
@@ -516,6 +730,19 @@ impl Iterator for ScrcpyAudioSource {
     }
 }
 
+impl Iterator for ScrcpyAudioSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffered_samples.is_empty() {
+            // refreshed once per local chunk, so volume changes apply within LOCAL_BUFFER_MS
+            self.gain = AUDIO_OUTPUT.effective_gain();
+        }
+        self.next_sample()
+            .map(|sample| (sample * self.gain).clamp(-1.0, 1.0))
+    }
+}
+
 impl Source for ScrcpyAudioSource {
     fn current_span_len(&self) -> Option<usize> {
         if self.queue.is_closed()
@@ -0,0 +1,308 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::audio::{AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+// size field used by streamed wav headers, the length is unknown
+const WAV_STREAM_SIZE: u32 = u32::MAX;
+// about 2.5 seconds of 10ms packets, slower listeners skip ahead
+const STREAM_CHANNEL_CAPACITY: usize = 256;
+
+/// Sample encoding of tapped audio, always interleaved 48 kHz stereo little endian
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PcmFormat {
+    #[default]
+    F32,
+    S16,
+}
+
+impl PcmFormat {
+    fn bits_per_sample(self) -> u16 {
+        match self {
+            PcmFormat::F32 => 32,
+            PcmFormat::S16 => 16,
+        }
+    }
+
+    fn format_tag(self) -> u16 {
+        match self {
+            PcmFormat::F32 => WAVE_FORMAT_IEEE_FLOAT,
+            PcmFormat::S16 => WAVE_FORMAT_PCM,
+        }
+    }
+
+    fn bytes_per_frame(self) -> u32 {
+        AUDIO_CHANNELS as u32 * self.bits_per_sample() as u32 / 8
+    }
+
+    pub fn encode(self, samples: &[f32]) -> Vec<u8> {
+        match self {
+            PcmFormat::F32 => samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+            PcmFormat::S16 => samples
+                .iter()
+                .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+                .collect(),
+        }
+    }
+}
+
+/// Canonical wav header, `data_len` is the byte length of the data chunk
+pub fn wav_header(format: PcmFormat, data_len: u32) -> Vec<u8> {
+    let block_align = format.bytes_per_frame();
+    let mut header = Vec::with_capacity(58);
+    // float wav needs an extended fmt chunk and a fact chunk
+    let float = format == PcmFormat::F32;
+    let fmt_len: u32 = if float { 18 } else { 16 };
+    let extra_len: u32 = if float { 12 } else { 0 };
+    let riff_len = if data_len == WAV_STREAM_SIZE {
+        WAV_STREAM_SIZE
+    } else {
+        4 + (8 + fmt_len) + extra_len + 8 + data_len
+    };
+
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&riff_len.to_le_bytes());
+    header.extend_from_slice(b"WAVE");
+    header.extend_from_slice(b"fmt ");
+    header.extend_from_slice(&fmt_len.to_le_bytes());
+    header.extend_from_slice(&format.format_tag().to_le_bytes());
+    header.extend_from_slice(&AUDIO_CHANNELS.to_le_bytes());
+    header.extend_from_slice(&AUDIO_SAMPLE_RATE.to_le_bytes());
+    header.extend_from_slice(&(AUDIO_SAMPLE_RATE * block_align).to_le_bytes());
+    header.extend_from_slice(&(block_align as u16).to_le_bytes());
+    header.extend_from_slice(&format.bits_per_sample().to_le_bytes());
+    if float {
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(b"fact");
+        header.extend_from_slice(&4u32.to_le_bytes());
+        let frames = if data_len == WAV_STREAM_SIZE {
+            WAV_STREAM_SIZE
+        } else {
+            data_len / block_align
+        };
+        header.extend_from_slice(&frames.to_le_bytes());
+    }
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header
+}
+
+/// Header for a wav stream of unknown length
+pub fn wav_stream_header(format: PcmFormat) -> Vec<u8> {
+    wav_header(format, WAV_STREAM_SIZE)
+}
+
+struct WavCapture {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    data_len: u32,
+    started_at: Instant,
+}
+
+impl WavCapture {
+    fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(&wav_header(PcmFormat::F32, 0))
+            .map_err(|e| format!("Failed to write wav header: {}", e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer,
+            data_len: 0,
+            started_at: Instant::now(),
+        })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let bytes = PcmFormat::F32.encode(samples);
+        // wav sizes are 32 bit, stop growing instead of writing a corrupted file
+        let Some(data_len) = self.data_len.checked_add(bytes.len() as u32) else {
+            return Err("Wav file reached its 4 GiB size limit".to_string());
+        };
+        self.writer
+            .write_all(&bytes)
+            .map_err(|e| format!("Failed to write audio samples: {}", e))?;
+        self.data_len = data_len;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<AudioCaptureSummary, String> {
+        let header = wav_header(PcmFormat::F32, self.data_len);
+        self.writer
+            .flush()
+            .and_then(|_| self.writer.seek(SeekFrom::Start(0)))
+            .and_then(|_| self.writer.write_all(&header))
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to finalize {}: {}", self.path.display(), e))?;
+        Ok(self.summary())
+    }
+
+    fn summary(&self) -> AudioCaptureSummary {
+        AudioCaptureSummary {
+            path: self.path.clone(),
+            duration_ms: self.data_len as u64 * 1000
+                / (AUDIO_SAMPLE_RATE as u64 * PcmFormat::F32.bytes_per_frame() as u64),
+            elapsed_ms: self.started_at.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioCaptureSummary {
+    pub path: PathBuf,
+    /// Length of the captured audio
+    pub duration_ms: u64,
+    /// Wall time since the capture started, longer than duration when the stream paused
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioTapStatus {
+    pub capture: Option<AudioCaptureSummary>,
+    pub stream_listeners: usize,
+}
+
+struct AudioTapState {
+    // fast path for the audio thread, true when someone consumes the samples
+    enabled: AtomicBool,
+    capture: Mutex<Option<WavCapture>>,
+    stream_tx: broadcast::Sender<Arc<[f32]>>,
+}
+
+impl AudioTapState {
+    fn update_enabled(&self, capturing: bool) {
+        let enabled = capturing || self.stream_tx.receiver_count() > 0;
+        self.enabled.store(enabled, Ordering::Release);
+    }
+}
+
+static AUDIO_TAP: Lazy<AudioTapState> = Lazy::new(|| AudioTapState {
+    enabled: AtomicBool::new(false),
+    capture: Mutex::new(None),
+    stream_tx: broadcast::channel(STREAM_CHANNEL_CAPACITY).0,
+});
+
+/// Taps the decoded 48 kHz stereo samples of the playing device, for every audio codec
+pub struct AudioTap;
+
+impl AudioTap {
+    /// Called by the sample queue with every decoded packet before it is buffered for playback
+    pub(crate) fn feed(samples: &[f32]) {
+        let tap = &*AUDIO_TAP;
+        if !tap.enabled.load(Ordering::Acquire) || samples.is_empty() {
+            return;
+        }
+
+        if tap.stream_tx.receiver_count() > 0 {
+            let _ = tap.stream_tx.send(Arc::from(samples));
+        }
+
+        let mut capture = tap.capture.lock().unwrap();
+        if let Some(wav) = capture.as_mut()
+            && let Err(e) = wav.write(samples)
+        {
+            log::error!("[AudioTap] {}, capture stopped", e);
+            if let Some(wav) = capture.take() {
+                let _ = wav.finish();
+            }
+        }
+        tap.update_enabled(capture.is_some());
+    }
+
+    pub fn start_capture(path: &Path) -> Result<(), String> {
+        let tap = &*AUDIO_TAP;
+        let mut capture = tap.capture.lock().unwrap();
+        if capture.is_some() {
+            return Err("Audio capture is already running".to_string());
+        }
+        *capture = Some(WavCapture::create(path)?);
+        tap.update_enabled(true);
+        log::info!("[AudioTap] Capture started: {}", path.display());
+        Ok(())
+    }
+
+    pub fn stop_capture() -> Result<AudioCaptureSummary, String> {
+        let tap = &*AUDIO_TAP;
+        let mut capture = tap.capture.lock().unwrap();
+        let wav = capture
+            .take()
+            .ok_or_else(|| "Audio capture is not running".to_string())?;
+        tap.update_enabled(false);
+        let summary = wav.finish()?;
+        log::info!("[AudioTap] Capture saved: {}", summary.path.display());
+        Ok(summary)
+    }
+
+    /// Subscribe to the raw f32 sample stream, drop the receiver to unsubscribe
+    pub fn subscribe() -> broadcast::Receiver<Arc<[f32]>> {
+        let tap = &*AUDIO_TAP;
+        let rx = tap.stream_tx.subscribe();
+        tap.enabled.store(true, Ordering::Release);
+        rx
+    }
+
+    pub fn status() -> AudioTapStatus {
+        let tap = &*AUDIO_TAP;
+        let capture = tap.capture.lock().unwrap();
+        AudioTapStatus {
+            capture: capture.as_ref().map(WavCapture::summary),
+            stream_listeners: tap.stream_tx.receiver_count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_wav_header_layout() {
+        let header = wav_header(PcmFormat::F32, 800);
+        assert_eq!(header.len(), 58);
+        assert_eq!(&header[0..4], b"RIFF");
+        assert_eq!(
+            u32::from_le_bytes(header[4..8].try_into().unwrap()),
+            50 + 800
+        );
+        assert_eq!(u16::from_le_bytes(header[20..22].try_into().unwrap()), 3);
+        assert_eq!(&header[38..42], b"fact");
+        // 800 bytes of stereo f32 are 100 frames
+        assert_eq!(u32::from_le_bytes(header[46..50].try_into().unwrap()), 100);
+        assert_eq!(&header[50..54], b"data");
+    }
+
+    #[test]
+    fn pcm_wav_header_layout() {
+        let header = wav_header(PcmFormat::S16, 400);
+        assert_eq!(header.len(), 44);
+        assert_eq!(
+            u32::from_le_bytes(header[4..8].try_into().unwrap()),
+            36 + 400
+        );
+        assert_eq!(u16::from_le_bytes(header[32..34].try_into().unwrap()), 4);
+        assert_eq!(&header[36..40], b"data");
+    }
+
+    #[test]
+    fn s16_encoding_clamps() {
+        let bytes = PcmFormat::S16.encode(&[2.0, -2.0, 0.0]);
+        assert_eq!(bytes, [0xff, 0x7f, 0x01, 0x80, 0x00, 0x00]);
+    }
+}
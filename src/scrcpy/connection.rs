@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use ffmpeg_next::{
     ChannelLayout, Error as FfmpegError, error, ffi, frame,
@@ -29,7 +29,7 @@ use crate::{
     scrcpy::{
         audio::{
             AUDIO_CHANNELS, AUDIO_SAMPLE_RATE, AudioCompensation, AudioSampleQueue,
-            ScrcpyAudioPlayer,
+            ScrcpyAudioPlayer, current_audio_stats,
         },
         control_msg::{ScrcpyControlMsg, ScrcpyDeviceMsg},
         media::{
@@ -38,10 +38,14 @@ use crate::{
             VideoDecoder, VideoMsg, YuvColorInfo, YuvMatrix, YuvPlaneLayout, YuvRange,
             read_media_packet,
         },
+        video_stats::VideoStatsRecorder,
     },
     utils::{LatestVideoFrame, share::ControlledDevice},
+    web::ws::WebSocketNotification,
 };
 
+const AUDIO_STATS_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct ScrcpyConnection {
     pub socket: TcpStream,
 }
@@ -318,6 +322,7 @@ impl ScrcpyConnection {
                 return;
             }
         };
+        VideoStatsRecorder::start(codec_id, width, height);
 
         // read video packets
         loop {
@@ -332,6 +337,7 @@ impl ScrcpyConnection {
                         );
                         continue;
                     }
+                    VideoStatsRecorder::record_packet(media_packet.data_len(), media_packet.pts());
 
                     let packet = if video_decoder.must_merge_config {
                         video_decoder.packet_merger.merge(media_packet)
@@ -345,6 +351,7 @@ impl ScrcpyConnection {
                         continue;
                     };
 
+                    let decode_start = Instant::now();
                     match video_decoder.decoder.send_packet(&packet) {
                         Ok(()) => {}
                         Err(e) if is_ffmpeg_again(e) => {
@@ -365,6 +372,7 @@ impl ScrcpyConnection {
                     if !drain_video_decoder(&mut video_decoder, &v_tx) {
                         break;
                     }
+                    VideoStatsRecorder::record_decode(decode_start.elapsed());
                 }
                 Err(e) => {
                     log::error!("[Controller] {}", e);
@@ -402,11 +410,16 @@ impl ScrcpyConnection {
             }
         }
         v_tx.send(VideoMsg::Close);
+        VideoStatsRecorder::stop();
         log::info!("[Controller] {}", t!("scrcpy.videoConnectionClosed"));
         self.socket.shutdown().await.unwrap();
     }
 
-    async fn audio_handler(&mut self) -> Result<(), String> {
+    async fn audio_handler(
+        &mut self,
+        scid: &str,
+        ws_tx: &broadcast::Sender<WebSocketNotification>,
+    ) -> Result<(), String> {
         let mut buf = [0u8; 4];
         if let Err(e) = self.socket.read_exact(&mut buf).await {
             return Err(format!("Failed to read audio metadata: {e}"));
@@ -430,7 +443,7 @@ impl ScrcpyConnection {
         };
         log::info!("[Controller] Audio codec: {}", codec_id);
 
-        let player = match ScrcpyAudioPlayer::new(codec_id) {
+        let mut player = match ScrcpyAudioPlayer::new(codec_id) {
             Ok(player) => player,
             Err(e) => {
                 return Err(e);
@@ -438,11 +451,18 @@ impl ScrcpyConnection {
         };
         let audio_queue = player.queue();
 
-        match codec_id {
-            AudioCodec::Raw => self.raw_audio_handler(audio_queue).await?,
-            AudioCodec::Opus | AudioCodec::Aac | AudioCodec::Flac => {
-                self.encoded_audio_handler(codec_id, audio_queue).await?
+        let reader = async {
+            match codec_id {
+                AudioCodec::Raw => self.raw_audio_handler(audio_queue).await,
+                AudioCodec::Opus | AudioCodec::Aac | AudioCodec::Flac => {
+                    self.encoded_audio_handler(codec_id, audio_queue).await
+                }
             }
+        };
+        tokio::select! {
+            result = reader => result?,
+            _ = player.follow_output_device() => {}
+            _ = report_audio_stats(scid, ws_tx) => {}
         }
 
         Ok(())
@@ -525,7 +545,13 @@ impl ScrcpyConnection {
         }
     }
 
-    pub async fn handle_audio(mut self, token: CancellationToken, meta_flag: bool, scid: &str) {
+    pub async fn handle_audio(
+        mut self,
+        token: CancellationToken,
+        meta_flag: bool,
+        scid: &str,
+        ws_tx: broadcast::Sender<WebSocketNotification>,
+    ) {
         log::info!("[Controller] Handle audio connection");
         if meta_flag {
             if let Err(e) = self.read_device_metadata(scid.to_string()).await {
@@ -540,7 +566,7 @@ impl ScrcpyConnection {
             _ = token.cancelled()=>{
                 log::info!("[Controller] Audio connection reader cancelled");
             }
-            result = self.audio_handler()=>{
+            result = self.audio_handler(scid, &ws_tx)=>{
                 match result {
                     Ok(()) => log::error!("[Controller] Audio read shutdown unexpectedly"),
                     Err(e) => log::error!("[Controller] Audio connection failed: {}", e),
@@ -553,6 +579,21 @@ impl ScrcpyConnection {
     }
 }
 
+async fn report_audio_stats(scid: &str, ws_tx: &broadcast::Sender<WebSocketNotification>) {
+    let mut interval = tokio::time::interval(AUDIO_STATS_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Some(stats) = current_audio_stats() {
+            ws_tx
+                .send(WebSocketNotification::AudioStats {
+                    scid: scid.to_string(),
+                    stats,
+                })
+                .ok();
+        }
+    }
+}
+
 fn drain_video_decoder(video_decoder: &mut VideoDecoder, v_tx: &LatestVideoFrame) -> bool {
     loop {
         let mut decoded = frame::Video::empty();
@@ -568,7 +609,9 @@ fn drain_video_decoder(video_decoder: &mut VideoDecoder, v_tx: &LatestVideoFrame
 
                 if format_changed {
                     log_video_frame_metadata(video_decoder, &decoded);
+                    VideoStatsRecorder::set_size(video_decoder.width, video_decoder.height);
                 }
+                VideoStatsRecorder::record_decoded_frame();
 
                 let color = map_yuv_color_info(&decoded, format_changed);
                 let planes = YuvPlaneLayout::new(video_decoder.width, video_decoder.height);
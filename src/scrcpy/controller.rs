@@ -4,7 +4,7 @@ use bevy::log;
 use copypasta::{ClipboardContext, ClipboardProvider};
 use rust_i18n::t;
 use tokio::{
-    net::TcpListener,
+    net::{TcpListener, TcpStream},
     sync::{
         broadcast,
         mpsc::{self, UnboundedReceiver},
@@ -29,6 +29,11 @@ pub enum ControllerCommand {
     ConnectMainControl(String, bool),
     ConnectMainVideo(String, bool),
     ConnectMainAudio(String, bool),
+    /// Close the main video before a reconfigured stream replaces it, replies once done.
+    /// Only a video-only server's stream is released, closing its socket stops that server
+    ReleaseMainVideo(String, oneshot::Sender<()>),
+    /// Read the main video from a connected video-only server, (main scid, video scid, socket)
+    AttachMainVideo(String, String, TcpStream),
     ConnectSubControl(String),
     ShutdownMain(String),
     ShutdownSub(String),
@@ -219,17 +224,9 @@ impl Controller {
                         let v_tx_copy = v_tx.clone();
                         match listener.accept().await {
                             Ok((socket, _)) => {
-                                thread::spawn(move || {
-                                    tokio::runtime::Builder::new_current_thread()
-                                        .enable_all()
-                                        .build()
-                                        .unwrap()
-                                        .block_on(async move {
-                                            ScrcpyConnection::new(socket)
-                                                .handle_video(token, v_tx_copy, meta_flag, &scid)
-                                                .await;
-                                        });
-                                });
+                                Self::spawn_video_connection(
+                                    socket, token, v_tx_copy, meta_flag, scid,
+                                );
                             }
                             Err(e) => {
                                 log::error!(
@@ -262,6 +259,7 @@ impl Controller {
                         log::info!("[Controller] Creating main audio connection: {}", scid);
                         match listener.accept().await {
                             Ok((socket, _)) => {
+                                let ws_tx_copy = ws_tx.clone();
                                 thread::spawn(move || {
                                     tokio::runtime::Builder::new_current_thread()
                                         .enable_all()
@@ -269,7 +267,7 @@ impl Controller {
                                         .unwrap()
                                         .block_on(async move {
                                             ScrcpyConnection::new(socket)
-                                                .handle_audio(token, meta_flag, &scid)
+                                                .handle_audio(token, meta_flag, &scid, ws_tx_copy)
                                                 .await;
                                         });
                                 });
@@ -292,6 +290,35 @@ impl Controller {
                             }
                         }
                     }
+                    ControllerCommand::ReleaseMainVideo(scid, reply) => {
+                        if let Some(token) = signal_map.remove("main_video") {
+                            log::info!("[Controller] Releasing main video of {}", scid);
+                            token.cancel();
+                        }
+                        reply.send(()).ok();
+                    }
+                    ControllerCommand::AttachMainVideo(scid, video_scid, socket) => {
+                        if !ControlledDevice::is_scid_controlled(&scid).await {
+                            // dropping the socket stops the video-only server
+                            log::warn!("[Controller] {}: {}", t!("scrcpy.deviceNotRecorded"), scid);
+                            continue;
+                        }
+
+                        log::info!(
+                            "[Controller] Attaching video of {} to main device {}",
+                            video_scid,
+                            scid
+                        );
+                        let token = CancellationToken::new();
+                        signal_map.insert("main_video".to_string(), token.clone());
+                        Self::spawn_video_connection(
+                            socket,
+                            token,
+                            v_tx.clone(),
+                            false,
+                            video_scid,
+                        );
+                    }
                     ControllerCommand::ConnectSubControl(scid) => {
                         let socket_id = format!("sub_control_{}", scid);
 
@@ -392,4 +419,24 @@ impl Controller {
         }
         log::info!("[Controller] {}", t!("scrcpy.controllerStopped"));
     }
+
+    fn spawn_video_connection(
+        socket: TcpStream,
+        token: CancellationToken,
+        v_tx: LatestVideoFrame,
+        meta_flag: bool,
+        scid: String,
+    ) {
+        thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async move {
+                    ScrcpyConnection::new(socket)
+                        .handle_video(token, v_tx, meta_flag, &scid)
+                        .await;
+                });
+        });
+    }
 }
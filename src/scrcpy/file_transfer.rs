@@ -0,0 +1,498 @@
+use std::{
+    fs::File,
+    io::{Read, Result as IoResult, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use rust_i18n::t;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::{scrcpy::adb::Device, web::ws::WebSocketNotification};
+
+/// Where dropped files end up, the same folder scrcpy pushes to
+pub const PUSH_TARGET_DIR: &str = "/sdcard/Download";
+const INSTALL_TMP_DIR: &str = "/data/local/tmp";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    Push,
+    Pull,
+    Install,
+    Uninstall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStage {
+    Transferring,
+    Installing,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceFileKind {
+    File,
+    Dir,
+    Link,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceFileEntry {
+    pub name: String,
+    pub kind: DeviceFileKind,
+    pub size: u64,
+    pub permissions: String,
+    /// As printed by `ls`, e.g. `2024-05-01 10:00`
+    pub modified: String,
+    pub link_target: Option<String>,
+}
+
+/// Reports the progress of one transfer as `DeviceTransfer` notifications
+pub struct TransferProgress {
+    ws_tx: broadcast::Sender<WebSocketNotification>,
+    pub id: String,
+    device_id: String,
+    kind: TransferKind,
+    name: String,
+    stage: TransferStage,
+    pub total: u64,
+    transferred: u64,
+    last_sent: Option<Instant>,
+}
+
+impl TransferProgress {
+    pub fn new(
+        ws_tx: broadcast::Sender<WebSocketNotification>,
+        device_id: &str,
+        kind: TransferKind,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            ws_tx,
+            id: format!("{:08x}", rand::random::<u32>()),
+            device_id: device_id.to_string(),
+            kind,
+            name: name.into(),
+            stage: TransferStage::Transferring,
+            total: 0,
+            transferred: 0,
+            last_sent: None,
+        }
+    }
+
+    fn notify(&mut self, message: Option<String>) {
+        self.last_sent = Some(Instant::now());
+        let _ = self.ws_tx.send(WebSocketNotification::DeviceTransfer {
+            id: self.id.clone(),
+            device_id: self.device_id.clone(),
+            kind: self.kind,
+            name: self.name.clone(),
+            stage: self.stage,
+            transferred: self.transferred,
+            total: self.total,
+            message,
+        });
+    }
+
+    fn advance(&mut self, n: u64) {
+        self.transferred += n;
+        if self
+            .last_sent
+            .is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL)
+        {
+            self.notify(None);
+        }
+    }
+
+    pub fn stage(&mut self, stage: TransferStage) {
+        self.stage = stage;
+        self.notify(None);
+    }
+
+    /// Sends the final notification and passes the result on
+    pub fn finish<T>(mut self, result: Result<T, String>) -> Result<T, String> {
+        match &result {
+            Ok(_) => {
+                self.stage = TransferStage::Done;
+                self.notify(None);
+            }
+            Err(e) => {
+                log::error!("[Adb] {:?} {} failed: {}", self.kind, self.name, e);
+                self.stage = TransferStage::Failed;
+                self.notify(Some(e.clone()));
+            }
+        }
+        result
+    }
+}
+
+struct ProgressReader<'a, R> {
+    inner: R,
+    progress: &'a mut TransferProgress,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let n = self.inner.read(buf)?;
+        self.progress.advance(n as u64);
+        Ok(n)
+    }
+}
+
+struct ProgressWriter<'a, W> {
+    inner: W,
+    progress: &'a mut TransferProgress,
+}
+
+impl<W: Write> Write for ProgressWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let n = self.inner.write(buf)?;
+        self.progress.advance(n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.inner.flush()
+    }
+}
+
+/// Quotes an argument for the device shell
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Device paths are absolute, stay on one line and have no `.` or `..` segments,
+/// the shell sees them quoted
+pub fn check_device_path(path: &str) -> Result<(), String> {
+    if !path.starts_with('/')
+        || path.contains(['\n', '\r', '\0'])
+        || path
+            .split('/')
+            .any(|segment| segment == "." || segment == "..")
+    {
+        return Err(format!(
+            "{}: {}",
+            t!("scrcpy.fileTransfer.invalidDevicePath"),
+            path
+        ));
+    }
+    Ok(())
+}
+
+pub fn check_package_name(package: &str) -> Result<(), String> {
+    if package.is_empty()
+        || !package
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+    {
+        return Err(format!(
+            "{}: {}",
+            t!("scrcpy.fileTransfer.invalidPackageName"),
+            package
+        ));
+    }
+    Ok(())
+}
+
+/// Joins a device directory and a file name
+pub fn device_path_join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+/// The next whitespace separated field of `line` from byte `from`, with the offset just past it
+fn next_field(line: &str, from: usize) -> Option<(&str, usize)> {
+    let rest = &line[from..];
+    let start = from + rest.len() - rest.trim_start().len();
+    let end = line[start..]
+        .find(char::is_whitespace)
+        .map_or(line.len(), |i| start + i);
+    (start < end).then(|| (&line[start..end], end))
+}
+
+/// A line of toybox `ls -la`, none for the total and the `.` and `..` entries
+fn parse_ls_line(line: &str) -> Option<DeviceFileEntry> {
+    let (permissions, at) = next_field(line, 0)?;
+    let (_links, at) = next_field(line, at)?;
+    let (_owner, at) = next_field(line, at)?;
+    let (_group, at) = next_field(line, at)?;
+    let (size, mut at) = next_field(line, at)?;
+    if size.ends_with(',') {
+        // device nodes print "major, minor" instead of a size
+        at = next_field(line, at)?.1;
+    }
+    let (date, at) = next_field(line, at)?;
+    let (time, at) = next_field(line, at)?;
+    // a single space follows the time, the name may start with spaces itself
+    let name = line.get(at + 1..).filter(|name| !name.is_empty())?;
+
+    let kind = match permissions.chars().next()? {
+        '-' => DeviceFileKind::File,
+        'd' => DeviceFileKind::Dir,
+        'l' => DeviceFileKind::Link,
+        _ => DeviceFileKind::Other,
+    };
+    let (name, link_target) = match (kind, name.split_once(" -> ")) {
+        (DeviceFileKind::Link, Some((name, target))) => (name, Some(target.to_string())),
+        _ => (name, None),
+    };
+    if name == "." || name == ".." {
+        return None;
+    }
+    Some(DeviceFileEntry {
+        name: name.to_string(),
+        kind,
+        size: size.parse().unwrap_or_default(),
+        permissions: permissions.to_string(),
+        modified: format!("{} {}", date, time),
+        link_target,
+    })
+}
+
+/// Entries of a device directory, directories first
+pub fn list_dir(id: &str, path: &str) -> Result<Vec<DeviceFileEntry>, String> {
+    check_device_path(path)?;
+    // the trailing slash lists the target of a link to a directory
+    let dir = format!("{}/", path.trim_end_matches('/'));
+    let output = Device::shell_checked(id, ["ls", "-la", &shell_quote(&dir)])?;
+    let mut entries = output.lines().filter_map(parse_ls_line).collect::<Vec<_>>();
+    entries.sort_by(|a, b| {
+        (a.kind != DeviceFileKind::Dir)
+            .cmp(&(b.kind != DeviceFileKind::Dir))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(entries)
+}
+
+pub fn delete_path(id: &str, path: &str) -> Result<(), String> {
+    check_device_path(path)?;
+    if path.trim_end_matches('/').is_empty() {
+        return Err(t!("scrcpy.fileTransfer.refuseDeleteRoot").to_string());
+    }
+    Device::shell_checked(id, ["rm", "-rf", &shell_quote(path)]).map(|_| ())
+}
+
+pub fn push_file(
+    id: &str,
+    src: &Path,
+    des: &str,
+    progress: &mut TransferProgress,
+) -> Result<(), String> {
+    check_device_path(des)?;
+    let file = File::open(src)
+        .map_err(|e| format!("{} {}: {}", t!("adb.failedToOpenFile"), src.display(), e))?;
+    progress.total = file.metadata().map(|m| m.len()).unwrap_or_default();
+    let mut reader = ProgressReader {
+        inner: file,
+        progress,
+    };
+    Device::push_from(id, &mut reader, &src.display().to_string(), des)
+}
+
+/// Size of a device file as reported by `stat`
+pub fn file_size(id: &str, path: &str) -> Result<u64, String> {
+    check_device_path(path)?;
+    let size = Device::shell_checked(id, ["stat", "-c", "%s", &shell_quote(path)])?;
+    Ok(size.trim().parse().unwrap_or_default())
+}
+
+/// Pulls a device file into `output`, set `progress.total` from [`file_size`] beforehand
+pub fn pull_file(
+    id: &str,
+    src: &str,
+    output: impl Write,
+    progress: &mut TransferProgress,
+) -> Result<(), String> {
+    check_device_path(src)?;
+    let mut writer = ProgressWriter {
+        inner: output,
+        progress,
+    };
+    Device::pull(id, src.to_string(), &mut writer)
+}
+
+/// Pushes the apk to a temporary path and installs it from there, returns the output of pm
+pub fn install_apk(
+    id: &str,
+    src: &Path,
+    progress: &mut TransferProgress,
+) -> Result<String, String> {
+    let remote = device_path_join(
+        INSTALL_TMP_DIR,
+        &format!("_install_scrcpy_mask_{}.apk", progress.id),
+    );
+    push_file(id, src, &remote, progress)?;
+    progress.stage(TransferStage::Installing);
+    let res = Device::shell_checked(id, ["pm", "install", "-r", &shell_quote(&remote)]);
+    if let Err(e) = Device::shell_logged(id, ["rm", "-f", &shell_quote(&remote)]) {
+        log::warn!(
+            "[Adb] {}: {}",
+            t!("scrcpy.fileTransfer.removeTempFailed", path => remote),
+            e
+        );
+    }
+    res.map(|output| output.trim().to_string())
+}
+
+pub fn uninstall_package(id: &str, package: &str) -> Result<String, String> {
+    check_package_name(package)?;
+    Device::shell_checked(id, ["pm", "uninstall", package]).map(|output| output.trim().to_string())
+}
+
+pub fn is_apk(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("apk"))
+}
+
+/// What dropping a file onto the mask does, like scrcpy: apks are installed,
+/// anything else is pushed to the download folder
+pub fn push_or_install(
+    id: &str,
+    path: &Path,
+    ws_tx: broadcast::Sender<WebSocketNotification>,
+) -> Result<(), String> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            format!(
+                "{}: {}",
+                t!("scrcpy.fileTransfer.invalidFilePath"),
+                path.display()
+            )
+        })?
+        .to_string();
+    if is_apk(path) {
+        let mut progress = TransferProgress::new(ws_tx, id, TransferKind::Install, &name);
+        let res = install_apk(id, path, &mut progress);
+        progress.finish(res).map(|_| ())
+    } else {
+        let mut progress = TransferProgress::new(ws_tx, id, TransferKind::Push, &name);
+        let res = push_file(
+            id,
+            path,
+            &device_path_join(PUSH_TARGET_DIR, &name),
+            &mut progress,
+        );
+        progress.finish(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_toybox_ls_lines() {
+        let output = "total 20
+drwxrws--x 4 media_rw media_rw 3452 2024-05-01 10:00 .
+drwx--x--x 4 root     root     4096 2024-05-01 10:00 ..
+-rw-rw---- 1 u0_a123  media_rw 1234 2024-05-02 11:30 my file.txt
+-rw-rw---- 1 u0_a123  media_rw    5 2024-05-02 11:31   padded.txt
+lrwxrwxrwx 1 root     root       21 2009-01-01 08:00 sdcard -> /storage/self/primary
+crw-rw-rw- 1 root     root     1,   3 2024-05-01 09:00 null";
+        let entries = output.lines().filter_map(parse_ls_line).collect::<Vec<_>>();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].name, "my file.txt");
+        assert_eq!(entries[0].size, 1234);
+        assert_eq!(entries[0].modified, "2024-05-02 11:30");
+        assert_eq!(entries[1].name, "  padded.txt");
+        assert_eq!(entries[1].size, 5);
+        assert_eq!(entries[2].name, "sdcard");
+        assert_eq!(entries[2].kind, DeviceFileKind::Link);
+        assert_eq!(
+            entries[2].link_target.as_deref(),
+            Some("/storage/self/primary")
+        );
+        assert_eq!(entries[3].name, "null");
+        assert_eq!(entries[3].kind, DeviceFileKind::Other);
+    }
+
+    #[test]
+    fn quotes_shell_arguments() {
+        assert_eq!(shell_quote("/sdcard/it's here"), r"'/sdcard/it'\''s here'");
+        assert!(check_device_path("relative/path").is_err());
+        assert!(check_package_name("com.example.app").is_ok());
+        assert!(check_package_name("com.example;reboot").is_err());
+    }
+
+    #[test]
+    fn rejects_dot_segments() {
+        assert!(check_device_path("/sdcard/Download").is_ok());
+        assert!(check_device_path("/sdcard/Download/").is_ok());
+        assert!(check_device_path("/sdcard/.hidden/..file").is_ok());
+        assert!(check_device_path("/sdcard/..").is_err());
+        assert!(check_device_path("/sdcard/../data").is_err());
+        assert!(check_device_path("/sdcard/./Download").is_err());
+        assert!(check_device_path("/.").is_err());
+        assert!(delete_path("any", "/sdcard/Download/..").is_err());
+    }
+
+    /// Stage, transferred bytes and message of the notifications sent so far
+    fn sent(
+        rx: &mut broadcast::Receiver<WebSocketNotification>,
+    ) -> Vec<(TransferStage, u64, Option<String>)> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|msg| match msg {
+                WebSocketNotification::DeviceTransfer {
+                    stage,
+                    transferred,
+                    message,
+                    ..
+                } => (stage, transferred, message),
+                other => panic!("unexpected notification {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn progress_is_throttled() {
+        let (tx, mut rx) = broadcast::channel(16);
+        let mut progress = TransferProgress::new(tx, "device", TransferKind::Push, "a.txt");
+        progress.advance(10);
+        progress.advance(20);
+        progress.advance(30);
+        // only the first chunk is reported within the interval
+        assert_eq!(sent(&mut rx), [(TransferStage::Transferring, 10, None)]);
+
+        progress.last_sent = Some(Instant::now() - PROGRESS_INTERVAL);
+        progress.advance(40);
+        assert_eq!(sent(&mut rx), [(TransferStage::Transferring, 100, None)]);
+
+        // stage changes are never held back
+        progress.stage(TransferStage::Installing);
+        assert_eq!(sent(&mut rx), [(TransferStage::Installing, 100, None)]);
+    }
+
+    #[test]
+    fn finish_reports_the_result() {
+        let (tx, mut rx) = broadcast::channel(16);
+        let mut progress = TransferProgress::new(tx.clone(), "device", TransferKind::Push, "a.txt");
+        progress.advance(5);
+        assert_eq!(progress.finish(Ok(7)), Ok(7));
+        assert_eq!(
+            sent(&mut rx),
+            [
+                (TransferStage::Transferring, 5, None),
+                (TransferStage::Done, 5, None),
+            ]
+        );
+
+        let progress = TransferProgress::new(tx, "device", TransferKind::Install, "b.apk");
+        assert_eq!(
+            progress.finish::<()>(Err("no space left".to_string())),
+            Err("no space left".to_string())
+        );
+        assert_eq!(
+            sent(&mut rx),
+            [(TransferStage::Failed, 0, Some("no space left".to_string()))]
+        );
+    }
+}
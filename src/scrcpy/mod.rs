@@ -1,13 +1,18 @@
 use serde::Serialize;
 
+pub mod adaptive_video;
 pub mod adb;
 pub mod audio;
+pub mod audio_tap;
 pub mod connection;
 pub mod constant;
 pub mod control_msg;
 pub mod controller;
 pub mod device_action;
+pub mod file_transfer;
 pub mod media;
+pub mod recorder;
+pub mod video_stats;
 
 #[derive(Clone, Serialize, Debug)]
 pub struct ScrcpyDevice {
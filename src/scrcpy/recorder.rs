@@ -0,0 +1,598 @@
+use std::{collections::HashMap, time::Instant};
+
+use bevy::math::Vec2;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{RwLock, broadcast};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    mask::mapping::{tap::MappingMultipleTapItem, utils::Position},
+    scrcpy::{
+        constant::{KeyEventAction, MetaState, MotionEventAction},
+        control_msg::ScrcpyControlMsg,
+    },
+};
+
+pub const MACRO_FORMAT_VERSION: u32 = 1;
+// taps shorter than this are exported as a plain `tap(...)` call
+const SCRIPT_TAP_MAX_DURATION: u64 = 50;
+// moves closer than this (in target pixels) to the last kept point are dropped
+const SCRIPT_MIN_SEGMENT_LENGTH: f32 = 10.0;
+const SCRIPT_MAX_SWIPE_POINTS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedTouchAction {
+    Down,
+    Up,
+    Move,
+}
+
+impl RecordedTouchAction {
+    fn script_name(&self) -> &'static str {
+        match self {
+            RecordedTouchAction::Down => "down",
+            RecordedTouchAction::Up => "up",
+            RecordedTouchAction::Move => "move",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    Touch {
+        time: u64,
+        action: RecordedTouchAction,
+        pointer_id: u64,
+        x: i32,
+        y: i32,
+        w: u16,
+        h: u16,
+    },
+    Key {
+        time: u64,
+        down: bool,
+        keycode: String,
+        metastate: u32,
+    },
+}
+
+impl RecordedEvent {
+    pub fn from_control_msg(msg: &ScrcpyControlMsg, time: u64) -> Option<Self> {
+        match msg {
+            ScrcpyControlMsg::InjectTouchEvent {
+                action,
+                pointer_id,
+                x,
+                y,
+                w,
+                h,
+                ..
+            } => Some(RecordedEvent::Touch {
+                time,
+                action: match action {
+                    MotionEventAction::Down => RecordedTouchAction::Down,
+                    MotionEventAction::Up => RecordedTouchAction::Up,
+                    MotionEventAction::Move => RecordedTouchAction::Move,
+                },
+                pointer_id: *pointer_id,
+                x: *x,
+                y: *y,
+                w: *w,
+                h: *h,
+            }),
+            ScrcpyControlMsg::InjectKeycode {
+                action,
+                keycode,
+                metastate,
+                ..
+            } => match action {
+                KeyEventAction::Down | KeyEventAction::Up => Some(RecordedEvent::Key {
+                    time,
+                    down: matches!(action, KeyEventAction::Down),
+                    // variant names match the serde names used by scripts
+                    keycode: format!("{:?}", keycode),
+                    metastate: metastate.bits(),
+                }),
+                KeyEventAction::Multiple => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn time(&self) -> u64 {
+        match self {
+            RecordedEvent::Touch { time, .. } | RecordedEvent::Key { time, .. } => *time,
+        }
+    }
+}
+
+/// A raw recording as written to a macro file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedMacro {
+    pub version: u32,
+    pub duration: u64,
+    pub events: Vec<RecordedEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecorderStatus {
+    pub recording: bool,
+    pub event_count: usize,
+    pub duration: u64,
+}
+
+#[derive(Default)]
+struct RecorderState {
+    token: Option<CancellationToken>,
+    started_at: Option<Instant>,
+    duration: u64,
+    events: Vec<RecordedEvent>,
+}
+
+impl RecorderState {
+    fn elapsed(&self) -> u64 {
+        match (&self.token, self.started_at) {
+            (Some(_), Some(started_at)) => started_at.elapsed().as_millis() as u64,
+            _ => self.duration,
+        }
+    }
+}
+
+static RECORDER: Lazy<RwLock<RecorderState>> = Lazy::new(|| RwLock::new(RecorderState::default()));
+
+pub struct TouchRecorder;
+
+impl TouchRecorder {
+    /// Start logging touch and key messages sent through `cs_tx`.
+    /// The previous recording is discarded.
+    pub async fn start(cs_tx: &broadcast::Sender<ScrcpyControlMsg>) -> Result<(), String> {
+        let mut state = RECORDER.write().await;
+        if state.token.is_some() {
+            return Err("Recorder is already running".to_string());
+        }
+
+        let token = CancellationToken::new();
+        let started_at = Instant::now();
+        *state = RecorderState {
+            token: Some(token.clone()),
+            started_at: Some(started_at),
+            duration: 0,
+            events: Vec::new(),
+        };
+
+        let mut cs_rx = cs_tx.subscribe();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    msg = cs_rx.recv() => match msg {
+                        Ok(msg) => {
+                            let time = started_at.elapsed().as_millis() as u64;
+                            if let Some(event) = RecordedEvent::from_control_msg(&msg, time) {
+                                RECORDER.write().await.events.push(event);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            log::warn!("[Recorder] lagged, {} control messages skipped", n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            }
+        });
+        log::info!("[Recorder] recording started");
+        Ok(())
+    }
+
+    pub async fn stop() -> Result<RecordedMacro, String> {
+        let mut state = RECORDER.write().await;
+        let Some(token) = state.token.take() else {
+            return Err("Recorder is not running".to_string());
+        };
+        token.cancel();
+        state.duration = state
+            .started_at
+            .map(|started_at| started_at.elapsed().as_millis() as u64)
+            .unwrap_or(0);
+        log::info!(
+            "[Recorder] recording stopped, {} events in {}ms",
+            state.events.len(),
+            state.duration
+        );
+        Ok(Self::snapshot(&state))
+    }
+
+    pub async fn status() -> RecorderStatus {
+        let state = RECORDER.read().await;
+        RecorderStatus {
+            recording: state.token.is_some(),
+            event_count: state.events.len(),
+            duration: state.elapsed(),
+        }
+    }
+
+    /// The last finished recording, or `None` while recording or if nothing was recorded.
+    pub async fn last_recording() -> Option<RecordedMacro> {
+        let state = RECORDER.read().await;
+        if state.token.is_some() || state.events.is_empty() {
+            None
+        } else {
+            Some(Self::snapshot(&state))
+        }
+    }
+
+    fn snapshot(state: &RecorderState) -> RecordedMacro {
+        RecordedMacro {
+            version: MACRO_FORMAT_VERSION,
+            duration: state.duration,
+            events: state.events.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TouchStroke {
+    pointer_id: u64,
+    start: u64,
+    end: u64,
+    // (time, position in target space, action)
+    points: Vec<(u64, Vec2, RecordedTouchAction)>,
+}
+
+fn scale_point(x: i32, y: i32, w: u16, h: u16, target_size: Option<Vec2>) -> Vec2 {
+    let pos = Vec2::new(x as f32, y as f32);
+    match target_size {
+        Some(target) if w > 0 && h > 0 => pos * target / Vec2::new(w as f32, h as f32),
+        _ => pos,
+    }
+}
+
+fn build_strokes(events: &[RecordedEvent], target_size: Option<Vec2>) -> Vec<TouchStroke> {
+    let mut strokes: Vec<TouchStroke> = Vec::new();
+    let mut active: HashMap<u64, usize> = HashMap::new();
+
+    for event in events {
+        let RecordedEvent::Touch {
+            time,
+            action,
+            pointer_id,
+            x,
+            y,
+            w,
+            h,
+        } = event
+        else {
+            continue;
+        };
+        let pos = scale_point(*x, *y, *w, *h, target_size);
+
+        match action {
+            RecordedTouchAction::Down => {
+                // a repeated down leaves the previous stroke of the same pointer behind
+                active.insert(*pointer_id, strokes.len());
+                strokes.push(TouchStroke {
+                    pointer_id: *pointer_id,
+                    start: *time,
+                    end: *time,
+                    points: vec![(*time, pos, RecordedTouchAction::Down)],
+                });
+            }
+            RecordedTouchAction::Move | RecordedTouchAction::Up => {
+                let Some(&index) = active.get(pointer_id) else {
+                    continue;
+                };
+                let stroke = &mut strokes[index];
+                stroke.end = *time;
+                stroke.points.push((*time, pos, *action));
+                if *action == RecordedTouchAction::Up {
+                    active.remove(pointer_id);
+                }
+            }
+        }
+    }
+
+    // strokes still pressed when recording stopped get an implicit release
+    for index in active.into_values() {
+        let stroke = &mut strokes[index];
+        let (time, pos, _) = *stroke.points.last().unwrap();
+        stroke.points.push((time, pos, RecordedTouchAction::Up));
+    }
+
+    strokes
+}
+
+fn simplify_stroke_points(stroke: &TouchStroke) -> Vec<Vec2> {
+    let mut points: Vec<Vec2> = Vec::new();
+    for (_, pos, _) in &stroke.points {
+        match points.last() {
+            Some(last) if last.distance(*pos) < SCRIPT_MIN_SEGMENT_LENGTH => {}
+            _ => points.push(*pos),
+        }
+    }
+    let end = stroke.points.last().unwrap().1;
+    if points.len() > 1 && *points.last().unwrap() != end {
+        *points.last_mut().unwrap() = end;
+    }
+
+    if points.len() > SCRIPT_MAX_SWIPE_POINTS {
+        let step = points.len().div_ceil(SCRIPT_MAX_SWIPE_POINTS - 1);
+        let last = *points.last().unwrap();
+        points = points.into_iter().step_by(step).collect();
+        if *points.last().unwrap() != last {
+            points.push(last);
+        }
+    }
+    points
+}
+
+fn format_pos(pos: Vec2) -> String {
+    format!("{}, {}", pos.x.round() as i32, pos.y.round() as i32)
+}
+
+fn format_metastate(bits: u32) -> Option<String> {
+    let names: Vec<&str> = MetaState::from_bits_truncate(bits)
+        .iter_names()
+        .map(|(name, _)| name)
+        .collect();
+    if names.is_empty() {
+        None
+    } else {
+        Some(names.join(" | "))
+    }
+}
+
+struct ScriptItem {
+    start: u64,
+    end: u64,
+    lines: Vec<String>,
+}
+
+fn isolated_stroke_lines(stroke: &TouchStroke) -> Vec<String> {
+    let duration = stroke.end - stroke.start;
+    let points = simplify_stroke_points(stroke);
+    let p = stroke.pointer_id;
+    if points.len() <= 1 {
+        let pos = format_pos(points[0]);
+        if duration <= SCRIPT_TAP_MAX_DURATION {
+            vec![format!("tap({}, {})", p, pos)]
+        } else {
+            vec![
+                format!("tap({}, {}, \"down\")", p, pos),
+                format!("wait({})", duration),
+                format!("tap({}, {}, \"up\")", p, pos),
+            ]
+        }
+    } else {
+        let interval = duration / (points.len() as u64 - 1);
+        let coords: Vec<String> = points.into_iter().map(format_pos).collect();
+        vec![format!("swipe({}, {}, {})", p, interval, coords.join(", "))]
+    }
+}
+
+/// Convert a recording into a script made of `tap`, `swipe`, `send_key` and `wait` calls.
+///
+/// Strokes that do not overlap with another pointer become a single `tap` or `swipe`;
+/// overlapping multi-touch strokes are replayed event by event.
+pub fn recording_to_script(recording: &RecordedMacro, target_size: Option<Vec2>) -> String {
+    let strokes = build_strokes(&recording.events, target_size);
+    let mut items: Vec<ScriptItem> = Vec::new();
+
+    for (i, stroke) in strokes.iter().enumerate() {
+        let overlapping = strokes
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && other.start <= stroke.end && stroke.start <= other.end);
+        if overlapping {
+            let mut last_pos: Option<Vec2> = None;
+            for (time, pos, action) in &stroke.points {
+                if *action == RecordedTouchAction::Move && last_pos == Some(*pos) {
+                    continue;
+                }
+                last_pos = Some(*pos);
+                items.push(ScriptItem {
+                    start: *time,
+                    end: *time,
+                    lines: vec![format!(
+                        "tap({}, {}, \"{}\")",
+                        stroke.pointer_id,
+                        format_pos(*pos),
+                        action.script_name()
+                    )],
+                });
+            }
+        } else {
+            items.push(ScriptItem {
+                start: stroke.start,
+                end: stroke.end,
+                lines: isolated_stroke_lines(stroke),
+            });
+        }
+    }
+
+    for event in &recording.events {
+        if let RecordedEvent::Key {
+            time,
+            down,
+            keycode,
+            metastate,
+        } = event
+        {
+            let action = if *down { "down" } else { "up" };
+            let line = match format_metastate(*metastate) {
+                Some(meta) => format!("send_key(\"{}\", \"{}\", \"{}\")", keycode, action, meta),
+                None => format!("send_key(\"{}\", \"{}\")", keycode, action),
+            };
+            items.push(ScriptItem {
+                start: *time,
+                end: *time,
+                lines: vec![line],
+            });
+        }
+    }
+
+    items.sort_by_key(|item| item.start);
+
+    let mut lines = vec![format!(
+        "// recorded {} events in {}ms",
+        recording.events.len(),
+        recording.duration
+    )];
+    let mut cursor = items.first().map(|item| item.start).unwrap_or(0);
+    for item in items {
+        let gap = item.start.saturating_sub(cursor);
+        if gap > 0 {
+            lines.push(format!("wait({})", gap));
+        }
+        lines.extend(item.lines);
+        cursor = cursor.max(item.end);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Convert the strokes of one pointer into `MultipleTap` items.
+/// Only the touch-down position of each stroke is kept.
+pub fn recording_to_multiple_tap_items(
+    recording: &RecordedMacro,
+    pointer_id: Option<u64>,
+    target_size: Option<Vec2>,
+) -> Result<(u64, Vec<MappingMultipleTapItem>), String> {
+    let strokes = build_strokes(&recording.events, target_size);
+    let Some(pointer_id) = pointer_id.or_else(|| strokes.first().map(|s| s.pointer_id)) else {
+        return Err("No touch recorded".to_string());
+    };
+
+    let mut items = Vec::new();
+    let mut last_end: Option<u64> = None;
+    for stroke in strokes.iter().filter(|s| s.pointer_id == pointer_id) {
+        let pos = stroke.points[0].1;
+        items.push(MappingMultipleTapItem {
+            position: Position {
+                x: pos.x.round() as i32,
+                y: pos.y.round() as i32,
+            },
+            duration: stroke.end - stroke.start,
+            wait: last_end.map_or(0, |end| stroke.start.saturating_sub(end)),
+        });
+        last_end = Some(stroke.end);
+    }
+
+    if items.is_empty() {
+        return Err(format!("No touch recorded for pointer {}", pointer_id));
+    }
+    Ok((pointer_id, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::mapping::script_helper::ScriptAST;
+
+    fn touch(
+        time: u64,
+        action: RecordedTouchAction,
+        pointer_id: u64,
+        x: i32,
+        y: i32,
+    ) -> RecordedEvent {
+        RecordedEvent::Touch {
+            time,
+            action,
+            pointer_id,
+            x,
+            y,
+            w: 1000,
+            h: 500,
+        }
+    }
+
+    fn recording(events: Vec<RecordedEvent>) -> RecordedMacro {
+        RecordedMacro {
+            version: MACRO_FORMAT_VERSION,
+            duration: events.last().map(|e| e.time()).unwrap_or(0),
+            events,
+        }
+    }
+
+    #[test]
+    fn exports_taps_swipes_and_keys_as_valid_script() {
+        use RecordedTouchAction::*;
+        let rec = recording(vec![
+            touch(100, Down, 0, 10, 20),
+            touch(130, Up, 0, 10, 20),
+            touch(300, Down, 1, 100, 100),
+            touch(350, Move, 1, 200, 100),
+            touch(400, Move, 1, 300, 100),
+            touch(450, Up, 1, 300, 100),
+            RecordedEvent::Key {
+                time: 500,
+                down: true,
+                keycode: "Home".to_string(),
+                metastate: 0,
+            },
+            RecordedEvent::Key {
+                time: 540,
+                down: false,
+                keycode: "Home".to_string(),
+                metastate: MetaState::SHIFT_ON.bits(),
+            },
+        ]);
+
+        let script = recording_to_script(&rec, None);
+        let body: Vec<&str> = script.lines().skip(1).collect();
+        assert_eq!(
+            body,
+            vec![
+                "tap(0, 10, 20)",
+                "wait(170)",
+                "swipe(1, 75, 100, 100, 200, 100, 300, 100)",
+                "wait(50)",
+                "send_key(\"Home\", \"down\")",
+                "wait(40)",
+                "send_key(\"Home\", \"up\", \"SHIFT_ON\")",
+            ]
+        );
+        assert!(ScriptAST::validate_diagnostics(&script).is_empty());
+    }
+
+    #[test]
+    fn overlapping_strokes_are_replayed_event_by_event() {
+        use RecordedTouchAction::*;
+        let rec = recording(vec![
+            touch(0, Down, 0, 10, 10),
+            touch(10, Down, 1, 50, 50),
+            touch(20, Move, 0, 20, 10),
+            touch(30, Up, 1, 50, 50),
+            touch(40, Up, 0, 20, 10),
+        ]);
+
+        let script = recording_to_script(&rec, Some(Vec2::new(2000.0, 1000.0)));
+        let body: Vec<&str> = script.lines().skip(1).collect();
+        assert_eq!(body[0], "tap(0, 20, 20, \"down\")");
+        assert_eq!(body[2], "tap(1, 100, 100, \"down\")");
+        assert_eq!(body.last().unwrap(), &"tap(0, 40, 20, \"up\")");
+        assert!(ScriptAST::validate_diagnostics(&script).is_empty());
+    }
+
+    #[test]
+    fn exports_multiple_tap_items_for_one_pointer() {
+        use RecordedTouchAction::*;
+        let rec = recording(vec![
+            touch(100, Down, 2, 10, 20),
+            touch(160, Up, 2, 10, 20),
+            touch(200, Down, 3, 99, 99),
+            touch(220, Up, 3, 99, 99),
+            touch(400, Down, 2, 30, 40),
+        ]);
+
+        let (pointer_id, items) = recording_to_multiple_tap_items(&rec, None, None).unwrap();
+        assert_eq!(pointer_id, 2);
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].duration, items[0].wait), (60, 0));
+        assert_eq!((items[1].position.x, items[1].position.y), (30, 40));
+        assert_eq!(items[1].wait, 240);
+        assert!(recording_to_multiple_tap_items(&rec, Some(7), None).is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use super::media::VideoCodec;
+
+// rates are averaged over this window
+const STATS_WINDOW: Duration = Duration::from_secs(1);
+// smoothing of the interarrival jitter, as in RFC 3550
+const JITTER_GAIN: f32 = 1.0 / 16.0;
+// backlog is measured against the fastest packets of the last few seconds only, so drift
+// between the host and device clocks doesn't add up over a long session
+const BASELINE_BUCKET_US: i64 = 1_000_000;
+const BASELINE_BUCKETS: i64 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoStats {
+    pub codec: VideoCodec,
+    pub width: u32,
+    pub height: u32,
+    /// Packets read from the video socket per second
+    pub received_fps: f32,
+    /// Frames produced by the decoder per second
+    pub decoded_fps: f32,
+    /// Frames picked up by the mask renderer per second
+    pub rendered_fps: f32,
+    /// Decoded frames replaced before the renderer picked them up, since the stream started
+    pub dropped_frames: u64,
+    pub bitrate_kbps: f32,
+    /// Average time to decode one packet
+    pub decode_ms: f32,
+    pub decode_max_ms: f32,
+    /// Average time a decoded frame waited for the renderer
+    pub present_ms: f32,
+    /// Smoothed variation of packet arrival against the device clock
+    pub jitter_ms: f32,
+    /// How far packets arrive behind the fastest ones of the last seconds, grows when the link backs up
+    pub backlog_ms: f32,
+}
+
+#[derive(Clone, Copy, Default)]
+struct VideoTotals {
+    packets: u64,
+    bytes: u64,
+    decoded_frames: u64,
+    decode_us: u64,
+    rendered_frames: u64,
+    present_us: u64,
+}
+
+impl VideoTotals {
+    fn since(self, earlier: VideoTotals) -> VideoTotals {
+        VideoTotals {
+            packets: self.packets - earlier.packets,
+            bytes: self.bytes - earlier.bytes,
+            decoded_frames: self.decoded_frames - earlier.decoded_frames,
+            decode_us: self.decode_us - earlier.decode_us,
+            rendered_frames: self.rendered_frames - earlier.rendered_frames,
+            present_us: self.present_us - earlier.present_us,
+        }
+    }
+}
+
+struct VideoSession {
+    codec: VideoCodec,
+    width: u32,
+    height: u32,
+}
+
+/// Compares packet arrival with their pts to find jitter and backlog.
+/// Starts over with every video connection, including reconfigured streams.
+#[derive(Default)]
+struct ArrivalClock {
+    last_offset_us: Option<i64>,
+    /// Lowest offset per second of arrival time, oldest first
+    baseline: VecDeque<(i64, i64)>,
+    jitter_us: f32,
+    backlog_us: i64,
+}
+
+impl ArrivalClock {
+    fn record(&mut self, arrival_us: i64, pts_us: i64) {
+        let offset = arrival_us - pts_us;
+        if let Some(last) = self.last_offset_us {
+            let delta = (offset - last).abs() as f32;
+            self.jitter_us += (delta - self.jitter_us) * JITTER_GAIN;
+        }
+        self.last_offset_us = Some(offset);
+
+        let bucket = arrival_us.div_euclid(BASELINE_BUCKET_US);
+        match self.baseline.back_mut() {
+            Some((last_bucket, min)) if *last_bucket == bucket => *min = (*min).min(offset),
+            _ => self.baseline.push_back((bucket, offset)),
+        }
+        while self
+            .baseline
+            .front()
+            .is_some_and(|(first, _)| bucket - first >= BASELINE_BUCKETS)
+        {
+            self.baseline.pop_front();
+        }
+        let min_offset = self.baseline.iter().map(|(_, min)| *min).min();
+        self.backlog_us = offset - min_offset.unwrap_or(offset);
+    }
+}
+
+struct StatsWindow {
+    started_at: Instant,
+    totals: VideoTotals,
+    last: Option<VideoStats>,
+}
+
+impl StatsWindow {
+    fn new(now: Instant, totals: VideoTotals) -> Self {
+        Self {
+            started_at: now,
+            totals,
+            last: None,
+        }
+    }
+
+    /// The cached stats are refreshed once the window is full, or when there are none yet
+    fn is_due(&self, now: Instant) -> bool {
+        self.last.is_none() || now.saturating_duration_since(self.started_at) >= STATS_WINDOW
+    }
+
+    /// Counts since the window started and the seconds they cover, a full window starts over.
+    /// The flag tells whether the window was full.
+    fn advance(&mut self, now: Instant, totals: VideoTotals) -> (VideoTotals, f32, bool) {
+        let elapsed = now.saturating_duration_since(self.started_at);
+        let delta = totals.since(self.totals);
+        let full = elapsed >= STATS_WINDOW;
+        if full {
+            self.started_at = now;
+            self.totals = totals;
+        }
+        (delta, elapsed.as_secs_f32().max(f32::EPSILON), full)
+    }
+}
+
+struct VideoStatsState {
+    epoch: Instant,
+    session: Mutex<Option<VideoSession>>,
+    window: Mutex<StatsWindow>,
+    arrival: Mutex<ArrivalClock>,
+    packets: AtomicU64,
+    bytes: AtomicU64,
+    decoded_frames: AtomicU64,
+    decode_us: AtomicU64,
+    // reset every window
+    decode_max_us: AtomicU64,
+    dropped_frames: AtomicU64,
+    rendered_frames: AtomicU64,
+    present_us: AtomicU64,
+    // when the pending frame was handed to the renderer, relative to `epoch`
+    last_sent_us: AtomicU64,
+}
+
+impl VideoStatsState {
+    fn now_us(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+
+    fn totals(&self) -> VideoTotals {
+        VideoTotals {
+            packets: self.packets.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            decoded_frames: self.decoded_frames.load(Ordering::Relaxed),
+            decode_us: self.decode_us.load(Ordering::Relaxed),
+            rendered_frames: self.rendered_frames.load(Ordering::Relaxed),
+            present_us: self.present_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+static VIDEO_STATS: Lazy<VideoStatsState> = Lazy::new(|| VideoStatsState {
+    epoch: Instant::now(),
+    session: Mutex::new(None),
+    window: Mutex::new(StatsWindow::new(Instant::now(), VideoTotals::default())),
+    arrival: Mutex::new(ArrivalClock::default()),
+    packets: AtomicU64::new(0),
+    bytes: AtomicU64::new(0),
+    decoded_frames: AtomicU64::new(0),
+    decode_us: AtomicU64::new(0),
+    decode_max_us: AtomicU64::new(0),
+    dropped_frames: AtomicU64::new(0),
+    rendered_frames: AtomicU64::new(0),
+    present_us: AtomicU64::new(0),
+    last_sent_us: AtomicU64::new(0),
+});
+
+/// Counters fed by the video connection and the renderer, read through `current_video_stats`
+pub struct VideoStatsRecorder;
+
+impl VideoStatsRecorder {
+    pub fn start(codec: VideoCodec, width: u32, height: u32) {
+        let stats = &*VIDEO_STATS;
+        *stats.session.lock().unwrap() = Some(VideoSession {
+            codec,
+            width,
+            height,
+        });
+        stats.dropped_frames.store(0, Ordering::Relaxed);
+        *stats.arrival.lock().unwrap() = ArrivalClock::default();
+        *stats.window.lock().unwrap() = StatsWindow::new(Instant::now(), stats.totals());
+    }
+
+    pub fn stop() {
+        *VIDEO_STATS.session.lock().unwrap() = None;
+    }
+
+    pub fn set_size(width: u32, height: u32) {
+        if let Some(session) = VIDEO_STATS.session.lock().unwrap().as_mut() {
+            session.width = width;
+            session.height = height;
+        }
+    }
+
+    pub fn record_packet(size: usize, pts: Option<i64>) {
+        let stats = &*VIDEO_STATS;
+        stats.packets.fetch_add(1, Ordering::Relaxed);
+        stats.bytes.fetch_add(size as u64, Ordering::Relaxed);
+        if let Some(pts) = pts {
+            let arrival_us = stats.now_us() as i64;
+            stats.arrival.lock().unwrap().record(arrival_us, pts);
+        }
+    }
+
+    pub fn record_decode(elapsed: Duration) {
+        let stats = &*VIDEO_STATS;
+        let us = elapsed.as_micros() as u64;
+        stats.decode_us.fetch_add(us, Ordering::Relaxed);
+        stats.decode_max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    pub fn record_decoded_frame() {
+        VIDEO_STATS.decoded_frames.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A frame was handed to the renderer, `replaced` when it overwrote one never displayed
+    pub fn record_sent(replaced: bool) {
+        let stats = &*VIDEO_STATS;
+        if replaced {
+            stats.dropped_frames.fetch_add(1, Ordering::Relaxed);
+        }
+        stats.last_sent_us.store(stats.now_us(), Ordering::Relaxed);
+    }
+
+    pub fn record_rendered() {
+        let stats = &*VIDEO_STATS;
+        let wait = stats
+            .now_us()
+            .saturating_sub(stats.last_sent_us.load(Ordering::Relaxed));
+        stats.rendered_frames.fetch_add(1, Ordering::Relaxed);
+        stats.present_us.fetch_add(wait, Ordering::Relaxed);
+    }
+}
+
+/// Stats of the playing video stream, rates refresh once per window
+pub fn current_video_stats() -> Option<VideoStats> {
+    let stats = &*VIDEO_STATS;
+    let session = stats.session.lock().unwrap();
+    let session = session.as_ref()?;
+    let mut window = stats.window.lock().unwrap();
+
+    let now = Instant::now();
+    if window.is_due(now) {
+        let (delta, secs, full) = window.advance(now, stats.totals());
+        let average_ms = |sum_us: u64, count: u64| {
+            if count == 0 {
+                0.0
+            } else {
+                sum_us as f32 / count as f32 / 1000.0
+            }
+        };
+        let decode_max_us = if full {
+            stats.decode_max_us.swap(0, Ordering::Relaxed)
+        } else {
+            stats.decode_max_us.load(Ordering::Relaxed)
+        };
+        let (jitter_us, backlog_us) = {
+            let arrival = stats.arrival.lock().unwrap();
+            (arrival.jitter_us, arrival.backlog_us)
+        };
+        window.last = Some(VideoStats {
+            codec: session.codec,
+            width: session.width,
+            height: session.height,
+            received_fps: delta.packets as f32 / secs,
+            decoded_fps: delta.decoded_frames as f32 / secs,
+            rendered_fps: delta.rendered_frames as f32 / secs,
+            dropped_frames: stats.dropped_frames.load(Ordering::Relaxed),
+            bitrate_kbps: delta.bytes as f32 * 8.0 / 1000.0 / secs,
+            decode_ms: average_ms(delta.decode_us, delta.packets),
+            decode_max_ms: decode_max_us as f32 / 1000.0,
+            present_ms: average_ms(delta.present_us, delta.rendered_frames),
+            jitter_ms: jitter_us / 1000.0,
+            backlog_ms: backlog_us as f32 / 1000.0,
+        });
+    }
+    window.last.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packets(packets: u64) -> VideoTotals {
+        VideoTotals {
+            packets,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn window_covers_counts_until_it_is_full() {
+        let start = Instant::now();
+        let mut window = StatsWindow::new(start, packets(100));
+        assert!(window.is_due(start));
+
+        // a partial window reports what it has so far and keeps accumulating
+        let (delta, secs, full) = window.advance(start + Duration::from_millis(500), packets(130));
+        assert_eq!((delta.packets, full), (30, false));
+        assert!((delta.packets as f32 / secs - 60.0).abs() < 0.01);
+        window.last = None;
+        assert!(window.is_due(start + Duration::from_millis(600)));
+
+        let (delta, secs, full) = window.advance(start + STATS_WINDOW, packets(160));
+        assert_eq!((delta.packets, full), (60, true));
+        assert!((delta.packets as f32 / secs - 60.0).abs() < 0.01);
+
+        // the next window only counts what came after
+        let (delta, _, full) = window.advance(start + STATS_WINDOW * 2, packets(190));
+        assert_eq!((delta.packets, full), (30, true));
+    }
+
+    /// Packets every 16ms for `secs` seconds, the device clock runs `drift_ppm` slower
+    fn feed(clock: &mut ArrivalClock, start_us: i64, secs: i64, drift_ppm: i64) -> i64 {
+        let mut arrival = start_us;
+        while arrival < start_us + secs * 1_000_000 {
+            let pts = arrival - arrival * drift_ppm / 1_000_000;
+            clock.record(arrival, pts);
+            arrival += 16_000;
+        }
+        arrival
+    }
+
+    #[test]
+    fn clock_drift_does_not_build_up_backlog() {
+        let mut clock = ArrivalClock::default();
+        // 1000 ppm adds a millisecond of offset every second
+        feed(&mut clock, 0, 600, 1000);
+        assert!(clock.backlog_us < 12_000, "{}", clock.backlog_us);
+        assert!(clock.jitter_us < 100.0);
+    }
+
+    #[test]
+    fn late_packets_show_up_as_backlog() {
+        let mut clock = ArrivalClock::default();
+        let arrival = feed(&mut clock, 0, 5, 0);
+        // the link backs up, packets arrive 200ms after they were captured
+        clock.record(arrival + 200_000, arrival);
+        assert_eq!(clock.backlog_us, 200_000);
+        clock.record(arrival + 216_000, arrival + 216_000);
+        assert_eq!(clock.backlog_us, 0);
+    }
+
+    #[test]
+    fn cached_stats_wait_for_a_full_window() {
+        let start = Instant::now();
+        let mut window = StatsWindow::new(start, VideoTotals::default());
+        window.last = Some(VideoStats {
+            codec: VideoCodec::H264,
+            width: 1920,
+            height: 1080,
+            received_fps: 0.0,
+            decoded_fps: 0.0,
+            rendered_fps: 0.0,
+            dropped_frames: 0,
+            bitrate_kbps: 0.0,
+            decode_ms: 0.0,
+            decode_max_ms: 0.0,
+            present_ms: 0.0,
+            jitter_ms: 0.0,
+            backlog_ms: 0.0,
+        });
+        assert!(!window.is_due(start + Duration::from_millis(999)));
+        assert!(window.is_due(start + STATS_WINDOW));
+    }
+}
@@ -1,9 +1,11 @@
 pub mod share;
 
 use std::{
-    env,
+    collections::HashMap,
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::{Arc, LazyLock, Mutex, OnceLock},
 };
 
 use axum::http::{HeaderMap, HeaderValue};
@@ -17,7 +19,10 @@ use tokio::sync::{broadcast, oneshot};
 use crate::{
     config::LocalConfig,
     mask::mask_command::MaskCommand,
-    scrcpy::{control_msg::ScrcpyControlMsg, controller::ControllerCommand, media::VideoMsg},
+    scrcpy::{
+        control_msg::ScrcpyControlMsg, controller::ControllerCommand, media::VideoMsg,
+        video_stats::VideoStatsRecorder,
+    },
     utils::share::UpdateInfo,
     web::ws::WebSocketNotification,
 };
@@ -25,6 +30,30 @@ use crate::{
 pub const IDENTIFIER: &str = "com.akichase.scrcpy-mask";
 const VIDEO_BUFFER_POOL_LIMIT: usize = 12;
 
+/// How the process was started, set once by main before the servers start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunMode {
+    Window,
+    /// No mask window, devices are driven through the web API only and video isn't decoded
+    Headless,
+}
+
+impl RunMode {
+    pub fn is_headless(self) -> bool {
+        self == RunMode::Headless
+    }
+}
+
+static RUN_MODE: OnceLock<RunMode> = OnceLock::new();
+
+pub fn set_run_mode(mode: RunMode) {
+    let _ = RUN_MODE.set(mode);
+}
+
+pub fn run_mode() -> RunMode {
+    RUN_MODE.get().copied().unwrap_or(RunMode::Window)
+}
+
 pub fn relate_to_data_path<P>(segments: P) -> PathBuf
 where
     P: IntoIterator,
@@ -46,6 +75,52 @@ where
     segments.into_iter().fold(root, |acc, seg| acc.join(seg))
 }
 
+// content hashes of data files as last written or seen by the app
+static DATA_FILE_HASHES: LazyLock<Mutex<HashMap<PathBuf, u64>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn content_hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Writes a data file through a temporary file and a rename, so it is never seen half written.
+/// The content is recorded first, the data watcher then knows the change came from the app
+pub fn write_data_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    DATA_FILE_HASHES
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), content_hash(contents));
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
+}
+
+/// Records a data file the app just moved or copied, so the data watcher does not treat it as an outside edit
+pub fn stamp_data_file(path: &Path) {
+    if let Ok(contents) = fs::read(path) {
+        restamp_data_file(path, &contents);
+    }
+}
+
+/// Records the content of the file and returns whether it differs from the previous record
+pub fn restamp_data_file(path: &Path, contents: &[u8]) -> bool {
+    let hash = content_hash(contents);
+    DATA_FILE_HASHES
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), hash)
+        != Some(hash)
+}
+
+pub fn forget_data_file(path: &Path) {
+    DATA_FILE_HASHES.lock().unwrap().remove(path);
+}
+
 const ILLEGAL_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
 
 pub fn is_safe_file_name(name: &str) -> bool {
@@ -89,12 +164,23 @@ struct LatestVideoFrameInner {
 
 impl LatestVideoFrame {
     pub fn send(&self, msg: VideoMsg) {
+        let is_frame = !matches!(msg, VideoMsg::Close);
         let old_msg = self.inner.slot.lock().unwrap().replace(msg);
+        if is_frame {
+            VideoStatsRecorder::record_sent(matches!(
+                old_msg,
+                Some(VideoMsg::Yuv420p { .. } | VideoMsg::Nv12 { .. })
+            ));
+        }
         self.recycle_msg(old_msg);
     }
 
     pub fn take(&self) -> Option<VideoMsg> {
-        self.inner.slot.lock().unwrap().take()
+        let msg = self.inner.slot.lock().unwrap().take();
+        if matches!(msg, Some(VideoMsg::Yuv420p { .. } | VideoMsg::Nv12 { .. })) {
+            VideoStatsRecorder::record_rendered();
+        }
+        msg
     }
 
     pub fn take_buffer(&self, size: usize) -> Vec<u8> {
@@ -116,6 +202,11 @@ impl LatestVideoFrame {
         }
     }
 
+    /// Hand the buffers of a consumed frame back to the decoder
+    pub fn recycle(&self, msg: VideoMsg) {
+        self.recycle_msg(Some(msg));
+    }
+
     fn recycle_msg(&self, msg: Option<VideoMsg>) {
         match msg {
             Some(VideoMsg::Yuv420p { y, u, v, .. }) => {
@@ -2,7 +2,7 @@ use once_cell::sync::Lazy;
 use serde::Serialize;
 use tokio::sync::RwLock;
 
-use crate::scrcpy::ScrcpyDevice;
+use crate::{mask::mapping::config::MappingDiagnostic, scrcpy::ScrcpyDevice};
 
 static CONTROLLED_DEVICES: Lazy<RwLock<Vec<ScrcpyDevice>>> = Lazy::new(|| RwLock::new(Vec::new()));
 
@@ -97,3 +97,25 @@ static UPDATE_INFO: Lazy<RwLock<UpdateInfo>> = Lazy::new(|| {
         time: "".to_string(),
     })
 });
+
+/// The configured mapping that could not be loaded at startup and why, cleared once a mapping is activated
+#[derive(Debug, Clone, Serialize)]
+pub struct MappingFallback {
+    /// Named in the config, it stays there to be tried again on the next start
+    pub file: String,
+    /// Used instead
+    pub active: String,
+    pub diagnostics: Vec<MappingDiagnostic>,
+}
+
+impl MappingFallback {
+    pub async fn get() -> Option<MappingFallback> {
+        MAPPING_FALLBACK.read().await.clone()
+    }
+
+    pub fn set_blocking(fallback: Option<MappingFallback>) {
+        *MAPPING_FALLBACK.blocking_write() = fallback;
+    }
+}
+
+static MAPPING_FALLBACK: Lazy<RwLock<Option<MappingFallback>>> = Lazy::new(|| RwLock::new(None));
@@ -0,0 +1,627 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, mpsc as std_mpsc},
+};
+
+use axum::{
+    extract::{
+        WebSocketUpgrade,
+        ws::{Message, WebSocket},
+    },
+    response::IntoResponse,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    task::JoinHandle,
+};
+
+use crate::{
+    config::LocalConfig,
+    scrcpy::adb::{Adb, Device},
+};
+
+/// Sessions a single console connection may keep open
+const MAX_SESSIONS: usize = 8;
+/// Events waiting for the socket. Shell output waits for room, logcat lines are dropped
+/// and counted instead so a busy device can't stall the stream.
+const EVENT_CAPACITY: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum LogLevel {
+    #[default]
+    #[serde(rename = "V")]
+    Verbose,
+    #[serde(rename = "D")]
+    Debug,
+    #[serde(rename = "I")]
+    Info,
+    #[serde(rename = "W")]
+    Warn,
+    #[serde(rename = "E")]
+    Error,
+    #[serde(rename = "F")]
+    Fatal,
+}
+
+impl LogLevel {
+    fn letter(self) -> char {
+        match self {
+            LogLevel::Verbose => 'V',
+            LogLevel::Debug => 'D',
+            LogLevel::Info => 'I',
+            LogLevel::Warn => 'W',
+            LogLevel::Error => 'E',
+            LogLevel::Fatal => 'F',
+        }
+    }
+
+    fn from_letter(letter: &str) -> Option<Self> {
+        match letter {
+            "V" => Some(LogLevel::Verbose),
+            "D" => Some(LogLevel::Debug),
+            "I" => Some(LogLevel::Info),
+            "W" => Some(LogLevel::Warn),
+            "E" => Some(LogLevel::Error),
+            "F" | "A" => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+}
+
+/// A line of `logcat -v threadtime`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LogcatEntry {
+    pub date: String,
+    pub time: String,
+    pub pid: u32,
+    pub tid: u32,
+    pub level: LogLevel,
+    pub tag: String,
+    pub message: String,
+}
+
+/// Splits off the first whitespace separated field
+fn split_field(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace)?;
+    Some((&s[..end], s[end..].trim_start()))
+}
+
+impl LogcatEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let (date, rest) = split_field(line)?;
+        let (time, rest) = split_field(rest)?;
+        let (pid, rest) = split_field(rest)?;
+        let (tid, rest) = split_field(rest)?;
+        let (level, rest) = split_field(rest)?;
+        let (tag, message) = match rest.split_once(": ") {
+            Some((tag, message)) => (tag, message),
+            None => (rest.strip_suffix(':').unwrap_or(rest), ""),
+        };
+        Some(Self {
+            date: date.to_string(),
+            time: time.to_string(),
+            pid: pid.parse().ok()?,
+            tid: tid.parse().ok()?,
+            level: LogLevel::from_letter(level)?,
+            tag: tag.trim().to_string(),
+            message: message.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ConsoleRequest {
+    OpenShell {
+        session: String,
+        device_id: String,
+    },
+    /// Written to the stdin of a shell session as is
+    Input {
+        session: String,
+        data: String,
+    },
+    OpenLogcat {
+        session: String,
+        device_id: String,
+        #[serde(flatten)]
+        filter: LogcatFilter,
+    },
+    Close {
+        session: String,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct LogcatFilter {
+    /// Only these tags when not empty
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Lowest level passed on
+    #[serde(default)]
+    level: LogLevel,
+    /// Only lines containing this text, case insensitive
+    #[serde(default)]
+    contains: Option<String>,
+    #[serde(default)]
+    pid: Option<u32>,
+    /// Start with the last lines of the buffer instead of all of it
+    #[serde(default)]
+    tail: Option<u32>,
+}
+
+impl LogcatFilter {
+    fn is_valid_tag(tag: &str) -> bool {
+        !tag.is_empty()
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-./".contains(c))
+    }
+
+    /// Level and tags are filtered by logcat itself, the command goes through the device shell.
+    /// The shell prints its pid and becomes logcat, so the session can kill it when closed
+    fn args(&self) -> Result<Vec<String>, String> {
+        let mut args = vec![
+            "echo".to_string(),
+            "$$;".to_string(),
+            "exec".to_string(),
+            "logcat".to_string(),
+            "-v".to_string(),
+            "threadtime".to_string(),
+        ];
+        if let Some(pid) = self.pid {
+            args.push(format!("--pid={}", pid));
+        }
+        if let Some(tail) = self.tail {
+            args.push("-T".to_string());
+            args.push(tail.max(1).to_string());
+        }
+        if self.tags.is_empty() {
+            args.push(format!("*:{}", self.level.letter()));
+        } else {
+            for tag in &self.tags {
+                if !Self::is_valid_tag(tag) {
+                    return Err(format!("Invalid logcat tag: {}", tag));
+                }
+                args.push(format!("{}:{}", tag, self.level.letter()));
+            }
+            args.push("*:S".to_string());
+        }
+        Ok(args)
+    }
+
+    fn matches(&self, line: &str) -> bool {
+        match &self.contains {
+            Some(text) if !text.is_empty() => line.to_lowercase().contains(&text.to_lowercase()),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+enum ConsoleEvent {
+    Opened {
+        session: String,
+        device_id: String,
+        kind: SessionKind,
+    },
+    Output {
+        session: String,
+        data: String,
+    },
+    /// `entry` is none for lines logcat doesn't format as an entry, e.g. buffer headers
+    Log {
+        session: String,
+        line: String,
+        entry: Option<LogcatEntry>,
+    },
+    /// Logcat lines skipped because the socket couldn't keep up
+    Dropped {
+        session: String,
+        count: u64,
+    },
+    Closed {
+        session: String,
+        error: Option<String>,
+    },
+    Error {
+        session: Option<String>,
+        message: String,
+    },
+}
+
+impl From<ConsoleEvent> for Message {
+    fn from(event: ConsoleEvent) -> Self {
+        let json = serde_json::to_string(&event).unwrap();
+        Message::Text(json.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SessionKind {
+    Shell,
+    Logcat,
+}
+
+/// Pid of the logcat process on the device, known once its first line is read
+#[derive(Clone)]
+struct LogcatProcess {
+    device_id: String,
+    pid: Arc<OnceLock<u32>>,
+}
+
+struct Session {
+    /// Stdin of a shell session
+    input: Option<std_mpsc::Sender<Vec<u8>>>,
+    logcat: Option<LogcatProcess>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // a finished logcat is gone already, its pid may belong to another process by now
+        let running = !self.task.is_finished();
+        // a shell stops once its output receiver is gone
+        self.task.abort();
+        // a quiet logcat would never notice, killing it ends the command and frees its connection
+        if let Some(logcat) = self.logcat.take().filter(|_| running) {
+            if let Some(pid) = logcat.pid.get().copied() {
+                tokio::task::spawn_blocking(move || {
+                    let mut output = Vec::<u8>::new();
+                    let _ = Device::shell(
+                        &logcat.device_id,
+                        ["kill".to_string(), pid.to_string()],
+                        &mut output,
+                    );
+                });
+            }
+        }
+    }
+}
+
+pub(super) async fn ws_handler(ws: WebSocketUpgrade) -> impl IntoResponse {
+    ws.on_upgrade(handle_socket)
+}
+
+async fn handle_socket(socket: WebSocket) {
+    log::info!("[AdbConsole] Console connected");
+    let (mut sender, mut receiver) = socket.split();
+    let (event_tx, mut event_rx) = mpsc::channel::<ConsoleEvent>(EVENT_CAPACITY);
+
+    let mut send_handler = tokio::spawn(async move {
+        while let Some(event) = event_rx.recv().await {
+            if sender.send(event.into()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut recv_handler = tokio::spawn(async move {
+        let mut sessions = HashMap::<String, Session>::new();
+        while let Some(Ok(msg)) = receiver.next().await {
+            match msg {
+                Message::Text(text) => {
+                    let request = match serde_json::from_str::<ConsoleRequest>(&text) {
+                        Ok(request) => request,
+                        Err(e) => {
+                            send_error(&event_tx, None, format!("Invalid console request: {}", e))
+                                .await;
+                            continue;
+                        }
+                    };
+                    handle_request(request, &mut sessions, &event_tx).await;
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+    });
+
+    tokio::select! {
+        _ = (&mut send_handler) => {
+            recv_handler.abort();
+        },
+        _ = (&mut recv_handler) => {
+            send_handler.abort();
+        }
+    }
+    log::info!("[AdbConsole] Console disconnected");
+}
+
+async fn send_error(
+    event_tx: &mpsc::Sender<ConsoleEvent>,
+    session: Option<String>,
+    message: impl Into<String>,
+) {
+    let message = message.into();
+    log::warn!("[AdbConsole] {}", message);
+    let _ = event_tx
+        .send(ConsoleEvent::Error { session, message })
+        .await;
+}
+
+async fn check_device(device_id: &str) -> Result<(), String> {
+    let adb_path = LocalConfig::get().adb_path;
+    let devices = tokio::task::spawn_blocking(move || Adb::new(adb_path).devices())
+        .await
+        .map_err(|e| e.to_string())??;
+    match devices.iter().find(|device| device.id == device_id) {
+        Some(device) if device.status == "device" => Ok(()),
+        Some(device) => Err(format!("Device {} is {}", device_id, device.status)),
+        None => Err(format!("Device not found: {}", device_id)),
+    }
+}
+
+async fn handle_request(
+    request: ConsoleRequest,
+    sessions: &mut HashMap<String, Session>,
+    event_tx: &mpsc::Sender<ConsoleEvent>,
+) {
+    sessions.retain(|_, session| !session.task.is_finished());
+
+    match request {
+        ConsoleRequest::OpenShell { session, device_id } => {
+            if let Err(e) = check_session_slot(sessions, &session) {
+                send_error(event_tx, Some(session), e).await;
+                return;
+            }
+            if let Err(e) = check_device(&device_id).await {
+                send_error(event_tx, Some(session), e).await;
+                return;
+            }
+            let (input, output, h) = Device::interactive_shell(&device_id);
+            let task = tokio::spawn(forward_shell(session.clone(), output, h, event_tx.clone()));
+            open_session(
+                sessions,
+                event_tx,
+                session,
+                device_id,
+                SessionKind::Shell,
+                Some(input),
+                None,
+                task,
+            )
+            .await;
+        }
+        ConsoleRequest::OpenLogcat {
+            session,
+            device_id,
+            filter,
+        } => {
+            let args = match check_session_slot(sessions, &session).and_then(|_| filter.args()) {
+                Ok(args) => args,
+                Err(e) => {
+                    send_error(event_tx, Some(session), e).await;
+                    return;
+                }
+            };
+            if let Err(e) = check_device(&device_id).await {
+                send_error(event_tx, Some(session), e).await;
+                return;
+            }
+            let (lines, h) = Device::shell_stream(&device_id, args);
+            let logcat = LogcatProcess {
+                device_id: device_id.clone(),
+                pid: Arc::new(OnceLock::new()),
+            };
+            let task = tokio::spawn(forward_logcat(
+                session.clone(),
+                filter,
+                lines,
+                h,
+                logcat.pid.clone(),
+                event_tx.clone(),
+            ));
+            open_session(
+                sessions,
+                event_tx,
+                session,
+                device_id,
+                SessionKind::Logcat,
+                None,
+                Some(logcat),
+                task,
+            )
+            .await;
+        }
+        ConsoleRequest::Input { session, data } => {
+            let sent = sessions
+                .get(&session)
+                .and_then(|s| s.input.as_ref())
+                .is_some_and(|input| input.send(data.into_bytes()).is_ok());
+            if !sent {
+                send_error(event_tx, Some(session), "No open shell session").await;
+            }
+        }
+        ConsoleRequest::Close { session } => {
+            if sessions.remove(&session).is_some() {
+                let _ = event_tx
+                    .send(ConsoleEvent::Closed {
+                        session,
+                        error: None,
+                    })
+                    .await;
+            }
+        }
+    }
+}
+
+fn check_session_slot(sessions: &HashMap<String, Session>, session: &str) -> Result<(), String> {
+    if sessions.contains_key(session) {
+        return Err(format!("Session {} is already open", session));
+    }
+    if sessions.len() >= MAX_SESSIONS {
+        return Err(format!(
+            "At most {} console sessions can be open",
+            MAX_SESSIONS
+        ));
+    }
+    Ok(())
+}
+
+async fn open_session(
+    sessions: &mut HashMap<String, Session>,
+    event_tx: &mpsc::Sender<ConsoleEvent>,
+    session: String,
+    device_id: String,
+    kind: SessionKind,
+    input: Option<std_mpsc::Sender<Vec<u8>>>,
+    logcat: Option<LogcatProcess>,
+    task: JoinHandle<()>,
+) {
+    log::info!(
+        "[AdbConsole] Opened {:?} session {} on {}",
+        kind,
+        session,
+        device_id
+    );
+    sessions.insert(
+        session.clone(),
+        Session {
+            input,
+            logcat,
+            task,
+        },
+    );
+    let _ = event_tx
+        .send(ConsoleEvent::Opened {
+            session,
+            device_id,
+            kind,
+        })
+        .await;
+}
+
+async fn session_result(h: JoinHandle<Result<(), String>>) -> Option<String> {
+    match h.await {
+        Ok(res) => res.err(),
+        Err(e) => Some(e.to_string()),
+    }
+}
+
+/// Waits for room on the socket, which in turn holds the shell back
+async fn forward_shell(
+    session: String,
+    mut output: mpsc::Receiver<String>,
+    h: JoinHandle<Result<(), String>>,
+    event_tx: mpsc::Sender<ConsoleEvent>,
+) {
+    while let Some(data) = output.recv().await {
+        let event = ConsoleEvent::Output {
+            session: session.clone(),
+            data,
+        };
+        if event_tx.send(event).await.is_err() {
+            return;
+        }
+    }
+    let error = session_result(h).await;
+    let _ = event_tx.send(ConsoleEvent::Closed { session, error }).await;
+}
+
+async fn forward_logcat(
+    session: String,
+    filter: LogcatFilter,
+    mut lines: mpsc::Receiver<String>,
+    h: JoinHandle<Result<(), String>>,
+    pid: Arc<OnceLock<u32>>,
+    event_tx: mpsc::Sender<ConsoleEvent>,
+) {
+    // the first line is the pid printed before logcat starts
+    if let Some(line) = lines.recv().await {
+        if let Ok(value) = line.trim().parse() {
+            let _ = pid.set(value);
+        }
+    }
+    let mut dropped = 0;
+    while let Some(line) = lines.recv().await {
+        if !filter.matches(&line) {
+            continue;
+        }
+        if dropped > 0 {
+            let event = ConsoleEvent::Dropped {
+                session: session.clone(),
+                count: dropped,
+            };
+            match event_tx.try_send(event) {
+                Ok(()) => dropped = 0,
+                Err(TrySendError::Full(_)) => {
+                    dropped += 1;
+                    continue;
+                }
+                Err(TrySendError::Closed(_)) => return,
+            }
+        }
+        let event = ConsoleEvent::Log {
+            session: session.clone(),
+            entry: LogcatEntry::parse(&line),
+            line,
+        };
+        match event_tx.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => dropped += 1,
+            Err(TrySendError::Closed(_)) => return,
+        }
+    }
+    let error = session_result(h).await;
+    let _ = event_tx.send(ConsoleEvent::Closed { session, error }).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_threadtime_lines() {
+        let entry = LogcatEntry::parse(
+            "10-19 12:30:01.123  1234  1250 W InputDispatcher: channel is unresponsive",
+        )
+        .unwrap();
+        assert_eq!(entry.pid, 1234);
+        assert_eq!(entry.tid, 1250);
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.tag, "InputDispatcher");
+        assert_eq!(entry.message, "channel is unresponsive");
+
+        assert!(LogcatEntry::parse("--------- beginning of main").is_none());
+    }
+
+    #[test]
+    fn logcat_filter_builds_filter_specs() {
+        let filter = LogcatFilter {
+            tags: vec!["Unity".to_string(), "scrcpy".to_string()],
+            level: LogLevel::Info,
+            tail: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.args().unwrap(),
+            [
+                "echo",
+                "$$;",
+                "exec",
+                "logcat",
+                "-v",
+                "threadtime",
+                "-T",
+                "100",
+                "Unity:I",
+                "scrcpy:I",
+                "*:S"
+            ]
+        );
+
+        for tag in ["a;reboot", "$(reboot)", "$PATH"] {
+            let filter = LogcatFilter {
+                tags: vec![tag.to_string()],
+                ..Default::default()
+            };
+            assert!(filter.args().is_err());
+        }
+    }
+}
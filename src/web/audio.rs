@@ -0,0 +1,160 @@
+use std::{
+    convert::Infallible,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    Json, Router,
+    body::{Body, Bytes},
+    extract::{
+        Query, WebSocketUpgrade,
+        ws::{Message, WebSocket},
+    },
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{any, get, post},
+};
+use futures_util::{
+    SinkExt,
+    stream::{self, StreamExt},
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    scrcpy::audio_tap::{AudioTap, PcmFormat, wav_stream_header},
+    utils::{is_safe_file_name, relate_to_data_path},
+    web::{JsonResponse, WebServerError},
+};
+
+pub fn routers() -> Router {
+    Router::new()
+        .route("/status", get(tap_status))
+        .route("/capture/start", post(start_capture))
+        .route("/capture/stop", post(stop_capture))
+        .route("/stream.wav", get(wav_stream))
+        .route("/ws", any(ws_stream))
+}
+
+async fn tap_status() -> Result<JsonResponse, WebServerError> {
+    Ok(JsonResponse::success(
+        "Audio tap status obtained",
+        Some(json!(AudioTap::status())),
+    ))
+}
+
+#[derive(Deserialize)]
+struct PostDataStartCapture {
+    file: Option<String>,
+}
+
+async fn start_capture(
+    Json(payload): Json<PostDataStartCapture>,
+) -> Result<JsonResponse, WebServerError> {
+    let mut file = payload.file.unwrap_or_else(|| {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        format!("audio-{}.wav", secs)
+    });
+    if !file.ends_with(".wav") {
+        file.push_str(".wav");
+    }
+    if !is_safe_file_name(&file) {
+        return Err(WebServerError::bad_request(format!(
+            "Audio file name is not safe: {}",
+            file
+        )));
+    }
+
+    let dir = relate_to_data_path(["audio"]);
+    fs::create_dir_all(&dir).map_err(|e| {
+        WebServerError::internal_error(format!("Failed to create audio directory: {}", e))
+    })?;
+    let file_path = dir.join(&file);
+    AudioTap::start_capture(&file_path).map_err(WebServerError::bad_request)?;
+    Ok(JsonResponse::success(
+        format!("Audio capture started: {}", file),
+        Some(json!({ "path": file_path })),
+    ))
+}
+
+async fn stop_capture() -> Result<JsonResponse, WebServerError> {
+    let summary = AudioTap::stop_capture().map_err(WebServerError::bad_request)?;
+    Ok(JsonResponse::success(
+        "Audio capture saved",
+        Some(json!(summary)),
+    ))
+}
+
+#[derive(Deserialize)]
+struct StreamQuery {
+    #[serde(default)]
+    format: PcmFormat,
+}
+
+/// Endless wav over http, usable as a media source by browsers and OBS
+async fn wav_stream(Query(query): Query<StreamQuery>) -> Response {
+    let format = query.format;
+    let rx = AudioTap::subscribe();
+    let header =
+        stream::once(async move { Ok::<_, Infallible>(Bytes::from(wav_stream_header(format))) });
+    let samples = stream::unfold(rx, move |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(samples) => {
+                    return Some((Ok(Bytes::from(format.encode(&samples))), rx));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("[AudioTap] Wav stream lagged, skipped {} packets", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "audio/wav"),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        Body::from_stream(header.chain(samples)),
+    )
+        .into_response()
+}
+
+/// Binary messages of interleaved 48 kHz stereo samples, for Web Audio consumers
+async fn ws_stream(ws: WebSocketUpgrade, Query(query): Query<StreamQuery>) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_ws_stream(socket, query.format))
+}
+
+async fn handle_ws_stream(socket: WebSocket, format: PcmFormat) {
+    log::info!("[AudioTap] Audio websocket connected");
+    let (mut sender, mut receiver) = socket.split();
+    let mut rx = AudioTap::subscribe();
+
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(samples) => {
+                    let data = Bytes::from(format.encode(&samples));
+                    if sender.send(Message::Binary(data)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("[AudioTap] Audio websocket lagged, skipped {} packets", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            msg = receiver.next() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+    log::info!("[AudioTap] Audio websocket disconnected");
+}
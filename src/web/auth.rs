@@ -0,0 +1,425 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use axum::{
+    Extension, Json, Router,
+    extract::{ConnectInfo, Query, Request},
+    http::{Method, header},
+    middleware::Next,
+    response::Response,
+    routing::{get, post},
+};
+use rand::Rng;
+use rust_i18n::t;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    config::{ApiToken, AuthScope, LocalConfig, WebAuthConfig},
+    web::{JsonResponse, WebServerError},
+};
+
+const ALL_SCOPES: [AuthScope; 3] = [AuthScope::Read, AuthScope::Control, AuthScope::Config];
+
+/// Scopes granted to the current request, inserted by [`require_auth`]
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub token_name: Option<String>,
+    pub scopes: Vec<AuthScope>,
+}
+
+impl AuthContext {
+    fn full() -> Self {
+        Self {
+            token_name: None,
+            scopes: ALL_SCOPES.to_vec(),
+        }
+    }
+
+    pub fn allows(&self, scope: AuthScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+pub fn routers() -> Router {
+    Router::new()
+        .route("/status", get(auth_status))
+        .route("/tokens", get(list_tokens))
+        .route("/tokens/create", post(create_token))
+        .route("/tokens/delete", post(delete_token))
+        .route("/settings", post(update_settings))
+}
+
+/// Logs a warning when the web API is reachable from other machines without authentication
+pub fn warn_if_exposed(addr: Ipv4Addr) {
+    if !addr.is_loopback() && !LocalConfig::get_web_auth().enabled {
+        log::warn!(
+            "[WebServe] The web API is bound to {} without authentication, anyone on the network can control your devices. Enable web API access control in the settings",
+            addr
+        );
+    }
+}
+
+/// The scope a request needs, `path` is relative to `/api`
+fn required_scope(method: &Method, path: &str) -> AuthScope {
+    match path {
+        "/mapping/read_mapping" | "/mapping/validate" | "/script/validate" => AuthScope::Read,
+        // the adb console and the audio stream are websockets or media urls fetched by GET,
+        // but they reach into the device
+        "/mapping/change_active_mapping" | "/ws/adb" | "/audio/stream.wav" | "/audio/ws" => {
+            AuthScope::Control
+        }
+        "/config/open_data_path" | "/config/export_bundle" => AuthScope::Config,
+        _ if path.starts_with("/auth/") => AuthScope::Config,
+        // device files may be private, even listing and pulling them needs control
+        _ if path.starts_with("/device/files/") || path.starts_with("/device/apk/") => {
+            AuthScope::Control
+        }
+        _ if path.starts_with("/config/") || path.starts_with("/mapping/") => {
+            if method == Method::GET {
+                AuthScope::Read
+            } else {
+                AuthScope::Config
+            }
+        }
+        _ if method == Method::GET => AuthScope::Read,
+        _ => AuthScope::Control,
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// Bearer header first, then the `token` query parameter for WebSocket and media urls
+fn request_token(req: &Request) -> Option<String> {
+    if let Some(value) = req.headers().get(header::AUTHORIZATION) {
+        return value
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+    }
+    // the frontend percent-encodes the token, decode it like any other query value
+    Query::<TokenQuery>::try_from_uri(req.uri())
+        .ok()
+        .and_then(|Query(query)| query.token)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn find_token<'a>(config: &'a WebAuthConfig, token: &str) -> Option<&'a ApiToken> {
+    // compare against every token so the timing does not reveal which one matched
+    config.tokens.iter().fold(None, |found, api_token| {
+        if constant_time_eq(api_token.token.as_bytes(), token.as_bytes()) {
+            Some(api_token)
+        } else {
+            found
+        }
+    })
+}
+
+fn context_for(
+    config: &WebAuthConfig,
+    remote: Option<SocketAddr>,
+    token: Option<&str>,
+) -> Result<AuthContext, WebServerError> {
+    if !config.enabled
+        || (config.allow_loopback && remote.is_some_and(|addr| addr.ip().is_loopback()))
+    {
+        return Ok(AuthContext::full());
+    }
+    let token =
+        token.ok_or_else(|| WebServerError::unauthorized(t!("web.auth.authenticationRequired")))?;
+    let api_token = find_token(config, token)
+        .ok_or_else(|| WebServerError::unauthorized(t!("web.auth.invalidToken")))?;
+    Ok(AuthContext {
+        token_name: Some(api_token.name.clone()),
+        scopes: ALL_SCOPES
+            .into_iter()
+            .filter(|scope| api_token.allows(*scope))
+            .collect(),
+    })
+}
+
+pub async fn require_auth(mut req: Request, next: Next) -> Result<Response, WebServerError> {
+    let config = LocalConfig::get_web_auth();
+    let remote = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0);
+    let path = req.uri().path();
+    let path = path.strip_prefix("/api").unwrap_or(path).to_string();
+
+    let context = match context_for(&config, remote, request_token(&req).as_deref()) {
+        Ok(context) => context,
+        // the status route tells the frontend whether it has to ask for a token
+        Err(_) if path == "/auth/status" => AuthContext {
+            token_name: None,
+            scopes: Vec::new(),
+        },
+        Err(e) => return Err(e),
+    };
+
+    if path != "/auth/status" {
+        let scope = required_scope(req.method(), &path);
+        if !context.allows(scope) {
+            return Err(WebServerError::forbidden(t!(
+                "web.auth.missingScope",
+                name => context.token_name.as_deref().unwrap_or_default(),
+                scope => format!("{:?}", scope)
+            )));
+        }
+    }
+
+    req.extensions_mut().insert(context);
+    Ok(next.run(req).await)
+}
+
+async fn auth_status(Extension(context): Extension<AuthContext>) -> JsonResponse {
+    let config = LocalConfig::get_web_auth();
+    JsonResponse::success(
+        t!("web.auth.statusObtained"),
+        Some(json!({
+            "enabled": config.enabled,
+            "allow_loopback": config.allow_loopback,
+            "authenticated": !context.scopes.is_empty(),
+            "token_name": context.token_name,
+            "scopes": context.scopes,
+        })),
+    )
+}
+
+fn token_hint(token: &str) -> String {
+    let start = token.char_indices().rev().nth(3).map_or(0, |(i, _)| i);
+    format!("…{}", &token[start..])
+}
+
+async fn list_tokens() -> JsonResponse {
+    let tokens: Vec<_> = LocalConfig::get_web_auth()
+        .tokens
+        .iter()
+        .map(|token| {
+            json!({
+                "name": token.name,
+                "scopes": token.scopes,
+                "hint": token_hint(&token.token),
+            })
+        })
+        .collect();
+    JsonResponse::success(t!("web.auth.tokensObtained"), Some(json!(tokens)))
+}
+
+#[derive(Deserialize)]
+struct PostDataCreateToken {
+    name: String,
+    scopes: Vec<AuthScope>,
+}
+
+async fn create_token(
+    Json(payload): Json<PostDataCreateToken>,
+) -> Result<JsonResponse, WebServerError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err(WebServerError::bad_request(t!("web.auth.emptyTokenName")));
+    }
+    let mut config = LocalConfig::get_web_auth();
+    if config.tokens.iter().any(|token| token.name == name) {
+        return Err(WebServerError::bad_request(t!(
+            "web.auth.tokenExists",
+            name => name
+        )));
+    }
+
+    let bytes: [u8; 16] = rand::rng().random();
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let mut scopes = payload.scopes;
+    scopes.sort_by_key(|scope| *scope as u8);
+    scopes.dedup();
+    config.tokens.push(ApiToken {
+        name: name.clone(),
+        token: token.clone(),
+        scopes: scopes.clone(),
+    });
+    LocalConfig::set_web_auth(config);
+    log::info!("[WebServe] Web API token created: {}", name);
+
+    // the token is only ever returned here
+    Ok(JsonResponse::success(
+        t!("web.auth.tokenCreated", name => name),
+        Some(json!({ "name": name, "token": token, "scopes": scopes })),
+    ))
+}
+
+#[derive(Deserialize)]
+struct PostDataDeleteToken {
+    name: String,
+}
+
+async fn delete_token(
+    Extension(context): Extension<AuthContext>,
+    Json(payload): Json<PostDataDeleteToken>,
+) -> Result<JsonResponse, WebServerError> {
+    let mut config = LocalConfig::get_web_auth();
+    let len = config.tokens.len();
+    config.tokens.retain(|token| token.name != payload.name);
+    if config.tokens.len() == len {
+        return Err(WebServerError::bad_request(t!(
+            "web.auth.tokenNotFound",
+            name => payload.name
+        )));
+    }
+    check_reachable(&config, &context)?;
+    LocalConfig::set_web_auth(config);
+    log::info!("[WebServe] Web API token deleted: {}", payload.name);
+    Ok(JsonResponse::success(
+        t!("web.auth.tokenDeleted", name => payload.name),
+        None,
+    ))
+}
+
+#[derive(Deserialize)]
+struct PostDataAuthSettings {
+    enabled: bool,
+    allow_loopback: bool,
+}
+
+async fn update_settings(
+    Extension(context): Extension<AuthContext>,
+    Json(payload): Json<PostDataAuthSettings>,
+) -> Result<JsonResponse, WebServerError> {
+    let mut config = LocalConfig::get_web_auth();
+    config.enabled = payload.enabled;
+    config.allow_loopback = payload.allow_loopback;
+    check_reachable(&config, &context)?;
+    LocalConfig::set_web_auth(config);
+    warn_if_exposed(LocalConfig::get().web_bind_addr);
+    Ok(JsonResponse::success(t!("web.auth.settingsSaved"), None))
+}
+
+/// Refuses changes that would leave nobody able to change the auth settings again
+fn check_reachable(config: &WebAuthConfig, context: &AuthContext) -> Result<(), WebServerError> {
+    if !config.enabled || config.allow_loopback {
+        return Ok(());
+    }
+    if !config
+        .tokens
+        .iter()
+        .any(|token| token.allows(AuthScope::Config))
+    {
+        return Err(WebServerError::bad_request(t!(
+            "web.auth.configTokenRequired"
+        )));
+    }
+    // a remote client must keep a token that still works for itself
+    if let Some(name) = &context.token_name {
+        if !config
+            .tokens
+            .iter()
+            .any(|token| &token.name == name && token.allows(AuthScope::Config))
+        {
+            return Err(WebServerError::bad_request(t!("web.auth.ownAccessRevoked")));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WebAuthConfig {
+        WebAuthConfig {
+            enabled: true,
+            allow_loopback: true,
+            tokens: vec![ApiToken {
+                name: "viewer".to_string(),
+                token: "secret".to_string(),
+                scopes: vec![AuthScope::Read],
+            }],
+        }
+    }
+
+    #[test]
+    fn scopes_follow_routes() {
+        assert_eq!(
+            required_scope(&Method::GET, "/device/device_list"),
+            AuthScope::Read
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/device/control_device"),
+            AuthScope::Control
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/config/update_config"),
+            AuthScope::Config
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/mapping/read_mapping"),
+            AuthScope::Read
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/auth/tokens"),
+            AuthScope::Config
+        );
+        assert_eq!(required_scope(&Method::GET, "/ws/adb"), AuthScope::Control);
+        assert_eq!(
+            required_scope(&Method::GET, "/audio/stream.wav"),
+            AuthScope::Control
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/audio/ws"),
+            AuthScope::Control
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/audio/status"),
+            AuthScope::Read
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/device/files/pull"),
+            AuthScope::Control
+        );
+    }
+
+    #[test]
+    fn loopback_and_tokens() {
+        let config = config();
+        let local: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.2:5000".parse().unwrap();
+
+        assert!(context_for(&config, Some(local), None).is_ok());
+        assert!(context_for(&config, Some(remote), None).is_err());
+        assert!(context_for(&config, Some(remote), Some("wrong")).is_err());
+
+        let context = context_for(&config, Some(remote), Some("secret")).unwrap();
+        assert!(context.allows(AuthScope::Read));
+        assert!(!context.allows(AuthScope::Control));
+    }
+
+    #[test]
+    fn query_token_is_decoded() {
+        let req = Request::builder()
+            .uri("/api/audio/ws?format=s16&token=a%2Bb%20c")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(request_token(&req).as_deref(), Some("a+b c"));
+
+        let req = Request::builder()
+            .uri("/api/audio/ws?token=x")
+            .header(header::AUTHORIZATION, "Bearer secret ")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(request_token(&req).as_deref(), Some("secret"));
+
+        let req = Request::builder()
+            .uri("/api/audio/ws")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(request_token(&req), None);
+    }
+}
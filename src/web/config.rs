@@ -4,44 +4,81 @@ use axum::{
     routing::{get, post},
 };
 use rust_i18n::t;
-use serde::Deserialize;
-use std::net::Ipv4Addr;
-use tokio::sync::oneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::{collections::BTreeMap, fs};
+use tokio::sync::{broadcast, oneshot};
 
 use crate::{
-    config::{AUDIO_BIT_RATE_MIN, LocalConfig},
-    is_available_language,
-    mask::mask_command::MaskCommand,
+    config::{ApplyMode, CONFIG_SCHEMA, LocalConfig, config_field},
+    mask::{
+        mapping::config::{
+            MappingConfig, format_mapping_diagnostics, save_mapping_config,
+            validate_mapping_config_diagnostics,
+        },
+        mask_command::MaskCommand,
+    },
     scrcpy::{
         adb::Adb,
-        media::{AudioCodec, AudioSource, VideoCodec},
+        audio::{
+            audio_output_devices, set_audio_output_device, set_audio_output_gain,
+            set_audio_output_muted,
+        },
     },
     utils::{
-        IDENTIFIER, check_for_update, get_mask_scale_factor, mask_win_move_helper,
+        IDENTIFIER, check_for_update, get_mask_scale_factor, is_safe_file_name,
+        mask_win_move_helper, relate_to_data_path,
         share::{ControlledDevice, UpdateInfo},
     },
-    web::{JsonResponse, WebServerError},
+    web::{JsonResponse, WebServerError, auth, mapping, ws::WebSocketNotification},
 };
 
+const BUNDLE_VERSION: u32 = 1;
+// never leave the machine, or would be wrong on another one
+const BUNDLE_LOCAL_KEYS: [&str; 7] = [
+    "web_port",
+    "web_bind_addr",
+    "controller_port",
+    "web_auth",
+    "adb_path",
+    "adb_connect_address",
+    "active_profile",
+];
+
 #[derive(Debug, Clone)]
 pub struct AppStatConfig {
     m_tx: crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    ws_tx: broadcast::Sender<WebSocketNotification>,
 }
 
 pub fn routers(
     m_tx: crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    ws_tx: broadcast::Sender<WebSocketNotification>,
 ) -> Router {
     Router::new()
         .route("/get_config", get(get_config))
+        .route("/schema", get(get_schema))
         .route("/update_config", post(update_config))
+        .route("/update_config_batch", post(update_config_batch))
         .route("/open_data_path", get(open_data_path))
         .route("/get_update_info", get(get_update_info))
         .route("/check_update", get(check_update))
-        .with_state(AppStatConfig { m_tx })
+        .route("/audio_output_devices", get(get_audio_output_devices))
+        .route("/profiles", get(get_profiles))
+        .route("/profiles/save", post(save_profile))
+        .route("/profiles/apply", post(apply_profile))
+        .route("/profiles/delete", post(delete_profile))
+        .route("/export_bundle", get(export_bundle))
+        .route("/import_bundle", post(import_bundle))
+        .with_state(AppStatConfig { m_tx, ws_tx })
 }
 
 async fn get_config() -> Result<JsonResponse, WebServerError> {
-    let config = LocalConfig::get();
+    let mut config = LocalConfig::get();
+    // token secrets are only shown once, when they are created
+    for token in config.web_auth.tokens.iter_mut() {
+        token.token.clear();
+    }
     Ok(JsonResponse::success(
         t!("web.config.getLocalConfigSuccess"),
         Some(serde_json::to_value(&config).unwrap()),
@@ -83,6 +120,17 @@ async fn check_update() -> Result<JsonResponse, WebServerError> {
     };
 }
 
+async fn get_audio_output_devices() -> Result<JsonResponse, WebServerError> {
+    let devices = tokio::task::spawn_blocking(audio_output_devices)
+        .await
+        .map_err(|e| WebServerError::internal_error(e.to_string()))?
+        .map_err(WebServerError::internal_error)?;
+    Ok(JsonResponse::success(
+        "Audio output devices obtained",
+        Some(serde_json::to_value(&devices).unwrap()),
+    ))
+}
+
 #[derive(Deserialize)]
 struct PostDataUpdateConfig {
     key: String,
@@ -124,583 +172,600 @@ fn i32_to_logical(value: i64, scale_factor: f32) -> i32 {
     (value as f32 / scale_factor).round() as i32
 }
 
+async fn get_schema() -> JsonResponse {
+    JsonResponse::success(
+        "Config schema obtained",
+        Some(serde_json::to_value(CONFIG_SCHEMA).unwrap()),
+    )
+}
+
 async fn update_config(
     State(state): State<AppStatConfig>,
     Json(payload): Json<PostDataUpdateConfig>,
 ) -> Result<JsonResponse, WebServerError> {
-    // sync with src/config.rs
-    match payload.key.as_str() {
-        "language" => {
-            if let Some(value) = payload.value.as_str() {
-                if !is_available_language(value) {
-                    return Err(WebServerError::bad_request(format!(
-                        "{}: {}",
-                        t!("web.config.invalidLanguage"),
-                        value
-                    )));
-                }
-                rust_i18n::set_locale(value);
-                LocalConfig::set_language(value.to_string());
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.setLanguageSuccess"), value),
-                    None,
-                ));
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.languageMustBeString"
-                )));
-            }
-        }
-        "web_port" => {
-            if let Some(value) = payload.value.as_u64() {
-                LocalConfig::set_web_port(value as u16);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.restartToApplyWebPort"), value),
-                    None,
-                ));
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.webPortMustBeU16"
-                )));
-            }
-        }
-        "web_bind_addr" => {
-            if let Some(value) = payload.value.as_str() {
-                let value = value.trim();
-                let addr = value.parse::<Ipv4Addr>().map_err(|_| {
-                    WebServerError::bad_request(format!(
-                        "{}: {}",
-                        t!("web.config.webBindAddrMustBeIpv4"),
-                        value
-                    ))
-                })?;
-                LocalConfig::set_web_bind_addr(addr);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.restartToApplyWebBindAddr"), addr),
-                    None,
-                ));
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.webBindAddrMustBeIpv4"
-                )));
-            }
-        }
-        "adb_path" => {
-            if let Some(value) = payload.value.as_str() {
-                match Adb::check_adb_path(value) {
-                    Ok(_) => {
-                        LocalConfig::set_adb_path(value.to_string());
-                        return Ok(JsonResponse::success(
-                            format!("{}: {}", t!("web.config.adbPathSetSuccess"), value),
-                            None,
-                        ));
-                    }
-                    Err(e) => {
-                        return Err(WebServerError::bad_request(format!(
-                            "{}: {}",
-                            t!("web.config.adbPathSetFailed"),
-                            e
-                        )));
-                    }
-                }
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.adbPathMustBeString"
-                )));
-            }
-        }
-        "adb_connect_address" => {
-            if let Some(value) = payload.value.as_str() {
-                let address = value.trim();
-                LocalConfig::set_adb_connect_address(address.to_string());
-                return Ok(JsonResponse::success(
-                    format!(
-                        "{}: {}",
-                        t!("web.config.setAdbConnectAddressSuccess"),
-                        address
-                    ),
-                    None,
-                ));
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.adbConnectAddressMustBeString"
-                )));
-            }
-        }
-        "controller_port" => {
-            if let Some(value) = payload.value.as_u64() {
-                LocalConfig::set_controller_port(value as u16);
-                return Ok(JsonResponse::success(
-                    format!(
-                        "{}: {}",
-                        t!("web.config.restartToApplyControllerPort"),
-                        value
-                    ),
-                    None,
-                ));
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.controllerPortMustBeU16"
-                )));
-            }
-        }
-        "always_on_top" => {
-            if let Some(value) = payload.value.as_bool() {
-                LocalConfig::set_always_on_top(value);
-                let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
-                state
-                    .m_tx
-                    .send((MaskCommand::WinSwitchLevel { top: value }, oneshot_tx))
-                    .unwrap();
-                let msg = oneshot_rx.await.unwrap().unwrap();
-                return Ok(JsonResponse::success(msg, None));
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.alwaysOnTopMustBeBool"
-                )));
-            }
+    let mut values = Map::new();
+    values.insert(payload.key, payload.value);
+    apply_config_updates(&state, values, payload.space).await
+}
+
+#[derive(Deserialize)]
+struct PostDataUpdateConfigBatch {
+    values: Map<String, Value>,
+    #[serde(default)]
+    space: PixelSpace,
+}
+
+/// All values are checked first, then stored together or not at all
+async fn update_config_batch(
+    State(state): State<AppStatConfig>,
+    Json(payload): Json<PostDataUpdateConfigBatch>,
+) -> Result<JsonResponse, WebServerError> {
+    if payload.values.is_empty() {
+        return Err(WebServerError::bad_request(t!("web.config.noConfigValues")));
+    }
+    apply_config_updates(&state, payload.values, payload.space).await
+}
+
+/// Mask pixel values sent in physical pixels are stored in logical pixels
+async fn to_logical_pixels(
+    values: &mut Map<String, Value>,
+    space: PixelSpace,
+    state: &AppStatConfig,
+) -> Result<(), WebServerError> {
+    let pixel_keys: Vec<String> = values
+        .keys()
+        .filter(|key| config_field(key).is_some_and(|field| field.pixels))
+        .cloned()
+        .collect();
+    if pixel_keys.is_empty() {
+        return Ok(());
+    }
+    let scale_factor = scale_factor_for_pixel_space(space, state).await?;
+    for key in pixel_keys {
+        let value = values.get_mut(&key).unwrap();
+        let logical = match &*value {
+            Value::Number(n) => n.as_u64().map(|n| json!(u32_to_logical(n, scale_factor))),
+            Value::Array(point) => Some(Value::Array(
+                point
+                    .iter()
+                    .map(|n| {
+                        n.as_i64()
+                            .map_or_else(|| n.clone(), |n| json!(i32_to_logical(n, scale_factor)))
+                    })
+                    .collect(),
+            )),
+            _ => None,
+        };
+        // values of the wrong type are left for the schema check to reject
+        if let Some(logical) = logical {
+            *value = logical;
         }
-        "titlebar_visible" => {
-            if let Some(value) = payload.value.as_bool() {
-                LocalConfig::set_titlebar_visible(value);
-                let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
-                state
-                    .m_tx
-                    .send((MaskCommand::ToggleTitlebar, oneshot_tx))
-                    .unwrap();
-                let msg = oneshot_rx.await.unwrap().unwrap();
-                return Ok(JsonResponse::success(msg, None));
-            } else {
-                return Err(WebServerError::bad_request(t!(
-                    "web.config.titlebarVisibleMustBeBool"
+    }
+    Ok(())
+}
+
+/// Checks that need the system, the rest is covered by the schema
+async fn check_with_system(values: &Map<String, Value>) -> Result<(), WebServerError> {
+    if let Some(path) = values.get("adb_path").and_then(Value::as_str) {
+        Adb::check_adb_path(path).map_err(|e| {
+            WebServerError::bad_request(format!("{}: {}", t!("web.config.adbPathSetFailed"), e))
+        })?;
+    }
+    if let Some(device) = values.get("audio_output_device").and_then(Value::as_str) {
+        if !device.is_empty() {
+            let devices = tokio::task::spawn_blocking(audio_output_devices)
+                .await
+                .map_err(|e| WebServerError::internal_error(e.to_string()))?
+                .map_err(WebServerError::internal_error)?;
+            if !devices.devices.iter().any(|d| d == device) {
+                return Err(WebServerError::bad_request(format!(
+                    "Audio output device not found: {}",
+                    device
                 )));
             }
         }
-        "vertical_mask_height" => {
-            if let Some(value) = payload.value.as_u64() {
-                let scale_factor = scale_factor_for_pixel_space(payload.space, &state).await?;
-                LocalConfig::set_vertical_mask_height(u32_to_logical(value, scale_factor));
-                if let Some(main_device) = ControlledDevice::get_main_device().await {
-                    let (device_w, device_h) = main_device.device_size;
-                    let msg = mask_win_move_helper(device_w, device_h, &state.m_tx).await;
-                    return Ok(JsonResponse::success(
-                        format!("{}. {}", t!("web.config.setVerticalMaskHeightSuccess"), msg),
-                        None,
-                    ));
-                }
-                return Ok(JsonResponse::success(
-                    format!("{}", t!("web.config.setVerticalMaskHeightSuccess")),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.verticalMaskHeightMustBeu32"
-            )));
-        }
-        "horizontal_mask_width" => {
-            if let Some(value) = payload.value.as_u64() {
-                let scale_factor = scale_factor_for_pixel_space(payload.space, &state).await?;
-                LocalConfig::set_horizontal_mask_width(u32_to_logical(value, scale_factor));
-                if let Some(main_device) = ControlledDevice::get_main_device().await {
-                    let (device_w, device_h) = main_device.device_size;
-                    let msg = mask_win_move_helper(device_w, device_h, &state.m_tx).await;
-                    return Ok(JsonResponse::success(
-                        format!(
-                            "{}. {}",
-                            t!("web.config.setHorizontalMaskWidthSuccess"),
-                            msg
-                        ),
-                        None,
-                    ));
-                }
-                return Ok(JsonResponse::success(
-                    t!("web.config.setHorizontalMaskWidthSuccess"),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.horizontalMaskWidthMustBeu32"
-            )));
-        }
-        "vertical_position" => {
-            if let Some(value) = payload.value.as_array() {
-                if value.len() == 2 {
-                    if let (Some(x), Some(y)) = (value[0].as_i64(), value[1].as_i64()) {
-                        let scale_factor =
-                            scale_factor_for_pixel_space(payload.space, &state).await?;
-                        LocalConfig::set_vertical_position((
-                            i32_to_logical(x, scale_factor),
-                            i32_to_logical(y, scale_factor),
-                        ));
-                        if let Some(main_device) = ControlledDevice::get_main_device().await {
-                            let (device_w, device_h) = main_device.device_size;
-                            let msg = mask_win_move_helper(device_w, device_h, &state.m_tx).await;
-                            return Ok(JsonResponse::success(
-                                format!("{}. {}", t!("web.config.setVerticalPositionSuccess"), msg),
-                                None,
-                            ));
-                        }
-                        return Ok(JsonResponse::success(
-                            format!("{}", t!("web.config.setVerticalPositionSuccess")),
-                            None,
-                        ));
-                    }
-                }
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.verticalPositionTypeError"
-            )));
-        }
-        "horizontal_position" => {
-            if let Some(value) = payload.value.as_array() {
-                if value.len() == 2 {
-                    if let (Some(x), Some(y)) = (value[0].as_i64(), value[1].as_i64()) {
-                        let scale_factor =
-                            scale_factor_for_pixel_space(payload.space, &state).await?;
-                        LocalConfig::set_horizontal_position((
-                            i32_to_logical(x, scale_factor),
-                            i32_to_logical(y, scale_factor),
-                        ));
-                        if let Some(main_device) = ControlledDevice::get_main_device().await {
-                            let (device_w, device_h) = main_device.device_size;
-                            let msg = mask_win_move_helper(device_w, device_h, &state.m_tx).await;
-                            return Ok(JsonResponse::success(
-                                format!(
-                                    "{}. {}",
-                                    t!("web.config.setHorizontalPositionSuccess"),
-                                    msg
-                                ),
-                                None,
-                            ));
-                        }
-                        return Ok(JsonResponse::success(
-                            format!("{}", t!("web.config.setHorizontalPositionSuccess")),
-                            None,
-                        ));
-                    }
-                }
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.horizontalPositionTypeError"
-            )));
-        }
-        "active_mapping_file" => {
-            return Err(WebServerError::bad_request(format!(
-                "{}",
-                t!("web.config.pleaseRequestForOperation", api => "/api/mapping/change_active_mapping")
-            )));
-        }
-        "mapping_label_opacity" => {
-            if let Some(value) = payload.value.as_f64() {
-                if value <= 1.0 && value >= 0.0 {
-                    LocalConfig::set_mapping_label_opacity(value as f32);
-                    return Ok(JsonResponse::success(
-                        format!(
-                            "{}: {}",
-                            t!("web.config.setMappingLabelOpacitySuccess"),
-                            value
-                        ),
-                        None,
-                    ));
-                }
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.mappingLabelOpacityRange"
-            )));
-        }
-        "clipboard_sync" => {
-            if let Some(value) = payload.value.as_bool() {
-                LocalConfig::set_clipboard_sync(value);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.setClipboardSyncSuccess"), value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.clipboardSyncTypeError"
-            )));
-        }
-        "video_codec" => {
-            if let Some(value) = payload.value.as_str() {
-                let codec = match value {
-                    "H264" => VideoCodec::H264,
-                    "H265" => VideoCodec::H265,
-                    "AV1" => VideoCodec::AV1,
-                    _ => {
-                        return Err(WebServerError::bad_request(format!(
-                            "{}: {}",
-                            t!("web.config.invalidVideoCodec"),
-                            value
-                        )));
-                    }
-                };
-                LocalConfig::set_video_codec(codec);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.setVideoCodecSuccess"), value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.videoCodecTypeError"
-            )));
-        }
-        "video_bit_rate" => {
-            if let Some(value) = payload.value.as_u64() {
-                LocalConfig::set_video_bit_rate(value as u32);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.setVideoBitRateSuccess"), value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.videoBitRateTypeError"
-            )));
-        }
-        "video_max_size" => {
-            if let Some(value) = payload.value.as_u64() {
-                LocalConfig::set_video_max_size(value as u32);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.setVideoMaxSizeSuccess"), value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.videoMaxSizeTypeError"
-            )));
-        }
-        "video_max_fps" => {
-            if let Some(value) = payload.value.as_u64() {
-                LocalConfig::set_video_max_fps(value as u32);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.setVideoMaxFpsSuccess"), value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.videoMaxFpsTypeError"
-            )));
-        }
-        "display_id" => {
-            if let Some(value) = payload.value.as_i64() {
-                let value = i32::try_from(value).map_err(|_| {
-                    WebServerError::bad_request(t!("web.config.displayIdTypeError"))
-                })?;
-                LocalConfig::set_display_id(value);
-                return Ok(JsonResponse::success(
-                    format!("{}: {}", t!("web.config.setDisplayIdSuccess"), value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.displayIdTypeError"
-            )));
-        }
-        "new_display_enabled" => {
-            if let Some(value) = payload.value.as_bool() {
-                LocalConfig::set_new_display_enabled(value);
-                return Ok(JsonResponse::success(
-                    format!(
-                        "{}: {}",
-                        t!("web.config.setNewDisplayEnabledSuccess"),
-                        value
-                    ),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.newDisplayEnabledTypeError"
-            )));
-        }
-        "new_display_use_main_size" => {
-            if let Some(value) = payload.value.as_bool() {
-                LocalConfig::set_new_display_use_main_size(value);
-                return Ok(JsonResponse::success(
-                    format!(
-                        "{}: {}",
-                        t!("web.config.setNewDisplayUseMainSizeSuccess"),
-                        value
-                    ),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.newDisplayUseMainSizeTypeError"
-            )));
-        }
-        "new_display_width" => {
-            if let Some(value) = payload.value.as_u64() {
-                if let Ok(value) = u32::try_from(value) {
-                    if value > 0 {
-                        LocalConfig::set_new_display_width(value);
-                        return Ok(JsonResponse::success(
-                            format!("{}: {}", t!("web.config.setNewDisplayWidthSuccess"), value),
-                            None,
-                        ));
-                    }
-                }
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.newDisplayWidthTypeError"
-            )));
-        }
-        "new_display_height" => {
-            if let Some(value) = payload.value.as_u64() {
-                if let Ok(value) = u32::try_from(value) {
-                    if value > 0 {
-                        LocalConfig::set_new_display_height(value);
-                        return Ok(JsonResponse::success(
-                            format!("{}: {}", t!("web.config.setNewDisplayHeightSuccess"), value),
-                            None,
-                        ));
-                    }
-                }
-            }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.newDisplayHeightTypeError"
-            )));
+    }
+    Ok(())
+}
+
+async fn apply_config_updates(
+    state: &AppStatConfig,
+    mut values: Map<String, Value>,
+    space: PixelSpace,
+) -> Result<JsonResponse, WebServerError> {
+    if values.contains_key("active_mapping_file") {
+        return Err(WebServerError::bad_request(format!(
+            "{}",
+            t!("web.config.pleaseRequestForOperation", api => "/api/mapping/change_active_mapping")
+        )));
+    }
+    for key in ["adb_connect_address", "web_bind_addr"] {
+        if let Some(Value::String(value)) = values.get_mut(key) {
+            *value = value.trim().to_string();
         }
-        "new_display_dpi" => {
-            if let Some(value) = payload.value.as_u64() {
-                if let Ok(value) = u32::try_from(value) {
-                    if value > 0 {
-                        LocalConfig::set_new_display_dpi(value);
-                        return Ok(JsonResponse::success(
-                            format!("{}: {}", t!("web.config.setNewDisplayDpiSuccess"), value),
-                            None,
-                        ));
-                    }
-                }
+    }
+    to_logical_pixels(&mut values, space, state).await?;
+    LocalConfig::with_updates(&values).map_err(WebServerError::bad_request)?;
+    check_with_system(&values).await?;
+
+    // the config may have changed while the system was checked
+    let (previous, config) = LocalConfig::update(&values).map_err(WebServerError::bad_request)?;
+    let notes = apply_config_changes(&state.m_tx, &previous, &config, &values).await;
+
+    let keys: Vec<&str> = values.keys().map(String::as_str).collect();
+    let set_message = match keys.as_slice() {
+        [key] => config_field(key).and_then(|field| field.set_message),
+        _ => None,
+    };
+    let mut message = match set_message {
+        // these already tell when the value applies
+        Some(locale_key) => {
+            let value = &values[keys[0]];
+            let value = value
+                .as_str()
+                .map_or_else(|| value.to_string(), str::to_string);
+            format!("{}: {}", t!(locale_key), value)
+        }
+        None => {
+            let mut message = format!("{}: {}", t!("web.config.successfullySet"), keys.join(", "));
+            let applies = |mode: ApplyMode| {
+                keys.iter()
+                    .any(|key| config_field(key).is_some_and(|field| field.apply == mode))
+            };
+            if applies(ApplyMode::NextConnection) {
+                message.push_str(&format!(". {}", t!("web.config.takesEffectNextConnection")));
             }
-            return Err(WebServerError::bad_request(t!(
-                "web.config.newDisplayDpiTypeError"
-            )));
-        }
-        "audio_codec" => {
-            if let Some(value) = payload.value.as_str() {
-                let codec = match value {
-                    "OPUS" => AudioCodec::Opus,
-                    "AAC" => AudioCodec::Aac,
-                    "FLAC" => AudioCodec::Flac,
-                    "RAW" => AudioCodec::Raw,
-                    _ => {
-                        return Err(WebServerError::bad_request(format!(
-                            "Invalid audio codec: {}",
-                            value
-                        )));
-                    }
-                };
-                LocalConfig::set_audio_codec(codec);
-                return Ok(JsonResponse::success(
-                    format!("Audio codec set: {}", value),
-                    None,
-                ));
+            if applies(ApplyMode::Restart) {
+                message.push_str(&format!(". {}", t!("web.config.restartToApply")));
             }
-            return Err(WebServerError::bad_request("Audio codec must be string"));
+            message
         }
-        "audio_bit_rate" => {
-            if let Some(value) = payload.value.as_u64() {
-                let value = u32::try_from(value)
-                    .map_err(|_| WebServerError::bad_request("Audio bit rate must be u32"))?;
-                if value < AUDIO_BIT_RATE_MIN {
-                    return Err(WebServerError::bad_request(format!(
-                        "Audio bit rate must be at least {}",
-                        AUDIO_BIT_RATE_MIN
-                    )));
-                }
-                LocalConfig::set_audio_bit_rate(value);
-                return Ok(JsonResponse::success(
-                    format!("Audio bit rate set: {}", value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request("Audio bit rate must be u32"));
+    };
+    for note in notes {
+        message.push_str(". ");
+        message.push_str(&note);
+    }
+    Ok(JsonResponse::success(message, None))
+}
+
+/// Top level values of `config` that differ from `previous`
+pub(super) fn changed_values(previous: &LocalConfig, config: &LocalConfig) -> Map<String, Value> {
+    let (Ok(Value::Object(previous)), Ok(Value::Object(config))) =
+        (serde_json::to_value(previous), serde_json::to_value(config))
+    else {
+        return Map::new();
+    };
+    config
+        .into_iter()
+        .filter(|(key, value)| previous.get(key) != Some(value))
+        .collect()
+}
+
+/// Applies updated values that take effect immediately, returns notes for the reply
+pub(super) async fn apply_config_changes(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    previous: &LocalConfig,
+    config: &LocalConfig,
+    values: &Map<String, Value>,
+) -> Vec<String> {
+    let mut notes = Vec::new();
+    let mut mask_result = |result: Result<String, String>| match result {
+        Ok(msg) if !msg.is_empty() => notes.push(msg),
+        Ok(_) => {}
+        Err(e) => notes.push(e),
+    };
+
+    if values.contains_key("language") {
+        rust_i18n::set_locale(&config.language);
+    }
+    if values.contains_key("web_bind_addr") {
+        auth::warn_if_exposed(config.web_bind_addr);
+    }
+    if values.contains_key("always_on_top") {
+        mask_result(
+            send_mask_command(
+                m_tx,
+                MaskCommand::WinSwitchLevel {
+                    top: config.always_on_top,
+                },
+            )
+            .await,
+        );
+    }
+    // the mask flips its titlebar and stores the new state itself
+    if config.titlebar_visible != previous.titlebar_visible {
+        mask_result(send_mask_command(m_tx, MaskCommand::ToggleTitlebar).await);
+    }
+    if values
+        .keys()
+        .any(|key| config_field(key).is_some_and(|field| field.pixels))
+    {
+        if let Some(main_device) = ControlledDevice::get_main_device().await {
+            let (device_w, device_h) = main_device.device_size;
+            mask_result(Ok(mask_win_move_helper(device_w, device_h, m_tx).await));
         }
-        "audio_source" => {
-            if let Some(value) = payload.value.as_str() {
-                let source = match value {
-                    "OUTPUT" => AudioSource::Output,
-                    "PLAYBACK" => AudioSource::Playback,
-                    "MIC" => AudioSource::Mic,
-                    _ => {
-                        return Err(WebServerError::bad_request(format!(
-                            "Invalid audio source: {}",
-                            value
-                        )));
-                    }
-                };
-                LocalConfig::set_audio_source(source);
-                if !source.is_playback() {
-                    LocalConfig::set_audio_dup(false);
-                }
-                return Ok(JsonResponse::success(
-                    format!("Audio source set: {}", value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request("Audio source must be string"));
+    }
+    if values.contains_key("audio_output_device") {
+        set_audio_output_device(config.audio_output_device.clone());
+    }
+    if values.contains_key("audio_gain") {
+        set_audio_output_gain(config.audio_gain);
+    }
+    if values.contains_key("audio_muted") {
+        set_audio_output_muted(config.audio_muted);
+    }
+    notes
+}
+
+async fn send_mask_command(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    command: MaskCommand,
+) -> Result<String, String> {
+    let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
+    m_tx.send((command, oneshot_tx)).unwrap();
+    oneshot_rx.await.unwrap()
+}
+
+async fn get_profiles() -> JsonResponse {
+    JsonResponse::success(
+        t!("web.config.profilesObtained"),
+        Some(json!({
+            "active": LocalConfig::get().active_profile,
+            "profiles": LocalConfig::list_profiles(),
+        })),
+    )
+}
+
+#[derive(Deserialize)]
+struct PostDataProfile {
+    name: String,
+}
+
+async fn save_profile(
+    Json(payload): Json<PostDataProfile>,
+) -> Result<JsonResponse, WebServerError> {
+    LocalConfig::save_profile(&payload.name).map_err(WebServerError::bad_request)?;
+    Ok(JsonResponse::success(
+        t!("web.config.profileSaved", name => payload.name.trim()),
+        None,
+    ))
+}
+
+async fn apply_profile(
+    State(state): State<AppStatConfig>,
+    Json(payload): Json<PostDataProfile>,
+) -> Result<JsonResponse, WebServerError> {
+    let config = LocalConfig::with_profile(&payload.name).map_err(WebServerError::bad_request)?;
+    let (keys, notes) = replace_config(&state, config, false).await?;
+    let _ = state
+        .ws_tx
+        .send(WebSocketNotification::ConfigChanged { keys });
+    log::info!("[WebServe] Config profile applied: {}", payload.name.trim());
+
+    let mut message = t!("web.config.profileApplied", name => payload.name.trim()).to_string();
+    for note in notes {
+        message.push_str(". ");
+        message.push_str(&note);
+    }
+    Ok(JsonResponse::success(message, None))
+}
+
+async fn delete_profile(
+    Json(payload): Json<PostDataProfile>,
+) -> Result<JsonResponse, WebServerError> {
+    LocalConfig::delete_profile(&payload.name).map_err(WebServerError::bad_request)?;
+    Ok(JsonResponse::success(
+        t!("web.config.profileDeleted", name => payload.name.trim()),
+        None,
+    ))
+}
+
+/// Replaces the whole config and applies what differs from the current one like an update does,
+/// returns the changed keys and notes for the reply. `reload_mapping` loads the active mapping
+/// again even if its file name is unchanged
+async fn replace_config(
+    state: &AppStatConfig,
+    mut config: LocalConfig,
+    reload_mapping: bool,
+) -> Result<(Vec<String>, Vec<String>), WebServerError> {
+    let previous = LocalConfig::get();
+    let mut values = changed_values(&previous, &config);
+    let mut notes = Vec::new();
+    if reload_mapping || values.contains_key("active_mapping_file") {
+        let file = config.active_mapping_file.clone();
+        if let Err(e) = mapping::change_active_mapping_with(&state.m_tx, file).await {
+            // the mask keeps the mapping it had
+            notes.push(e.message().to_string());
+            config.active_mapping_file = previous.active_mapping_file.clone();
+            values.remove("active_mapping_file");
         }
-        "audio_dup" => {
-            if let Some(value) = payload.value.as_bool() {
-                if value && !LocalConfig::get().audio_source.is_playback() {
-                    return Err(WebServerError::bad_request(
-                        "Audio duplication requires playback source",
-                    ));
-                }
-                LocalConfig::set_audio_dup(value);
-                return Ok(JsonResponse::success(
-                    format!("Audio duplication set: {}", value),
-                    None,
-                ));
+    }
+    LocalConfig::replace(config.clone()).map_err(WebServerError::internal_error)?;
+    notes.extend(apply_config_changes(&state.m_tx, &previous, &config, &values).await);
+    Ok((values.keys().cloned().collect(), notes))
+}
+
+/// Config, profiles and mapping files of this machine in a single json file
+#[derive(Serialize, Deserialize)]
+struct ConfigBundle {
+    version: u32,
+    config: Map<String, Value>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Map<String, Value>>,
+    #[serde(default)]
+    mappings: BTreeMap<String, MappingConfig>,
+}
+
+/// Mapping files that can't be read are left out and returned by name with the reason
+fn read_mapping_files() -> Result<(BTreeMap<String, MappingConfig>, Vec<String>), String> {
+    let entries = fs::read_dir(relate_to_data_path(["mapping"]))
+        .map_err(|e| format!("{}: {}", t!("web.mapping.unableReadMappingConfigDir"), e))?;
+    let mut mappings = BTreeMap::new();
+    let mut failed = Vec::new();
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if !path.is_file() || path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+        {
+            Ok(mapping) => {
+                mappings.insert(file_name.to_string(), mapping);
             }
-            return Err(WebServerError::bad_request(
-                "Audio duplication must be bool",
-            ));
-        }
-        "stay_awake" => {
-            if let Some(value) = payload.value.as_bool() {
-                LocalConfig::set_stay_awake(value);
-                return Ok(JsonResponse::success(
-                    format!("Stay awake set: {}", value),
-                    None,
-                ));
+            Err(e) => {
+                log::warn!(
+                    "[WebServe] Mapping {} left out of the bundle: {}",
+                    file_name,
+                    e
+                );
+                failed.push(format!("{}: {}", file_name, e));
             }
-            return Err(WebServerError::bad_request("Stay awake must be bool"));
         }
-        "screen_off_timeout" => {
-            if let Some(value) = payload.value.as_i64() {
-                let value = i32::try_from(value)
-                    .map_err(|_| WebServerError::bad_request("Screen off timeout must be i32"))?;
-                if value < -1 {
-                    return Err(WebServerError::bad_request(
-                        "Screen off timeout must be -1 or greater",
-                    ));
-                }
-                LocalConfig::set_screen_off_timeout(value);
-                return Ok(JsonResponse::success(
-                    format!("Screen off timeout set: {}", value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(
-                "Screen off timeout must be i32",
+    }
+    Ok((mappings, failed))
+}
+
+/// The config values a bundle carries, everything but the local keys
+fn bundle_config(config: &LocalConfig) -> Result<Map<String, Value>, String> {
+    let mut config = match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => return Err(t!("localConfig.serializeConfigError").to_string()),
+    };
+    for key in BUNDLE_LOCAL_KEYS {
+        config.remove(key);
+    }
+    Ok(config)
+}
+
+async fn export_bundle() -> Result<JsonResponse, WebServerError> {
+    let config = bundle_config(&LocalConfig::get()).map_err(WebServerError::internal_error)?;
+    let mut profiles = BTreeMap::new();
+    for name in LocalConfig::list_profiles() {
+        let profile = LocalConfig::read_profile(&name).map_err(WebServerError::internal_error)?;
+        profiles.insert(name, profile);
+    }
+    let (mappings, failed) = read_mapping_files().map_err(WebServerError::internal_error)?;
+    let bundle = ConfigBundle {
+        version: BUNDLE_VERSION,
+        config,
+        profiles,
+        mappings,
+    };
+
+    let mut message = t!("web.config.bundleExported").to_string();
+    if !failed.is_empty() {
+        message.push_str(&format!(
+            ". {}: {}",
+            t!("web.config.bundleLeftOut"),
+            failed.join("; ")
+        ));
+    }
+    Ok(JsonResponse::success(
+        message,
+        Some(serde_json::to_value(&bundle).unwrap()),
+    ))
+}
+
+/// Checks the whole bundle against `current`, returns the config and profiles to store
+fn check_bundle(
+    current: &LocalConfig,
+    bundle: &ConfigBundle,
+) -> Result<(LocalConfig, BTreeMap<String, Map<String, Value>>), String> {
+    if bundle.version > BUNDLE_VERSION {
+        return Err(
+            t!("web.config.unsupportedBundleVersion", version => bundle.version).to_string(),
+        );
+    }
+    let config = current.overlaid(&bundle.config, |key| !BUNDLE_LOCAL_KEYS.contains(&key))?;
+    config.validate()?;
+    for (file_name, mapping) in &bundle.mappings {
+        if !file_name.ends_with(".json") || !is_safe_file_name(file_name) {
+            return Err(format!(
+                "{}: {}",
+                t!("mask.mapping.fileNameNotSafe"),
+                file_name
             ));
         }
-        "power_off_on_close" => {
-            if let Some(value) = payload.value.as_bool() {
-                LocalConfig::set_power_off_on_close(value);
-                return Ok(JsonResponse::success(
-                    format!("Power off on close set: {}", value),
-                    None,
-                ));
-            }
-            return Err(WebServerError::bad_request(
-                "Power off on close must be bool",
+        let diagnostics = validate_mapping_config_diagnostics(mapping);
+        if !diagnostics.is_empty() {
+            return Err(format!(
+                "{}: {}",
+                file_name,
+                format_mapping_diagnostics(&diagnostics)
             ));
         }
-        _ => Err(WebServerError::bad_request(format!(
-            "{}: {}",
-            t!("web.config.invalidMappingKey"),
-            payload.key
-        ))),
+    }
+    let mut profiles = BTreeMap::new();
+    for (name, values) in &bundle.profiles {
+        let values = LocalConfig::check_profile(&config, name, values)?;
+        profiles.insert(name.trim().to_string(), values);
+    }
+    Ok((config, profiles))
+}
+
+#[derive(Deserialize)]
+struct PostDataImportBundle {
+    bundle: ConfigBundle,
+    /// Replace mapping files and profiles that already exist
+    #[serde(default)]
+    overwrite: bool,
+}
+
+async fn import_bundle(
+    State(state): State<AppStatConfig>,
+    Json(payload): Json<PostDataImportBundle>,
+) -> Result<JsonResponse, WebServerError> {
+    let bundle = payload.bundle;
+    // check everything before writing anything
+    let (mut config, profiles) =
+        check_bundle(&LocalConfig::get(), &bundle).map_err(WebServerError::bad_request)?;
+
+    let mut skipped: Vec<String> = Vec::new();
+    let mut mapping_count = 0;
+    let mut active_mapping_written = false;
+    for (file_name, mapping) in &bundle.mappings {
+        let path = relate_to_data_path(["mapping", file_name]);
+        if path.exists() && !payload.overwrite {
+            skipped.push(file_name.clone());
+            continue;
+        }
+        save_mapping_config(mapping, &path).map_err(WebServerError::internal_error)?;
+        active_mapping_written |= *file_name == config.active_mapping_file;
+        mapping_count += 1;
+    }
+    // a skipped file has other content here, a missing one can't be loaded
+    let active_mapping_path = relate_to_data_path(["mapping", &config.active_mapping_file]);
+    if !active_mapping_written
+        && (skipped.contains(&config.active_mapping_file) || !active_mapping_path.exists())
+    {
+        skipped.push(format!("{} (active mapping)", config.active_mapping_file));
+        config.active_mapping_file = LocalConfig::get().active_mapping_file;
+    }
+    let existing_profiles = LocalConfig::list_profiles();
+    let mut profile_count = 0;
+    for (name, values) in &profiles {
+        if existing_profiles.contains(name) && !payload.overwrite {
+            skipped.push(format!("{} (profile)", name));
+            continue;
+        }
+        LocalConfig::write_profile(name, values).map_err(WebServerError::internal_error)?;
+        profile_count += 1;
+    }
+    let (keys, notes) = replace_config(&state, config, active_mapping_written).await?;
+    let _ = state
+        .ws_tx
+        .send(WebSocketNotification::ConfigChanged { keys });
+    log::info!(
+        "[WebServe] Config bundle imported: {} mappings, {} profiles",
+        mapping_count,
+        profile_count
+    );
+
+    let mut message = t!(
+        "web.config.bundleImported",
+        mappings => mapping_count,
+        profiles => profile_count
+    )
+    .to_string();
+    for note in notes {
+        message.push_str(". ");
+        message.push_str(&note);
+    }
+    Ok(JsonResponse::success(
+        message,
+        Some(json!({
+            "mappings": mapping_count,
+            "profiles": profile_count,
+            "skipped": skipped,
+        })),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::mapping::config::default_mapping_config;
+
+    fn bundle(value: Value) -> ConfigBundle {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn bundle_round_trip_keeps_local_keys() {
+        let exported = LocalConfig {
+            web_port: 9000,
+            adb_path: "/a/adb".to_string(),
+            video_bit_rate: 4_000_000,
+            ..Default::default()
+        };
+        let text = serde_json::to_string(&ConfigBundle {
+            version: BUNDLE_VERSION,
+            config: bundle_config(&exported).unwrap(),
+            profiles: BTreeMap::from([(
+                "game".to_string(),
+                json!({ "video_max_fps": 90, "web_port": 1 })
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            )]),
+            mappings: BTreeMap::from([("a.json".to_string(), default_mapping_config())]),
+        })
+        .unwrap();
+        assert!(!text.contains("/a/adb"));
+
+        let current = LocalConfig {
+            web_port: 8000,
+            adb_path: "/b/adb".to_string(),
+            ..Default::default()
+        };
+        let (config, profiles) =
+            check_bundle(&current, &serde_json::from_str(&text).unwrap()).unwrap();
+        assert_eq!(config.video_bit_rate, 4_000_000);
+        assert_eq!(config.web_port, 8000);
+        assert_eq!(config.adb_path, "/b/adb");
+        // only profile keys are kept
+        assert_eq!(
+            Value::Object(profiles["game"].clone()),
+            json!({ "video_max_fps": 90 })
+        );
+    }
+
+    #[test]
+    fn bad_bundles_are_rejected() {
+        let current = LocalConfig::default();
+        let rejected = |value: Value| check_bundle(&current, &bundle(value)).is_err();
+        let mapping = serde_json::to_value(default_mapping_config()).unwrap();
+        let mut empty_size = mapping.clone();
+        empty_size["original_size"]["width"] = json!(0);
+
+        assert!(!rejected(json!({ "version": 1, "config": {} })));
+        assert!(rejected(json!({ "version": 2, "config": {} })));
+        assert!(rejected(
+            json!({ "version": 1, "config": { "language": "xx" } })
+        ));
+        assert!(rejected(json!({
+            "version": 1,
+            "config": {},
+            "mappings": { "../a.json": mapping },
+        })));
+        assert!(rejected(json!({
+            "version": 1,
+            "config": {},
+            "mappings": { "a.json": empty_size },
+        })));
+        assert!(rejected(json!({
+            "version": 1,
+            "config": {},
+            "profiles": { "a/b": {} },
+        })));
+        assert!(rejected(json!({
+            "version": 1,
+            "config": {},
+            "profiles": { "game": { "video_max_fps": "fast" } },
+        })));
     }
 }
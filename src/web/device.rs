@@ -1,4 +1,4 @@
-use std::{collections::BTreeMap, time::Duration};
+use std::{collections::BTreeMap, net::Ipv4Addr, sync::Mutex, time::Duration};
 
 use axum::{
     Json, Router,
@@ -7,30 +7,62 @@ use axum::{
     response::IntoResponse,
     routing::{get, post},
 };
+use once_cell::sync::Lazy;
 use rand::Rng;
 use rust_i18n::t;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio::{
-    sync::{broadcast, mpsc::UnboundedSender},
-    time::sleep,
+    net::{TcpListener, TcpStream},
+    sync::{Mutex as AsyncMutex, broadcast, mpsc::UnboundedSender, oneshot},
+    task::JoinHandle,
+    time::{sleep, timeout},
 };
 
 use crate::{
     config::LocalConfig,
     scrcpy::{
+        adaptive_video::AdaptiveVideoController,
         adb::{Adb, Device},
+        audio::current_audio_stats,
         constant::Keycode,
         control_msg::ScrcpyControlMsg,
         controller::ControllerCommand,
         device_action,
         media::AudioCodec,
+        video_stats::current_video_stats,
     },
-    utils::{relate_to_root_path, share::ControlledDevice},
-    web::{JsonResponse, WebServerError, ws::WebSocketNotification},
+    utils::{relate_to_root_path, run_mode, share::ControlledDevice},
+    web::{JsonResponse, WebServerError, device_files, ws::WebSocketNotification},
 };
 
 const SCRCPY_SERVER_VERSION: &str = "4.0";
+const SCRCPY_SERVER_DEVICE_PATH: &str = "/data/local/tmp/scrcpy-server.jar";
+const ADAPTIVE_VIDEO_INTERVAL: Duration = Duration::from_secs(1);
+// how long a video-only server gets to connect its video socket
+const VIDEO_SERVER_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// how long a replaced video-only server gets to exit once its socket is closed
+const VIDEO_SERVER_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A video-only server streaming in place of the main server's own video
+struct VideoServer {
+    scid: String,
+    handle: JoinHandle<Result<(), String>>,
+}
+
+/// Display and video arguments the main device's video stream currently runs with
+struct MainVideoLaunch {
+    scid: String,
+    device_id: String,
+    display_args: Vec<String>,
+    /// `None` while no stream runs, e.g. after a failed reconfiguration
+    video_args: Option<Vec<String>>,
+    video_server: Option<VideoServer>,
+}
+
+static MAIN_VIDEO_LAUNCH: Lazy<Mutex<Option<MainVideoLaunch>>> = Lazy::new(|| Mutex::new(None));
+// the api and adaptive video may both reconfigure, one at a time
+static RECONFIGURE_VIDEO_LOCK: Lazy<AsyncMutex<()>> = Lazy::new(|| AsyncMutex::new(()));
 
 #[derive(Debug, Clone)]
 pub struct AppStateDevice {
@@ -46,9 +78,12 @@ pub fn routers(
 ) -> Router {
     Router::new()
         .route("/device_list", get(device_list))
+        .route("/stats", get(stream_stats))
+        .route("/audio_stats", get(audio_stats))
         .route("/control_device", post(control_device))
         .route("/decontrol_device", post(decontrol_device))
         .route("/reconnect_device", post(reconnect_device))
+        .route("/reconfigure_video", post(reconfigure_video))
         .route("/adb_connect", post(adb_connect))
         .route("/adb_pair", post(adb_pair))
         .route("/adb_restart", post(adb_restart))
@@ -59,10 +94,12 @@ pub fn routers(
         .route("/control/set_display_power", post(set_display_power))
         .route("/control/set_pointer_location", post(set_pointer_location))
         .route("/control/send_key", post(send_key))
+        .nest("/files", device_files::file_routers(ws_tx.clone()))
+        .nest("/apk", device_files::apk_routers(ws_tx.clone()))
         .with_state(AppStateDevice { cs_tx, d_tx, ws_tx })
 }
 
-async fn device_list() -> Result<JsonResponse, WebServerError> {
+pub(super) async fn device_list() -> Result<JsonResponse, WebServerError> {
     let controlled_devices = ControlledDevice::get_device_list().await;
     let config = LocalConfig::get();
     let all_devices = Adb::new(config.adb_path)
@@ -78,6 +115,116 @@ async fn device_list() -> Result<JsonResponse, WebServerError> {
     ))
 }
 
+async fn stream_stats() -> Result<JsonResponse, WebServerError> {
+    Ok(JsonResponse::success(
+        "Stream stats obtained",
+        Some(json!({
+            "video": current_video_stats(),
+            "audio": current_audio_stats(),
+        })),
+    ))
+}
+
+async fn audio_stats() -> Result<JsonResponse, WebServerError> {
+    let stats = current_audio_stats()
+        .ok_or_else(|| WebServerError::bad_request("No audio stream is playing"))?;
+    Ok(JsonResponse::success(
+        "Audio stats obtained",
+        Some(json!(stats)),
+    ))
+}
+
+fn server_base_args(scid: &str) -> Vec<String> {
+    let mut args = [
+        "CLASSPATH=/data/local/tmp/scrcpy-server.jar",
+        "app_process",
+        "/",
+        "com.genymobile.scrcpy.Server",
+    ]
+    .iter_mut()
+    .map(|arg| arg.to_string())
+    .collect::<Vec<String>>();
+
+    args.push(SCRCPY_SERVER_VERSION.to_string());
+    args.push(format!("scid={}", scid));
+    args
+}
+
+fn video_args(local_config: &LocalConfig) -> Vec<String> {
+    let mut args = vec![
+        format!("video_codec={}", local_config.video_codec),
+        format!("video_bit_rate={}", local_config.video_bit_rate),
+    ];
+    if local_config.video_max_size > 0 {
+        args.push(format!("max_size={}", local_config.video_max_size));
+    }
+    if local_config.video_max_fps > 0 {
+        args.push(format!("max_fps={}", local_config.video_max_fps));
+    }
+    args
+}
+
+fn push_scrcpy_server(device_id: &str) -> Result<(), WebServerError> {
+    let scrcpy_path = relate_to_root_path([
+        "assets",
+        &format!("scrcpy-mask-server-v{}", SCRCPY_SERVER_VERSION),
+    ]);
+    Device::push(
+        device_id,
+        scrcpy_path.to_str().unwrap(),
+        SCRCPY_SERVER_DEVICE_PATH,
+    )
+    .map_err(WebServerError::internal_error)?;
+    log::info!("[WebServe] {}", t!("web.device.pushScrcpyServerSuccess"));
+    Ok(())
+}
+
+fn scrcpy_socket_name(scid: &str) -> String {
+    format!("localabstract:scrcpy_{}", scid)
+}
+
+fn reverse_scrcpy_socket(
+    device_id: &str,
+    scid: &str,
+    controller_port: u16,
+) -> Result<(), WebServerError> {
+    let remote = scrcpy_socket_name(scid);
+    let local = format!("tcp:{}", controller_port);
+    Device::reverse(device_id, &remote, &local).map_err(WebServerError::internal_error)?;
+    log::info!(
+        "[WebServe] {}",
+        t!("web.device.reverseSuccess", remote => remote, local => local)
+    );
+    Ok(())
+}
+
+/// Removes the reverse tunnel of one server once dropped, the connection made through it stays
+struct ReverseTunnelGuard {
+    device_id: String,
+    remote: String,
+}
+
+impl Drop for ReverseTunnelGuard {
+    fn drop(&mut self) {
+        let device_id = std::mem::take(&mut self.device_id);
+        let remote = std::mem::take(&mut self.remote);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) =
+                Adb::new(LocalConfig::get().adb_path).reverse_remove(&device_id, &remote)
+            {
+                log::warn!("[WebServe] {}", e);
+            }
+        });
+    }
+}
+
+fn clear_main_video_launch(scid: &str) {
+    let mut launch = MAIN_VIDEO_LAUNCH.lock().unwrap();
+    if launch.as_ref().is_some_and(|launch| launch.scid == scid) {
+        *launch = None;
+    }
+}
+
 fn gen_scid() -> String {
     let mut rng = rand::rng();
     let suffix: String = (0..6)
@@ -118,56 +265,39 @@ async fn _control_device(
     }
     let main = device_list.len() == 0;
     let audio = audio && main;
+    let video = if video && run_mode().is_headless() {
+        log::warn!("[WebServe] Nothing shows the video in headless mode, controlling without it");
+        false
+    } else {
+        video
+    };
 
     // prepare for scrcpy app
     let scid = gen_scid();
-    let scrcpy_path = relate_to_root_path([
-        "assets",
-        &format!("scrcpy-mask-server-v{}", SCRCPY_SERVER_VERSION),
-    ]);
-    Device::push(
-        &device_id,
-        scrcpy_path.to_str().unwrap(),
-        "/data/local/tmp/scrcpy-server.jar",
-    )
-    .map_err(WebServerError::internal_error)?;
-    log::info!("[WebServe] {}", t!("web.device.pushScrcpyServerSuccess"));
-
-    let remote = format!("localabstract:scrcpy_{}", scid);
-    let local = format!("tcp:{}", local_config.controller_port);
-    Device::reverse(&device_id, &remote, &local).map_err(WebServerError::internal_error)?;
-    log::info!(
-        "[WebServe] {}",
-        t!("web.device.reverseSuccess", remote => remote, local => local)
-    );
-
-    let mut args = [
-        "CLASSPATH=/data/local/tmp/scrcpy-server.jar",
-        "app_process",
-        "/",
-        "com.genymobile.scrcpy.Server",
-    ]
-    .iter_mut()
-    .map(|arg| arg.to_string())
-    .collect::<Vec<String>>();
-
-    args.push(SCRCPY_SERVER_VERSION.to_string());
-    args.push(format!("scid={}", scid));
-    args.push(format!("video={}", video));
-    if video && local_config.new_display_enabled {
+    push_scrcpy_server(&device_id)?;
+    reverse_scrcpy_socket(&device_id, &scid, local_config.controller_port)?;
+
+    // the main display is streamed by a video-only server that can be replaced on its own
+    // when the video settings change. A new virtual display is streamed by the main server,
+    // a second server would create another display
+    let separate_video = main && video && !local_config.new_display_enabled;
+    let mut args = server_base_args(&scid);
+    args.push(format!("video={}", video && !separate_video));
+    let display_args = if video && local_config.new_display_enabled {
         if local_config.new_display_use_main_size {
-            args.push("new_display=".to_string());
+            vec!["new_display=".to_string()]
         } else {
-            args.push(format!(
+            vec![format!(
                 "new_display={}x{}/{}",
                 local_config.new_display_width,
                 local_config.new_display_height,
                 local_config.new_display_dpi
-            ));
+            )]
         }
     } else {
-        args.push(format!("display_id={}", local_config.display_id));
-    }
+        vec![format!("display_id={}", local_config.display_id)]
+    };
+    args.extend(display_args.iter().cloned());
     args.push(format!("audio={}", audio));
     args.push(format!("stay_awake={}", local_config.stay_awake));
     args.push(format!(
@@ -186,20 +316,24 @@ async fn _control_device(
         let mut meta_flag = true;
         if video {
             socket_id.push("main_video".to_string());
-            commands.push(ControllerCommand::ConnectMainVideo(scid.clone(), meta_flag));
-            if meta_flag {
-                meta_flag = false;
-            }
+            let video_args = video_args(&local_config);
+            if !separate_video {
+                commands.push(ControllerCommand::ConnectMainVideo(scid.clone(), meta_flag));
+                if meta_flag {
+                    meta_flag = false;
+                }
 
-            // video shell args
-            args.push(format!("video_codec={}", local_config.video_codec));
-            args.push(format!("video_bit_rate={}", local_config.video_bit_rate));
-            if local_config.video_max_size > 0 {
-                args.push(format!("max_size={}", local_config.video_max_size));
-            }
-            if local_config.video_max_fps > 0 {
-                args.push(format!("max_fps={}", local_config.video_max_fps));
+                // video shell args
+                args.extend(video_args.iter().cloned());
             }
+            *MAIN_VIDEO_LAUNCH.lock().unwrap() = Some(MainVideoLaunch {
+                scid: scid.clone(),
+                device_id: device_id.clone(),
+                display_args: display_args.clone(),
+                // the video-only server records its arguments once it streams
+                video_args: (!separate_video).then_some(video_args),
+                video_server: None,
+            });
         }
         if audio {
             socket_id.push("main_audio".to_string());
@@ -240,12 +374,39 @@ async fn _control_device(
 
     let h = Device::shell_process(&device_id, args);
 
+    if separate_video {
+        let device_id = device_id.clone();
+        let scid = scid.clone();
+        let video_args = video_args(&local_config);
+        let d_tx = d_tx.clone();
+        tokio::spawn(async move {
+            let _reconfiguring = RECONFIGURE_VIDEO_LOCK.lock().await;
+            if let Err(e) =
+                start_main_video(&device_id, &scid, &display_args, &video_args, &d_tx).await
+            {
+                log::error!("[WebServe] {}", e.message());
+            }
+        });
+    }
+
     let scid_copy = scid.clone();
     let ws_tx_copy = ws_tx.clone();
+    let d_tx_copy = d_tx.clone();
     tokio::spawn(async move {
         h.await.unwrap().unwrap();
         log::info!("[WebServe] {}", t!("web.device.removingDeviceAfterExit"));
+        // a decontrolled device is already removed, its video was closed with it
+        if separate_video && ControlledDevice::is_scid_controlled(&scid_copy).await {
+            let (reply_tx, _) = oneshot::channel();
+            d_tx_copy
+                .send(ControllerCommand::ReleaseMainVideo(
+                    scid_copy.clone(),
+                    reply_tx,
+                ))
+                .ok();
+        }
         ControlledDevice::remove_device(&scid_copy).await;
+        clear_main_video_launch(&scid_copy);
         ws_tx_copy
             .send(WebSocketNotification::ScrcpyDeviceConnection {
                 scid: scid_copy,
@@ -310,6 +471,293 @@ async fn reconnect_device(
     )))
 }
 
+/// Apply the current video settings to the main device without reconnecting it
+async fn reconfigure_video(
+    State(state): State<AppStateDevice>,
+) -> Result<JsonResponse, WebServerError> {
+    _reconfigure_video(&LocalConfig::get(), &state.d_tx).await
+}
+
+async fn _reconfigure_video(
+    local_config: &LocalConfig,
+    d_tx: &UnboundedSender<ControllerCommand>,
+) -> Result<JsonResponse, WebServerError> {
+    let _reconfiguring = RECONFIGURE_VIDEO_LOCK.lock().await;
+    let main_device = ControlledDevice::get_main_device()
+        .await
+        .ok_or_else(|| WebServerError::bad_request(t!("web.device.noMainDevice")))?;
+    let new_video_args = video_args(local_config);
+
+    let (device_id, display_args, old_video_args, old_server) = {
+        let mut launch = MAIN_VIDEO_LAUNCH.lock().unwrap();
+        let launch = launch
+            .as_mut()
+            .filter(|launch| launch.scid == main_device.scid)
+            .ok_or_else(|| WebServerError::bad_request(t!("web.device.noMainVideo")))?;
+        if launch
+            .display_args
+            .iter()
+            .any(|arg| arg.starts_with("new_display="))
+        {
+            // the main server streams a new virtual display itself, a second server would
+            // create another display instead of mirroring ours
+            return Err(WebServerError::bad_request(t!(
+                "web.device.newDisplayVideoNextConnection"
+            )));
+        }
+        (
+            launch.device_id.clone(),
+            launch.display_args.clone(),
+            launch.video_args.take(),
+            launch.video_server.take(),
+        )
+    };
+
+    // stop the running stream first, devices may not run two encoders at once.
+    // Closing the socket of a video-only server stops it along with its encoder
+    let (reply_tx, reply_rx) = oneshot::channel();
+    d_tx.send(ControllerCommand::ReleaseMainVideo(
+        main_device.scid.clone(),
+        reply_tx,
+    ))
+    .map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    reply_rx
+        .await
+        .map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    if let Some(VideoServer { scid, handle }) = old_server {
+        if timeout(VIDEO_SERVER_EXIT_TIMEOUT, handle).await.is_err() {
+            log::warn!(
+                "[WebServe] Video-only scrcpy server {} did not exit in time",
+                scid
+            );
+        }
+    }
+
+    match start_main_video(
+        &device_id,
+        &main_device.scid,
+        &display_args,
+        &new_video_args,
+        d_tx,
+    )
+    .await
+    {
+        Ok(video_scid) => Ok(JsonResponse::success(
+            t!("web.device.videoReconfigured"),
+            Some(json!({ "scid": main_device.scid, "video_scid": video_scid })),
+        )),
+        Err(e) => {
+            // bring the previous stream back rather than leave the device without video
+            if let Some(old_video_args) = old_video_args {
+                log::warn!(
+                    "[WebServe] {}, restoring the previous video stream",
+                    e.message()
+                );
+                if let Err(restore_e) = start_main_video(
+                    &device_id,
+                    &main_device.scid,
+                    &display_args,
+                    &old_video_args,
+                    d_tx,
+                )
+                .await
+                {
+                    log::error!(
+                        "[WebServe] Failed to restore the previous video stream: {}",
+                        restore_e.message()
+                    );
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Streams the main display from a new video-only server and hands it to the controller.
+/// Only a running stream is recorded, so a failed attempt is retried with the same settings
+async fn start_main_video(
+    device_id: &str,
+    scid: &str,
+    display_args: &[String],
+    video_args: &[String],
+    d_tx: &UnboundedSender<ControllerCommand>,
+) -> Result<String, WebServerError> {
+    let video_scid = gen_scid();
+    let (video_server, socket) =
+        start_video_server(device_id, &video_scid, display_args, video_args).await?;
+    d_tx.send(ControllerCommand::AttachMainVideo(
+        scid.to_string(),
+        video_scid.clone(),
+        socket,
+    ))
+    .map_err(|e| WebServerError::internal_error(e.to_string()))?;
+
+    if let Some(launch) = MAIN_VIDEO_LAUNCH
+        .lock()
+        .unwrap()
+        .as_mut()
+        .filter(|launch| launch.scid == scid)
+    {
+        launch.video_args = Some(video_args.to_vec());
+        launch.video_server = Some(video_server);
+    }
+    Ok(video_scid)
+}
+
+/// Starts a video-only server streaming the main display and waits for its video socket.
+/// It connects to a listener of its own, so the controller never waits on it.
+async fn start_video_server(
+    device_id: &str,
+    scid: &str,
+    display_args: &[String],
+    video_args: &[String],
+) -> Result<(VideoServer, TcpStream), WebServerError> {
+    push_scrcpy_server(device_id)?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| WebServerError::internal_error(e.to_string()))?
+        .port();
+    reverse_scrcpy_socket(device_id, scid, port)?;
+    let _tunnel = ReverseTunnelGuard {
+        device_id: device_id.to_string(),
+        remote: scrcpy_socket_name(scid),
+    };
+
+    let mut args = server_base_args(scid);
+    args.push("video=true".to_string());
+    args.push("audio=false".to_string());
+    args.push("control=false".to_string());
+    args.push("send_device_meta=false".to_string());
+    args.push("cleanup=false".to_string());
+    args.extend(display_args.iter().cloned());
+    args.extend(video_args.iter().cloned());
+    log::info!(
+        "[WebServe] Starting video-only scrcpy server: {}",
+        video_args.join(" ")
+    );
+    let mut handle = Device::shell_process(device_id, args);
+
+    let socket = tokio::select! {
+        accepted = timeout(VIDEO_SERVER_CONNECT_TIMEOUT, listener.accept()) => match accepted {
+            Ok(Ok((socket, _))) => socket,
+            Ok(Err(e)) => {
+                return Err(WebServerError::internal_error(format!(
+                    "{}: {}",
+                    t!("scrcpy.errorAcceptingConnection"),
+                    e
+                )));
+            }
+            Err(_) => {
+                // without its tunnel the server gives up connecting and exits
+                return Err(WebServerError::internal_error(t!(
+                    "web.device.videoServerConnectTimeout"
+                )));
+            }
+        },
+        exited = &mut handle => {
+            let reason = match exited {
+                Ok(Err(e)) => e,
+                Ok(Ok(())) => "no error".to_string(),
+                Err(e) => e.to_string(),
+            };
+            return Err(WebServerError::bad_request(format!(
+                "{}: {}",
+                t!("web.device.videoServerExited"),
+                reason
+            )));
+        }
+    };
+    Ok((
+        VideoServer {
+            scid: scid.to_string(),
+            handle,
+        },
+        socket,
+    ))
+}
+
+/// Bit rate and fps limit the main video stream runs with
+fn main_video_rate() -> Option<(String, u32, u32)> {
+    let launch = MAIN_VIDEO_LAUNCH.lock().unwrap();
+    let launch = launch.as_ref()?;
+    let video_args = launch.video_args.as_ref()?;
+    let arg = |key: &str| {
+        video_args
+            .iter()
+            .find_map(|arg| arg.strip_prefix(key))
+            .and_then(|value| value.parse::<u32>().ok())
+    };
+    Some((
+        launch.scid.clone(),
+        arg("video_bit_rate=")?,
+        arg("max_fps=").unwrap_or(0),
+    ))
+}
+
+/// Adjust the main video stream to the link health when adaptive video is enabled.
+/// The user's video settings are left untouched, only the running stream changes.
+pub fn spawn_adaptive_video(
+    d_tx: UnboundedSender<ControllerCommand>,
+    ws_tx: broadcast::Sender<WebSocketNotification>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ADAPTIVE_VIDEO_INTERVAL);
+        let mut controller = AdaptiveVideoController::default();
+        let mut session: Option<String> = None;
+        // sessions the stream can't be replaced for, e.g. a new virtual display
+        let mut unsupported: Option<String> = None;
+        let mut last_health = None;
+        loop {
+            interval.tick().await;
+            let local_config = LocalConfig::get();
+            let config = local_config.adaptive_video;
+            let (true, Some((scid, bit_rate, max_fps)), Some(stats)) =
+                (config.enabled, main_video_rate(), current_video_stats())
+            else {
+                session = None;
+                continue;
+            };
+            if session.as_ref() != Some(&scid) {
+                controller = AdaptiveVideoController::default();
+                last_health = None;
+                session = Some(scid.clone());
+            }
+            if unsupported.as_ref() == Some(&scid) {
+                continue;
+            }
+
+            let (status, target) = controller.evaluate(&stats, bit_rate, max_fps, &config);
+            if let Some(target) = target {
+                let mut adjusted = local_config.clone();
+                adjusted.video_bit_rate = target.bit_rate;
+                adjusted.video_max_fps = target.max_fps;
+                log::info!(
+                    "[WebServe] Adaptive video: {} bps, {} fps ({})",
+                    target.bit_rate,
+                    target.max_fps,
+                    status.reason
+                );
+                if let Err(e) = _reconfigure_video(&adjusted, &d_tx).await {
+                    log::warn!(
+                        "[WebServe] Adaptive video disabled for {}: {}",
+                        scid,
+                        e.message()
+                    );
+                    unsupported = Some(scid);
+                    continue;
+                }
+            }
+            if status.adjusted || last_health != Some(status.health) {
+                last_health = Some(status.health);
+                let _ = ws_tx.send(WebSocketNotification::AdaptiveVideo { scid, status });
+            }
+        }
+    });
+}
+
 #[derive(Deserialize)]
 struct PostDataDeControlDevice {
     device_id: String,
@@ -329,6 +777,7 @@ async fn _decontrol_device(
                 d_tx.send(ControllerCommand::ShutdownSub(scid)).unwrap();
             }
             ControlledDevice::remove_device(&device.scid).await;
+            clear_main_video_launch(&device.scid);
             return Ok(JsonResponse::success(
                 format!("{}: {}", t!("web.device.decontrolDevice"), device_id),
                 None,
@@ -381,7 +830,7 @@ struct AndroidDisplay {
     name: Option<String>,
 }
 
-async fn ensure_device_controlled(device_id: &str) -> Result<(), WebServerError> {
+pub(super) async fn ensure_device_controlled(device_id: &str) -> Result<(), WebServerError> {
     let device_list = ControlledDevice::get_device_list().await;
     if device_list
         .iter()
@@ -801,51 +1250,7 @@ struct PostDataId {
 async fn adb_screenshot(
     Json(payload): Json<PostDataId>,
 ) -> Result<impl IntoResponse, WebServerError> {
-    let src = "/data/local/tmp/_screenshot_scrcpy_mask.png";
-
-    let mut display_id_info = Vec::new();
-    Device::shell(
-        &payload.id,
-        ["dumpsys", "SurfaceFlinger", "--display-id"],
-        &mut display_id_info,
-    )
-    .map_err(|e| WebServerError::bad_request(format!("failed get display id: {}", e)))?;
-    let text = String::from_utf8_lossy(&display_id_info);
-    let first_line = text
-        .lines()
-        .next()
-        .ok_or_else(|| WebServerError::bad_request("no display found"))?;
-    let display_id = first_line
-        .split_whitespace()
-        .nth(1)
-        .ok_or_else(|| WebServerError::bad_request("invalid display line"))?;
-
-    Device::shell_logged(&payload.id, ["screencap", "-p", "-d", display_id, src]).map_err(|e| {
-        WebServerError::bad_request(format!(
-            "{} {}: {}",
-            t!("web.device.screenshotError"),
-            payload.id,
-            e
-        ))
-    })?;
-
-    let mut image_bytes = Vec::<u8>::new();
-    Device::pull(&payload.id, src.to_string(), &mut image_bytes).map_err(|e| {
-        WebServerError::bad_request(format!(
-            "{}: {}",
-            t!("web.device.failedGetScreenshotFile"),
-            e
-        ))
-    })?;
-
-    Device::shell_logged(&payload.id, ["rm", src]).map_err(|e| {
-        WebServerError::bad_request(format!(
-            "{} {}: {}",
-            t!("web.device.failedRemoveScreenshot"),
-            payload.id,
-            e
-        ))
-    })?;
+    let image_bytes = Device::screenshot(&payload.id).map_err(WebServerError::bad_request)?;
 
     let mut headers = HeaderMap::new();
     headers.insert("Content-Type", HeaderValue::from_static("image/png"));
@@ -917,6 +1322,8 @@ async fn set_pointer_location(
 #[derive(Deserialize)]
 struct PostDataSendKey {
     keycode: Keycode,
+    #[serde(default)]
+    device_id: Option<String>,
 }
 
 async fn send_key(
@@ -929,6 +1336,18 @@ async fn send_key(
         )));
     }
 
+    // control messages go to every controlled device, a single one is reached through adb
+    if let Some(device_id) = payload.device_id {
+        ensure_device_controlled(&device_id).await?;
+        let code = (payload.keycode as u32).to_string();
+        Device::shell_logged(&device_id, ["input", "keyevent", code.as_str()])
+            .map_err(WebServerError::bad_request)?;
+        return Ok(JsonResponse::success(
+            t!("web.device.sendKeyToDeviceSuccess", device => device_id),
+            None,
+        ));
+    }
+
     device_action::inject_keycode(&state.cs_tx, payload.keycode);
     Ok(JsonResponse::success(t!("web.device.sendKeySuccess"), None))
 }
@@ -0,0 +1,336 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use axum::{
+    Json, Router,
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::IntoResponse,
+    routing::{get, post},
+};
+use futures_util::stream::{self, StreamExt};
+use rust_i18n::t;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    fs::File,
+    io::AsyncWriteExt,
+    sync::{broadcast, mpsc},
+};
+
+use crate::{
+    scrcpy::file_transfer::{
+        self, TransferKind, TransferProgress, check_device_path, device_path_join,
+    },
+    utils::is_safe_file_name,
+    web::{
+        JsonResponse, WebServerError, device::ensure_device_controlled, ws::WebSocketNotification,
+    },
+};
+
+/// Chunks of a pull buffered ahead of the client
+const PULL_CHANNEL_SIZE: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct AppStateDeviceFiles {
+    ws_tx: broadcast::Sender<WebSocketNotification>,
+}
+
+pub fn file_routers(ws_tx: broadcast::Sender<WebSocketNotification>) -> Router {
+    Router::new()
+        .route("/list", post(list_files))
+        .route("/push", post(push_file))
+        .route("/pull", get(pull_file))
+        .route("/delete", post(delete_file))
+        .with_state(AppStateDeviceFiles { ws_tx })
+}
+
+pub fn apk_routers(ws_tx: broadcast::Sender<WebSocketNotification>) -> Router {
+    Router::new()
+        .route("/install", post(install_apk))
+        .route("/uninstall", post(uninstall_apk))
+        .with_state(AppStateDeviceFiles { ws_tx })
+}
+
+/// Runs a blocking adb operation off the async runtime
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, WebServerError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| WebServerError::internal_error(e.to_string()))?
+        .map_err(WebServerError::bad_request)
+}
+
+/// An upload kept in the temp dir while it's pushed, removed once dropped
+struct UploadFile(PathBuf);
+
+impl UploadFile {
+    async fn receive(name: &str, body: Body) -> Result<Self, WebServerError> {
+        let path = std::env::temp_dir().join(format!(
+            "scrcpy-mask-upload-{:08x}-{}",
+            rand::random::<u32>(),
+            name
+        ));
+        let upload = UploadFile(path);
+        let mut file = File::create(&upload.0).await.map_err(|e| {
+            WebServerError::internal_error(format!(
+                "{}: {}",
+                t!("web.deviceFiles.createUploadFailed"),
+                e
+            ))
+        })?;
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| {
+                WebServerError::bad_request(format!(
+                    "{}: {}",
+                    t!("web.deviceFiles.receiveUploadFailed"),
+                    e
+                ))
+            })?;
+            file.write_all(&chunk).await.map_err(|e| {
+                WebServerError::internal_error(format!(
+                    "{}: {}",
+                    t!("web.deviceFiles.writeUploadFailed"),
+                    e
+                ))
+            })?;
+        }
+        // the push reads the file from another thread, make sure every write landed
+        file.flush().await.map_err(|e| {
+            WebServerError::internal_error(format!(
+                "{}: {}",
+                t!("web.deviceFiles.writeUploadFailed"),
+                e
+            ))
+        })?;
+        Ok(upload)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for UploadFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Hands the pulled bytes to the response body, blocks while the client falls behind
+struct ChannelWriter(mpsc::Sender<io::Result<Bytes>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    t!("web.deviceFiles.downloadCancelled").to_string(),
+                )
+            })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn upload_name(name: &str) -> Result<String, WebServerError> {
+    if name.is_empty() || !is_safe_file_name(name) {
+        return Err(WebServerError::bad_request(format!(
+            "{}: {}",
+            t!("web.deviceFiles.invalidFileName"),
+            name
+        )));
+    }
+    Ok(name.to_string())
+}
+
+#[derive(Deserialize)]
+struct DevicePath {
+    device_id: String,
+    path: String,
+}
+
+async fn list_files(Json(payload): Json<DevicePath>) -> Result<JsonResponse, WebServerError> {
+    ensure_device_controlled(&payload.device_id).await?;
+    let path = payload.path.clone();
+    let entries =
+        run_blocking(move || file_transfer::list_dir(&payload.device_id, &payload.path)).await?;
+    Ok(JsonResponse::success(
+        t!("web.deviceFiles.filesListed"),
+        Some(json!({ "path": path, "entries": entries })),
+    ))
+}
+
+async fn delete_file(Json(payload): Json<DevicePath>) -> Result<JsonResponse, WebServerError> {
+    ensure_device_controlled(&payload.device_id).await?;
+    let path = payload.path.clone();
+    run_blocking(move || file_transfer::delete_path(&payload.device_id, &payload.path)).await?;
+    log::info!("[WebServe] Deleted {} from the device", path);
+    Ok(JsonResponse::success(
+        t!("web.deviceFiles.deleted", path => path),
+        None,
+    ))
+}
+
+#[derive(Deserialize)]
+struct QueryPushFile {
+    device_id: String,
+    /// Device directory the file is pushed into
+    dir: String,
+    name: String,
+}
+
+/// The request body is the raw file content
+async fn push_file(
+    State(state): State<AppStateDeviceFiles>,
+    Query(query): Query<QueryPushFile>,
+    body: Body,
+) -> Result<JsonResponse, WebServerError> {
+    ensure_device_controlled(&query.device_id).await?;
+    let name = upload_name(&query.name)?;
+    check_device_path(&query.dir).map_err(WebServerError::bad_request)?;
+    let des = device_path_join(&query.dir, &name);
+    let upload = UploadFile::receive(&name, body).await?;
+
+    let mut progress =
+        TransferProgress::new(state.ws_tx, &query.device_id, TransferKind::Push, &name);
+    let transfer_id = progress.id.clone();
+    let device_path = des.clone();
+    run_blocking(move || {
+        let res = file_transfer::push_file(&query.device_id, upload.path(), &des, &mut progress);
+        progress.finish(res)
+    })
+    .await?;
+    Ok(JsonResponse::success(
+        t!("web.deviceFiles.pushed", name => name, path => device_path),
+        Some(json!({ "id": transfer_id, "path": device_path })),
+    ))
+}
+
+async fn pull_file(
+    State(state): State<AppStateDeviceFiles>,
+    Query(query): Query<DevicePath>,
+) -> Result<impl IntoResponse, WebServerError> {
+    ensure_device_controlled(&query.device_id).await?;
+    let name = query
+        .path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    // fails before the response starts if the path is invalid or missing
+    let total = {
+        let (device_id, path) = (query.device_id.clone(), query.path.clone());
+        run_blocking(move || file_transfer::file_size(&device_id, &path)).await?
+    };
+
+    let mut progress =
+        TransferProgress::new(state.ws_tx, &query.device_id, TransferKind::Pull, &name);
+    progress.total = total;
+    let (tx, rx) = mpsc::channel(PULL_CHANNEL_SIZE);
+    tokio::task::spawn_blocking(move || {
+        let writer = ChannelWriter(tx.clone());
+        let res = file_transfer::pull_file(&query.device_id, &query.path, writer, &mut progress);
+        if let Err(e) = progress.finish(res) {
+            // aborts the response so the client doesn't keep a truncated file
+            let _ = tx.blocking_send(Err(io::Error::other(e)));
+        }
+    });
+    let content = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    // the name came from the device, keep the header plain ascii
+    let ascii_name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", ascii_name)) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    Ok((StatusCode::OK, headers, Body::from_stream(content)))
+}
+
+#[derive(Deserialize)]
+struct QueryInstallApk {
+    device_id: String,
+    name: String,
+}
+
+/// The request body is the raw apk
+async fn install_apk(
+    State(state): State<AppStateDeviceFiles>,
+    Query(query): Query<QueryInstallApk>,
+    body: Body,
+) -> Result<JsonResponse, WebServerError> {
+    ensure_device_controlled(&query.device_id).await?;
+    let name = upload_name(&query.name)?;
+    let upload = UploadFile::receive(&name, body).await?;
+
+    let mut progress =
+        TransferProgress::new(state.ws_tx, &query.device_id, TransferKind::Install, &name);
+    let transfer_id = progress.id.clone();
+    let output = run_blocking(move || {
+        let res = file_transfer::install_apk(&query.device_id, upload.path(), &mut progress);
+        progress.finish(res)
+    })
+    .await?;
+    log::info!("[WebServe] Installed {}: {}", name, output);
+    Ok(JsonResponse::success(
+        t!("web.deviceFiles.installed", name => name),
+        Some(json!({ "id": transfer_id, "output": output })),
+    ))
+}
+
+#[derive(Deserialize)]
+struct PostDataUninstallApk {
+    device_id: String,
+    package: String,
+}
+
+async fn uninstall_apk(
+    State(state): State<AppStateDeviceFiles>,
+    Json(payload): Json<PostDataUninstallApk>,
+) -> Result<JsonResponse, WebServerError> {
+    ensure_device_controlled(&payload.device_id).await?;
+    let package = payload.package.clone();
+    let progress = TransferProgress::new(
+        state.ws_tx,
+        &payload.device_id,
+        TransferKind::Uninstall,
+        &package,
+    );
+    let output = run_blocking(move || {
+        let res = file_transfer::uninstall_package(&payload.device_id, &payload.package);
+        progress.finish(res)
+    })
+    .await?;
+    log::info!("[WebServe] Uninstalled {}: {}", package, output);
+    Ok(JsonResponse::success(
+        t!("web.deviceFiles.uninstalled", package => package),
+        Some(json!({ "output": output })),
+    ))
+}
@@ -2,10 +2,9 @@ use std::fs;
 
 use axum::{
     Json, Router,
-    extract::State,
+    extract::{Query, State},
     routing::{get, post},
 };
-use bevy::math::Vec2;
 use rust_i18n::t;
 use serde::Deserialize;
 use serde_json::json;
@@ -14,12 +13,27 @@ use tokio::sync::oneshot;
 use crate::{
     config::LocalConfig,
     mask::{
-        mapping::config::{
-            MappingConfig, MappingType, save_mapping_config, validate_mapping_config_diagnostics,
+        mapping::{
+            config::{
+                MappingConfig, MappingType, read_mapping_file, save_mapping_config,
+                validate_mapping_config_diagnostics,
+            },
+            history::{
+                diff_mapping_configs, list_revisions, list_trash, move_history, read_revision,
+                restore_trashed, trash_mapping_file,
+            },
+            import::{ImportFormat, import_mapping_config},
+            layout::{ResizeMode, SafeArea, resize_mapping_config},
+            lint::lint_mapping_config,
+            migration::migrate_mapping_value,
+            utils::Size,
         },
         mask_command::MaskCommand,
     },
-    utils::{is_safe_file_name, relate_to_data_path},
+    utils::{
+        forget_data_file, is_safe_file_name, relate_to_data_path, share::MappingFallback,
+        stamp_data_file,
+    },
     web::{JsonResponse, WebServerError},
 };
 
@@ -34,6 +48,7 @@ pub fn routers(
     Router::new()
         .route("/change_active_mapping", post(change_active_mapping))
         .route("/create_mapping", post(create_mapping))
+        .route("/import", post(import_mapping))
         .route("/rename_mapping", post(rename_mapping))
         .route("/duplicate_mapping", post(duplicate_mapping))
         .route("/delete_mapping", post(delete_mapping))
@@ -42,6 +57,11 @@ pub fn routers(
         .route("/read_mapping", post(read_mapping))
         .route("/get_mapping_list", get(get_mapping_list))
         .route("/migrate_mapping", post(migrate_mapping))
+        .route("/list_revisions", get(list_mapping_revisions))
+        .route("/diff_revision", get(diff_mapping_revision))
+        .route("/restore_revision", post(restore_mapping_revision))
+        .route("/list_trash", get(list_mapping_trash))
+        .route("/restore_trash", post(restore_mapping_trash))
         .with_state(AppStatMapping { m_tx })
 }
 
@@ -52,46 +72,60 @@ struct PostDataChangeActiveMapping {
 
 async fn change_active_mapping(
     State(state): State<AppStatMapping>,
-    Json(mut payload): Json<PostDataChangeActiveMapping>,
+    Json(payload): Json<PostDataChangeActiveMapping>,
 ) -> Result<JsonResponse, WebServerError> {
-    if !payload.file.ends_with(".json") {
-        payload.file.push_str(".json");
+    change_active_mapping_with(&state.m_tx, payload.file).await
+}
+
+pub(super) async fn change_active_mapping_with(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    mut file: String,
+) -> Result<JsonResponse, WebServerError> {
+    if !file.ends_with(".json") {
+        file.push_str(".json");
     }
 
     let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
-    state
-        .m_tx
-        .send((
-            MaskCommand::LoadAndActivateMappingConfig {
-                file_name: payload.file.clone(),
-            },
-            oneshot_tx,
-        ))
-        .unwrap();
+    m_tx.send((
+        MaskCommand::LoadAndActivateMappingConfig {
+            file_name: file.clone(),
+        },
+        oneshot_tx,
+    ))
+    .unwrap();
     match oneshot_rx.await.unwrap() {
         Ok(_) => {
-            LocalConfig::set_active_mapping_file(payload.file.clone());
+            LocalConfig::set_active_mapping_file(file.clone());
             log::info!(
                 "[WebServer] {}: {}",
                 t!("web.mapping.setActiveMapping"),
-                payload.file
+                file
             );
 
             Ok(JsonResponse::success(
-                format!(
-                    "{}: {}",
-                    t!("web.mapping.setActiveMappingSuccess"),
-                    payload.file
-                ),
+                format!("{}: {}", t!("web.mapping.setActiveMappingSuccess"), file),
                 None,
             ))
         }
-        Err(e) => Err(WebServerError::bad_request(format!(
-            "{}: {}. {}",
-            t!("web.mapping.failedToLoadMappingConfig"),
-            payload.file,
-            e
-        ))),
+        Err(e) => {
+            let message = format!(
+                "{}: {}. {}",
+                t!("web.mapping.failedToLoadMappingConfig"),
+                file,
+                e
+            );
+            // the mask only reports text, read the file again for the structured diagnostics
+            match read_mapping_file(&file) {
+                Err(diagnostics) => Err(WebServerError::bad_request_data(
+                    message,
+                    json!({
+                        "valid": false,
+                        "diagnostics": diagnostics,
+                    }),
+                )),
+                Ok(_) => Err(WebServerError::bad_request(message)),
+            }
+        }
     }
 }
 
@@ -126,11 +160,13 @@ async fn validate_mapping(
     Json(payload): Json<PostDataNewMapping>,
 ) -> Result<JsonResponse, WebServerError> {
     let diagnostics = validate_mapping_config_diagnostics(&payload.config);
+    let warnings = lint_mapping_config(&payload.config);
     Ok(JsonResponse::success(
         t!("web.script.validateScriptSuccess"),
         Some(json!({
             "valid": diagnostics.is_empty(),
             "diagnostics": diagnostics,
+            "warnings": warnings,
         })),
     ))
 }
@@ -185,6 +221,91 @@ async fn create_mapping(
     ))
 }
 
+#[derive(Deserialize)]
+struct PostDataImportMapping {
+    file: String,
+    /// Keymap of another keymapper
+    content: serde_json::Value,
+    /// Detected from the content when missing
+    #[serde(default)]
+    format: Option<ImportFormat>,
+    /// Screen size for formats that store relative positions
+    #[serde(default)]
+    width: Option<u32>,
+    #[serde(default)]
+    height: Option<u32>,
+}
+
+async fn import_mapping(
+    Json(mut payload): Json<PostDataImportMapping>,
+) -> Result<JsonResponse, WebServerError> {
+    if !payload.file.ends_with(".json") {
+        payload.file.push_str(".json");
+    }
+
+    if !is_safe_file_name(payload.file.as_ref()) {
+        return Err(WebServerError::bad_request(format!(
+            "{}: {}",
+            t!("web.mapping.nameNotSafe"),
+            payload.file
+        )));
+    }
+
+    let config_path = relate_to_data_path(["mapping", &payload.file]);
+    if config_path.exists() {
+        return Err(WebServerError::bad_request(format!(
+            "{}: {}",
+            t!("web.mapping.mappingConfigExists"),
+            payload.file
+        )));
+    }
+
+    let size = match (payload.width, payload.height) {
+        (Some(width), Some(height)) if width > 0 && height > 0 => Some(Size { width, height }),
+        (None, None) => None,
+        (width, height) => {
+            return Err(WebServerError::bad_request(format!(
+                "{}: {}, {}",
+                t!("web.mapping.invalidSize"),
+                width.unwrap_or_default(),
+                height.unwrap_or_default()
+            )));
+        }
+    };
+
+    let imported = import_mapping_config(&payload.content, payload.format, size).map_err(|d| {
+        WebServerError::bad_request_data(
+            d.message.clone(),
+            json!({ "valid": false, "diagnostics": [d] }),
+        )
+    })?;
+
+    let mut diagnostics = validate_mapping_config_diagnostics(&imported.config);
+    if !diagnostics.is_empty() {
+        diagnostics.extend(imported.diagnostics);
+        return Err(WebServerError::bad_request_data(
+            t!("mask.mapping.mappingConfigValidationFailed").to_string(),
+            json!({ "valid": false, "diagnostics": diagnostics }),
+        ));
+    }
+
+    save_mapping_config(&imported.config, &config_path).map_err(WebServerError::bad_request)?;
+
+    let msg = t!(
+        "web.mapping.importMappingConfig",
+        count => imported.config.mappings.len(),
+        file => payload.file
+    );
+    log::info!("[WebServer] {}", msg);
+    Ok(JsonResponse::success(
+        msg,
+        Some(json!({
+            "format": imported.format,
+            "diagnostics": imported.diagnostics,
+        })),
+    ))
+}
+
 #[derive(Deserialize)]
 struct PostDataMappingFile {
     file: String,
@@ -226,7 +347,8 @@ async fn delete_mapping(
             payload.file
         ));
     }
-    fs::remove_file(file_path).map_err(|e| {
+    // deleted files go to the trash so they can be restored
+    trash_mapping_file(&file_path).map_err(|e| {
         WebServerError::bad_request(format!(
             "{} {}: {}",
             t!("web.mapping.deleteMappingConfigError"),
@@ -234,6 +356,7 @@ async fn delete_mapping(
             e
         ))
     })?;
+    forget_data_file(&file_path);
 
     log::info!(
         "[WebServer] {}: {}",
@@ -303,7 +426,12 @@ async fn rename_mapping(
             new_path.to_str().unwrap()
         ));
     }
-    fs::rename(old_path, new_path).map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    fs::rename(&old_path, &new_path).map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    forget_data_file(&old_path);
+    stamp_data_file(&new_path);
+    if let Err(e) = move_history(&payload.file, &payload.new_file) {
+        log::warn!("[WebServer] {}", e);
+    }
 
     // get active mapping file
     let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
@@ -406,7 +534,8 @@ async fn duplicate_mapping(
             new_path.to_str().unwrap()
         ));
     }
-    fs::copy(old_path, new_path).map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    fs::copy(old_path, &new_path).map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    stamp_data_file(&new_path);
     log::info!(
         "[WebServer] {}",
         t!(
@@ -453,49 +582,212 @@ async fn update_mapping(
     save_mapping_config(&payload.config, &config_path)
         .map_err(|e| WebServerError::bad_request(e))?;
 
+    let msg = if reload_if_active(&state.m_tx, &payload.file).await? {
+        format!(
+            "{}: {}",
+            t!("web.mapping.updateAndActivateMappingConfig"),
+            payload.file
+        )
+    } else {
+        format!("{} {}", t!("web.mapping.updateMappingConfig"), payload.file)
+    };
+    log::info!("[WebServer] {}", msg);
+    Ok(JsonResponse::success(msg, None))
+}
+
+/// Reloads `file` if it's the active mapping, returns whether it was
+async fn reload_if_active(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    file: &str,
+) -> Result<bool, WebServerError> {
     // get active mapping file
     let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
-    state
-        .m_tx
-        .send((MaskCommand::GetActiveMapping, oneshot_tx))
+    m_tx.send((MaskCommand::GetActiveMapping, oneshot_tx))
         .unwrap();
-    let file = oneshot_rx.await.unwrap().unwrap();
-    if file == payload.file {
-        // if active, refresh active mapping
-        let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
-        state
-            .m_tx
-            .send((
-                MaskCommand::LoadAndActivateMappingConfig {
-                    file_name: payload.file.clone(),
-                },
-                oneshot_tx,
-            ))
-            .unwrap();
-        match oneshot_rx.await.unwrap() {
-            Ok(_) => {
-                LocalConfig::set_active_mapping_file(payload.file.clone());
-                let msg = format!(
-                    "{}: {}",
-                    t!("web.mapping.updateAndActivateMappingConfig"),
-                    payload.file
-                );
+    if oneshot_rx.await.unwrap().unwrap() != file {
+        return Ok(false);
+    }
 
-                log::info!("[WebServer] {}", msg);
-                Ok(JsonResponse::success(msg, None))
-            }
-            Err(e) => Err(WebServerError::bad_request(format!(
-                "{} {}. {}",
-                t!("web.mapping.failedToLoadUpdatedMappingConfig"),
-                payload.file,
-                e
-            ))),
+    // if active, refresh active mapping
+    let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
+    m_tx.send((
+        MaskCommand::LoadAndActivateMappingConfig {
+            file_name: file.to_string(),
+        },
+        oneshot_tx,
+    ))
+    .unwrap();
+    match oneshot_rx.await.unwrap() {
+        Ok(_) => {
+            LocalConfig::set_active_mapping_file(file.to_string());
+            Ok(true)
         }
-    } else {
-        let msg = format!("{} {}", t!("web.mapping.updateMappingConfig"), payload.file);
-        log::info!("[WebServer] {}", msg);
-        Ok(JsonResponse::success(msg, None))
+        Err(e) => Err(WebServerError::bad_request(format!(
+            "{} {}. {}",
+            t!("web.mapping.failedToLoadUpdatedMappingConfig"),
+            file,
+            e
+        ))),
+    }
+}
+
+fn mapping_file_name(mut file: String) -> Result<String, WebServerError> {
+    if !file.ends_with(".json") {
+        file.push_str(".json");
+    }
+    if !is_safe_file_name(&file) {
+        return Err(WebServerError::bad_request(format!(
+            "{}: {}",
+            t!("web.mapping.nameNotSafe"),
+            file
+        )));
     }
+    Ok(file)
+}
+
+#[derive(Deserialize)]
+struct QueryMappingFile {
+    file: String,
+}
+
+async fn list_mapping_revisions(
+    Query(query): Query<QueryMappingFile>,
+) -> Result<JsonResponse, WebServerError> {
+    let file = mapping_file_name(query.file)?;
+    Ok(JsonResponse::success(
+        format!("Revisions of {}", file),
+        Some(json!({ "revisions": list_revisions(&file) })),
+    ))
+}
+
+#[derive(Deserialize)]
+struct QueryMappingRevision {
+    file: String,
+    revision: String,
+    /// Revision to compare with, the current file when missing
+    #[serde(default)]
+    against: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PostDataMappingRevision {
+    file: String,
+    revision: String,
+}
+
+fn parse_revision(content: &str) -> Result<serde_json::Value, WebServerError> {
+    serde_json::from_str(content).map_err(|e| {
+        WebServerError::bad_request(format!(
+            "{}: {}",
+            t!("web.mapping.cannotDeserializeConfig"),
+            e
+        ))
+    })
+}
+
+async fn diff_mapping_revision(
+    Query(query): Query<QueryMappingRevision>,
+) -> Result<JsonResponse, WebServerError> {
+    let file = mapping_file_name(query.file)?;
+    let old = parse_revision(
+        &read_revision(&file, &query.revision).map_err(WebServerError::bad_request)?,
+    )?;
+    let new_content = match &query.against {
+        Some(revision) => read_revision(&file, revision).map_err(WebServerError::bad_request)?,
+        None => fs::read_to_string(relate_to_data_path(["mapping", &file])).map_err(|e| {
+            WebServerError::bad_request(format!(
+                "{} {}: {}",
+                t!("web.mapping.cannotReadMappingConfig"),
+                file,
+                e
+            ))
+        })?,
+    };
+    let new = parse_revision(&new_content)?;
+    Ok(JsonResponse::success(
+        format!("Diff of {} revision {}", file, query.revision),
+        Some(json!({ "diff": diff_mapping_configs(&old, &new) })),
+    ))
+}
+
+async fn restore_mapping_revision(
+    State(state): State<AppStatMapping>,
+    Json(payload): Json<PostDataMappingRevision>,
+) -> Result<JsonResponse, WebServerError> {
+    let file = mapping_file_name(payload.file)?;
+    let mut value = parse_revision(
+        &read_revision(&file, &payload.revision).map_err(WebServerError::bad_request)?,
+    )?;
+    migrate_mapping_value(&mut value).map_err(|d| WebServerError::bad_request(d.message))?;
+    let config: MappingConfig = serde_json::from_value(value).map_err(|e| {
+        WebServerError::bad_request(format!(
+            "{} {}: {}",
+            t!("web.mapping.cannotDeserializeConfig"),
+            file,
+            e
+        ))
+    })?;
+    if let Some(error) = mapping_validation_error(&config) {
+        return Err(error);
+    }
+
+    // the current content becomes a revision itself, so restoring can be undone
+    save_mapping_config(&config, &relate_to_data_path(["mapping", &file]))
+        .map_err(WebServerError::bad_request)?;
+    reload_if_active(&state.m_tx, &file).await?;
+
+    let msg = t!(
+        "web.mapping.restoreMappingRevision",
+        file => file,
+        revision => payload.revision
+    );
+    log::info!("[WebServer] {}", msg);
+    Ok(JsonResponse::success(msg, None))
+}
+
+async fn list_mapping_trash() -> Result<JsonResponse, WebServerError> {
+    Ok(JsonResponse::success(
+        "Trashed mapping configs",
+        Some(json!({ "trash": list_trash() })),
+    ))
+}
+
+#[derive(Deserialize)]
+struct PostDataRestoreTrash {
+    id: String,
+    /// Restore under another name, the original name when missing
+    #[serde(default)]
+    file: Option<String>,
+}
+
+async fn restore_mapping_trash(
+    Json(payload): Json<PostDataRestoreTrash>,
+) -> Result<JsonResponse, WebServerError> {
+    let original = list_trash()
+        .into_iter()
+        .find(|item| item.id == payload.id)
+        .ok_or_else(|| {
+            WebServerError::bad_request(format!(
+                "{}: {}",
+                t!("web.mapping.mappingConfigNotFound"),
+                payload.id
+            ))
+        })?;
+    let file = mapping_file_name(payload.file.unwrap_or(original.file))?;
+    let path = relate_to_data_path(["mapping", &file]);
+    if path.exists() {
+        return Err(WebServerError::bad_request(format!(
+            "{}: {}",
+            t!("web.mapping.mappingConfigExists"),
+            file
+        )));
+    }
+    restore_trashed(&payload.id, &path).map_err(WebServerError::bad_request)?;
+    stamp_data_file(&path);
+
+    let msg = t!("web.mapping.restoreTrashedMapping", file => file);
+    log::info!("[WebServer] {}", msg);
+    Ok(JsonResponse::success(msg, None))
 }
 
 async fn get_mapping_list(
@@ -538,6 +830,7 @@ async fn get_mapping_list(
         Some(json!({
             "mapping_list": mapping_files,
             "active_mapping": file,
+            "fallback": MappingFallback::get().await,
         })),
     ))
 }
@@ -560,42 +853,20 @@ async fn read_mapping(
         ));
     }
 
-    // load from file
-    let path = relate_to_data_path(["mapping", &payload.file]);
-    if !path.exists() {
-        return bad_request(format!(
-            "{}: {}",
-            t!("web.mapping.mappingConfigExists"),
-            payload.file
-        ));
-    }
-    let config_string = std::fs::read_to_string(path).map_err(|e| {
-        WebServerError::bad_request(format!(
-            "{} {}: {}",
-            t!("web.mapping.cannotReadMappingConfig"),
-            payload.file,
-            e
-        ))
-    })?;
-    let mapping_config: MappingConfig = serde_json::from_str(&config_string).map_err(|e| {
-        WebServerError::bad_request(format!(
-            "{} {}: {}",
-            t!("web.mapping.cannotDeserializeConfig"),
-            payload.file,
-            e
-        ))
-    })?;
-
-    if let Some(data) = mapping_validation_data(&mapping_config) {
-        return Err(WebServerError::bad_request_data(
+    // older schema versions are migrated while reading
+    let mapping_config = read_mapping_file(&payload.file).map_err(|diagnostics| {
+        WebServerError::bad_request_data(
             format!(
                 "{} {}",
                 t!("web.mapping.invalidMappingConfig"),
                 payload.file
             ),
-            data,
-        ));
-    }
+            json!({
+                "valid": false,
+                "diagnostics": diagnostics,
+            }),
+        )
+    })?;
 
     Ok(JsonResponse::success(
         format!("{} {}", t!("web.mapping.mappingReadSuccess"), payload.file),
@@ -611,6 +882,13 @@ struct PostDataMigrateMappingFile {
     new_file: String,
     width: u32,
     height: u32,
+    #[serde(default)]
+    mode: ResizeMode,
+    #[serde(default)]
+    safe_area: SafeArea,
+    /// Return the migrated config and the moved positions without writing the new file
+    #[serde(default)]
+    preview: bool,
 }
 
 async fn migrate_mapping(
@@ -645,7 +923,7 @@ async fn migrate_mapping(
     }
 
     let new_path = relate_to_data_path(["mapping", &payload.new_file]);
-    if new_path.exists() {
+    if new_path.exists() && !payload.preview {
         return bad_request(format!(
             "{}: {}",
             t!("web.mapping.mappingConfigExists"),
@@ -679,85 +957,27 @@ async fn migrate_mapping(
         ));
     }
 
-    let scale = Vec2::new(
-        payload.width as f32 / mapping_config.original_size.width as f32,
-        payload.height as f32 / mapping_config.original_size.height as f32,
-    );
-
-    mapping_config.original_size.width = payload.width;
-    mapping_config.original_size.height = payload.height;
-
-    mapping_config
-        .mappings
-        .iter_mut()
-        .for_each(|mapping| match mapping {
-            MappingType::SingleTap(m) => {
-                m.position *= scale;
-                m.random_offset_x *= scale.x;
-                m.random_offset_y *= scale.y;
-            }
-            MappingType::RepeatTap(m) => {
-                m.position *= scale;
-                m.random_offset_x *= scale.x;
-                m.random_offset_y *= scale.y;
-            }
-            MappingType::MultipleTap(m) => {
-                m.random_offset_x *= scale.x;
-                m.random_offset_y *= scale.y;
-                m.items.iter_mut().for_each(|item| {
-                    item.position *= scale;
-                });
-            }
-            MappingType::Swipe(m) => {
-                m.positions.iter_mut().for_each(|p| {
-                    *p *= scale;
-                });
-            }
-            MappingType::DirectionPad(m) => {
-                m.position *= scale;
-                m.max_offset_x *= scale.x;
-                m.max_offset_y *= scale.y;
-                m.random_offset_x *= scale.x;
-                m.random_offset_y *= scale.y;
-                m.jitter_offset_x *= scale.x;
-                m.jitter_offset_y *= scale.y;
-            }
-            MappingType::MouseCastSpell(m) => {
-                m.position *= scale;
-                m.cast_radius *= scale.y;
-                m.center *= scale;
-                m.drag_radius *= scale.y;
-            }
-            MappingType::PadCastSpell(m) => {
-                m.drag_radius *= scale.y;
-                m.position *= scale;
-            }
-            MappingType::CancelCast(m) => {
-                m.position *= scale;
-            }
-            MappingType::Observation(m) => {
-                m.position *= scale;
-                m.max_radius *= scale.y;
-            }
-            MappingType::Fps(m) => {
-                m.position *= scale;
-                if m.max_offset_x > 0.0 {
-                    m.max_offset_x *= scale.x;
-                }
-                if m.max_offset_y > 0.0 {
-                    m.max_offset_y *= scale.y;
-                }
-            }
-            MappingType::Fire(m) => {
-                m.position *= scale;
-            }
-            MappingType::RawInput(m) => {
-                m.position *= scale;
-            }
-            MappingType::Script(m) => {
-                m.position *= scale;
-            }
-        });
+    let original = mapping_config.clone();
+    resize_mapping_config(
+        &mut mapping_config,
+        Size {
+            width: payload.width,
+            height: payload.height,
+        },
+        payload.safe_area,
+        payload.mode,
+    )
+    .map_err(WebServerError::bad_request)?;
+
+    if payload.preview {
+        return Ok(JsonResponse::success(
+            format!("Migration preview of {}", payload.file),
+            Some(json!({
+                "mapping_config": mapping_config,
+                "changes": position_changes(&original, &mapping_config),
+            })),
+        ));
+    }
 
     // save to file
     save_mapping_config(&mapping_config, &new_path).map_err(|e| WebServerError::bad_request(e))?;
@@ -772,3 +992,28 @@ async fn migrate_mapping(
     log::info!("[WebServer] {}", msg);
     Ok(JsonResponse::success(msg, None))
 }
+
+/// Where the main position of each mapping moves to, for the migration preview
+fn position_changes(before: &MappingConfig, after: &MappingConfig) -> Vec<serde_json::Value> {
+    before
+        .mappings
+        .iter()
+        .zip(&after.mappings)
+        .filter_map(|(before, after)| {
+            let position = |mapping: &MappingType| {
+                let value = serde_json::to_value(mapping).ok()?;
+                value
+                    .get("position")
+                    .or_else(|| value.pointer("/positions/0"))
+                    .or_else(|| value.pointer("/items/0/position"))
+                    .cloned()
+            };
+            Some(json!({
+                "id": before.id(),
+                "type": AsRef::<str>::as_ref(before),
+                "from": position(before)?,
+                "to": position(after)?,
+            }))
+        })
+        .collect()
+}
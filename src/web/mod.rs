@@ -1,18 +1,28 @@
+pub mod adb_console;
+pub mod audio;
+pub mod auth;
 pub mod config;
 pub mod device;
+pub mod device_files;
 pub mod mapping;
+pub mod record;
 pub mod script;
+pub mod watcher;
 pub mod ws;
 
 use axum::{
     Json, Router,
     http::{HeaderValue, StatusCode, header},
+    middleware,
     response::{IntoResponse, Response},
 };
 use rust_i18n::t;
 use serde::Serialize;
 use serde_json::Value;
-use std::{net::SocketAddrV4, thread};
+use std::{
+    net::{SocketAddr, SocketAddrV4},
+    thread,
+};
 use tokio::sync::{broadcast, mpsc::UnboundedSender, oneshot};
 use tower::ServiceBuilder;
 use tower_http::{
@@ -23,7 +33,7 @@ use tower_http::{
 use crate::{
     mask::mask_command::MaskCommand,
     scrcpy::{control_msg::ScrcpyControlMsg, controller::ControllerCommand},
-    utils::relate_to_root_path,
+    utils::{relate_to_root_path, run_mode},
     web::ws::WebSocketNotification,
 };
 
@@ -58,6 +68,7 @@ impl Server {
         log::info!("[WebServe] {}: {}", t!("web.server.startingOn"), addr);
 
         let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+        auth::warn_if_exposed(*addr.ip());
 
         let ip_str = if addr.ip().is_unspecified() || addr.ip().is_loopback() {
             "localhost"
@@ -71,13 +82,21 @@ impl Server {
             url
         );
 
-        opener::open(url).unwrap_or_else(|e| {
-            log::error!("[WebServe] {}: {}", t!("web.server.failedToOpenBrowser"), e)
-        });
+        // nobody is in front of a headless instance
+        if !run_mode().is_headless() {
+            opener::open(url).unwrap_or_else(|e| {
+                log::error!("[WebServe] {}: {}", t!("web.server.failedToOpenBrowser"), e)
+            });
+        }
 
-        axum::serve(listener, Self::app(cs_tx, d_tx, m_tx, ws_tx))
-            .await
-            .unwrap();
+        device::spawn_adaptive_video(d_tx.clone(), ws_tx.clone());
+        watcher::spawn_data_watcher(m_tx.clone(), ws_tx.clone());
+        axum::serve(
+            listener,
+            Self::app(cs_tx, d_tx, m_tx, ws_tx).into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
     }
 
     fn app(
@@ -107,17 +126,25 @@ impl Server {
                     .not_found_service(ServeFile::new(web_root.join("index.html"))),
             );
 
-        let router = Router::new()
-            .nest_service("/assets", hashed_assets)
-            .fallback_service(html_shell)
+        let api = Router::new()
             .nest(
-                "/api/device",
+                "/device",
                 device::routers(cs_tx.clone(), d_tx, ws_tx.clone()),
             )
-            .nest("/api/script", script::routers(m_tx.clone()))
-            .nest("/api/mapping", mapping::routers(m_tx.clone()))
-            .nest("/api/config", config::routers(m_tx.clone()))
-            .nest("/api/ws", ws::routers(cs_tx, ws_tx));
+            .nest("/script", script::routers(m_tx.clone()))
+            .nest("/mapping", mapping::routers(m_tx.clone()))
+            .nest("/config", config::routers(m_tx.clone(), ws_tx.clone()))
+            .nest("/record", record::routers(cs_tx.clone()))
+            .nest("/audio", audio::routers())
+            .nest("/ws", ws::routers(cs_tx, m_tx, ws_tx))
+            .nest("/auth", auth::routers())
+            .layer(middleware::from_fn(auth::require_auth));
+
+        // the static frontend stays public, it asks for a token once the api refuses it
+        let router = Router::new()
+            .nest_service("/assets", hashed_assets)
+            .fallback_service(html_shell)
+            .nest("/api", api);
 
         #[cfg(debug_assertions)]
         {
@@ -180,6 +207,16 @@ impl WebServerError {
     pub fn bad_request(message: impl Into<String>) -> Self {
         Self(400, message.into(), None)
     }
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self(401, message.into(), None)
+    }
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self(403, message.into(), None)
+    }
+
+    pub fn message(&self) -> &str {
+        &self.1
+    }
 
     pub fn bad_request_data(message: impl Into<String>, data: Value) -> Self {
         Self(400, message.into(), Some(data))
@@ -0,0 +1,180 @@
+use std::fs;
+
+use axum::{
+    Json, Router,
+    extract::State,
+    routing::{get, post},
+};
+use bevy::math::Vec2;
+use rust_i18n::t;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast;
+
+use crate::{
+    mask::mapping::{
+        binding::ButtonBinding,
+        config::{MappingType, default_mapping_id},
+        layout::MappingAnchor,
+        script::MappingScriptHooks,
+        tap::MappingMultipleTap,
+        utils::default_random_offset,
+    },
+    scrcpy::{
+        control_msg::ScrcpyControlMsg,
+        recorder::{
+            RecordedMacro, TouchRecorder, recording_to_multiple_tap_items, recording_to_script,
+        },
+    },
+    utils::{is_safe_file_name, relate_to_data_path},
+    web::{JsonResponse, WebServerError},
+};
+
+#[derive(Debug, Clone)]
+pub struct AppStateRecord {
+    cs_tx: broadcast::Sender<ScrcpyControlMsg>,
+}
+
+pub fn routers(cs_tx: broadcast::Sender<ScrcpyControlMsg>) -> Router {
+    Router::new()
+        .route("/status", get(record_status))
+        .route("/start", post(start_record))
+        .route("/stop", post(stop_record))
+        .route("/export", post(export_record))
+        .route("/save_macro", post(save_macro))
+        .with_state(AppStateRecord { cs_tx })
+}
+
+async fn record_status() -> Result<JsonResponse, WebServerError> {
+    let status = TouchRecorder::status().await;
+    Ok(JsonResponse::success(
+        t!("web.record.statusObtained"),
+        Some(json!(status)),
+    ))
+}
+
+async fn start_record(State(state): State<AppStateRecord>) -> Result<JsonResponse, WebServerError> {
+    TouchRecorder::start(&state.cs_tx)
+        .await
+        .map_err(WebServerError::bad_request)?;
+    Ok(JsonResponse::success(t!("web.record.started"), None))
+}
+
+async fn stop_record() -> Result<JsonResponse, WebServerError> {
+    let recording = TouchRecorder::stop()
+        .await
+        .map_err(WebServerError::bad_request)?;
+    Ok(JsonResponse::success(
+        t!("web.record.stopped"),
+        Some(json!({
+            "event_count": recording.events.len(),
+            "duration": recording.duration,
+        })),
+    ))
+}
+
+async fn last_recording() -> Result<RecordedMacro, WebServerError> {
+    TouchRecorder::last_recording()
+        .await
+        .ok_or_else(|| WebServerError::bad_request(t!("web.record.noRecording")))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RecordExportFormat {
+    Script,
+    MultipleTap,
+    Macro,
+}
+
+#[derive(Deserialize)]
+struct PostDataExportRecord {
+    format: RecordExportFormat,
+    // target coordinate space, usually the original size of the active mapping
+    width: Option<u32>,
+    height: Option<u32>,
+    pointer_id: Option<u64>,
+    bind: Option<ButtonBinding>,
+    #[serde(default)]
+    note: String,
+}
+
+async fn export_record(
+    Json(payload): Json<PostDataExportRecord>,
+) -> Result<JsonResponse, WebServerError> {
+    let recording = last_recording().await?;
+    let target_size = match (payload.width, payload.height) {
+        (Some(w), Some(h)) if w > 0 && h > 0 => Some(Vec2::new(w as f32, h as f32)),
+        (None, None) => None,
+        _ => {
+            return Err(WebServerError::bad_request(t!("web.record.invalidSize")));
+        }
+    };
+
+    let data = match payload.format {
+        RecordExportFormat::Script => {
+            json!({ "script": recording_to_script(&recording, target_size) })
+        }
+        RecordExportFormat::MultipleTap => {
+            let (pointer_id, items) =
+                recording_to_multiple_tap_items(&recording, payload.pointer_id, target_size)
+                    .map_err(WebServerError::bad_request)?;
+            let mapping = MappingType::MultipleTap(MappingMultipleTap {
+                id: default_mapping_id(),
+                anchor: MappingAnchor::default(),
+                note: payload.note,
+                pointer_id,
+                items,
+                bind: payload.bind.unwrap_or(ButtonBinding::new(Vec::new())),
+                random_offset_x: default_random_offset(),
+                random_offset_y: default_random_offset(),
+                script_hooks: MappingScriptHooks::default(),
+            });
+            json!({ "mapping": mapping })
+        }
+        RecordExportFormat::Macro => json!({ "macro": recording }),
+    };
+
+    Ok(JsonResponse::success(t!("web.record.exported"), Some(data)))
+}
+
+#[derive(Deserialize)]
+struct PostDataSaveMacro {
+    file: String,
+}
+
+async fn save_macro(
+    Json(mut payload): Json<PostDataSaveMacro>,
+) -> Result<JsonResponse, WebServerError> {
+    if !payload.file.ends_with(".json") {
+        payload.file.push_str(".json");
+    }
+    if !is_safe_file_name(payload.file.as_ref()) {
+        return Err(WebServerError::bad_request(format!(
+            "{}: {}",
+            t!("web.record.unsafeMacroName"),
+            payload.file
+        )));
+    }
+
+    let recording = last_recording().await?;
+    let dir = relate_to_data_path(["macro"]);
+    let file_path = dir.join(&payload.file);
+    let content = serde_json::to_string_pretty(&recording)
+        .map_err(|e| WebServerError::internal_error(e.to_string()))?;
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&file_path, content))
+        .map_err(|e| {
+            WebServerError::internal_error(format!(
+                "{}: {}",
+                t!("web.record.failedToSaveMacro", file => payload.file),
+                e
+            ))
+        })?;
+
+    log::info!("[WebServer] Macro saved: {}", file_path.display());
+    Ok(JsonResponse::success(
+        format!("{}: {}", t!("web.record.macroSaved"), payload.file),
+        Some(json!({ "path": file_path })),
+    ))
+}
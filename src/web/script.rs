@@ -47,6 +47,13 @@ async fn validate_script(
 async fn run_script(
     State(state): State<AppStateScript>,
     Json(payload): Json<PostDataScript>,
+) -> Result<JsonResponse, WebServerError> {
+    run_script_with(&state.m_tx, payload.script).await
+}
+
+pub(super) async fn run_script_with(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    script: String,
 ) -> Result<JsonResponse, WebServerError> {
     if !ControlledDevice::is_any_device_controlled().await {
         return Err(WebServerError::bad_request(t!(
@@ -55,14 +62,7 @@ async fn run_script(
     }
 
     let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
-    state
-        .m_tx
-        .send((
-            MaskCommand::RunScript {
-                script: payload.script,
-            },
-            oneshot_tx,
-        ))
+    m_tx.send((MaskCommand::RunScript { script }, oneshot_tx))
         .unwrap();
 
     match oneshot_rx.await.unwrap() {
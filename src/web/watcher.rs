@@ -0,0 +1,393 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use rust_i18n::t;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use crate::{
+    config::{LocalConfig, config_field},
+    mask::{
+        mapping::config::{format_mapping_diagnostics, read_mapping_file},
+        mask_command::MaskCommand,
+    },
+    utils::{forget_data_file, relate_to_data_path, restamp_data_file, stamp_data_file},
+    web::{
+        config::{apply_config_changes, changed_values},
+        ws::WebSocketNotification,
+    },
+};
+
+// editors often save in several steps, changes are picked up once the file settles
+const DATA_WATCH_SETTLE: Duration = Duration::from_millis(200);
+const CONFIG_FILE: &str = "config.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum DataFile {
+    Config,
+    Mapping(String),
+}
+
+impl DataFile {
+    /// The data file an event path points to, other files in the watched folders are ignored
+    fn from_event_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        let in_mapping_dir = path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .is_some_and(|name| name == "mapping");
+        if in_mapping_dir {
+            (file_name.ends_with(".json") && !file_name.starts_with('.'))
+                .then(|| Self::Mapping(file_name.to_string()))
+        } else {
+            (file_name == CONFIG_FILE).then_some(Self::Config)
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        match self {
+            Self::Config => relate_to_data_path([CONFIG_FILE]),
+            Self::Mapping(file) => relate_to_data_path(["mapping", file]),
+        }
+    }
+}
+
+/// Watches config.json and the mapping files, and applies edits made outside the app.
+/// Files the app writes itself are recorded by content when written and are not picked up here
+pub fn spawn_data_watcher(
+    m_tx: crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    ws_tx: broadcast::Sender<WebSocketNotification>,
+) {
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel::<DataFile>();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for file in event
+            .paths
+            .iter()
+            .filter_map(|path| DataFile::from_event_path(path))
+        {
+            let _ = event_tx.send(file);
+        }
+    })
+    .and_then(|mut watcher| {
+        let mapping_dir = relate_to_data_path(["mapping"]);
+        fs::create_dir_all(&mapping_dir).map_err(notify::Error::io)?;
+        // config.json sits next to the mapping folder
+        watcher.watch(mapping_dir.parent().unwrap(), RecursiveMode::NonRecursive)?;
+        watcher.watch(&mapping_dir, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+    let watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log::error!("[Watcher] Failed to watch the data folder: {}", e);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        // dropping the watcher stops the events
+        let _watcher = watcher;
+        let _ = tokio::task::spawn_blocking(record_data_files).await;
+        while let Some(file) = event_rx.recv().await {
+            let mut files = BTreeSet::from([file]);
+            tokio::time::sleep(DATA_WATCH_SETTLE).await;
+            while let Ok(file) = event_rx.try_recv() {
+                files.insert(file);
+            }
+            for file in files {
+                handle_change(&m_tx, &ws_tx, file).await;
+            }
+        }
+    });
+}
+
+/// Records the files present at start, so only later edits are picked up
+fn record_data_files() {
+    stamp_data_file(&DataFile::Config.path());
+    if let Ok(entries) = fs::read_dir(relate_to_data_path(["mapping"])) {
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            if path.extension().is_some_and(|ext| ext == "json") {
+                stamp_data_file(&path);
+            }
+        }
+    }
+}
+
+async fn handle_change(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    ws_tx: &broadcast::Sender<WebSocketNotification>,
+    file: DataFile,
+) {
+    let path = file.path();
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            forget_data_file(&path);
+            if let DataFile::Mapping(file) = &file {
+                if *file == LocalConfig::get().active_mapping_file {
+                    log::warn!(
+                        "[Watcher] Active mapping file was removed: {}",
+                        path.display()
+                    );
+                }
+            }
+            return;
+        }
+        Err(e) => {
+            log::warn!("[Watcher] Failed to read {}: {}", path.display(), e);
+            return;
+        }
+    };
+    if !restamp_data_file(&path, &contents) {
+        return;
+    }
+    match file {
+        DataFile::Config => reload_config(m_tx, ws_tx, &contents).await,
+        DataFile::Mapping(file) => reload_mapping(m_tx, ws_tx, &file).await,
+    }
+}
+
+fn notify(
+    ws_tx: &broadcast::Sender<WebSocketNotification>,
+    file: &str,
+    applied: bool,
+    message: String,
+) {
+    let _ = ws_tx.send(WebSocketNotification::DataFileChanged {
+        file: file.to_string(),
+        applied,
+        message,
+        diagnostics: Vec::new(),
+    });
+}
+
+/// Parses config.json and checks the values that differ from `previous`
+fn read_config(previous: &LocalConfig, contents: &[u8]) -> Result<LocalConfig, String> {
+    let config = LocalConfig::parse(&String::from_utf8_lossy(contents))?;
+    for (key, value) in changed_values(previous, &config) {
+        if let Some(field) = config_field(&key) {
+            field.check(&value)?;
+        }
+    }
+    config.validate()?;
+    Ok(config)
+}
+
+async fn reload_config(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    ws_tx: &broadcast::Sender<WebSocketNotification>,
+    contents: &[u8],
+) {
+    let mut config = match read_config(&LocalConfig::get(), contents) {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!(
+                "[Watcher] Ignored external change of {}: {}",
+                CONFIG_FILE,
+                e
+            );
+            notify(ws_tx, CONFIG_FILE, false, e);
+            return;
+        }
+    };
+    let previous = LocalConfig::get();
+    let mut values = changed_values(&previous, &config);
+    if values.is_empty() {
+        return;
+    }
+
+    let mut notes = Vec::new();
+    if values.contains_key("active_mapping_file") {
+        if let Err(e) = load_mapping(m_tx, &config.active_mapping_file).await {
+            notes.push(format!(
+                "{}: {}. {}",
+                t!("web.mapping.failedToLoadMappingConfig"),
+                config.active_mapping_file,
+                e
+            ));
+            config.active_mapping_file = previous.active_mapping_file.clone();
+            values.remove("active_mapping_file");
+        }
+    }
+    LocalConfig::reload(config.clone());
+    notes.extend(apply_config_changes(m_tx, &previous, &config, &values).await);
+
+    let keys: Vec<String> = values.keys().cloned().collect();
+    log::info!(
+        "[Watcher] Reloaded {} changed outside the app: {}",
+        CONFIG_FILE,
+        keys.join(", ")
+    );
+    let mut message = format!("{} reloaded: {}", CONFIG_FILE, keys.join(", "));
+    for note in notes {
+        message.push_str(". ");
+        message.push_str(&note);
+    }
+    let _ = ws_tx.send(WebSocketNotification::ConfigChanged { keys });
+    notify(ws_tx, CONFIG_FILE, true, message);
+}
+
+async fn reload_mapping(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    ws_tx: &broadcast::Sender<WebSocketNotification>,
+    file: &str,
+) {
+    // older schema versions are upgraded in memory, the file is left as it is
+    if let Err(diagnostics) = read_mapping_file(file) {
+        log::warn!(
+            "[Watcher] Ignored external change of {}: {}",
+            file,
+            format_mapping_diagnostics(&diagnostics)
+        );
+        let _ = ws_tx.send(WebSocketNotification::DataFileChanged {
+            file: file.to_string(),
+            applied: false,
+            message: format!(
+                "{}: {}",
+                t!("mask.mapping.mappingConfigValidationFailed"),
+                file
+            ),
+            diagnostics,
+        });
+        return;
+    }
+
+    if LocalConfig::get().active_mapping_file != file {
+        log::info!("[Watcher] Mapping file changed outside the app: {}", file);
+        return;
+    }
+    match load_mapping(m_tx, file).await {
+        Ok(_) => {
+            log::info!("[Watcher] Reloaded active mapping: {}", file);
+            notify(
+                ws_tx,
+                file,
+                true,
+                format!("Active mapping reloaded: {}", file),
+            );
+        }
+        Err(e) => {
+            log::warn!("[Watcher] Failed to reload active mapping {}: {}", file, e);
+            notify(
+                ws_tx,
+                file,
+                false,
+                format!(
+                    "{}: {}. {}",
+                    t!("web.mapping.failedToLoadMappingConfig"),
+                    file,
+                    e
+                ),
+            );
+        }
+    }
+}
+
+async fn load_mapping(
+    m_tx: &crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
+    file: &str,
+) -> Result<String, String> {
+    let (oneshot_tx, oneshot_rx) = oneshot::channel::<Result<String, String>>();
+    m_tx.send((
+        MaskCommand::LoadAndActivateMappingConfig {
+            file_name: file.to_string(),
+        },
+        oneshot_tx,
+    ))
+    .map_err(|e| e.to_string())?;
+    oneshot_rx.await.map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_differing_values_are_changed() {
+        let previous = LocalConfig::default();
+        let config = LocalConfig {
+            video_bit_rate: previous.video_bit_rate + 1,
+            audio_muted: !previous.audio_muted,
+            ..previous.clone()
+        };
+        let values = changed_values(&previous, &config);
+        let mut keys: Vec<&str> = values.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["audio_muted", "video_bit_rate"]);
+        assert!(changed_values(&config, &config).is_empty());
+    }
+
+    #[test]
+    fn changed_config_is_checked() {
+        let previous = LocalConfig::default();
+        let text = |values: serde_json::Value| {
+            let mut config = serde_json::to_value(&previous).unwrap();
+            for (key, value) in values.as_object().unwrap() {
+                config[key] = value.clone();
+            }
+            config.to_string()
+        };
+
+        let config = read_config(
+            &previous,
+            text(serde_json::json!({ "video_max_fps": 30 })).as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(config.video_max_fps, 30);
+        // parsed but out of the schema range
+        assert!(
+            read_config(
+                &previous,
+                text(serde_json::json!({ "video_bit_rate": 0 })).as_bytes()
+            )
+            .is_err()
+        );
+        assert!(
+            read_config(
+                &previous,
+                text(serde_json::json!({ "language": "xx" })).as_bytes()
+            )
+            .is_err()
+        );
+        assert!(
+            read_config(
+                &previous,
+                text(serde_json::json!({ "web_port": "80" })).as_bytes()
+            )
+            .is_err()
+        );
+        assert!(read_config(&previous, b"{").is_err());
+    }
+
+    #[test]
+    fn event_paths_map_to_data_files() {
+        let root = Path::new("/data");
+        assert_eq!(
+            DataFile::from_event_path(&root.join("config.json")),
+            Some(DataFile::Config)
+        );
+        assert_eq!(
+            DataFile::from_event_path(&root.join("mapping").join("a.json")),
+            Some(DataFile::Mapping("a.json".to_string()))
+        );
+        assert_eq!(
+            DataFile::from_event_path(&root.join("mapping").join(".a.json.tmp")),
+            None
+        );
+        assert_eq!(
+            DataFile::from_event_path(&root.join(".config.json.tmp")),
+            None
+        );
+        assert_eq!(DataFile::from_event_path(&root.join("profiles.json")), None);
+    }
+}
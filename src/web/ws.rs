@@ -1,5 +1,5 @@
 use axum::{
-    Router,
+    Extension, Router,
     extract::{
         State, WebSocketUpgrade,
         ws::{Message, WebSocket},
@@ -9,11 +9,26 @@ use axum::{
 };
 use rust_i18n::t;
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast::{self, error::RecvError};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc, oneshot, watch,
+};
 
 use crate::{
-    scrcpy::{ScrcpyDevice, constant, control_msg::ScrcpyControlMsg},
-    utils::share::ControlledDevice,
+    config::AuthScope,
+    mask::{mapping::config::MappingDiagnostic, mask_command::MaskCommand},
+    scrcpy::{
+        ScrcpyDevice,
+        adaptive_video::AdaptiveVideoStatus,
+        audio::AudioStats,
+        constant,
+        control_msg::ScrcpyControlMsg,
+        file_transfer::{TransferKind, TransferStage},
+    },
+    utils::share::{ControlledDevice, MappingFallback},
+    web::{JsonResponse, WebServerError, adb_console, auth::AuthContext, device, mapping, script},
 };
 use futures_util::{
     SinkExt,
@@ -40,6 +55,32 @@ pub enum WebSocketNotification {
     ConfigChanged {
         keys: Vec<String>,
     },
+    AudioStats {
+        scid: String,
+        stats: AudioStats,
+    },
+    AdaptiveVideo {
+        scid: String,
+        status: AdaptiveVideoStatus,
+    },
+    /// config.json or a mapping file was changed outside the app
+    DataFileChanged {
+        file: String,
+        applied: bool,
+        message: String,
+        diagnostics: Vec<MappingDiagnostic>,
+    },
+    /// Progress of a file push or pull, or of an apk install
+    DeviceTransfer {
+        id: String,
+        device_id: String,
+        kind: TransferKind,
+        name: String,
+        stage: TransferStage,
+        transferred: u64,
+        total: u64,
+        message: Option<String>,
+    },
 }
 
 impl From<WebSocketNotification> for Message {
@@ -147,39 +188,167 @@ impl From<WebSocketMsg> for ScrcpyControlMsg {
     }
 }
 
+/// Notification groups a client can subscribe to, all of them by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationTopic {
+    Rotation,
+    Connection,
+    DeviceList,
+    Config,
+    AudioStats,
+    AdaptiveVideo,
+    Transfer,
+}
+
+const ALL_TOPICS: [NotificationTopic; 7] = [
+    NotificationTopic::Rotation,
+    NotificationTopic::Connection,
+    NotificationTopic::DeviceList,
+    NotificationTopic::Config,
+    NotificationTopic::AudioStats,
+    NotificationTopic::AdaptiveVideo,
+    NotificationTopic::Transfer,
+];
+
+impl WebSocketNotification {
+    fn topic(&self) -> NotificationTopic {
+        match self {
+            WebSocketNotification::ScrcpyDeviceRotation { .. } => NotificationTopic::Rotation,
+            WebSocketNotification::ScrcpyDeviceConnection { .. } => NotificationTopic::Connection,
+            WebSocketNotification::ScrcpyDeviceList { .. } => NotificationTopic::DeviceList,
+            WebSocketNotification::ConfigChanged { .. } => NotificationTopic::Config,
+            WebSocketNotification::AudioStats { .. } => NotificationTopic::AudioStats,
+            WebSocketNotification::AdaptiveVideo { .. } => NotificationTopic::AdaptiveVideo,
+            WebSocketNotification::DataFileChanged { .. } => NotificationTopic::Config,
+            WebSocketNotification::DeviceTransfer { .. } => NotificationTopic::Transfer,
+        }
+    }
+}
+
+/// A message carrying an `id` is a request, it is always answered with a reply of the same `id`
+#[derive(Debug, Deserialize)]
+struct WebSocketRequest {
+    id: Value,
+    #[serde(flatten)]
+    op: WebSocketOp,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum WebSocketOp {
+    RunScript {
+        script: String,
+    },
+    ChangeActiveMapping {
+        file: String,
+    },
+    GetDeviceList,
+    /// Only receive notifications of these topics
+    Subscribe {
+        topics: Vec<NotificationTopic>,
+    },
+    Unsubscribe {
+        topics: Vec<NotificationTopic>,
+    },
+    #[serde(untagged)]
+    Control(WebSocketMsg),
+}
+
+impl WebSocketOp {
+    fn scope(&self) -> AuthScope {
+        match self {
+            WebSocketOp::RunScript { .. }
+            | WebSocketOp::ChangeActiveMapping { .. }
+            | WebSocketOp::Control(_) => AuthScope::Control,
+            WebSocketOp::GetDeviceList
+            | WebSocketOp::Subscribe { .. }
+            | WebSocketOp::Unsubscribe { .. } => AuthScope::Read,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+enum WebSocketReply {
+    Response {
+        id: Value,
+        message: String,
+        data: Option<Value>,
+    },
+    Error {
+        id: Value,
+        code: u16,
+        message: String,
+    },
+}
+
+impl WebSocketReply {
+    fn new(id: Value, result: Result<JsonResponse, WebServerError>) -> Self {
+        match result {
+            Ok(res) => WebSocketReply::Response {
+                id,
+                message: res.message,
+                data: res.data,
+            },
+            Err(e) => {
+                log::warn!("[WebSocket] Request {} failed: {}", id, e.1);
+                WebSocketReply::Error {
+                    id,
+                    code: e.0,
+                    message: e.1,
+                }
+            }
+        }
+    }
+}
+
+impl From<WebSocketReply> for Message {
+    fn from(reply: WebSocketReply) -> Self {
+        let json = serde_json::to_string(&reply).unwrap();
+        Message::Text(json.into())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AppStateWS {
     cs_tx: broadcast::Sender<ScrcpyControlMsg>,
+    m_tx: crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
     ws_tx: broadcast::Sender<WebSocketNotification>,
 }
 
 pub fn routers(
     cs_tx: broadcast::Sender<ScrcpyControlMsg>,
+    m_tx: crossbeam_channel::Sender<(MaskCommand, oneshot::Sender<Result<String, String>>)>,
     ws_tx: broadcast::Sender<WebSocketNotification>,
 ) -> Router {
     Router::new()
         .route("/connect", any(ws_handler))
-        .with_state(AppStateWS { cs_tx, ws_tx })
+        .route("/adb", any(adb_console::ws_handler))
+        .with_state(AppStateWS { cs_tx, m_tx, ws_tx })
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppStateWS>) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state.cs_tx, state.ws_tx.subscribe()))
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppStateWS>,
+    Extension(auth): Extension<AuthContext>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state, auth))
 }
 
-async fn handle_socket(
-    socket: WebSocket,
-    cs_tx: broadcast::Sender<ScrcpyControlMsg>,
-    ws_rx: broadcast::Receiver<WebSocketNotification>,
-) {
+async fn handle_socket(socket: WebSocket, state: AppStateWS, auth: AuthContext) {
     log::info!("[WebSocket] {}", t!("web.ws.connected"));
     let (sender, receiver) = socket.split();
+    let ws_rx = state.ws_tx.subscribe();
+    let (reply_tx, reply_rx) = mpsc::unbounded_channel::<WebSocketReply>();
+    let (topics_tx, topics_rx) = watch::channel(BTreeSet::from(ALL_TOPICS));
 
     let mut send_handler = tokio::spawn(async move {
-        handle_send(sender, ws_rx).await;
+        handle_send(sender, ws_rx, reply_rx, topics_rx).await;
     });
 
     let mut recv_handler = tokio::spawn(async move {
-        handle_recv(receiver, cs_tx).await;
+        handle_recv(receiver, state, auth, reply_tx, topics_tx).await;
     });
 
     tokio::select! {
@@ -196,6 +365,8 @@ async fn handle_socket(
 async fn handle_send(
     mut sender: SplitSink<WebSocket, Message>,
     mut ws_rx: broadcast::Receiver<WebSocketNotification>,
+    mut reply_rx: mpsc::UnboundedReceiver<WebSocketReply>,
+    topics_rx: watch::Receiver<BTreeSet<NotificationTopic>>,
 ) {
     if sender
         .send(
@@ -209,23 +380,53 @@ async fn handle_send(
     {
         return;
     }
+    // the mask may have started before this client could be told
+    if let Some(fallback) = MappingFallback::get().await {
+        let notification = WebSocketNotification::DataFileChanged {
+            message: t!(
+                "mask.mapping.fallbackInUse",
+                file => fallback.file,
+                active => fallback.active
+            )
+            .to_string(),
+            file: fallback.file,
+            applied: false,
+            diagnostics: fallback.diagnostics,
+        };
+        if sender.send(notification.into()).await.is_err() {
+            return;
+        }
+    }
 
     loop {
-        match ws_rx.recv().await {
-            Ok(msg) => {
-                if sender.send(msg.into()).await.is_err() {
+        tokio::select! {
+            reply = reply_rx.recv() => {
+                let Some(reply) = reply else {
+                    break;
+                };
+                if sender.send(reply.into()).await.is_err() {
                     break;
                 }
             }
-            Err(RecvError::Lagged(skipped)) => {
-                log::warn!(
-                    "[WebSocket] {}",
-                    t!("web.ws.receiverLagged", skipped => skipped)
-                );
-            }
-            Err(e) => {
-                log::info!("[WebSocket] {}: {}", t!("web.ws.wsChannelClosed"), e);
-                break;
+            msg = ws_rx.recv() => match msg {
+                Ok(msg) => {
+                    if !topics_rx.borrow().contains(&msg.topic()) {
+                        continue;
+                    }
+                    if sender.send(msg.into()).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!(
+                        "[WebSocket] {}",
+                        t!("web.ws.receiverLagged", skipped => skipped)
+                    );
+                }
+                Err(e) => {
+                    log::info!("[WebSocket] {}: {}", t!("web.ws.wsChannelClosed"), e);
+                    break;
+                }
             }
         }
     }
@@ -233,19 +434,49 @@ async fn handle_send(
 
 async fn handle_recv(
     mut receiver: SplitStream<WebSocket>,
-    cs_tx: broadcast::Sender<ScrcpyControlMsg>,
+    state: AppStateWS,
+    auth: AuthContext,
+    reply_tx: mpsc::UnboundedSender<WebSocketReply>,
+    topics_tx: watch::Sender<BTreeSet<NotificationTopic>>,
 ) {
     while let Some(Ok(msg)) = receiver.next().await {
         match msg {
-            Message::Text(t) => {
-                let msg: WebSocketMsg = match serde_json::from_str(&t) {
-                    Ok(m) => m,
+            Message::Text(text) => {
+                let value: Value = match serde_json::from_str(&text) {
+                    Ok(v) => v,
                     Err(e) => {
                         log::error!("[WebSocket] {}: {}", t!("web.ws.failedToParseMessage"), e);
                         continue;
                     }
                 };
-                cs_tx.send(msg.into()).unwrap();
+
+                let id = match request_id(&value) {
+                    Some(Ok(id)) => id,
+                    Some(Err(e)) => {
+                        let _ = reply_tx.send(WebSocketReply::new(Value::Null, Err(e)));
+                        continue;
+                    }
+                    // messages without an id are fire-and-forget control messages
+                    None => {
+                        handle_control_msg(value, &state.cs_tx, &auth);
+                        continue;
+                    }
+                };
+                let op = match serde_json::from_value::<WebSocketRequest>(value) {
+                    Ok(request) => request.op,
+                    Err(e) => {
+                        let _ = reply_tx.send(WebSocketReply::new(
+                            id,
+                            Err(WebServerError::bad_request(format!(
+                                "{}: {}",
+                                t!("web.ws.failedToParseMessage"),
+                                e
+                            ))),
+                        ));
+                        continue;
+                    }
+                };
+                handle_request(id, op, &state, &auth, &reply_tx, &topics_tx);
             }
             Message::Close(_) => {
                 break;
@@ -254,3 +485,230 @@ async fn handle_recv(
         }
     }
 }
+
+/// A null id could not be told apart from the replies to unparsable requests, so it is rejected
+fn request_id(value: &Value) -> Option<Result<Value, WebServerError>> {
+    match value.get("id")? {
+        Value::Null => Some(Err(WebServerError::bad_request(t!("web.ws.nullRequestId")))),
+        id => Some(Ok(id.clone())),
+    }
+}
+
+fn handle_control_msg(
+    value: Value,
+    cs_tx: &broadcast::Sender<ScrcpyControlMsg>,
+    auth: &AuthContext,
+) {
+    let msg: WebSocketMsg = match serde_json::from_value(value) {
+        Ok(m) => m,
+        Err(e) => {
+            log::error!("[WebSocket] {}: {}", t!("web.ws.failedToParseMessage"), e);
+            return;
+        }
+    };
+    // read only tokens may watch notifications but not inject input
+    if !auth.allows(AuthScope::Control) {
+        log::warn!("[WebSocket] {}", t!("web.ws.controlIgnored"));
+        return;
+    }
+    if let Err(e) = send_control_msg(cs_tx, msg) {
+        log::error!("[WebSocket] {}", e.1);
+    }
+}
+
+fn send_control_msg(
+    cs_tx: &broadcast::Sender<ScrcpyControlMsg>,
+    msg: WebSocketMsg,
+) -> Result<JsonResponse, WebServerError> {
+    cs_tx
+        .send(msg.into())
+        .map_err(|_| WebServerError::bad_request(t!("web.device.noDeviceControlled")))?;
+    Ok(JsonResponse::success(t!("web.ws.controlMessageSent"), None))
+}
+
+fn topics_response(topics: &BTreeSet<NotificationTopic>) -> Result<JsonResponse, WebServerError> {
+    Ok(JsonResponse::success(
+        t!("web.ws.subscriptionUpdated"),
+        Some(json!({ "topics": topics })),
+    ))
+}
+
+/// Quick operations reply in order, scripts and mapping loads reply once they finish
+fn handle_request(
+    id: Value,
+    op: WebSocketOp,
+    state: &AppStateWS,
+    auth: &AuthContext,
+    reply_tx: &mpsc::UnboundedSender<WebSocketReply>,
+    topics_tx: &watch::Sender<BTreeSet<NotificationTopic>>,
+) {
+    let scope = op.scope();
+    if !auth.allows(scope) {
+        let _ = reply_tx.send(WebSocketReply::new(
+            id,
+            Err(WebServerError::forbidden(t!(
+                "web.auth.missingScope",
+                name => auth.token_name.as_deref().unwrap_or_default(),
+                scope => format!("{:?}", scope)
+            ))),
+        ));
+        return;
+    }
+
+    let result = match op {
+        WebSocketOp::Control(msg) => send_control_msg(&state.cs_tx, msg),
+        WebSocketOp::Subscribe { topics } => {
+            topics_tx.send_replace(topics.into_iter().collect());
+            topics_response(&topics_tx.borrow())
+        }
+        WebSocketOp::Unsubscribe { topics } => {
+            topics_tx.send_modify(|current| current.retain(|topic| !topics.contains(topic)));
+            topics_response(&topics_tx.borrow())
+        }
+        WebSocketOp::GetDeviceList => {
+            spawn_reply(id, reply_tx, device::device_list());
+            return;
+        }
+        WebSocketOp::RunScript { script } => {
+            let m_tx = state.m_tx.clone();
+            spawn_reply(id, reply_tx, async move {
+                script::run_script_with(&m_tx, script).await
+            });
+            return;
+        }
+        WebSocketOp::ChangeActiveMapping { file } => {
+            let m_tx = state.m_tx.clone();
+            spawn_reply(id, reply_tx, async move {
+                mapping::change_active_mapping_with(&m_tx, file).await
+            });
+            return;
+        }
+    };
+    let _ = reply_tx.send(WebSocketReply::new(id, result));
+}
+
+fn spawn_reply(
+    id: Value,
+    reply_tx: &mpsc::UnboundedSender<WebSocketReply>,
+    task: impl Future<Output = Result<JsonResponse, WebServerError>> + Send + 'static,
+) {
+    let reply_tx = reply_tx.clone();
+    tokio::spawn(async move {
+        let _ = reply_tx.send(WebSocketReply::new(id, task.await));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> WebSocketRequest {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn requests_and_control_messages_share_the_envelope() {
+        let request = parse(r#"{"id":1,"type":"RunScript","script":"tap(1,1)"}"#);
+        assert_eq!(request.id, json!(1));
+        assert!(matches!(request.op, WebSocketOp::RunScript { .. }));
+
+        let request = parse(r#"{"id":"a","type":"Subscribe","topics":["device_list"]}"#);
+        assert!(matches!(
+            request.op,
+            WebSocketOp::Subscribe { topics } if topics == vec![NotificationTopic::DeviceList]
+        ));
+
+        let request = parse(r#"{"id":2,"type":"InjectText","text":"hi"}"#);
+        assert!(matches!(
+            request.op,
+            WebSocketOp::Control(WebSocketMsg::InjectText { .. })
+        ));
+        assert_eq!(request.op.scope(), AuthScope::Control);
+
+        assert!(serde_json::from_str::<WebSocketRequest>(r#"{"id":3,"type":"Nope"}"#).is_err());
+    }
+
+    #[test]
+    fn null_ids_are_rejected() {
+        assert!(request_id(&json!({ "type": "InjectText", "text": "hi" })).is_none());
+        assert!(matches!(request_id(&json!({ "id": 0 })), Some(Ok(id)) if id == json!(0)));
+        assert!(matches!(request_id(&json!({ "id": null })), Some(Err(_))));
+    }
+
+    struct Harness {
+        state: AppStateWS,
+        cs_rx: broadcast::Receiver<ScrcpyControlMsg>,
+        reply_rx: mpsc::UnboundedReceiver<WebSocketReply>,
+        reply_tx: mpsc::UnboundedSender<WebSocketReply>,
+        topics_tx: watch::Sender<BTreeSet<NotificationTopic>>,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let (cs_tx, cs_rx) = broadcast::channel(4);
+            let (m_tx, _) = crossbeam_channel::unbounded();
+            let (ws_tx, _) = broadcast::channel(4);
+            let (reply_tx, reply_rx) = mpsc::unbounded_channel();
+            Self {
+                state: AppStateWS { cs_tx, m_tx, ws_tx },
+                cs_rx,
+                reply_rx,
+                reply_tx,
+                topics_tx: watch::channel(ALL_TOPICS.into_iter().collect()).0,
+            }
+        }
+
+        fn request(&mut self, scopes: &[AuthScope], text: &str) -> WebSocketReply {
+            let request = parse(text);
+            let auth = AuthContext {
+                token_name: Some("viewer".to_string()),
+                scopes: scopes.to_vec(),
+            };
+            handle_request(
+                request.id,
+                request.op,
+                &self.state,
+                &auth,
+                &self.reply_tx,
+                &self.topics_tx,
+            );
+            self.reply_rx.try_recv().unwrap()
+        }
+    }
+
+    #[test]
+    fn requests_without_scope_are_rejected() {
+        let mut harness = Harness::new();
+        let reply = harness.request(
+            &[AuthScope::Read],
+            r#"{"id":7,"type":"InjectText","text":"hi"}"#,
+        );
+        assert!(matches!(reply, WebSocketReply::Error { id, code: 403, .. } if id == json!(7)));
+        assert!(harness.cs_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn replies_carry_the_request_id() {
+        let mut harness = Harness::new();
+        let reply = harness.request(
+            &[AuthScope::Read],
+            r#"{"id":"sub","type":"Subscribe","topics":["config"]}"#,
+        );
+        assert!(matches!(
+            reply,
+            WebSocketReply::Response { id, data: Some(data), .. }
+                if id == json!("sub") && data == json!({ "topics": ["config"] })
+        ));
+        assert_eq!(
+            *harness.topics_tx.borrow(),
+            BTreeSet::from([NotificationTopic::Config])
+        );
+
+        let reply = harness.request(
+            &[AuthScope::Control],
+            r#"{"id":8,"type":"InjectText","text":"hi"}"#,
+        );
+        assert!(matches!(reply, WebSocketReply::Response { id, .. } if id == json!(8)));
+        assert!(harness.cs_rx.try_recv().is_ok());
+    }
+}